@@ -6,6 +6,47 @@ pub struct RuntimeConfig {
     pub deterministic: bool,
     pub fuel: u64,
     pub memory_max_mb: u64,
+    #[serde(default = "default_wall_clock_ms")]
+    pub wall_clock_ms: u64,
+    #[serde(default = "default_max_stack_kb")]
+    pub max_stack_kb: u64,
+    #[serde(default = "default_table_elements_max")]
+    pub table_elements_max: u32,
+    #[serde(default = "default_instances_max")]
+    pub instances_max: usize,
+}
+
+fn default_wall_clock_ms() -> u64 { 2_000 }
+fn default_max_stack_kb() -> u64 { 512 }
+fn default_table_elements_max() -> u32 { 10_000 }
+fn default_instances_max() -> usize { 1 }
+
+impl RuntimeConfig {
+    /// Deterministic config with default wall-clock/stack/table/instance caps.
+    pub fn new(fuel: u64, memory_max_mb: u64) -> Self {
+        Self {
+            deterministic: true, fuel, memory_max_mb,
+            wall_clock_ms: default_wall_clock_ms(),
+            max_stack_kb: default_max_stack_kb(),
+            table_elements_max: default_table_elements_max(),
+            instances_max: default_instances_max(),
+        }
+    }
+
+    /// The part of the config that identifies a run: the caps added later only bound it and stay out
+    /// of the run CID, so adding one does not move every existing CID.
+    pub fn manifest(&self) -> serde_json::Value {
+        serde_json::json!({ "deterministic": self.deterministic, "fuel": self.fuel, "memory_max_mb": self.memory_max_mb })
+    }
+}
+
+/// Card decision, serialized as the spec's `ACK` / `NACK` / `ASK`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Decision {
+    #[serde(rename = "ACK")] Allow,
+    #[serde(rename = "NACK")] Deny,
+    /// The run could not conclude (trap, tripped limit); needs a human or a retry with other limits.
+    #[serde(rename = "ASK")] Doubt,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +55,24 @@ pub struct EerWasm {
     pub config: RuntimeConfig,
     pub digests: Digests,
     pub wasmtime: WasmtimeMeta,
+    #[serde(default)]
+    pub report: Option<ExecReport>,
+}
+
+/// Which runtime limit stopped the guest. `instances_max` has no kind: a run instantiates exactly one
+/// module, so only a host configured below 1 trips it, and that fails as a setup error.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKind { Fuel, Memory, Table, WallClock, Stack }
+
+/// Resource accounting for one execution, recorded in the EER.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecReport {
+    pub fuel_consumed: u64,
+    pub peak_memory_bytes: u64,
+    pub elapsed_ms: u64,
+    pub limit_hit: Option<LimitKind>,
+    pub trap: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,10 +95,11 @@ pub struct ReceiptProof { pub seal: ReceiptSeal, pub hash_chain: Vec<ChainStep>,
 pub struct Card {
     pub kind: String,
     pub realm: String,
-    pub decision: String,
+    pub decision: Decision,
     pub output_cid: String,
     pub proof: ReceiptProof,
     #[serde(default)] pub refs: Vec<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] pub poi: Option<serde_json::Value>,
     pub links: Links,
 }
 
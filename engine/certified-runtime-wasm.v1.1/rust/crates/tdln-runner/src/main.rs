@@ -4,6 +4,7 @@ use serde_json::json;
 use std::fs;
 use tdln_certified_runtime::{CertifiedRuntime, RuntimeConfig};
 use tdln_runtime_wasm::WasmCertifiedRuntime;
+use tdln_runtime_wasm::replay::{replay, LocalObjects};
 
 #[derive(Parser)]
 #[command(name="tdln-runner", version, about="Certified Runtime — WASM v1") ]
@@ -23,6 +24,20 @@ enum Cmd {
         fuel: u64,
         #[arg(long, default_value_t=256)]
         memory_max_mb: u64,
+        #[arg(long, default_value_t=2000)]
+        wall_clock_ms: u64,
+        #[arg(long)]
+        out: Option<String>,
+        /// Also store the unit and input under <dir>/objects so the card can be replayed later
+        #[arg(long)]
+        registry: Option<String>,
+    },
+    /// Re-execute a card's unit/input (fetched by CID from --registry) and compare bit for bit
+    Replay {
+        #[arg(long)]
+        card: String,
+        #[arg(long)]
+        registry: String,
         #[arg(long)]
         out: Option<String>,
     }
@@ -31,15 +46,28 @@ enum Cmd {
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
     match cli.cmd {
-        Cmd::Run { wasm, input, fuel, memory_max_mb, out } => {
+        Cmd::Run { wasm, input, fuel, memory_max_mb, wall_clock_ms, out, registry } => {
             let unit_bytes = fs::read(&wasm)?;
             let input_json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&input)?)?;
+            if let Some(dir) = registry {
+                let objects = LocalObjects::new(dir);
+                objects.put(&unit_bytes)?;
+                objects.put(&serde_json::to_vec(&input_json)?)?;
+            }
             let rt = WasmCertifiedRuntime { version: env!("CARGO_PKG_VERSION") };
-            let cfg = RuntimeConfig { deterministic: true, fuel, memory_max_mb };
+            let cfg = RuntimeConfig { wall_clock_ms, ..RuntimeConfig::new(fuel, memory_max_mb) };
             let card = rt.execute(&unit_bytes, &input_json, &cfg)?;
             let s = serde_json::to_string_pretty(&card)?;
             if let Some(p) = out { fs::write(p, s)?; } else { println!("{}", s); }
         }
+        Cmd::Replay { card, registry, out } => {
+            let card: tdln_certified_runtime::Card = serde_json::from_str(&fs::read_to_string(&card)?)?;
+            let rt = WasmCertifiedRuntime { version: env!("CARGO_PKG_VERSION") };
+            let report = replay(&card, &LocalObjects::new(registry), &rt)?;
+            let s = serde_json::to_string_pretty(&report)?;
+            if let Some(p) = out { fs::write(p, s)?; } else { println!("{}", s); }
+            if !report.ok { std::process::exit(2); }
+        }
     }
     Ok(())
 }
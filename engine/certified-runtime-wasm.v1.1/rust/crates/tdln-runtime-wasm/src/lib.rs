@@ -1,55 +1,115 @@
-
 use anyhow::Result;
 use blake3::Hasher;
 use serde_json::json;
-use tdln_certified_runtime::{CertifiedRuntime, RuntimeConfig, Card, ReceiptProof, ReceiptSeal, ChainStep, Links};
+use tdln_certified_runtime::{CertifiedRuntime, RuntimeConfig, Card, Decision, ReceiptProof, ReceiptSeal, ChainStep, Links, ExecReport};
+
+mod limits;
+use limits::{GuestAbiError, TrackedLimits};
+pub mod replay;
+
+/// Wasmtime release this runtime is pinned to; recorded in the EER and checked on replay.
+pub const WASMTIME_VERSION: &str = "24.0.5";
 
 pub struct WasmCertifiedRuntime {
     pub version: &'static str,
 }
 
-fn cid_bytes(bytes: &[u8]) -> String {
+pub fn cid_bytes(bytes: &[u8]) -> String {
     let mut h = Hasher::new();
     h.update(bytes);
     format!("cid:b3:{}", h.finalize().to_hex())
 }
 
-fn cid_json(v: &serde_json::Value) -> String {
+pub fn cid_json(v: &serde_json::Value) -> String {
     let enc = serde_json::to_vec(&v).unwrap();
     cid_bytes(&enc)
 }
 
+/// Instantiate and run the guest inside `store`. Any trap surfaces as `Err`.
+///
+/// Guest ABI: exports `memory`, `alloc(len) -> ptr` and `_run_json(ptr, len) -> (ptr, len)`; the host
+/// writes the input JSON into a block from `alloc` and reads the output JSON back from the returned
+/// region. `dealloc(ptr, len)`, when exported, gets both regions back. No imports are linked.
+fn run_guest(store: &mut wasmtime::Store<TrackedLimits>, module: &wasmtime::Module, input_json: &serde_json::Value) -> Result<serde_json::Value> {
+    let instance = wasmtime::Instance::new(&mut *store, module, &[])?;
+    let memory = instance.get_memory(&mut *store, "memory").ok_or_else(|| GuestAbiError("export memory required".into()))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc").map_err(|_| GuestAbiError("export alloc(i32) -> i32 required".into()))?;
+    let run = instance.get_typed_func::<(i32, i32), (i32, i32)>(&mut *store, "_run_json")
+        .map_err(|_| GuestAbiError("export _run_json(i32, i32) -> (i32, i32) required".into()))?;
+
+    let input = serde_json::to_vec(input_json)?;
+    let in_len = i32::try_from(input.len()).map_err(|_| GuestAbiError("input too large for a 32-bit guest".into()))?;
+    let in_ptr = alloc.call(&mut *store, in_len)?;
+    memory.write(&mut *store, in_ptr as u32 as usize, &input).map_err(|_| GuestAbiError(format!("alloc returned {in_ptr}, outside guest memory")))?;
+    let (out_ptr, out_len) = run.call(&mut *store, (in_ptr, in_len))?;
+    let (start, len) = (out_ptr as u32 as usize, out_len as u32 as usize);
+    let output = memory.data(&*store).get(start..start.saturating_add(len)).map(<[u8]>::to_vec)
+        .ok_or_else(|| GuestAbiError(format!("output ({out_ptr}, {out_len}) is outside guest memory")))?;
+    if let Ok(dealloc) = instance.get_typed_func::<(i32, i32), ()>(&mut *store, "dealloc") {
+        dealloc.call(&mut *store, (in_ptr, in_len))?;
+        dealloc.call(&mut *store, (out_ptr, out_len))?;
+    }
+    serde_json::from_slice(&output).map_err(|e| GuestAbiError(format!("output is not JSON: {e}")).into())
+}
+
 impl CertifiedRuntime for WasmCertifiedRuntime {
     fn execute(&self, unit_bytes: &[u8], input_json: &serde_json::Value, cfg: &RuntimeConfig) -> Result<Card> {
-        // Wasmtime setup (deterministic + fuel)
+        // Wasmtime setup (deterministic + fuel + epoch deadline + stack cap)
         let mut cfg_vm = wasmtime::Config::default();
         cfg_vm.consume_fuel(true);
+        cfg_vm.epoch_interruption(true);
+        cfg_vm.max_wasm_stack((cfg.max_stack_kb as usize) * 1024);
         cfg_vm.cranelift_nan_canonicalization(true);
         cfg_vm.wasm_threads(false);
         let engine = wasmtime::Engine::new(&cfg_vm)?;
-        let mut store = wasmtime::Store::new(&engine, ());
+        let mut store = wasmtime::Store::new(&engine, TrackedLimits::new(cfg));
+        store.limiter(|l| l);
         store.set_fuel(cfg.fuel)?;
+        store.set_epoch_deadline(1);
 
-        // Instantiate module
         let module = wasmtime::Module::new(&engine, unit_bytes)?;
-        // No WASI by default; imports must be explicit and safe (omitted here for brevity)
-        let instance = wasmtime::Instance::new(&mut store, &module, &[])?;
-
-        // Convention: exported func `_run_json(ptr,len) -> (ptr,len)` (optional). If missing, echo input.
-        // For production, wire an env adapter (json_in/json_out) or WIT.
-        let output_json = if let Some(export) = instance.get_func(&mut store, "_run_json") {
-            // placeholder: without memory adapters we can't marshal here; return input as passthrough
-            input_json.clone()
-        } else {
-            input_json.clone()
+
+        // Wall-clock watchdog: one epoch tick after the deadline interrupts the guest. The engine is this
+        // run's own, so the tick reaches no other store.
+        let (done, wait) = std::sync::mpsc::channel::<()>();
+        let ticker = engine.clone();
+        let deadline = std::time::Duration::from_millis(cfg.wall_clock_ms);
+        let watchdog = std::thread::spawn(move || {
+            if let Err(std::sync::mpsc::RecvTimeoutError::Timeout) = wait.recv_timeout(deadline) { ticker.increment_epoch(); }
+        });
+        let started = std::time::Instant::now();
+        let outcome = run_guest(&mut store, &module, input_json);
+        let _ = done.send(());
+        let _ = watchdog.join();
+
+        let mut report = ExecReport {
+            fuel_consumed: cfg.fuel.saturating_sub(store.get_fuel().unwrap_or(0)),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            ..Default::default()
+        };
+        let (decision, output_json, poi_v) = match outcome {
+            Ok(out) => (Decision::Allow, out, None),
+            Err(e) if limits::is_guest_fault(&e, store.data()) => {
+                report.limit_hit = limits::classify(&e, store.data());
+                report.trap = Some(e.to_string());
+                let violation = match report.limit_hit {
+                    Some(k) => format!("limit_exceeded:{}", serde_json::to_value(k)?.as_str().unwrap_or_default()),
+                    None if e.downcast_ref::<GuestAbiError>().is_some() => "guest_abi".to_string(),
+                    None => "guest_trap".to_string(),
+                };
+                (Decision::Doubt, serde_json::Value::Null, Some(poi("wasm_trap", &[], &[violation])))
+            }
+            Err(e) => return Err(e),
         };
+        report.peak_memory_bytes = store.data().peak_memory_bytes as u64;
 
         // Build proof/hash chain
         let input_cid = cid_json(input_json);
         let unit_cid = cid_bytes(unit_bytes);
         let output_cid = cid_json(&output_json);
+        let cfg_v = serde_json::to_value(cfg)?;
         let run_manifest = json!({
-            "unit_cid": unit_cid, "input_cid": input_cid, "cfg": { "deterministic": true, "fuel": cfg.fuel, "memory_max_mb": cfg.memory_max_mb }
+            "unit_cid": unit_cid, "input_cid": input_cid, "cfg": cfg.manifest()
         });
         let run_cid = cid_json(&run_manifest);
 
@@ -57,16 +117,17 @@ impl CertifiedRuntime for WasmCertifiedRuntime {
         let proof = ReceiptProof { 
             seal, 
             hash_chain: vec![ChainStep{kind:"input".into(), cid: input_cid.clone()}, ChainStep{kind:"output".into(), cid: output_cid.clone()}], 
-            eer: Some(json!({ "runtime":{"name":"tdln-runtime-wasm","version": self.version, "hash":"b3:demo"}, "config": {"deterministic": true, "fuel": cfg.fuel, "memory_max_mb": cfg.memory_max_mb}, "digests":{"unit_cid": unit_cid, "policy_cid": "cid:b3:policydemo"}, "wasmtime":{"version": "24.0.5"} }))
+            eer: Some(json!({ "runtime":{"name":"tdln-runtime-wasm","version": self.version, "hash":"b3:demo"}, "config": cfg_v, "digests":{"unit_cid": unit_cid, "policy_cid": "cid:b3:policydemo"}, "wasmtime":{"version": WASMTIME_VERSION}, "report": report }))
         };
 
         let card = Card {
             kind: "receipt.card.v1".into(),
             realm: "trust".into(),
-            decision: "ACK".into(),
+            decision,
             output_cid,
             proof,
-            refs: vec![json!({ "kind":"unit.wasm", "cid": unit_cid, "media_type":"application/wasm", "hrefs": ["tdln://objects/<cid>"] })],
+            refs: vec![json!({ "kind":"unit.wasm", "cid": unit_cid, "media_type":"application/wasm", "hrefs": [format!("tdln://objects/{unit_cid}")] })],
+            poi: poi_v,
             links: Links { url: String::new(), card_url: format!("https://cert.tdln.foundry/r/{}", run_cid.replace("cid:","")) },
         };
         
@@ -85,9 +146,11 @@ impl CertifiedRuntime for WasmCertifiedRuntime {
 }
 
 
-fn poi(missing: &[&str]) -> serde_json::Value {
+fn poi(reason: &str, missing: &[&str], violations: &[String]) -> serde_json::Value {
     serde_json::json!({
         "present": true,
+        "reason": reason,
         "missing": missing,
+        "violations": violations,
     })
 }
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use tdln_certified_runtime::{LimitKind, RuntimeConfig};
+use wasmtime::{ResourceLimiter, StoreLimits, StoreLimitsBuilder, Trap};
+
+/// `StoreLimits` plus a record of which cap refused a grow (StoreLimits alone only traps).
+pub struct TrackedLimits {
+    inner: StoreLimits,
+    pub peak_memory_bytes: usize,
+    pub denied: Option<LimitKind>,
+}
+
+impl TrackedLimits {
+    pub fn new(cfg: &RuntimeConfig) -> Self {
+        let inner = StoreLimitsBuilder::new()
+            .memory_size((cfg.memory_max_mb as usize) * 1024 * 1024)
+            .table_elements(cfg.table_elements_max as usize)
+            .instances(cfg.instances_max)
+            .trap_on_grow_failure(true)
+            .build();
+        Self { inner, peak_memory_bytes: 0, denied: None }
+    }
+}
+
+impl ResourceLimiter for TrackedLimits {
+    fn memory_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> Result<bool> {
+        let ok = self.inner.memory_growing(current, desired, maximum);
+        // Also called when a memory is first created, so this covers the initial pages too.
+        if matches!(ok, Ok(true)) { self.peak_memory_bytes = self.peak_memory_bytes.max(desired); }
+        else { self.denied.get_or_insert(LimitKind::Memory); }
+        ok
+    }
+    fn table_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> Result<bool> {
+        let ok = self.inner.table_growing(current, desired, maximum);
+        if !matches!(ok, Ok(true)) { self.denied.get_or_insert(LimitKind::Table); }
+        ok
+    }
+    fn instances(&self) -> usize { self.inner.instances() }
+    fn tables(&self) -> usize { self.inner.tables() }
+    fn memories(&self) -> usize { self.inner.memories() }
+}
+
+/// The guest does not follow the `_run_json` ABI (missing export, region outside its memory, output not JSON).
+#[derive(Debug)]
+pub struct GuestAbiError(pub String);
+impl std::fmt::Display for GuestAbiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "guest ABI: {}", self.0) }
+}
+impl std::error::Error for GuestAbiError {}
+
+/// Guest-side failure (trap, refused grow or ABI violation), as opposed to a host/setup error.
+pub fn is_guest_fault(err: &anyhow::Error, limits: &TrackedLimits) -> bool {
+    limits.denied.is_some() || err.downcast_ref::<Trap>().is_some() || err.downcast_ref::<GuestAbiError>().is_some()
+}
+
+pub fn classify(err: &anyhow::Error, limits: &TrackedLimits) -> Option<LimitKind> {
+    if let Some(k) = limits.denied { return Some(k); }
+    match err.downcast_ref::<Trap>()? {
+        Trap::OutOfFuel => Some(LimitKind::Fuel),
+        Trap::Interrupt => Some(LimitKind::WallClock),
+        Trap::StackOverflow => Some(LimitKind::Stack),
+        _ => None,
+    }
+}
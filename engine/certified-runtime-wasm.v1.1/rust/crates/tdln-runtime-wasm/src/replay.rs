@@ -0,0 +1,99 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use tdln_certified_runtime::{Card, CertifiedRuntime, ExecReport, LimitKind, RuntimeConfig};
+
+use crate::{cid_bytes, WasmCertifiedRuntime, WASMTIME_VERSION};
+
+/// Local content-addressed directory: `<root>/objects/<b3 hex>`, read back with hash verification.
+pub struct LocalObjects { pub root: PathBuf }
+
+impl LocalObjects {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self { Self { root: root.as_ref().into() } }
+
+    fn path_for(&self, cid: &str) -> Result<PathBuf> {
+        let hex = cid.strip_prefix("cid:b3:").ok_or_else(|| anyhow!("unsupported cid: {cid}"))?;
+        if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) { bail!("malformed cid: {cid}"); }
+        Ok(self.root.join("objects").join(hex))
+    }
+
+    pub fn put(&self, bytes: &[u8]) -> Result<String> {
+        let cid = cid_bytes(bytes);
+        let p = self.path_for(&cid)?;
+        if let Some(dir) = p.parent() { std::fs::create_dir_all(dir)?; }
+        if !p.exists() { std::fs::write(&p, bytes)?; }
+        Ok(cid)
+    }
+
+    pub fn get(&self, cid: &str) -> Result<Vec<u8>> {
+        let p = self.path_for(cid)?;
+        let bytes = std::fs::read(&p).map_err(|e| anyhow!("object {cid} not found at {}: {e}", p.display()))?;
+        let got = cid_bytes(&bytes);
+        if got != cid { bail!("object {cid} is corrupt (hashes to {got})"); }
+        Ok(bytes)
+    }
+}
+
+/// One field where the replay disagrees with the card.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Divergence { pub field: String, pub recorded: Value, pub replayed: Value }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub kind: String,
+    pub ok: bool,
+    pub unit_cid: String,
+    pub input_cid: String,
+    pub divergences: Vec<Divergence>,
+}
+
+fn step_cid(card: &Card, kind: &str) -> Result<String> {
+    card.proof.hash_chain.iter().find(|s| s.kind == kind).map(|s| s.cid.clone())
+        .ok_or_else(|| anyhow!("card hash_chain has no `{kind}` step"))
+}
+
+fn diff<T: Serialize + PartialEq>(out: &mut Vec<Divergence>, field: &str, recorded: &T, replayed: &T) {
+    if recorded != replayed {
+        out.push(Divergence { field: field.into(), recorded: json!(recorded), replayed: json!(replayed) });
+    }
+}
+
+/// Re-execute the unit behind `card` with its recorded config and compare the outcome bit for bit.
+///
+/// The wall clock is the one limit that does not reproduce, so the replay runs on fuel alone. A run the
+/// watchdog interrupted is replayed with exactly the fuel it had consumed: the guest stops at the same
+/// point, out of fuel, and that stands for the recorded `wall_clock` limit.
+pub fn replay(card: &Card, objects: &LocalObjects, rt: &WasmCertifiedRuntime) -> Result<ReplayReport> {
+    let eer = card.proof.eer.as_ref().ok_or_else(|| anyhow!("card has no EER; nothing to replay"))?;
+    let cfg: RuntimeConfig = serde_json::from_value(eer.get("config").cloned().unwrap_or(Value::Null))
+        .map_err(|e| anyhow!("EER config unreadable: {e}"))?;
+    let unit_cid = eer.pointer("/digests/unit_cid").and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("EER has no digests.unit_cid"))?.to_string();
+    let input_cid = step_cid(card, "input")?;
+    let recorded_report: ExecReport = serde_json::from_value(eer.get("report").cloned().unwrap_or(Value::Null)).unwrap_or_default();
+    let recorded_wasmtime = eer.pointer("/wasmtime/version").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+
+    let unit = objects.get(&unit_cid)?;
+    let input: Value = serde_json::from_slice(&objects.get(&input_cid)?)?;
+    let interrupted = recorded_report.limit_hit == Some(LimitKind::WallClock);
+    let replay_cfg = RuntimeConfig {
+        wall_clock_ms: u64::MAX,
+        fuel: if interrupted { recorded_report.fuel_consumed } else { cfg.fuel },
+        ..cfg.clone()
+    };
+    let again = rt.execute(&unit, &input, &replay_cfg)?;
+    let again_eer = again.proof.eer.clone().unwrap_or(Value::Null);
+    let mut again_report: ExecReport = serde_json::from_value(again_eer.get("report").cloned().unwrap_or(Value::Null)).unwrap_or_default();
+    if interrupted && again_report.limit_hit == Some(LimitKind::Fuel) { again_report.limit_hit = Some(LimitKind::WallClock); }
+
+    let mut divergences = Vec::new();
+    diff(&mut divergences, "wasmtime.version", &recorded_wasmtime, &WASMTIME_VERSION.to_string());
+    diff(&mut divergences, "decision", &card.decision, &again.decision);
+    diff(&mut divergences, "output_cid", &card.output_cid, &again.output_cid);
+    diff(&mut divergences, "report.fuel_consumed", &recorded_report.fuel_consumed, &again_report.fuel_consumed);
+    diff(&mut divergences, "report.peak_memory_bytes", &recorded_report.peak_memory_bytes, &again_report.peak_memory_bytes);
+    diff(&mut divergences, "report.limit_hit", &recorded_report.limit_hit, &again_report.limit_hit);
+
+    Ok(ReplayReport { kind: "replay.report.v1".into(), ok: divergences.is_empty(), unit_cid, input_cid, divergences })
+}
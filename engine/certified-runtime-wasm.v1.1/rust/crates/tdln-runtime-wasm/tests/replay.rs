@@ -0,0 +1,71 @@
+use serde_json::json;
+use tdln_certified_runtime::{CertifiedRuntime, Decision, LimitKind, RuntimeConfig};
+use tdln_runtime_wasm::replay::{replay, LocalObjects};
+use tdln_runtime_wasm::{cid_json, WasmCertifiedRuntime};
+
+/// Bump allocator shared by the guests below; `$body` is `_run_json`'s body.
+fn guest(body: &str) -> Vec<u8> {
+    wat::parse_str(format!(r#"(module
+        (memory (export "memory") 1)
+        (global $next (mut i32) (i32.const 1024))
+        (data (i32.const 16) "{{\"allow\":true}}")
+        (func (export "alloc") (param $len i32) (result i32)
+            (global.get $next)
+            (global.set $next (i32.add (global.get $next) (local.get $len))))
+        (func (export "_run_json") (param $ptr i32) (param $len i32) (result i32 i32) {body}))"#)).unwrap()
+}
+const ECHO: &str = "(local.get $ptr) (local.get $len)";
+const ALLOW: &str = "(i32.const 16) (i32.const 14)";
+const SPIN: &str = "(loop $l (br $l)) (unreachable)";
+
+fn rt() -> WasmCertifiedRuntime { WasmCertifiedRuntime { version: "test" } }
+
+fn violations(card: &tdln_certified_runtime::Card) -> serde_json::Value { card.poi.as_ref().unwrap()["violations"].clone() }
+
+#[test]
+fn the_guest_output_is_read_back_from_its_memory() {
+    let cfg = RuntimeConfig::new(1_000_000, 16);
+    let card = rt().execute(&guest(ALLOW), &json!({"amount": 5}), &cfg).unwrap();
+    assert_eq!((card.decision, card.output_cid), (Decision::Allow, cid_json(&json!({"allow": true}))));
+
+    let card = rt().execute(&guest(ECHO), &json!({"amount": 5}), &cfg).unwrap();
+    assert_eq!(card.output_cid, cid_json(&json!({"amount": 5})));
+
+    // Out-of-bounds output and a missing export are the guest's fault: ASK, not a host error.
+    let card = rt().execute(&guest("(i32.const 65530) (i32.const 100)"), &json!({}), &cfg).unwrap();
+    assert_eq!((card.decision, violations(&card)), (Decision::Doubt, json!(["guest_abi"])));
+    let card = rt().execute(&wat::parse_str(r#"(module (memory (export "memory") 1))"#).unwrap(), &json!({}), &cfg).unwrap();
+    assert_eq!(violations(&card), json!(["guest_abi"]));
+}
+
+#[test]
+fn replays_reproduce_runs_and_catch_tampering() {
+    let dir = tempfile::tempdir().unwrap();
+    let objects = LocalObjects::new(dir.path());
+    let (unit, input) = (guest(ECHO), json!({"amount": 5}));
+    objects.put(&unit).unwrap();
+    objects.put(&serde_json::to_vec(&input).unwrap()).unwrap();
+    let card = rt().execute(&unit, &input, &RuntimeConfig::new(1_000_000, 16)).unwrap();
+    let report = replay(&card, &objects, &rt()).unwrap();
+    assert!(report.ok, "{:?}", report.divergences);
+
+    let mut forged = card.clone();
+    forged.output_cid = cid_json(&json!({"amount": 6}));
+    let fields: Vec<_> = replay(&forged, &objects, &rt()).unwrap().divergences.into_iter().map(|d| d.field).collect();
+    assert_eq!(fields, ["output_cid"]);
+}
+
+#[test]
+fn wall_clock_interruptions_replay_on_the_fuel_they_used() {
+    let dir = tempfile::tempdir().unwrap();
+    let objects = LocalObjects::new(dir.path());
+    let unit = guest(SPIN);
+    objects.put(&unit).unwrap();
+    objects.put(b"{}").unwrap();
+    let cfg = RuntimeConfig { wall_clock_ms: 50, ..RuntimeConfig::new(u64::MAX / 2, 16) };
+    let card = rt().execute(&unit, &json!({}), &cfg).unwrap();
+    assert_eq!(violations(&card), json!([format!("limit_exceeded:{}", json!(LimitKind::WallClock).as_str().unwrap())]));
+
+    let report = replay(&card, &objects, &rt()).unwrap();
+    assert!(report.ok, "{:?}", report.divergences);
+}
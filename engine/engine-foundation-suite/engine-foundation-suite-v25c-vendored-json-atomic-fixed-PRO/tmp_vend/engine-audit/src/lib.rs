@@ -1,3 +1,4 @@
 
 pub mod report;
 pub mod sink_fs;
+pub mod review;
@@ -13,3 +13,27 @@ pub struct AuditReportV1 {
   pub proofs: serde_json::Value,
   pub receipt: ExecutionReceipt,
 }
+
+/// `audit.action.v1`: an action that is not itself an engine run (queueing a run for review,
+/// a reviewer's decision, ...): who did what to which run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditActionV1 {
+  pub kind: String, // "audit.action.v1"
+  pub audit_id: String,
+  pub ts: String,
+  pub actor: String,
+  /// Dotted action name (`review.enqueue`, `review.approve`, ...).
+  pub action: String,
+  /// Run CID the action is about.
+  pub subject: String,
+  pub proofs: serde_json::Value,
+}
+
+impl AuditActionV1 {
+  pub fn new(actor:&str, action:&str, subject:&str, proofs:serde_json::Value) -> Self {
+    Self{
+      kind: "audit.action.v1".into(), audit_id: ulid::Ulid::new().to_string(), ts: chrono::Utc::now().to_rfc3339(),
+      actor: actor.into(), action: action.into(), subject: subject.into(), proofs,
+    }
+  }
+}
@@ -0,0 +1,261 @@
+//! Human review of ASK runs.
+//!
+//! An ASK run (unless the caller opted out with `no_hitl`) opens a [`ReviewItem`] in a
+//! [`ReviewQueue`]. A reviewer answers it with a [`ReviewReceipt`] sealed by their own key
+//! (ADR-0001, same scheme as registry entries); the receipt names the run CID and the CID of the
+//! ASK receipt, and is stored under its own CID. `approve` and `deny` close the item and become its
+//! `override`; `request_info` keeps it open, and the final card is stored next to the item. Every
+//! queue action is written to the audit sink.
+use anyhow::{anyhow, bail, Context, Result};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use engine_auth::grant::GrantSeal;
+use engine_auth::signing::{sign_doc, verify_doc, verifying_key_b64, SEAL_ALG};
+use engine_core::json_atomic::compute_cid;
+use engine_core::model::ProofOfIndecision;
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::report::AuditActionV1;
+use crate::sink_fs::FsAudit;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewAction { Approve, Deny, RequestInfo }
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus { Pending, InfoRequested, Approved, Denied }
+impl ReviewStatus {
+  pub fn is_open(self) -> bool { matches!(self, Self::Pending | Self::InfoRequested) }
+}
+
+/// One ASK run waiting for (or settled by) a reviewer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewItem {
+  pub kind: String, // "review.item.v1"
+  pub run_cid: String,
+  pub unit_ref: String,
+  pub realm: String,
+  /// CID of the ASK execution receipt under review.
+  pub receipt_cid: String,
+  pub poi: ProofOfIndecision,
+  /// Card served for the ASK run; the final card is this one with the override applied.
+  #[serde(default)]
+  pub card: Value,
+  pub opened_at: String,
+  pub status: ReviewStatus,
+  /// CIDs of the review receipts for this run, oldest first.
+  #[serde(default)]
+  pub reviews: Vec<String>,
+  /// CID of the `approve`/`deny` receipt that closed the item.
+  #[serde(default, rename = "override", skip_serializing_if = "Option::is_none")]
+  pub override_cid: Option<String>,
+}
+
+impl ReviewItem {
+  pub fn new(run_cid:&str, unit_ref:&str, realm:&str, receipt_cid:&str, poi:ProofOfIndecision, card:Value) -> Self {
+    Self{
+      kind: "review.item.v1".into(), run_cid: run_cid.into(), unit_ref: unit_ref.into(), realm: realm.into(),
+      receipt_cid: receipt_cid.into(), poi, card, opened_at: chrono::Utc::now().to_rfc3339(),
+      status: ReviewStatus::Pending, reviews: vec![], override_cid: None,
+    }
+  }
+
+  /// The ASK card with the human decision in place and a link to the sealed override.
+  pub fn final_card(&self, review:&ReviewReceipt) -> Option<Value> {
+    let decision = match (self.status, &self.override_cid) {
+      (ReviewStatus::Approved, Some(_)) => "ACK",
+      (ReviewStatus::Denied, Some(_)) => "NACK",
+      _ => return None,
+    };
+    let mut card = if self.card.is_object() { self.card.clone() } else { json!({"kind": "receipt.card.v1", "run_cid": self.run_cid}) };
+    card["decision"] = json!(decision);
+    card["override"] = json!({
+      "review_cid": self.override_cid, "action": review.action, "reviewer": review.seal.kid,
+      "note": review.note, "ts": review.ts, "replaces": "ASK",
+    });
+    Some(card)
+  }
+}
+
+/// A reviewer's decision on one run, sealed by the reviewer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewReceipt {
+  pub kind: String, // "review.receipt.v1"
+  pub run_cid: String,
+  /// Must match the item's `receipt_cid`: the reviewer saw this ASK, not another run of the same manifest.
+  pub receipt_cid: String,
+  pub action: ReviewAction,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub note: Option<String>,
+  /// Fields the reviewer wants before deciding (`request_info`).
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub requested: Vec<String>,
+  pub ts: String,
+  pub seal: GrantSeal,
+}
+
+impl ReviewReceipt {
+  pub fn new(item:&ReviewItem, action:ReviewAction, note:Option<String>, requested:Vec<String>) -> Self {
+    Self{
+      kind: "review.receipt.v1".into(), run_cid: item.run_cid.clone(), receipt_cid: item.receipt_cid.clone(),
+      action, note, requested, ts: chrono::Utc::now().to_rfc3339(),
+      seal: GrantSeal{ alg: SEAL_ALG.into(), kid: String::new(), sig: String::new() },
+    }
+  }
+
+  pub fn sign(&mut self, key:&SigningKey, kid:&str) -> Result<()> {
+    self.seal = GrantSeal{ alg: SEAL_ALG.into(), kid: kid.into(), sig: String::new() };
+    self.seal.sig = sign_doc(key, self)?;
+    Ok(())
+  }
+
+  /// Seal valid and made by a known reviewer; returns the reviewer kid.
+  pub fn verify(&self, reviewers:&Reviewers) -> Result<String> {
+    if self.kind != "review.receipt.v1" { bail!("not a review.receipt.v1 document"); }
+    if self.seal.alg != SEAL_ALG { bail!("unsupported seal alg {}", self.seal.alg); }
+    let vk = reviewers.key(&self.seal.kid)?;
+    let mut unsigned = self.clone();
+    unsigned.seal.sig = String::new();
+    verify_doc(&vk, &unsigned, &self.seal.sig).context("review seal")?;
+    Ok(self.seal.kid.clone())
+  }
+
+  pub fn cid(&self) -> Result<String> { compute_cid(self) }
+}
+
+/// Who may review: kid → base64 ed25519 public key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reviewers {
+  pub kind: String, // "review.reviewers.v1"
+  pub keys: BTreeMap<String, String>,
+}
+
+impl Reviewers {
+  pub fn load(path:impl AsRef<Path>) -> Result<Self> {
+    let p = path.as_ref();
+    let r: Self = serde_json::from_slice(&std::fs::read(p).with_context(|| format!("read {}", p.display()))?)?;
+    if r.kind != "review.reviewers.v1" { bail!("{} is not a review.reviewers.v1 document", p.display()); }
+    Ok(r)
+  }
+  pub fn key(&self, kid:&str) -> Result<VerifyingKey> {
+    verifying_key_b64(self.keys.get(kid).ok_or_else(|| anyhow!("unknown reviewer {kid}"))?)
+  }
+}
+
+#[derive(Debug)]
+pub enum ReviewError {
+  NotFound(String),
+  /// Already approved or denied.
+  Closed(String),
+  /// Not a trusted reviewer, or the seal does not check out.
+  Forbidden(String),
+}
+impl std::fmt::Display for ReviewError {
+  fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::NotFound(run) => write!(f, "no review item for {run}"),
+      Self::Closed(run) => write!(f, "review of {run} is already closed"),
+      Self::Forbidden(why) => f.write_str(why),
+    }
+  }
+}
+impl std::error::Error for ReviewError {}
+
+/// `{dir}/items/<hex>.json` per run, `{dir}/receipts/<hex>.json` per review receipt and
+/// `{dir}/cards/<hex>.json` per decided run.
+pub struct ReviewQueue {
+  dir: PathBuf,
+  audit: FsAudit,
+  /// Serializes read-modify-write of items within this process.
+  lock: Mutex<()>,
+}
+
+fn file_name(cid:&str) -> Result<String> {
+  let hex = cid.strip_prefix("b3:").ok_or_else(|| anyhow!("not a b3 cid: {cid}"))?;
+  if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) { bail!("not a b3 cid: {cid}"); }
+  Ok(format!("{}.json", hex.to_ascii_lowercase()))
+}
+
+fn write_atomic<T: Serialize>(path:&Path, doc:&T) -> Result<()> {
+  std::fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+  let tmp = path.with_extension("json.tmp");
+  std::fs::write(&tmp, serde_json::to_vec_pretty(doc)?)?;
+  std::fs::rename(tmp, path)?;
+  Ok(())
+}
+
+fn read<T: for<'de> Deserialize<'de>>(path:&Path) -> Result<Option<T>> {
+  if !path.exists() { return Ok(None); }
+  Ok(Some(serde_json::from_slice(&std::fs::read(path)?)?))
+}
+
+impl ReviewQueue {
+  pub fn new(dir:impl Into<PathBuf>, audit:FsAudit) -> Self { Self{ dir: dir.into(), audit, lock: Mutex::new(()) } }
+
+  fn item_path(&self, run_cid:&str) -> Result<PathBuf> { Ok(self.dir.join("items").join(file_name(run_cid)?)) }
+
+  /// Open `item` on behalf of `actor`; `false` if the run is already queued (the same manifest ran again).
+  pub fn enqueue(&self, item:&ReviewItem, actor:&str) -> Result<bool> {
+    let _g = self.lock.lock().unwrap();
+    let path = self.item_path(&item.run_cid)?;
+    if path.exists() { return Ok(false); }
+    write_atomic(&path, item)?;
+    self.audit.emit_action(&AuditActionV1::new(actor, "review.enqueue", &item.run_cid,
+      json!({"receipt_cid": item.receipt_cid, "poi": item.poi})))?;
+    Ok(true)
+  }
+
+  pub fn get(&self, run_cid:&str) -> Result<Option<ReviewItem>> { read(&self.item_path(run_cid)?) }
+
+  pub fn receipt(&self, cid:&str) -> Result<Option<ReviewReceipt>> { read(&self.dir.join("receipts").join(file_name(cid)?)) }
+
+  /// Final card of a run a reviewer approved or denied.
+  pub fn card(&self, run_cid:&str) -> Result<Option<Value>> { read(&self.dir.join("cards").join(file_name(run_cid)?)) }
+
+  /// Items with `status` (all when `None`), oldest first.
+  pub fn list(&self, status:Option<ReviewStatus>) -> Result<Vec<ReviewItem>> {
+    let dir = self.dir.join("items");
+    if !dir.exists() { return Ok(vec![]); }
+    let mut items = vec![];
+    for e in std::fs::read_dir(dir)? {
+      let p = e?.path();
+      if p.extension().and_then(|x| x.to_str()) != Some("json") { continue; }
+      let item: ReviewItem = serde_json::from_slice(&std::fs::read(&p)?).with_context(|| p.display().to_string())?;
+      if status.map_or(true, |s| s == item.status) { items.push(item); }
+    }
+    items.sort_by(|a, b| a.opened_at.cmp(&b.opened_at).then_with(|| a.run_cid.cmp(&b.run_cid)));
+    Ok(items)
+  }
+
+  /// Check `review` against `reviewers` and the open item, store it by CID and apply it.
+  /// Errors are [`ReviewError`] where the caller can act on them.
+  pub fn decide(&self, review:&ReviewReceipt, reviewers:&Reviewers) -> Result<(ReviewItem, String)> {
+    let kid = review.verify(reviewers).map_err(|e| ReviewError::Forbidden(format!("{e:#}")))?;
+    let _g = self.lock.lock().unwrap();
+    let path = self.item_path(&review.run_cid)?;
+    let mut item: ReviewItem = read(&path)?.ok_or_else(|| ReviewError::NotFound(review.run_cid.clone()))?;
+    if !item.status.is_open() { return Err(ReviewError::Closed(item.run_cid).into()); }
+    if review.receipt_cid != item.receipt_cid {
+      bail!("review is for receipt {}, the item is {}", review.receipt_cid, item.receipt_cid);
+    }
+    let cid = review.cid()?;
+    write_atomic(&self.dir.join("receipts").join(file_name(&cid)?), review)?;
+    item.reviews.push(cid.clone());
+    item.status = match review.action {
+      ReviewAction::Approve => ReviewStatus::Approved,
+      ReviewAction::Deny => ReviewStatus::Denied,
+      ReviewAction::RequestInfo => ReviewStatus::InfoRequested,
+    };
+    if !item.status.is_open() { item.override_cid = Some(cid.clone()); }
+    write_atomic(&path, &item)?;
+    if let Some(card) = item.final_card(review) { write_atomic(&self.dir.join("cards").join(file_name(&item.run_cid)?), &card)?; }
+    let action = match review.action { ReviewAction::Approve => "review.approve", ReviewAction::Deny => "review.deny", ReviewAction::RequestInfo => "review.request_info" };
+    self.audit.emit_action(&AuditActionV1::new(&kid, action, &item.run_cid,
+      json!({"review_cid": cid, "receipt_cid": item.receipt_cid, "requested": review.requested})))?;
+    Ok((item, cid))
+  }
+}
@@ -1,17 +1,20 @@
 
 use anyhow::Result;
 use std::path::Path;
-use crate::report::AuditReportV1;
+use serde::Serialize;
+use crate::report::{AuditActionV1, AuditReportV1};
 
 pub struct FsAudit {
   pub dir: String
 }
 impl FsAudit {
   pub fn new<P: AsRef<Path>>(dir:P)->Self { Self{ dir: dir.as_ref().to_string_lossy().into() } }
-  pub fn emit(&self, a:&AuditReportV1) -> Result<()> {
+  pub fn emit(&self, a:&AuditReportV1) -> Result<()> { self.write(&a.kind, &a.audit_id, a) }
+  pub fn emit_action(&self, a:&AuditActionV1) -> Result<()> { self.write(&a.kind, &a.audit_id, a) }
+  fn write<T: Serialize>(&self, kind:&str, id:&str, doc:&T) -> Result<()> {
       std::fs::create_dir_all(&self.dir)?;
-      let path = format!("{}/{}_{}.json", self.dir, a.kind.replace(".","-"), a.audit_id);
-      std::fs::write(path, serde_json::to_string_pretty(a)?)?;
+      let path = format!("{}/{}_{}.json", self.dir, kind.replace(".","-"), id);
+      std::fs::write(path, serde_json::to_string_pretty(doc)?)?;
       Ok(())
   }
 }
@@ -0,0 +1,60 @@
+use base64::Engine as _;
+use ed25519_dalek::SigningKey;
+use engine_audit::review::*;
+use engine_audit::sink_fs::FsAudit;
+use engine_auth::signing::key_id;
+use engine_core::model::ProofOfIndecision;
+use rand_core::OsRng;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+fn reviewers(keys:&[&SigningKey]) -> Reviewers {
+    let keys = keys.iter().map(|k| (key_id(&k.verifying_key()), base64::engine::general_purpose::STANDARD.encode(k.verifying_key().as_bytes())));
+    Reviewers{ kind: "review.reviewers.v1".into(), keys: keys.collect::<BTreeMap<_, _>>() }
+}
+
+fn signed(item:&ReviewItem, key:&SigningKey, action:ReviewAction) -> ReviewReceipt {
+    let mut r = ReviewReceipt::new(item, action, Some("checked".into()), vec![]);
+    r.sign(key, &key_id(&key.verifying_key())).unwrap();
+    r
+}
+
+#[test]
+fn sealed_decisions_close_the_item_and_are_audited() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let q = ReviewQueue::new(dir.join("reviews"), FsAudit::new(dir.join("audit")));
+    let run = format!("b3:{}", "a".repeat(64));
+    let card = json!({"kind": "receipt.card.v1", "run_cid": run, "decision": "ASK"});
+    let item = ReviewItem::new(&run, "quota@stable", "trust", "b3:receipt", ProofOfIndecision::missing(&["actor.role"]), card);
+    assert!(q.enqueue(&item, "acme/alice").unwrap());
+    assert!(!q.enqueue(&item, "acme/alice").unwrap());
+
+    let (reviewer, stranger) = (SigningKey::generate(&mut OsRng), SigningKey::generate(&mut OsRng));
+    let trusted = reviewers(&[&reviewer]);
+    let e = q.decide(&signed(&item, &stranger, ReviewAction::Approve), &trusted).unwrap_err();
+    assert!(matches!(e.downcast_ref(), Some(ReviewError::Forbidden(_))), "{e}");
+    let mut forged = signed(&item, &reviewer, ReviewAction::Deny);
+    forged.action = ReviewAction::Approve;
+    assert!(matches!(q.decide(&forged, &trusted).unwrap_err().downcast_ref(), Some(ReviewError::Forbidden(_))));
+
+    let (open, _) = q.decide(&signed(&item, &reviewer, ReviewAction::RequestInfo), &trusted).unwrap();
+    assert_eq!(open.status, ReviewStatus::InfoRequested);
+    assert_eq!(q.list(Some(ReviewStatus::InfoRequested)).unwrap().len(), 1);
+
+    let approve = signed(&item, &reviewer, ReviewAction::Approve);
+    let (closed, cid) = q.decide(&approve, &trusted).unwrap();
+    assert_eq!((closed.status, closed.reviews.len()), (ReviewStatus::Approved, 2));
+    assert_eq!(closed.override_cid.as_deref(), Some(cid.as_str()));
+    assert_eq!(q.receipt(&cid).unwrap().unwrap().seal.sig, approve.seal.sig);
+    let card = closed.final_card(&approve).unwrap();
+    assert_eq!((card["decision"].as_str(), card["override"]["review_cid"].as_str()), (Some("ACK"), Some(cid.as_str())));
+    assert_eq!(q.card(&run).unwrap(), Some(card));
+
+    let e = q.decide(&signed(&item, &reviewer, ReviewAction::Deny), &trusted).unwrap_err();
+    assert!(matches!(e.downcast_ref(), Some(ReviewError::Closed(_))), "{e}");
+    // enqueue, request_info, approve
+    let audit: Vec<_> = std::fs::read_dir(dir.join("audit")).unwrap().map(|e| e.unwrap().file_name().into_string().unwrap()).collect();
+    assert_eq!(audit.len(), 3);
+    assert!(audit.iter().all(|f| f.starts_with("audit-action-v1_")), "{audit:?}");
+}
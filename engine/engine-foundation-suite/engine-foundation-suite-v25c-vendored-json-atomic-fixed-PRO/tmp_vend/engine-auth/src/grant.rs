@@ -1,6 +1,14 @@
 
 use serde::{Serialize, Deserialize};
 
+pub const GRANT_KIND: &str = "access.grant.v1";
+
+/// `constraints.ip_hash` of a client address, keyed with a secret of the engine: without the key
+/// the hash cannot be matched against candidate addresses.
+pub fn ip_hash(key: &[u8; 32], ip: &str) -> String {
+    format!("iphash:{}", blake3::keyed_hash(key, ip.trim().as_bytes()).to_hex())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccessGrant {
     pub kind: String,                 // "access.grant.v1"
@@ -11,9 +19,21 @@ pub struct AccessGrant {
     pub exp: String,                  // RFC3339
     pub iat: String,                  // RFC3339
     pub nonce: String,
+    /// Decision the grant was issued on; absent on grants that predate it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<GrantPolicy>,
     pub seal: GrantSeal,
 }
 
+/// Links a grant to the ACK receipt of the policy unit that allowed it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrantPolicy {
+    pub unit: String,                 // chip id of the policy unit
+    pub unit_cid: String,             // b3: CID of its spec (receipt.chip_hash)
+    pub run_cid: String,              // run the decision was recorded under (`/sirp/:cid` refs)
+    pub receipt_cid: String,          // b3: CID of the ExecutionReceipt
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GrantResource {
     pub store: String,                // e.g., "S3Compatible"
@@ -26,8 +46,8 @@ pub struct GrantResource {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GrantConstraints {
-    pub ip_hash: Option<String>,
-    pub byte_range_max: Option<u64>,
+    pub ip_hash: Option<String>,      // ip_hash(client address)
+    pub byte_range_max: Option<u64>,  // bytes per request; the request must carry a bounded `Range`
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,124 @@
+//! Grant Revocation List (`grant.revocations.v1`).
+//!
+//! Every revocation takes the next `serial`, so a list only grows and a consumer that has seen
+//! serial `n` can ask for the delta `since = n`. A list, full or delta, is sealed as in ADR-0001 by
+//! its issuer (`issuer` is the key id). [`Grl::merge`] takes an upstream list only when its seal
+//! verifies against a trusted key and it neither skips serials nor goes back to an older one;
+//! merged entries are renumbered in the local sequence, and the result is re-sealed with [`Grl::sign`].
+use anyhow::{bail, Result};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::grant::GrantSeal;
+use crate::signing::{key_id, sign_doc, verify_doc, SEAL_ALG};
+
+pub const GRL_KIND: &str = "grant.revocations.v1";
+
+fn now() -> String { chrono::Utc::now().to_rfc3339() }
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Revocation {
+    pub grant_id: String,
+    /// Position in the issuer's sequence.
+    pub serial: u64,
+    pub revoked_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Grl {
+    pub kind: String,                      // "grant.revocations.v1"
+    /// `signing::key_id` of the key that sealed the list; set by [`Grl::sign`].
+    pub issuer: String,
+    /// Highest serial issued so far.
+    pub serial: u64,
+    /// Entries with `serial > since` only; 0 for the full list.
+    #[serde(default)]
+    pub since: u64,
+    pub updated_at: String,
+    pub entries: Vec<Revocation>,
+    /// Upstream issuer → highest serial merged from it.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub upstream: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seal: Option<GrantSeal>,
+}
+
+impl Default for Grl {
+    fn default() -> Self {
+        Self{ kind: GRL_KIND.into(), issuer: String::new(), serial: 0, since: 0, updated_at: now(), entries: vec![], upstream: BTreeMap::new(), seal: None }
+    }
+}
+
+impl Grl {
+    pub fn is_revoked(&self, grant_id:&str) -> bool { self.entries.iter().any(|r| r.grant_id == grant_id) }
+
+    fn push(&mut self, grant_id:&str, revoked_at:String, reason:Option<String>) {
+        self.serial += 1;
+        self.entries.push(Revocation{ grant_id: grant_id.into(), serial: self.serial, revoked_at, reason });
+        self.updated_at = now();
+        self.seal = None;
+    }
+
+    /// Revokes `grant_id` under the next serial; `false` when it already was. Unseals the list.
+    pub fn revoke(&mut self, grant_id:&str, reason:Option<String>) -> bool {
+        if self.is_revoked(grant_id) { return false; }
+        self.push(grant_id, now(), reason);
+        true
+    }
+
+    /// Entries after serial `since`, unsealed. A consumer claiming more than was issued gets the full
+    /// list, so its own [`Grl::merge`] reports the rollback.
+    pub fn delta(&self, since:u64) -> Grl {
+        let since = if since > self.serial { 0 } else { since };
+        Grl{
+            kind: GRL_KIND.into(), issuer: self.issuer.clone(), serial: self.serial, since, updated_at: self.updated_at.clone(),
+            entries: self.entries.iter().filter(|r| r.serial > since).cloned().collect(), upstream: BTreeMap::new(), seal: None,
+        }
+    }
+
+    pub fn sign(&mut self, key:&SigningKey) -> Result<()> {
+        self.issuer = key_id(&key.verifying_key());
+        self.seal = None;
+        let sig = sign_doc(key, self)?;
+        self.seal = Some(GrantSeal{ alg: SEAL_ALG.into(), kid: self.issuer.clone(), sig });
+        Ok(())
+    }
+
+    /// The seal must be [`SEAL_ALG`] by the issuer's key, found among `trusted` by key id.
+    pub fn verify(&self, trusted:&[VerifyingKey]) -> Result<()> {
+        let Some(seal) = &self.seal else { bail!("revocation list is not sealed") };
+        if self.kind != GRL_KIND { bail!("not a {GRL_KIND} document: {}", self.kind); }
+        if seal.alg != SEAL_ALG { bail!("unsupported seal alg {}", seal.alg); }
+        if seal.kid != self.issuer { bail!("sealed by {}, issued by {}", seal.kid, self.issuer); }
+        let Some(vk) = trusted.iter().find(|vk| key_id(vk) == seal.kid) else { bail!("untrusted issuer {}", seal.kid) };
+        let mut unsigned = self.clone();
+        unsigned.seal = None;
+        verify_doc(vk, &unsigned, &seal.sig)
+    }
+
+    /// Verifies `other` and adds the grants it revokes that this list lacks; returns how many.
+    /// Fails, leaving `self` untouched, when `other` is not sealed by a `trusted` key, is this list's own,
+    /// starts after the last serial merged from its issuer, or is older than it.
+    pub fn merge(&mut self, other:&Grl, trusted:&[VerifyingKey]) -> Result<usize> {
+        other.verify(trusted)?;
+        if other.issuer == self.issuer { bail!("refusing to merge a list into itself"); }
+        let seen = self.upstream.get(&other.issuer).copied().unwrap_or(0);
+        if other.since > seen { bail!("{} delta starts after serial {}, only {seen} merged; fetch the full list", other.issuer, other.since); }
+        if other.serial < seen { bail!("{} went back from serial {seen} to {}", other.issuer, other.serial); }
+        if let Some(r) = other.entries.iter().find(|r| r.serial <= other.since || r.serial > other.serial) {
+            bail!("{} entry {} has serial {} outside ({}, {}]", other.issuer, r.grant_id, r.serial, other.since, other.serial);
+        }
+        let mut added = 0;
+        for r in &other.entries {
+            if self.is_revoked(&r.grant_id) { continue; }
+            self.push(&r.grant_id, r.revoked_at.clone(), r.reason.clone());
+            added += 1;
+        }
+        self.upstream.insert(other.issuer.clone(), other.serial);
+        self.seal = None;
+        Ok(added)
+    }
+}
@@ -1,3 +1,9 @@
 pub mod grant;
 
+pub mod grl;
+
+pub mod paseto;
+
 pub mod signing;
+
+pub mod verifier;
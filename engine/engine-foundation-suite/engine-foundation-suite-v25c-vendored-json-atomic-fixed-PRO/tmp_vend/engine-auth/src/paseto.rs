@@ -0,0 +1,107 @@
+//! PASETO `v4.public` tokens for access grants.
+//!
+//! [`sign`]/[`verify`] follow the PASETO v4 spec: Ed25519 over `PAE("v4.public.", m, f, i)`, where the
+//! footer `f` travels in the token and the implicit assertion `i` does not. A grant token carries the
+//! grant as JSON✯Atomic with `seal.alg` = [`ALG`], footer `{"kid":…}`, and `access.grant.v1` as implicit
+//! assertion, so any conformant library can check it.
+//!
+//! The engine's earlier flavor — `v4.public.` + b64url(json ‖ ed25519(blake3(json))), no PAE, no footer —
+//! is still read by [`open_legacy_grant`] during the deprecation window; grants read that way report
+//! `seal.alg` = [`LEGACY_ALG`].
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as B64URL;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::grant::{AccessGrant, GrantSeal, GRANT_KIND};
+use crate::signing::key_id;
+
+pub const HEADER: &str = "v4.public.";
+/// `seal.alg` of grants carried by a spec token.
+pub const ALG: &str = "paseto-v4-public";
+/// `seal.alg` of grants carried by a legacy (blake3) token.
+pub const LEGACY_ALG: &str = "paseto-v4-blake3";
+
+/// Pre-Authentication Encoding: LE64(count) then LE64(len) ‖ bytes for each piece.
+pub fn pae(pieces:&[&[u8]]) -> Vec<u8> {
+    let le64 = |n:usize| ((n as u64) & (u64::MAX >> 1)).to_le_bytes();
+    let mut out = le64(pieces.len()).to_vec();
+    for p in pieces { out.extend_from_slice(&le64(p.len())); out.extend_from_slice(p); }
+    out
+}
+
+pub fn sign(key:&SigningKey, payload:&[u8], footer:&[u8], implicit:&[u8]) -> String {
+    let sig = key.sign(&pae(&[HEADER.as_bytes(), payload, footer, implicit]));
+    let mut token = format!("{HEADER}{}", B64URL.encode([payload, sig.to_bytes().as_slice()].concat()));
+    if !footer.is_empty() { token.push('.'); token.push_str(&B64URL.encode(footer)); }
+    token
+}
+
+/// Splits a token into (payload ‖ signature, footer), unverified.
+fn parts(token:&str) -> Result<(Vec<u8>, Vec<u8>)> {
+    let Some(rest) = token.strip_prefix(HEADER) else { bail!("not a {HEADER} token") };
+    let (body, footer) = match rest.split_once('.') { Some((b, f)) => (b, B64URL.decode(f).context("footer is not base64url")?), None => (rest, vec![]) };
+    let body = B64URL.decode(body).context("body is not base64url")?;
+    if body.len() < 64 { bail!("token too short"); }
+    Ok((body, footer))
+}
+
+/// The token's footer, before verification (to pick the key).
+pub fn footer(token:&str) -> Result<Vec<u8>> { parts(token).map(|(_, f)| f) }
+
+/// Checks the signature; returns the payload.
+pub fn verify(vk:&VerifyingKey, token:&str, implicit:&[u8]) -> Result<Vec<u8>> {
+    let (body, footer) = parts(token)?;
+    let (payload, sig) = body.split_at(body.len() - 64);
+    let sig = Signature::from_slice(sig)?;
+    vk.verify(&pae(&[HEADER.as_bytes(), payload, &footer, implicit]), &sig).context("bad token signature")?;
+    Ok(payload.to_vec())
+}
+
+#[derive(Serialize, Deserialize)]
+struct Footer { kid: String }
+
+/// `kid` from the footer of a spec grant token; `None` for tokens without one (legacy).
+pub fn footer_kid(token:&str) -> Result<Option<String>> {
+    let f = footer(token)?;
+    if f.is_empty() { return Ok(None); }
+    Ok(Some(serde_json::from_slice::<Footer>(&f).context("footer is not {\"kid\":…}")?.kid))
+}
+
+/// The grant a token carries, unverified (to name it before opening the token).
+pub fn peek_grant(token:&str) -> Result<AccessGrant> {
+    let (body, _) = parts(token)?;
+    serde_json::from_slice(&body[..body.len() - 64]).context("payload is not a grant")
+}
+
+/// Spec token for `grant`; its `seal` becomes `{alg: ALG, kid, sig: ""}` (the token is the signature).
+pub fn sign_grant(key:&SigningKey, grant:&AccessGrant) -> Result<String> {
+    let kid = key_id(&key.verifying_key());
+    let mut g = grant.clone();
+    g.seal = GrantSeal{ alg: ALG.into(), kid: kid.clone(), sig: String::new() };
+    let payload = engine_core::json_atomic::to_json_atomic_bytes(&g).context("canonize grant")?;
+    let footer = engine_core::json_atomic::to_json_atomic_bytes(&Footer{ kid }).context("canonize footer")?;
+    Ok(sign(key, &payload, &footer, GRANT_KIND.as_bytes()))
+}
+
+/// Inverse of [`sign_grant`], `vk` being the key named by [`footer_kid`].
+pub fn open_grant(vk:&VerifyingKey, token:&str) -> Result<AccessGrant> {
+    let kid = footer_kid(token)?.context("token has no kid footer")?;
+    let grant: AccessGrant = serde_json::from_slice(&verify(vk, token, GRANT_KIND.as_bytes())?).context("payload is not a grant")?;
+    if grant.seal.alg != ALG || grant.seal.kid != kid { bail!("payload seal {}/{} does not match token {ALG}/{kid}", grant.seal.alg, grant.seal.kid); }
+    Ok(grant)
+}
+
+/// Legacy token: b64url(json ‖ ed25519(blake3(json))). It names no key, so each of `trusted` is tried.
+pub fn open_legacy_grant(trusted:&[VerifyingKey], token:&str) -> Result<AccessGrant> {
+    let (body, footer) = parts(token)?;
+    if !footer.is_empty() { bail!("legacy tokens have no footer"); }
+    let (msg, sig) = body.split_at(body.len() - 64);
+    let sig = Signature::from_slice(sig)?;
+    let digest = blake3::hash(msg);
+    if !trusted.iter().any(|vk| vk.verify(digest.as_bytes(), &sig).is_ok()) { bail!("bad legacy token signature"); }
+    let mut grant: AccessGrant = serde_json::from_slice(msg).context("payload is not a grant")?;
+    grant.seal.alg = LEGACY_ALG.into();
+    Ok(grant)
+}
@@ -1,9 +1,41 @@
-use anyhow::{Result, Context};
-use ed25519_dalek::{SigningKey, Signature, Signer};
+use anyhow::{Result, Context, bail};
+use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer, Verifier};
 use base64::engine::general_purpose::STANDARD as B64;
 use base64::Engine;
+use serde::Serialize;
 
-use crate::grant::AccessGrant;
+use crate::grant::{AccessGrant, GrantSeal};
+
+/// `seal.alg` for the ADR-0001 scheme.
+pub const SEAL_ALG: &str = "ed25519-blake3";
+
+/// Stable key id: `ed25519:` + first 16 hex chars of blake3(public key).
+pub fn key_id(vk: &VerifyingKey) -> String {
+    format!("ed25519:{}", &blake3::hash(vk.as_bytes()).to_hex()[..16])
+}
+
+/// ADR-0001 over any document: base64(ed25519(blake3(JSON✯Atomic(doc)))).
+/// The caller is responsible for blanking the document's own signature field first.
+pub fn sign_doc<T: Serialize>(signing_key: &SigningKey, doc: &T) -> Result<String> {
+    let msg = engine_core::json_atomic::to_json_atomic_bytes(doc).context("canonize document")?;
+    let digest = blake3::hash(&msg);
+    let sig: Signature = signing_key.sign(digest.as_bytes());
+    Ok(B64.encode(sig.to_bytes()))
+}
+
+/// Inverse of [`sign_doc`].
+pub fn verify_doc<T: Serialize>(vk: &VerifyingKey, doc: &T, sig_b64: &str) -> Result<()> {
+    let raw = B64.decode(sig_b64).context("signature is not base64")?;
+    let sig = Signature::from_slice(&raw).context("signature length")?;
+    let msg = engine_core::json_atomic::to_json_atomic_bytes(doc).context("canonize document")?;
+    vk.verify(blake3::hash(&msg).as_bytes(), &sig).context("bad signature")
+}
+
+/// Decode a base64 ed25519 public key (as found in trust policies).
+pub fn verifying_key_b64(b64: &str) -> Result<VerifyingKey> {
+    let raw: [u8; 32] = B64.decode(b64).context("key is not base64")?.try_into().map_err(|_| anyhow::anyhow!("ed25519 key must be 32 bytes"))?;
+    Ok(VerifyingKey::from_bytes(&raw)?)
+}
 
 /// Sign an AccessGrant as per ADR-0001 flavor:
 /// - set seal.sig = "" before hashing
@@ -14,12 +46,14 @@ use crate::grant::AccessGrant;
 pub fn sign_grant(signing_key: &SigningKey, grant: &mut AccessGrant) -> Result<()> {
     // ensure we don't self-sign over an existing signature
     grant.seal.sig = String::new();
-
-    let msg = engine_core::json_atomic::to_json_atomic_bytes(grant)
-        .context("canonize grant")?;
-
-    let digest = blake3::hash(&msg);
-    let sig: Signature = signing_key.sign(digest.as_bytes());
-    grant.seal.sig = B64.encode(sig.to_bytes());
+    grant.seal.sig = sign_doc(signing_key, grant).context("sign grant")?;
     Ok(())
 }
+
+/// Check `grant.seal` against `vk` (alg must be [`SEAL_ALG`]).
+pub fn verify_grant(vk: &VerifyingKey, grant: &AccessGrant) -> Result<()> {
+    let mut unsigned = grant.clone();
+    let seal = std::mem::replace(&mut unsigned.seal, GrantSeal{ sig: String::new(), ..grant.seal.clone() });
+    if seal.alg != SEAL_ALG { bail!("unsupported seal alg {}", seal.alg); }
+    verify_doc(vk, &unsigned, &seal.sig)
+}
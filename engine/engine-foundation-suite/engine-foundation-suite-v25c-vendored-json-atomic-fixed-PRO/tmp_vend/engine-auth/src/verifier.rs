@@ -0,0 +1,196 @@
+//! [`GrantVerifier`]: everything an `access.grant.v1` promises, checked where the grant is used.
+//!
+//! In order: the seal (key picked by `seal.kid` among the trusted ones), `kind`, the validity window
+//! (`iat`..`exp`, both widened by the clock skew), the resource (`bucket`, `prefix`, `object`,
+//! `verbs`), `tenants`, the constraints (`ip_hash` under the verifier's [`GrantVerifier::with_ip_key`],
+//! and `byte_range_max` against the `Range` header),
+//! and last the nonce. A nonce is burnt only once everything else passed, so a refused request does not
+//! use up the grant; it is remembered until the grant expires, in memory, so single use holds per process.
+//!
+//! Grants arrive as JSON (checked by `seal`) or as PASETO tokens ([`GrantVerifier::verify_token`]):
+//! spec `v4.public` tokens with a `kid` footer, and legacy blake3 tokens until [`LEGACY_TOKENS_UNTIL`].
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::VerifyingKey;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::grant::{ip_hash, AccessGrant, GRANT_KIND};
+use crate::paseto;
+use crate::signing::{key_id, verify_grant};
+
+/// Default tolerance between the issuer's clock and ours.
+pub const DEFAULT_SKEW_SECS: i64 = 30;
+
+/// End of the deprecation window for legacy (`ed25519(blake3(json))`) PASETO tokens, by default.
+pub const LEGACY_TOKENS_UNTIL: &str = "2027-04-01T00:00:00Z";
+
+/// One use of a grant, as the server sees it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Access<'a> {
+    pub verb: &'a str,
+    pub bucket: &'a str,
+    pub key: &'a str,
+    /// Tenant the caller authenticated as.
+    pub tenant: Option<&'a str>,
+    pub client_ip: Option<&'a str>,
+    /// Raw `Range` header.
+    pub range: Option<&'a str>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Denied {
+    /// Missing, from an untrusted key, or not matching the grant.
+    Seal(String),
+    /// Wrong `kind` or unreadable timestamps.
+    Malformed(String),
+    Expired,
+    NotYetValid,
+    /// The request is outside what the grant covers (resource, verb, tenant, client).
+    Scope(String),
+    /// `Range` missing, unbounded or larger than `byte_range_max`.
+    Range(String),
+    /// The nonce was already used.
+    Replayed,
+}
+impl std::fmt::Display for Denied {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Seal(why) => write!(f, "grant seal: {why}"),
+            Self::Malformed(why) | Self::Scope(why) | Self::Range(why) => f.write_str(why),
+            Self::Expired => f.write_str("grant expired"),
+            Self::NotYetValid => f.write_str("grant not yet valid"),
+            Self::Replayed => f.write_str("grant nonce already used"),
+        }
+    }
+}
+impl std::error::Error for Denied {}
+
+/// One `Range` spec: `a-b`, `a-` or `-n` (the last `n` bytes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange { FromTo(u64, u64), From(u64), Last(u64) }
+
+impl ByteRange {
+    /// Bytes the spec asks for, when bounded (`bytes=0-18446744073709551615` is not).
+    pub fn bytes(&self) -> Option<u64> {
+        match *self { Self::FromTo(a, b) => (b - a).checked_add(1), Self::From(_) => None, Self::Last(n) => Some(n) }
+    }
+
+    /// First and last byte selected from `len` bytes; `None` when it selects nothing.
+    pub fn resolve(&self, len:u64) -> Option<(u64, u64)> {
+        if len == 0 { return None; }
+        match *self {
+            Self::FromTo(a, b) => (a < len).then(|| (a, b.min(len - 1))),
+            Self::From(a) => (a < len).then(|| (a, len - 1)),
+            Self::Last(n) => Some((len - n.min(len), len - 1)),
+        }
+    }
+}
+
+/// Parses `bytes=<spec>[,<spec>…]`; `None` when malformed.
+pub fn parse_range(header:&str) -> Option<Vec<ByteRange>> {
+    let specs = header.trim().strip_prefix("bytes=")?;
+    specs.split(',').map(|s| {
+        let (a, b) = s.trim().split_once('-')?;
+        match (a.trim(), b.trim()) {
+            ("", n) => n.parse().ok().filter(|n| *n > 0).map(ByteRange::Last),
+            (a, "") => a.parse().ok().map(ByteRange::From),
+            (a, b) => { let (a, b) = (a.parse().ok()?, b.parse().ok()?); (a <= b).then_some(ByteRange::FromTo(a, b)) }
+        }
+    }).collect()
+}
+
+fn time(field:&str, v:&str) -> Result<DateTime<Utc>, Denied> {
+    DateTime::parse_from_rfc3339(v).map(|t| t.with_timezone(&Utc)).map_err(|_| Denied::Malformed(format!("grant {field} is not RFC 3339: {v}")))
+}
+
+pub struct GrantVerifier {
+    keys: BTreeMap<String, VerifyingKey>,
+    skew: Duration,
+    /// Legacy tokens are refused from then on (`None`: always).
+    legacy_until: Option<DateTime<Utc>>,
+    /// Key of `ip_hash` constraints; without one they are never met.
+    ip_key: Option<[u8; 32]>,
+    /// Used nonce → expiry of its grant.
+    used: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl GrantVerifier {
+    pub fn new(trusted:impl IntoIterator<Item=VerifyingKey>) -> Self {
+        Self{
+            keys: trusted.into_iter().map(|vk| (key_id(&vk), vk)).collect(), skew: Duration::seconds(DEFAULT_SKEW_SECS),
+            legacy_until: DateTime::parse_from_rfc3339(LEGACY_TOKENS_UNTIL).ok().map(|t| t.with_timezone(&Utc)), ip_key: None, used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_skew(mut self, skew:Duration) -> Self { self.skew = skew; self }
+
+    pub fn with_legacy_until(mut self, until:Option<DateTime<Utc>>) -> Self { self.legacy_until = until; self }
+
+    /// The key `ip_hash` constraints were issued with (see [`ip_hash`]).
+    pub fn with_ip_key(mut self, key:[u8; 32]) -> Self { self.ip_key = Some(key); self }
+
+    fn key(&self, kid:&str) -> Result<&VerifyingKey, Denied> {
+        self.keys.get(kid).ok_or_else(|| Denied::Seal(format!("untrusted key {kid}")))
+    }
+
+    pub fn verify(&self, grant:&AccessGrant, access:&Access<'_>) -> Result<(), Denied> { self.verify_at(grant, access, Utc::now()) }
+
+    pub fn verify_at(&self, grant:&AccessGrant, access:&Access<'_>, now:DateTime<Utc>) -> Result<(), Denied> {
+        verify_grant(self.key(&grant.seal.kid)?, grant).map_err(|e| Denied::Seal(format!("{e:#}")))?;
+        self.admit(grant, access, now)
+    }
+
+    /// A PASETO grant token; returns the grant, whose `seal.alg` tells the token format.
+    pub fn verify_token(&self, token:&str, access:&Access<'_>) -> Result<AccessGrant, Denied> { self.verify_token_at(token, access, Utc::now()) }
+
+    pub fn verify_token_at(&self, token:&str, access:&Access<'_>, now:DateTime<Utc>) -> Result<AccessGrant, Denied> {
+        let seal = |e:anyhow::Error| Denied::Seal(format!("{e:#}"));
+        let grant = match paseto::footer_kid(token).map_err(seal)? {
+            Some(kid) => paseto::open_grant(self.key(&kid)?, token).map_err(seal)?,
+            None if self.legacy_until.is_some_and(|t| now < t) => {
+                paseto::open_legacy_grant(&self.keys.values().copied().collect::<Vec<_>>(), token).map_err(seal)?
+            }
+            None => return Err(Denied::Seal("legacy tokens are no longer accepted".into())),
+        };
+        self.admit(&grant, access, now)?;
+        Ok(grant)
+    }
+
+    /// Everything but the seal.
+    fn admit(&self, grant:&AccessGrant, access:&Access<'_>, now:DateTime<Utc>) -> Result<(), Denied> {
+        if grant.kind != GRANT_KIND { return Err(Denied::Malformed(format!("not a {GRANT_KIND}: {}", grant.kind))); }
+        if grant.nonce.is_empty() { return Err(Denied::Malformed("grant has no nonce".into())); }
+        let (iat, exp) = (time("iat", &grant.iat)?, time("exp", &grant.exp)?);
+        if now < iat - self.skew { return Err(Denied::NotYetValid); }
+        if now > exp + self.skew { return Err(Denied::Expired); }
+
+        let r = &grant.resource;
+        if r.bucket != access.bucket { return Err(Denied::Scope(format!("bucket {} is not granted", access.bucket))); }
+        if !access.key.starts_with(&r.prefix) || access.key.split('/').any(|seg| seg == "..") {
+            return Err(Denied::Scope(format!("key {} is outside prefix {:?}", access.key, r.prefix)));
+        }
+        if r.object.as_deref().is_some_and(|o| o != access.key) { return Err(Denied::Scope(format!("key {} is not the granted object", access.key))); }
+        if !r.verbs.iter().any(|v| v.eq_ignore_ascii_case(access.verb)) { return Err(Denied::Scope(format!("verb {} is not granted", access.verb))); }
+        if !grant.tenants.is_empty() && !access.tenant.is_some_and(|t| grant.tenants.iter().any(|g| g == t)) {
+            return Err(Denied::Scope(format!("tenant {} is not granted", access.tenant.unwrap_or("(anonymous)"))));
+        }
+        let c = r.constraints.as_ref();
+        if let Some(h) = c.and_then(|c| c.ip_hash.as_deref()) {
+            let seen = self.ip_key.as_ref().zip(access.client_ip).map(|(k, ip)| ip_hash(k, ip));
+            if seen.as_deref() != Some(h) { return Err(Denied::Scope("client address does not match the grant".into())); }
+        }
+        if let Some(max) = c.and_then(|c| c.byte_range_max) {
+            let header = access.range.ok_or_else(|| Denied::Range(format!("Range required (at most {max} bytes)")))?;
+            let specs = parse_range(header).ok_or_else(|| Denied::Range(format!("bad Range {header}")))?;
+            let total = specs.iter().try_fold(0u64, |n, s| s.bytes().map(|l| n.saturating_add(l)))
+                .ok_or_else(|| Denied::Range(format!("unbounded Range {header} (at most {max} bytes)")))?;
+            if total > max { return Err(Denied::Range(format!("Range asks for {total} bytes, at most {max}"))); }
+        }
+
+        let mut used = self.used.lock().unwrap();
+        used.retain(|_, until| *until + self.skew >= now);
+        if used.contains_key(&grant.nonce) { return Err(Denied::Replayed); }
+        used.insert(grant.nonce.clone(), exp);
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use ed25519_dalek::SigningKey;
+use engine_auth::grl::Grl;
+
+fn key(n:u8) -> SigningKey { SigningKey::from_bytes(&[n; 32]) }
+
+#[test]
+fn serials_grow_and_deltas_carry_only_what_is_new() {
+    let k = key(1);
+    let mut grl = Grl::default();
+    assert!(grl.revoke("g1", None));
+    assert!(!grl.revoke("g1", None));
+    grl.revoke("g2", Some("leaked".into()));
+    grl.sign(&k).unwrap();
+    grl.verify(&[k.verifying_key()]).unwrap();
+    assert_eq!(grl.serial, 2);
+
+    let mut d = grl.delta(1);
+    d.sign(&k).unwrap();
+    assert_eq!((d.since, d.entries.len(), d.entries[0].grant_id.as_str()), (1, 1, "g2"));
+    assert_eq!(grl.delta(9).since, 0);
+
+    grl.entries[0].grant_id = "g9".into();
+    assert!(grl.verify(&[k.verifying_key()]).is_err());
+    assert!(grl.verify(&[key(2).verifying_key()]).is_err());
+}
+
+#[test]
+fn merges_verify_connect_and_never_go_back() {
+    let (up_key, local_key) = (key(1), key(2));
+    let trusted = [up_key.verifying_key()];
+    let mut up = Grl::default();
+    up.revoke("g1", None);
+    up.sign(&up_key).unwrap();
+
+    let mut local = Grl::default();
+    local.revoke("mine", None);
+    local.sign(&local_key).unwrap();
+    assert_eq!(local.merge(&up, &trusted).unwrap(), 1);
+    local.sign(&local_key).unwrap();
+    local.verify(&[local_key.verifying_key()]).unwrap();
+    assert!(local.is_revoked("g1"));
+    assert_eq!((local.serial, local.upstream[&up.issuer]), (2, 1));
+
+    // Untrusted, or tampered upstreams are not merged.
+    assert!(local.merge(&up, &[local_key.verifying_key()]).is_err());
+    let mut forged = up.clone();
+    forged.entries[0].grant_id = "other".into();
+    assert!(local.merge(&forged, &trusted).is_err());
+
+    up.revoke("g2", None);
+    up.revoke("g3", None);
+    // A delta starting after what was merged leaves a gap.
+    let mut gap = up.delta(2);
+    gap.sign(&up_key).unwrap();
+    assert!(local.merge(&gap, &trusted).unwrap_err().to_string().contains("fetch the full list"));
+    let mut d = up.delta(1);
+    d.sign(&up_key).unwrap();
+    assert_eq!(local.merge(&d, &trusted).unwrap(), 2);
+
+    // An older list from the same issuer is a rollback.
+    let mut old = Grl::default();
+    old.revoke("g1", None);
+    old.sign(&up_key).unwrap();
+    assert!(local.merge(&old, &trusted).unwrap_err().to_string().contains("went back"));
+    assert_eq!(local.serial, 4);
+}
@@ -0,0 +1,67 @@
+use base64::Engine;
+use chrono::{Duration, Utc};
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use engine_auth::grant::{AccessGrant, GrantResource, GrantSeal, GRANT_KIND};
+use engine_auth::paseto;
+use engine_auth::signing::SEAL_ALG;
+use engine_auth::verifier::{Access, Denied, GrantVerifier};
+
+fn unhex(s:&str) -> Vec<u8> { (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect() }
+
+// https://github.com/paseto-standard/test-vectors/blob/master/v4.json
+const SK: &str = "b4cbfb43df4ce210727d953e4a713307fa19bb7d9f85041438d9e11b942a3774";
+const PK: &str = "1eb9dbbbbc047c03fd70604e0071f0987e16b28b757225c11f00415d0e20b1a2";
+const PAYLOAD: &str = r#"{"data":"this is a signed message","exp":"2022-01-01T00:00:00+00:00"}"#;
+const FOOTER: &str = r#"{"kid":"zVhMiPBP9fRf2snEcT7gFTioeA9COcNy9DfgL1W60haN"}"#;
+
+#[test]
+fn official_v4_public_vectors() {
+    let sk = SigningKey::from_bytes(&unhex(SK).try_into().unwrap());
+    let pk = VerifyingKey::from_bytes(&unhex(PK).try_into().unwrap()).unwrap();
+    assert_eq!(sk.verifying_key(), pk);
+    for (name, footer, implicit, token) in [
+        ("4-S-1", "", "", "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9bg_XBBzds8lTZShVlwwKSgeKpLT3yukTw6JUz3W4h_ExsQV-P0V54zemZDcAxFaSeef1QlXEFtkqxT1ciiQEDA"),
+        ("4-S-2", FOOTER, "", "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9v3Jt8mx_TdM2ceTGoqwrh4yDFn0XsHvvV_D0DtwQxVrJEBMl0F2caAdgnpKlt4p7xBnx1HcO-SPo8FPp214HDw.eyJraWQiOiJ6VmhNaVBCUDlmUmYyc25FY1Q3Z0ZUaW9lQTlDT2NOeTlEZmdMMVc2MGhhTiJ9"),
+        ("4-S-3", FOOTER, r#"{"test-vector":"4-S-3"}"#, "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9NPWciuD3d0o5eXJXG5pJy-DiVEoyPYWs1YSTwWHNJq6DZD3je5gf-0M4JR9ipdUSJbIovzmBECeaWmaqcaP0DQ.eyJraWQiOiJ6VmhNaVBCUDlmUmYyc25FY1Q3Z0ZUaW9lQTlDT2NOeTlEZmdMMVc2MGhhTiJ9"),
+    ] {
+        assert_eq!(paseto::sign(&sk, PAYLOAD.as_bytes(), footer.as_bytes(), implicit.as_bytes()), token, "{name}");
+        assert_eq!(paseto::verify(&pk, token, implicit.as_bytes()).unwrap(), PAYLOAD.as_bytes(), "{name}");
+        assert!(paseto::verify(&pk, token, b"other").is_err(), "{name}: implicit assertion is bound");
+    }
+}
+
+fn grant(nonce:&str) -> AccessGrant {
+    let now = Utc::now();
+    AccessGrant {
+        kind: GRANT_KIND.into(), grant_id: "01HGRANT".into(), sub: "alice".into(), tenants: vec![],
+        resource: GrantResource{ store: "S3".into(), bucket: "b".into(), prefix: String::new(), object: Some("k".into()), verbs: vec!["GET".into()], constraints: None },
+        exp: (now + Duration::seconds(60)).to_rfc3339(), iat: now.to_rfc3339(), nonce: nonce.into(), policy: None,
+        seal: GrantSeal{ alg: SEAL_ALG.into(), kid: String::new(), sig: String::new() },
+    }
+}
+
+/// The engine's earlier token: b64url(json ‖ ed25519(blake3(json))).
+fn legacy_token(k:&SigningKey, g:&AccessGrant) -> String {
+    let msg = serde_json::to_vec(g).unwrap();
+    let sig = k.sign(blake3::hash(&msg).as_bytes());
+    format!("v4.public.{}", base64::engine::general_purpose::URL_SAFE_NO_PAD.encode([msg.as_slice(), &sig.to_bytes()].concat()))
+}
+
+#[test]
+fn grant_tokens_in_both_formats_during_the_window() {
+    let k = SigningKey::from_bytes(&[7; 32]);
+    let v = GrantVerifier::new([k.verifying_key()]);
+    let access = Access{ verb: "GET", bucket: "b", key: "k", ..Default::default() };
+
+    let token = paseto::sign_grant(&k, &grant("n1")).unwrap();
+    assert_eq!(v.verify_token(&token, &access).unwrap().seal.alg, paseto::ALG);
+    assert!(matches!(v.verify_token(&token, &access), Err(Denied::Replayed)));
+    let other = paseto::sign_grant(&SigningKey::from_bytes(&[8; 32]), &grant("n2")).unwrap();
+    assert!(matches!(v.verify_token(&other, &access), Err(Denied::Seal(_))));
+
+    let legacy = legacy_token(&k, &grant("n3"));
+    assert_eq!(v.verify_token(&legacy, &access).unwrap().seal.alg, paseto::LEGACY_ALG);
+    let closed = GrantVerifier::new([k.verifying_key()]).with_legacy_until(None);
+    assert!(matches!(closed.verify_token(&legacy_token(&k, &grant("n4")), &access), Err(Denied::Seal(_))));
+    assert!(closed.verify_token(&paseto::sign_grant(&k, &grant("n4")).unwrap(), &access).is_ok());
+}
@@ -1,4 +1,4 @@
-use engine_auth::{grant::{AccessGrant, GrantResource, GrantSeal}, signing::sign_grant};
+use engine_auth::{grant::{AccessGrant, GrantResource, GrantSeal}, signing::{sign_grant, verify_grant}};
 use ed25519_dalek::SigningKey;
 use rand_core::OsRng;
 
@@ -21,9 +21,13 @@ fn sign_sets_sig_and_is_stable() {
         exp: "2026-01-01T00:00:00Z".into(),
         iat: "2026-01-01T00:00:00Z".into(),
         nonce: "n".into(),
+        policy: None,
         seal: GrantSeal { alg: "ed25519-blake3".into(), kid: "k".into(), sig: "".into() },
     };
 
     sign_grant(&signing_key, &mut grant).unwrap();
     assert!(!grant.seal.sig.is_empty());
+    verify_grant(&signing_key.verifying_key(), &grant).unwrap();
+    grant.sub = "someone-else".into();
+    assert!(verify_grant(&signing_key.verifying_key(), &grant).is_err());
 }
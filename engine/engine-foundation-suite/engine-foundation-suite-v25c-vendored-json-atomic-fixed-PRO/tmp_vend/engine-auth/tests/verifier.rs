@@ -0,0 +1,85 @@
+use chrono::{Duration, Utc};
+use ed25519_dalek::SigningKey;
+use engine_auth::grant::{ip_hash, AccessGrant, GrantConstraints, GrantPolicy, GrantResource, GrantSeal, GRANT_KIND};
+use engine_auth::signing::{key_id, sign_grant, SEAL_ALG};
+use engine_auth::verifier::{Access, Denied, GrantVerifier};
+
+fn key(n:u8) -> SigningKey { SigningKey::from_bytes(&[n; 32]) }
+
+fn grant(k:&SigningKey, nonce:&str, constraints:Option<GrantConstraints>) -> AccessGrant {
+    let now = Utc::now();
+    let mut g = AccessGrant {
+        kind: GRANT_KIND.into(), grant_id: "01HGRANT".into(), sub: "alice".into(), tenants: vec!["acme".into()],
+        resource: GrantResource {
+            store: "S3".into(), bucket: "b".into(), prefix: "acme/".into(), object: None, verbs: vec!["GET".into()], constraints,
+        },
+        exp: (now + Duration::seconds(60)).to_rfc3339(), iat: now.to_rfc3339(), nonce: nonce.into(), policy: None,
+        seal: GrantSeal{ alg: SEAL_ALG.into(), kid: key_id(&k.verifying_key()), sig: String::new() },
+    };
+    sign_grant(k, &mut g).unwrap();
+    g
+}
+
+const OK: Access<'static> = Access{ verb: "GET", bucket: "b", key: "acme/x.json", tenant: Some("acme"), client_ip: None, range: None };
+
+#[test]
+fn scope_seal_and_time_are_enforced_before_the_nonce_is_burnt() {
+    let k = key(1);
+    let v = GrantVerifier::new([k.verifying_key()]);
+    let g = grant(&k, "n1", None);
+
+    for (access, want) in [
+        (Access{ verb: "PUT", ..OK }, "verb PUT"),
+        (Access{ bucket: "other", ..OK }, "bucket other"),
+        (Access{ key: "other/x.json", ..OK }, "outside prefix"),
+        (Access{ key: "acme/../other/x.json", ..OK }, "outside prefix"),
+        (Access{ tenant: Some("globex"), ..OK }, "tenant globex"),
+        (Access{ tenant: None, ..OK }, "tenant (anonymous)"),
+    ] {
+        let e = v.verify(&g, &access).unwrap_err();
+        assert!(matches!(e, Denied::Scope(_)) && e.to_string().contains(want), "{e}");
+    }
+
+    let now = Utc::now();
+    assert_eq!(v.verify_at(&g, &OK, now + Duration::seconds(120)), Err(Denied::Expired));
+    assert!(v.verify_at(&g, &OK, now + Duration::seconds(80)).is_ok(), "exp is widened by the skew");
+    assert_eq!(v.verify(&g, &OK), Err(Denied::Replayed));
+
+    let strict = GrantVerifier::new([k.verifying_key()]).with_skew(Duration::zero());
+    assert_eq!(strict.verify_at(&grant(&k, "n2", None), &OK, now - Duration::seconds(5)), Err(Denied::NotYetValid));
+
+    let mut forged = grant(&k, "n3", None);
+    forged.resource.prefix = String::new();
+    assert!(matches!(v.verify(&forged, &OK), Err(Denied::Seal(_))));
+    assert!(matches!(GrantVerifier::new([key(2).verifying_key()]).verify(&grant(&k, "n4", None), &OK), Err(Denied::Seal(_))));
+
+    // The policy link is sealed with the rest of the grant.
+    let mut linked = grant(&k, "n5", None);
+    linked.policy = Some(GrantPolicy{ unit: "presign.policy.v1".into(), unit_cid: "b3:u".into(), run_cid: "b3:r".into(), receipt_cid: "b3:a".into() });
+    sign_grant(&k, &mut linked).unwrap();
+    let mut relinked = linked.clone();
+    relinked.policy.as_mut().unwrap().receipt_cid = "b3:b".into();
+    assert!(matches!(v.verify(&relinked, &OK), Err(Denied::Seal(_))));
+    v.verify(&linked, &OK).unwrap();
+}
+
+#[test]
+fn client_address_and_range_constraints() {
+    let k = key(1);
+    let v = GrantVerifier::new([k.verifying_key()]).with_ip_key([7; 32]);
+    let c = GrantConstraints{ ip_hash: Some(ip_hash(&[7; 32], "10.0.0.7")), byte_range_max: Some(100) };
+    let g = |n:&str| grant(&k, n, Some(c.clone()));
+    let ok = Access{ client_ip: Some("10.0.0.7"), range: Some("bytes=0-99"), ..OK };
+
+    assert!(matches!(v.verify(&g("a"), &Access{ client_ip: Some("10.0.0.8"), ..ok }), Err(Denied::Scope(_))));
+    assert!(matches!(v.verify(&g("a"), &Access{ client_ip: None, ..ok }), Err(Denied::Scope(_))));
+    // Another key (or none) never matches: the hash only means something to the engine that issued it.
+    assert!(matches!(GrantVerifier::new([k.verifying_key()]).with_ip_key([8; 32]).verify(&g("a"), &ok), Err(Denied::Scope(_))));
+    assert!(matches!(GrantVerifier::new([k.verifying_key()]).verify(&g("a"), &ok), Err(Denied::Scope(_))));
+    for range in [None, Some("bytes=0-100"), Some("bytes=0-"), Some("bytes=0-49,60-110"), Some("items=0-1"), Some("bytes=0-18446744073709551615")] {
+        assert!(matches!(v.verify(&g("a"), &Access{ range, ..ok }), Err(Denied::Range(_))), "{range:?}");
+    }
+    v.verify(&g("a"), &ok).unwrap();
+    v.verify(&g("b"), &Access{ range: Some("bytes=-100"), ..ok }).unwrap();
+    assert_eq!(v.verify(&g("a"), &ok), Err(Denied::Replayed));
+}
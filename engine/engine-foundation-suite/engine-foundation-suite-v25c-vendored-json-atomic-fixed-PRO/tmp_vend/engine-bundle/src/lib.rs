@@ -0,0 +1,166 @@
+//! Offline verification bundle (`bundle.zip`) for one run.
+//!
+//! Everything a verifier needs without network access: the canonical unit spec
+//! (`policy.canonical.json`, whose CID is the receipt's `chip_hash`), the unit's wasm if any, the
+//! input (or per-field commitments when redacted), the receipt, the card, SIRP capsules, and the
+//! signer's public key. `manifest.json` lists the CID of every file and of the run's objects;
+//! `certification.json` carries the issuer's ed25519 signature over blake3 of the manifest bytes,
+//! so one signature covers the whole bundle. Entries are written in a fixed order with fixed
+//! timestamps: the same run sealed with the same key yields the same bytes.
+use anyhow::{bail, Result};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine as _;
+use ed25519_dalek::{Signer as _, SigningKey};
+use engine_auth::signing::{key_id, SEAL_ALG};
+use engine_core::json_atomic::{compute_cid, to_json_atomic_bytes};
+use engine_core::model::ExecutionReceipt;
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+pub const MANIFEST: &str = "manifest.json";
+pub const CERTIFICATION: &str = "certification.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub kind: String,                      // "bundle.manifest.v1"
+    pub run_cid: String,
+    pub unit: UnitInfo,
+    /// `full` (`input.json`) or `redacted` (`input.commitments.json`).
+    pub input: String,
+    /// `input`, `output`, `receipt`, `card` → CID.
+    pub cids: BTreeMap<String, String>,
+    /// File name → `b3:` of its bytes, for every file except the manifest and certification.
+    pub files: BTreeMap<String, String>,
+    pub signer: SignerInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnitInfo {
+    pub id: String,
+    /// Receipt `chip_hash`; equals `b3:` of `policy.canonical.json`.
+    pub hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasm: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignerInfo {
+    pub alg: String,
+    pub kid: String,
+    pub public_key_b64: String,
+}
+
+pub fn b3(bytes:&[u8]) -> String { format!("b3:{}", blake3::hash(bytes).to_hex()) }
+
+/// Builder for one run's bundle; [`Bundle::seal`] produces the zip.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    run_cid: String,
+    receipt: ExecutionReceipt,
+    card: Value,
+    unit: Option<Value>,
+    wasm: Option<Vec<u8>>,
+    sirp: BTreeMap<String, Value>,
+    redact_input: bool,
+}
+
+impl Bundle {
+    pub fn new(run_cid:&str, receipt:&ExecutionReceipt, card:&Value) -> Self {
+        Self{ run_cid: run_cid.into(), receipt: receipt.clone(), card: card.clone(), unit: None, wasm: None, sirp: BTreeMap::new(), redact_input: false }
+    }
+    /// Unit spec the run executed; must canonicalize to the receipt's `chip_hash`.
+    pub fn unit(mut self, spec:Value) -> Self { self.unit = Some(spec); self }
+    pub fn wasm(mut self, bytes:Vec<u8>) -> Self { self.wasm = Some(bytes); self }
+    /// A SIRP capsule or receipt, stored as `sirp/<name>.json`.
+    pub fn sirp(mut self, name:&str, doc:Value) -> Self { self.sirp.insert(name.into(), doc); self }
+    /// Ship `b3:` commitments to each top-level input field instead of the input, and blank the
+    /// input in the bundled receipt. The input CID is then attested, not re-derivable.
+    pub fn redact_input(mut self, yes:bool) -> Self { self.redact_input = yes; self }
+
+    pub fn seal(&self, key:&SigningKey) -> Result<Vec<u8>> {
+        let Some(spec) = &self.unit else { bail!("{}: no unit spec to bundle", self.receipt.chip_id) };
+        let policy = to_json_atomic_bytes(spec)?;
+        if b3(&policy) != self.receipt.chip_hash {
+            bail!("unit spec canonicalizes to {}, receipt chip_hash is {}", b3(&policy), self.receipt.chip_hash);
+        }
+        let mut receipt = self.receipt.clone();
+        let mut files: Vec<(String, Vec<u8>)> = vec![("policy.canonical.json".into(), policy.clone())];
+        if let Some(w) = &self.wasm { files.push(("policy.wasm".into(), w.clone())); }
+        if self.redact_input {
+            let fields: BTreeMap<_, _> = match &receipt.input.canon {
+                Value::Object(m) => m.iter().map(|(k, v)| Ok((k.clone(), compute_cid(v)?))).collect::<Result<_>>()?,
+                _ => BTreeMap::new(),
+            };
+            files.push(("input.commitments.json".into(), to_json_atomic_bytes(&json!({"cid": receipt.input.cid, "fields": fields}))?));
+            receipt.input.raw = Value::Null;
+            receipt.input.canon = Value::Null;
+        } else {
+            files.push(("input.json".into(), to_json_atomic_bytes(&receipt.input.canon)?));
+        }
+        files.push(("receipt.json".into(), to_json_atomic_bytes(&receipt)?));
+        files.push(("card.json".into(), to_json_atomic_bytes(&self.card)?));
+        for (name, doc) in &self.sirp { files.push((format!("sirp/{name}.json"), to_json_atomic_bytes(doc)?)); }
+        let vk = key.verifying_key();
+        let public_key_b64 = B64.encode(vk.as_bytes());
+        files.push(("signer.pub".into(), public_key_b64.clone().into_bytes()));
+        files.push(("verification-instructions.md".into(), INSTRUCTIONS.as_bytes().to_vec()));
+
+        let manifest = Manifest{
+            kind: "bundle.manifest.v1".into(),
+            run_cid: self.run_cid.clone(),
+            unit: UnitInfo{ id: receipt.chip_id.clone(), hash: receipt.chip_hash.clone(), wasm: self.wasm.as_deref().map(b3) },
+            input: if self.redact_input { "redacted" } else { "full" }.into(),
+            cids: BTreeMap::from([
+                ("input".into(), receipt.input.cid.clone()),
+                ("output".into(), receipt.output.cid.clone()),
+                ("receipt".into(), compute_cid(&receipt)?),
+                ("card".into(), compute_cid(&self.card)?),
+            ]),
+            files: files.iter().map(|(n, b)| (n.clone(), b3(b))).collect(),
+            signer: SignerInfo{ alg: SEAL_ALG.into(), kid: key_id(&vk), public_key_b64 },
+        };
+        let manifest_bytes = to_json_atomic_bytes(&manifest)?;
+        // Same `bundle_hash` as before the manifest existed: policy bytes followed by wasm bytes.
+        let mut h = blake3::Hasher::new();
+        h.update(&policy);
+        if let Some(w) = &self.wasm { h.update(w); }
+        let certification = json!({
+            "kind": "verification.bundle.v1",
+            "run_cid": self.run_cid,
+            "manifest_b3": b3(&manifest_bytes),
+            "certification": { "issuer": { "kid": manifest.signer.kid } },
+            "signatures": {
+                "alg": SEAL_ALG,
+                "kid": manifest.signer.kid,
+                "bundle_hash": format!("b3:{}", h.finalize().to_hex()),
+                "issuer_signature": B64.encode(key.sign(blake3::hash(&manifest_bytes).as_bytes()).to_bytes()),
+            },
+        });
+        files.push((MANIFEST.into(), manifest_bytes));
+        files.push((CERTIFICATION.into(), to_json_atomic_bytes(&certification)?));
+
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let opts = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, bytes) in files {
+            zip.start_file(name, opts)?;
+            zip.write_all(&bytes)?;
+        }
+        Ok(zip.finish()?.into_inner())
+    }
+}
+
+const INSTRUCTIONS: &str = "\
+# Verifying this bundle
+
+1. `manifest.json` lists `b3:` (blake3) of every other file; recompute them.
+2. `certification.json` `signatures.issuer_signature` is ed25519 over blake3(`manifest.json` bytes),
+   by the key in `signer.pub` (kid `manifest.signer.kid`); pin that key out of band.
+3. blake3 of `policy.canonical.json` is the receipt's `chip_hash`; the JSON files are JSON✯Atomic
+   (sorted keys, no whitespace), so the receipt and card CIDs are blake3 of their file bytes.
+4. `input.json` hashes to the receipt's input CID and the first `hash_chain` entry; the output CID
+   is the last. With `input.commitments.json` the input is redacted: only per-field CIDs ship.
+
+`receipt-verify bundle.zip --strict` does all of the above offline.
+";
@@ -0,0 +1,76 @@
+use base64::Engine as _;
+use ed25519_dalek::{Signature, SigningKey, Verifier};
+use engine_bundle::*;
+use engine_core::json_atomic::compute_cid;
+use engine_core::model::*;
+use engine_core::runtime::Engine;
+use rand_core::OsRng;
+use serde_json::{json, Value};
+use std::io::Read;
+
+fn files(zip:&[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut za = zip::ZipArchive::new(std::io::Cursor::new(zip)).unwrap();
+    (0..za.len()).map(|i| {
+        let mut f = za.by_index(i).unwrap();
+        let mut buf = vec![];
+        f.read_to_end(&mut buf).unwrap();
+        (f.name().to_string(), buf)
+    }).collect()
+}
+
+fn run() -> (Value, ExecutionReceipt) {
+    let spec = json!({"id": "gate", "policies": [], "wiring": {"type": "all", "policies": []}});
+    let mut chip = SemanticChip::builder("gate").wiring(Wiring::All{ policies: vec![] }).build();
+    chip.hash = Some(compute_cid(&spec).unwrap());
+    let receipt = Engine::default().build().execute_chip(&chip, json!({"user": {"id": "u-1"}, "amount": 10}), None).unwrap();
+    (spec, receipt)
+}
+
+#[test]
+fn manifest_covers_every_file_and_is_signed() {
+    let (spec, receipt) = run();
+    let key = SigningKey::generate(&mut OsRng);
+    let card = json!({"kind": "receipt.card.v1", "run_cid": "b3:run"});
+    let bundle = Bundle::new("b3:run", &receipt, &card).unit(spec).sirp("intent", json!({"type": "INTENT"}));
+    let zip = bundle.seal(&key).unwrap();
+    assert_eq!(zip, bundle.seal(&key).unwrap());
+
+    let files = files(&zip);
+    let get = |n:&str| files.iter().find(|(name, _)| name == n).map(|(_, b)| b.clone()).unwrap();
+    let manifest: Manifest = serde_json::from_slice(&get(MANIFEST)).unwrap();
+    assert_eq!(manifest.files.len(), files.len() - 2);
+    for (name, bytes) in &files {
+        if name != MANIFEST && name != CERTIFICATION { assert_eq!(manifest.files[name], b3(bytes), "{name}"); }
+    }
+    assert_eq!(b3(&get("policy.canonical.json")), receipt.chip_hash);
+    assert_eq!(b3(&get("input.json")), receipt.input.cid);
+    assert_eq!(b3(&get("receipt.json")), manifest.cids["receipt"]);
+    assert_eq!(b3(&get("card.json")), manifest.cids["card"]);
+
+    let cert: Value = serde_json::from_slice(&get(CERTIFICATION)).unwrap();
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let sig = Signature::from_slice(&b64.decode(cert["signatures"]["issuer_signature"].as_str().unwrap()).unwrap()).unwrap();
+    key.verifying_key().verify(blake3::hash(&get(MANIFEST)).as_bytes(), &sig).unwrap();
+    assert_eq!(String::from_utf8(get("signer.pub")).unwrap(), b64.encode(key.verifying_key().as_bytes()));
+}
+
+#[test]
+fn redacted_bundles_ship_commitments_only() {
+    let (spec, receipt) = run();
+    let zip = Bundle::new("b3:run", &receipt, &json!({})).unit(spec).redact_input(true).seal(&SigningKey::generate(&mut OsRng)).unwrap();
+    let files = files(&zip);
+    assert!(files.iter().all(|(n, _)| n != "input.json"));
+    let get = |n:&str| serde_json::from_slice::<Value>(&files.iter().find(|(name, _)| name == n).unwrap().1).unwrap();
+    let c = get("input.commitments.json");
+    assert_eq!(c["cid"], json!(receipt.input.cid));
+    assert_eq!(c["fields"]["amount"], json!(compute_cid(&json!(10)).unwrap()));
+    assert!(get("receipt.json")["input"]["raw"].is_null());
+    assert_eq!(get(MANIFEST)["input"], "redacted");
+}
+
+#[test]
+fn spec_must_match_the_receipt() {
+    let (_, receipt) = run();
+    let e = Bundle::new("b3:run", &receipt, &json!({})).unit(json!({"id": "other"})).seal(&SigningKey::generate(&mut OsRng)).unwrap_err();
+    assert!(e.to_string().contains("chip_hash"), "{e}");
+}
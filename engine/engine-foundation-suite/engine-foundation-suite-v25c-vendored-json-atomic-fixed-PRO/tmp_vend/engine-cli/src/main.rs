@@ -11,8 +11,10 @@ use engine_extras::sink_filesystem::FsSink;
 use engine_extras::signer_ed25519::Ed25519Signer;
 use engine_registry::file_registry::FileRegistry;
 use engine_registry::schema::EngineRegistryEntry;
+use engine_registry::provenance;
 use engine_audit::report::AuditReportV1;
 use engine_audit::sink_fs::FsAudit;
+use engine_audit::review::{ReviewQueue, ReviewReceipt, Reviewers};
 
 #[derive(Parser, Debug)]
 #[command(name="engine")]
@@ -34,9 +36,86 @@ enum Cmd {
     #[arg(long)] version: String,
     #[arg(long)] cid: String,
     #[arg(long, default_value = "./registry")] regdir: String,
+    /// Seal the entry as publisher (PKCS#8 PEM ed25519 key)
+    #[arg(long)] sign_pem: Option<String>,
+  },
+  /// Snapshot the registry into a signed index.json
+  RegistryIndex {
+    #[arg(long)] sign_pem: String,
+    #[arg(long, default_value_t = 7 * 24 * 3600)] ttl_secs: u64,
+    #[arg(long, default_value = "./registry")] regdir: String,
+  },
+  /// Verify an index (and optionally one entry) offline against a trust policy
+  RegistryVerify {
+    #[arg(long)] trust: String,
+    #[arg(long)] index: String,
+    /// Entry JSON file to check against the index
+    #[arg(long)] entry: Option<String>,
+    /// Last index version this client accepted (rollback protection)
+    #[arg(long, default_value_t = 0)] min_version: u64,
+  },
+  /// Resolve `name@range` or `name@channel` to the published entry
+  RegistryResolve {
+    unit_ref: String,
+    #[arg(long, default_value = "./registry")] regdir: String,
+  },
+  /// Point a channel (stable, canary, ...) at a published version
+  RegistryChannel {
+    #[arg(long)] name: String,
+    #[arg(long)] channel: String,
+    #[arg(long)] version: String,
+    #[arg(long, default_value = "./registry")] regdir: String,
+  },
+  /// Hide a version from range resolution (exact pins keep working)
+  RegistryYank {
+    #[arg(long)] name: String,
+    #[arg(long)] version: String,
+    #[arg(long, default_value = "")] reason: String,
+    #[arg(long)] undo: bool,
+    #[arg(long, default_value = "./registry")] regdir: String,
+  },
+  /// Seal an offline verification bundle.zip for a receipt
+  Bundle(BundleArgs),
+  /// List review items (`pending`, `info_requested`, `approved`, `denied`)
+  ReviewList {
+    #[arg(long)] status: Option<String>,
+    #[arg(short, long, default_value = "./out")] outdir: String,
+  },
+  /// Seal a decision on a review item as reviewer; prints the review receipt
+  ReviewSign {
+    /// review.item.v1 JSON (from the queue or `GET /reviews/:run_cid` `.item`)
+    #[arg(long)] item: String,
+    /// approve | deny | request_info
+    #[arg(long)] action: String,
+    #[arg(long)] note: Option<String>,
+    /// Field the reviewer needs before deciding (request_info); repeatable
+    #[arg(long)] field: Vec<String>,
+    #[arg(long)] sign_pem: String,
+  },
+  /// Apply a sealed review receipt to the local queue (checked against the reviewers file)
+  ReviewApply {
+    receipt: String,
+    #[arg(long)] reviewers: String,
+    #[arg(short, long, default_value = "./out")] outdir: String,
   }
 }
 
+#[derive(clap::Args, Debug)]
+struct BundleArgs {
+  /// Receipt JSON (as written by the receipt sink)
+  #[arg(long)] receipt: String,
+  /// Unit spec the receipt was produced by (canonicalizes to its chip_hash)
+  #[arg(long)] unit: String,
+  #[arg(long)] card: Option<String>,
+  #[arg(long)] wasm: Option<String>,
+  /// Defaults to the receipt CID
+  #[arg(long)] run_cid: Option<String>,
+  /// Ship per-field input commitments instead of the input
+  #[arg(long)] redact_input: bool,
+  #[arg(long)] sign_pem: String,
+  #[arg(short, long, default_value = "./out/bundle.zip")] out: String,
+}
+
 fn signer_from(pem_path: &Option<String>) -> Box<dyn Signer> {
     if let Some(p) = pem_path {
         if let Ok(pem) = std::fs::read_to_string(p) {
@@ -91,7 +170,7 @@ fn run_example(input_path:&str, outdir:&str, k:usize, sign_pem:&Option<String>)
       "output": { "cid": receipt.output.cid },
       "decision": receipt.decision,
       "policy_decisions": receipt.policy_decisions,
-      "missing": receipt.missing,
+      "poi": receipt.poi,
       "proof": receipt.proof,
       "ts": receipt.timestamp
     });
@@ -117,18 +196,121 @@ fn run_example(input_path:&str, outdir:&str, k:usize, sign_pem:&Option<String>)
     Ok(())
 }
 
-fn reg_put(name:&str, version:&str, cid:&str, regdir:&str) -> Result<()> {
+fn publisher_key(pem_path:&str) -> Result<(ed25519_dalek::SigningKey, String)> {
+  use ed25519_dalek::pkcs8::DecodePrivateKey;
+  let key = ed25519_dalek::SigningKey::from_pkcs8_pem(&std::fs::read_to_string(pem_path)?)
+    .map_err(|e| anyhow::anyhow!("{pem_path}: {e}"))?;
+  let kid = engine_auth::signing::key_id(&key.verifying_key());
+  Ok((key, kid))
+}
+
+fn reg_put(name:&str, version:&str, cid:&str, regdir:&str, sign_pem:&Option<String>) -> Result<()> {
   let reg = FileRegistry::new(regdir);
-  let e = EngineRegistryEntry{ kind:"engine.registry.entry.v1".into(), id: ulid::Ulid::new().to_string(), name:name.into(), version:version.into(), cid:cid.into(), meta: serde_json::json!({}) };
+  let mut e = EngineRegistryEntry{ kind:"engine.registry.entry.v1".into(), id: ulid::Ulid::new().to_string(), name:name.into(), version:version.into(), cid:cid.into(), meta: serde_json::json!({}), seal: None };
+  if let Some(pem) = sign_pem {
+    let (key, kid) = publisher_key(pem)?;
+    provenance::sign_entry(&key, &kid, &mut e)?;
+    println!("🔏 sealed by {kid}");
+  }
   let p = reg.put(&e)?;
   println!("📚 registry entry -> {}", p.display());
   Ok(())
 }
 
+fn reg_index(sign_pem:&str, ttl_secs:u64, regdir:&str) -> Result<()> {
+  let (key, kid) = publisher_key(sign_pem)?;
+  let index = provenance::publish_index(&FileRegistry::new(regdir), &key, &kid, ttl_secs)?;
+  println!("🗂️  index v{} ({} targets) sealed by {kid}", index.version, index.targets.len());
+  Ok(())
+}
+
+fn reg_verify(trust:&str, index:&str, entry:&Option<String>, min_version:u64) -> Result<()> {
+  let policy = provenance::TrustPolicy::load(trust)?;
+  let index: provenance::RegistryIndex = serde_json::from_slice(&std::fs::read(index)?)?;
+  let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+  provenance::verify_index(&index, &policy, now, min_version)?;
+  println!("✅ index v{} verified", index.version);
+  if let Some(path) = entry {
+    let e: EngineRegistryEntry = serde_json::from_slice(&std::fs::read(path)?)?;
+    let kid = provenance::verify_entry(&e, &policy)?;
+    let t = index.check_entry(&e)?;
+    println!("✅ {}@{} published by {kid}{}", e.name, e.version, if t.yanked { " (yanked)" } else { "" });
+  }
+  Ok(())
+}
+
+fn reg_resolve(unit_ref:&str, regdir:&str) -> Result<()> {
+  let Some((name, req)) = unit_ref.split_once('@') else { anyhow::bail!("expected name@range or name@channel") };
+  let e = FileRegistry::new(regdir).resolve(name, req)?;
+  println!("{}", serde_json::to_string_pretty(&e)?);
+  Ok(())
+}
+
+fn bundle(a:&BundleArgs) -> Result<()> {
+  let receipt: ExecutionReceipt = serde_json::from_slice(&std::fs::read(&a.receipt)?)?;
+  let card = match &a.card { Some(p) => serde_json::from_slice(&std::fs::read(p)?)?, None => json!({}) };
+  let run_cid = match &a.run_cid { Some(c) => c.clone(), None => engine_core::json_atomic::compute_cid(&receipt)? };
+  let mut b = engine_bundle::Bundle::new(&run_cid, &receipt, &card)
+    .unit(serde_json::from_slice(&std::fs::read(&a.unit)?)?)
+    .redact_input(a.redact_input);
+  if let Some(w) = &a.wasm { b = b.wasm(std::fs::read(w)?); }
+  let (key, kid) = publisher_key(&a.sign_pem)?;
+  let out = &a.out;
+  let zip = b.seal(&key)?;
+  if let Some(dir) = std::path::Path::new(out).parent() { std::fs::create_dir_all(dir)?; }
+  std::fs::write(out, zip)?;
+  println!("📦 bundle {out} ({run_cid}) sealed by {kid}");
+  Ok(())
+}
+
+/// Same layout as `run`: items under `{outdir}/reviews`, audit reports in `{outdir}/audit`.
+fn review_queue(outdir:&str) -> ReviewQueue {
+  ReviewQueue::new(format!("{outdir}/reviews"), FsAudit::new(format!("{outdir}/audit")))
+}
+
+fn review_list(status:&Option<String>, outdir:&str) -> Result<()> {
+  let status = status.as_ref().map(|s| serde_json::from_value(json!(s))).transpose()
+    .map_err(|_| anyhow::anyhow!("status must be pending, info_requested, approved or denied"))?;
+  for item in review_queue(outdir).list(status)? {
+    println!("{}  {:<14} {}  {}", item.run_cid, serde_json::to_value(item.status)?.as_str().unwrap_or(""), item.unit_ref, item.opened_at);
+  }
+  Ok(())
+}
+
+fn review_sign(item:&str, action:&str, note:&Option<String>, fields:&[String], sign_pem:&str) -> Result<()> {
+  let item = serde_json::from_slice(&std::fs::read(item)?)?;
+  let action = serde_json::from_value(json!(action)).map_err(|_| anyhow::anyhow!("action must be approve, deny or request_info"))?;
+  let (key, kid) = publisher_key(sign_pem)?;
+  let mut r = ReviewReceipt::new(&item, action, note.clone(), fields.to_vec());
+  r.sign(&key, &kid)?;
+  println!("{}", serde_json::to_string_pretty(&r)?);
+  Ok(())
+}
+
+fn review_apply(receipt:&str, reviewers:&str, outdir:&str) -> Result<()> {
+  let r: ReviewReceipt = serde_json::from_slice(&std::fs::read(receipt)?)?;
+  let (item, cid) = review_queue(outdir).decide(&r, &Reviewers::load(reviewers)?)?;
+  println!("🧑‍⚖️ {} -> {} ({cid})", item.run_cid, serde_json::to_value(item.status)?.as_str().unwrap_or(""));
+  if let Some(card) = item.final_card(&r) { println!("{}", serde_json::to_string_pretty(&card)?); }
+  Ok(())
+}
+
 fn main() -> Result<()> {
   let args = Cli::parse();
   match args.cmd {
     Cmd::Run { input, outdir, k, sign_pem } => run_example(&input, &outdir, k, &sign_pem),
-    Cmd::RegistryPut { name, version, cid, regdir } => reg_put(&name, &version, &cid, &regdir),
+    Cmd::RegistryPut { name, version, cid, regdir, sign_pem } => reg_put(&name, &version, &cid, &regdir, &sign_pem),
+    Cmd::RegistryIndex { sign_pem, ttl_secs, regdir } => reg_index(&sign_pem, ttl_secs, &regdir),
+    Cmd::RegistryVerify { trust, index, entry, min_version } => reg_verify(&trust, &index, &entry, min_version),
+    Cmd::RegistryResolve { unit_ref, regdir } => reg_resolve(&unit_ref, &regdir),
+    Cmd::RegistryChannel { name, channel, version, regdir } => FileRegistry::new(regdir).set_channel(&name, &channel, &version),
+    Cmd::RegistryYank { name, version, reason, undo, regdir } => {
+      let reg = FileRegistry::new(regdir);
+      if undo { reg.unyank(&name, &version) } else { reg.yank(&name, &version, &reason) }
+    }
+    Cmd::Bundle(a) => bundle(&a),
+    Cmd::ReviewList { status, outdir } => review_list(&status, &outdir),
+    Cmd::ReviewSign { item, action, note, field, sign_pem } => review_sign(&item, &action, &note, &field, &sign_pem),
+    Cmd::ReviewApply { receipt, reviewers, outdir } => review_apply(&receipt, &reviewers, &outdir),
   }
 }
@@ -0,0 +1,158 @@
+//! Server-side facts gathered before policy evaluation.
+//!
+//! A unit lists the facts it needs ([`ContextDecl`]); the engine asks the named [`ContextProvider`]
+//! for each one, with a deadline and only if the provider's effects are allowed by the
+//! [`EngineMode`], and places the value at `$context.<as>` in the input policies see. Whatever the
+//! caller sent under `$context` is dropped first. Every fact is recorded in the receipt
+//! ([`ContextFact`]) with the CID of the source it was read from, so a verifier holding the same
+//! sources can rebuild the exact input.
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value as Json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::model::{ContextDecl, ContextFact, Effect, EngineMode};
+
+/// Reserved input key for provider facts.
+pub const CONTEXT_NS: &str = "$context";
+/// Deadline for a declaration without `timeout_ms`.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// A fact and the CID of the bytes it was read from.
+#[derive(Debug, Clone)]
+pub struct Fact { pub value: Json, pub source_cid: String }
+
+pub trait ContextProvider: Send + Sync {
+    /// Effects a fetch may have; all must be enabled in the execution's mode.
+    fn effects(&self) -> &[Effect];
+    /// `params` come from the unit's declaration, `input` is the caller's input (for keys like `key_from`).
+    fn fetch(&self, params:&Json, input:&Json) -> Result<Fact>;
+}
+
+pub type Providers = HashMap<String, Arc<dyn ContextProvider>>;
+
+/// Threads shared by every engine for provider fetches. A fetch that overruns its deadline is abandoned,
+/// not cancelled, and keeps its thread until it returns; at most this many can be stuck at once.
+pub const FETCH_WORKERS: usize = 8;
+/// Fetches waiting for a worker; beyond this a fetch fails at once.
+const FETCH_QUEUE: usize = 64;
+
+struct Job { deadline: Instant, run: Box<dyn FnOnce() + Send> }
+
+fn pool() -> &'static SyncSender<Job> {
+    static POOL: OnceLock<SyncSender<Job>> = OnceLock::new();
+    POOL.get_or_init(|| {
+        let (tx, rx) = sync_channel::<Job>(FETCH_QUEUE);
+        let rx = Arc::new(Mutex::new(rx));
+        for i in 0..FETCH_WORKERS {
+            let rx = rx.clone();
+            std::thread::Builder::new().name(format!("context-fetch-{i}")).spawn(move || loop {
+                let Ok(job) = rx.lock().unwrap_or_else(|e| e.into_inner()).recv() else { return };
+                // Nobody waits for a job that sat in the queue past its deadline.
+                if Instant::now() < job.deadline { (job.run)(); }
+            }).expect("context fetch worker");
+        }
+        tx
+    })
+}
+
+fn fetch_with_timeout(p:Arc<dyn ContextProvider>, params:Json, input:Json, timeout:Duration) -> Result<Fact> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let job = Job{ deadline: Instant::now() + timeout, run: Box::new(move || { let _ = tx.send(p.fetch(&params, &input)); }) };
+    pool().try_send(job).map_err(|_| anyhow!("all {FETCH_WORKERS} context fetch workers are busy"))?;
+    rx.recv_timeout(timeout).map_err(|_| anyhow!("timed out after {}ms", timeout.as_millis()))?
+}
+
+/// `input` without caller-supplied `$context`, plus every fact that could be fetched; failed facts are
+/// left out of the input (policies requiring them get `Doubt`) and carry their error in the record.
+pub fn gather(providers:&Providers, decls:&[ContextDecl], input:Json, mode:&EngineMode, cid:&dyn Fn(&Json)->String) -> (Json, Vec<ContextFact>) {
+    let mut input = input;
+    if let Json::Object(m) = &mut input { m.remove(CONTEXT_NS); }
+    if decls.is_empty() { return (input, vec![]); }
+    let mut values = serde_json::Map::new();
+    let mut facts = Vec::with_capacity(decls.len());
+    for d in decls {
+        let got = match providers.get(&d.provider) {
+            None => Err(anyhow!("unknown provider {}", d.provider)),
+            Some(p) if !mode.allows_all(p.effects()) => Err(anyhow!("effects {:?} not allowed", p.effects())),
+            Some(p) => fetch_with_timeout(p.clone(), d.params.clone(), input.clone(), d.timeout_ms.map(Duration::from_millis).unwrap_or(DEFAULT_TIMEOUT)),
+        };
+        let mut fact = ContextFact{ name: d.name.clone(), provider: d.provider.clone(), params: d.params.clone(), source_cid: None, value_cid: None, error: None };
+        match got {
+            Ok(f) => {
+                fact.source_cid = Some(f.source_cid);
+                fact.value_cid = Some(cid(&f.value));
+                values.insert(d.name.clone(), f.value);
+            }
+            Err(e) => fact.error = Some(format!("{e:#}")),
+        }
+        facts.push(fact);
+    }
+    if let Json::Object(m) = &mut input { m.insert(CONTEXT_NS.into(), Json::Object(values)); }
+    (input, facts)
+}
+
+/// Key for a lookup: `params.key`, or the value at `params.key_from` (a path) in the input.
+fn lookup_key(params:&Json, input:&Json) -> Result<String> {
+    let v = match (params.get("key"), params.get("key_from").and_then(|p| p.as_array())) {
+        (Some(k), _) => k.clone(),
+        (None, Some(path)) => path.iter().try_fold(input, |cur, k| k.as_str().and_then(|k| cur.get(k)).ok_or_else(|| anyhow!("key_from {path:?} not in input")))?.clone(),
+        (None, None) => bail!("lookup needs key or key_from"),
+    };
+    Ok(match v { Json::String(s) => s, other => other.to_string() })
+}
+
+/// Splits one CSV record; `"..."` fields may contain commas and `""` escapes.
+fn csv_fields(line:&str) -> Vec<String> {
+    let mut out = vec![];
+    let mut cur = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => { cur.push('"'); chars.next(); }
+            ('"', _) => quoted = !quoted,
+            (',', false) => out.push(std::mem::take(&mut cur)),
+            _ => cur.push(c),
+        }
+    }
+    out.push(cur);
+    out
+}
+
+/// Lookup tables in a directory: `<table>.json` (object keyed by id) or `<table>.csv` (header row,
+/// first column is the key; a row becomes an object of strings). App registries and revocation
+/// lists are tables like any other. A missing key yields `null`, which is itself a fact.
+///
+/// Params: `{"table": "apps", "key": "app-1"}` or `{"table": "apps", "key_from": ["app", "id"]}`.
+pub struct LookupDir { dir: PathBuf }
+impl LookupDir {
+    pub fn new(dir:impl Into<PathBuf>) -> Self { Self{ dir: dir.into() } }
+}
+impl ContextProvider for LookupDir {
+    fn effects(&self) -> &[Effect] { &[Effect::Read] }
+    fn fetch(&self, params:&Json, input:&Json) -> Result<Fact> {
+        let table = params.get("table").and_then(|t| t.as_str()).ok_or_else(|| anyhow!("lookup needs table"))?;
+        if table.is_empty() || !table.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-') { bail!("invalid table name {table:?}"); }
+        let key = lookup_key(params, input)?;
+        let json = self.dir.join(format!("{table}.json"));
+        let (path, csv) = if json.exists() { (json, false) } else { (self.dir.join(format!("{table}.csv")), true) };
+        let bytes = std::fs::read(&path).with_context(|| format!("table {table}"))?;
+        let source_cid = format!("b3:{}", blake3::hash(&bytes).to_hex());
+        let value = if !csv {
+            let doc: Json = serde_json::from_slice(&bytes).with_context(|| format!("{}", path.display()))?;
+            doc.get(&key).cloned().unwrap_or(Json::Null)
+        } else {
+            let text = std::str::from_utf8(&bytes).with_context(|| format!("{}", path.display()))?;
+            let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+            let header = csv_fields(lines.next().ok_or_else(|| anyhow!("{}: no header", path.display()))?);
+            lines.map(csv_fields).find(|r| r.first() == Some(&key))
+                .map(|r| Json::Object(header.iter().cloned().zip(r.into_iter().map(Json::String)).collect()))
+                .unwrap_or(Json::Null)
+        };
+        Ok(Fact{ value, source_cid })
+    }
+}
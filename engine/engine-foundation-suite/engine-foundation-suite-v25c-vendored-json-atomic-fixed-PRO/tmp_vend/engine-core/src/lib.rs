@@ -1,6 +1,8 @@
+pub mod context;
 pub mod model;
 pub mod providers;
 pub mod runtime;
+pub mod verify;
 
 /// Public alias to avoid product/domain jargon.
 pub use crate::model::SemanticChip as AtomicUnit;
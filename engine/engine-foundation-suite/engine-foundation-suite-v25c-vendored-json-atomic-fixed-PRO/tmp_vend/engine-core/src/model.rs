@@ -130,10 +130,13 @@ pub struct SemanticChip {
     pub wiring: Wiring,
     pub required_effects: Vec<Effect>,
     pub hash: Option<String>,
+    /// Facts fetched by [`crate::context`] providers before evaluation, visible at `$context.<as>`.
+    #[serde(default)]
+    pub context: Vec<ContextDecl>,
 }
 impl SemanticChip {
-    pub fn builder(id:&str)->Builder { Builder{ chip: Self{ id:id.into(), name:None, policies:vec![], wiring: Wiring::All{policies:vec![]}, required_effects:vec![], hash:None } } }
-    pub fn simple(id:&str, pols:Vec<PolicyBit>, wiring:Wiring)->Self { Self{ id:id.into(), name:None, policies:pols, wiring, required_effects:vec![], hash:None } }
+    pub fn builder(id:&str)->Builder { Builder{ chip: Self{ id:id.into(), name:None, policies:vec![], wiring: Wiring::All{policies:vec![]}, required_effects:vec![], hash:None, context:vec![] } } }
+    pub fn simple(id:&str, pols:Vec<PolicyBit>, wiring:Wiring)->Self { Self{ id:id.into(), name:None, policies:pols, wiring, required_effects:vec![], hash:None, context:vec![] } }
     pub fn with_required_effects(mut self, e:Vec<Effect>)->Self { self.required_effects = e; self }
 }
 pub struct Builder{ chip: SemanticChip }
@@ -141,19 +144,85 @@ impl Builder {
     pub fn name(mut self, n:&str)->Self { self.chip.name=Some(n.into()); self }
     pub fn policy(mut self, p:PolicyBit)->Self { self.chip.policies.push(p); self }
     pub fn wiring(mut self, w:Wiring)->Self { self.chip.wiring=w; self }
+    pub fn context(mut self, d:ContextDecl)->Self { self.chip.context.push(d); self }
     pub fn build(self)->SemanticChip { self.chip }
 }
 
+/// One fact a unit needs: `provider` is asked with `params` and the answer lands at `$context.<as>`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContextDecl {
+    #[serde(rename = "as")]
+    pub name: String,
+    pub provider: String,
+    #[serde(default)]
+    pub params: Json,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+/// What a provider answered for one [`ContextDecl`]; `error` set means the fact was left out of the input.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CanonSlot { pub raw: Json, pub canon: Json, pub cid: String }
+pub struct ContextFact {
+    #[serde(rename = "as")]
+    pub name: String,
+    pub provider: String,
+    pub params: Json,
+    pub source_cid: Option<String>,
+    pub value_cid: Option<String>,
+    pub error: Option<String>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MissingInfo {
-    pub id: String,
-    pub reason: String,
-    pub missing_fields: Vec<String>,
-    pub missing_evidence: Vec<String>,
-    pub resolution_hint: Option<String>,
+pub struct CanonSlot { pub raw: Json, pub canon: Json, pub cid: String }
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PoiReason {
+    /// Required input is absent; `missing` lists it.
+    MissingFields,
+    /// A policy could not decide (evaluation error); `policies` names it.
+    PolicyDoubt,
+    /// The request could pass after changes; `violations` and `hints` say which.
+    ConstraintsNeedAdjustment,
+    /// A wasm unit declined with a typed error.
+    GuestError,
+    /// A wasm unit trapped or hit a limit.
+    WasmTrap,
+}
+
+/// Proof of Indecision: why a run is ASK rather than ACK/NACK, and what would settle it.
+/// Answering `missing` through `/run/resume` re-runs the unit with the original input plus the answers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProofOfIndecision {
+    pub reason: PoiReason,
+    /// Dotted input paths (`actor.role`).
+    #[serde(default)]
+    pub missing: Vec<String>,
+    #[serde(default)]
+    pub violations: Vec<String>,
+    #[serde(default)]
+    pub hints: Vec<String>,
+    /// Policies that returned `Doubt`.
+    #[serde(default)]
+    pub policies: Vec<String>,
+}
+impl ProofOfIndecision {
+    pub fn new(reason:PoiReason)->Self { Self{ reason, missing:vec![], violations:vec![], hints:vec![], policies:vec![] } }
+    pub fn missing(fields:&[&str])->Self { Self{ missing: fields.iter().map(|f| f.to_string()).collect(), ..Self::new(PoiReason::MissingFields) } }
+    pub fn violation(mut self, v:impl Into<String>)->Self { self.violations.push(v.into()); self }
+    pub fn hint(mut self, h:impl Into<String>)->Self { self.hints.push(h.into()); self }
+    /// `None` unless some policy returned `Doubt` or failed.
+    pub fn from_decisions(decisions:&[PolicyDecision])->Option<Self> {
+        let missing: Vec<_> = decisions.iter().filter(|d| d.decision == Decision::Doubt).flat_map(|d| d.missing_fields.clone()).collect();
+        let policies: Vec<_> = decisions.iter().filter(|d| d.decision == Decision::Doubt || d.error.is_some()).map(|d| d.policy_id.clone()).collect();
+        if missing.is_empty() && policies.is_empty() { return None; }
+        let poi = if missing.is_empty() {
+            Self::new(PoiReason::PolicyDoubt).hint(format!("Review policies: {}", policies.join(", ")))
+        } else {
+            Self::new(PoiReason::MissingFields).hint(format!("Provide missing fields: {}", missing.join(", ")))
+        };
+        Some(Self{ missing, policies, ..poi })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,8 +240,17 @@ pub struct ExecutionReceipt {
     pub policy_decisions: Vec<PolicyDecision>,
     pub output: CanonSlot,
     pub decision: Decision,
-    pub missing: Option<MissingInfo>,
+    pub poi: Option<ProofOfIndecision>,
     pub proof: Proof,
     pub timestamp: String,
     pub duration_ns: u64,
+    /// Chip-table generation the chip was taken from (`None` for chips run directly, e.g. by CID).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_generation: Option<u64>,
+    /// Facts gathered for `$context`, in declaration order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<ContextFact>,
+    /// Run CID of the ASK this run answers (see [`ProofOfIndecision`]); chained into the proof.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolves: Option<String>,
 }
@@ -127,6 +127,7 @@ impl ExprEval for DefaultExpr {
                     },
                     "is_string" => Json::Bool(args.first().map(|v| v.is_string()).unwrap_or(false)),
                     "is_number" => Json::Bool(args.first().map(|v| v.is_number()).unwrap_or(false)),
+                    "starts_with" => Json::Bool(matches!((args.first().and_then(|v| v.as_str()), args.get(1).and_then(|v| v.as_str())), (Some(s), Some(p)) if s.starts_with(p))),
                     _ => return Err(anyhow!("Unknown function: {function}")),
                 }.pipe(Ok)
             },
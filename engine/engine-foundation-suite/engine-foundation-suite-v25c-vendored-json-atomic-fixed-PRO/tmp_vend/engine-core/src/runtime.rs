@@ -4,14 +4,29 @@ use serde_json::Value as Json;
 use anyhow::{Result, anyhow};
 use crate::model::*;
 use crate::providers::*;
+use arc_swap::ArcSwap;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The chips an engine serves, replaced as a whole. `generation` is the unit-store generation it came from.
+#[derive(Debug, Clone, Default)]
+pub struct ChipTable { pub generation: u64, pub chips: HashMap<String, SemanticChip> }
+impl ChipTable {
+  pub fn new(generation:u64, chips:impl IntoIterator<Item=SemanticChip>)->Self {
+    Self{ generation, chips: chips.into_iter().map(|c| (c.id.clone(), c)).collect() }
+  }
+}
 
 pub struct Engine<G,E,A,CX,CD,S,T>
 where
   G: IdGen, E: ExprEval, A: AggregatorStrategy,
   CX: CanonProvider, CD: CidProvider, S: Signer, T: ReceiptSink
 {
-  chips: std::collections::HashMap<String, SemanticChip>,
+  /// Swapped by [`Engine::swap_chips`]; an execution keeps the snapshot it loaded until it finishes.
+  chips: Arc<ArcSwap<ChipTable>>,
   default_mode: EngineMode,
+  /// Named [`crate::context::ContextProvider`]s that units' `context` declarations refer to.
+  providers: crate::context::Providers,
   id: G, expr: E, agg: A, canon: CX, cid: CD, signer: S, sink: T, clock: Box<dyn Clock>,
 }
 
@@ -22,13 +37,14 @@ where
 {
   chips: std::collections::HashMap<String, SemanticChip>,
   default_mode: Option<EngineMode>,
+  providers: crate::context::Providers,
   id: Option<G>, expr: Option<E>, agg: Option<A>, canon: Option<CX>, cid: Option<CD>, signer: Option<S>, sink: Option<T>, clock: Option<Box<dyn Clock>>,
 }
 impl<G,E,A,CX,CD,S,T> Default for EngineBuilder<G,E,A,CX,CD,S,T>
 where G:IdGen+Default, E:ExprEval+Default, A:AggregatorStrategy+Default, CX:CanonProvider+Default, CD:CidProvider+Default, S:Signer+Default, T:ReceiptSink+Default
 {
   fn default()->Self {
-    Self{ chips:std::collections::HashMap::new(), default_mode:None, id:Some(G::default()), expr:Some(E::default()), agg:Some(A::default()), canon:Some(CX::default()), cid:Some(CD::default()), signer:Some(S::default()), sink:Some(T::default()), clock:None }
+    Self{ chips:std::collections::HashMap::new(), default_mode:None, providers:HashMap::new(), id:Some(G::default()), expr:Some(E::default()), agg:Some(A::default()), canon:Some(CX::default()), cid:Some(CD::default()), signer:Some(S::default()), sink:Some(T::default()), clock:None }
   }
 }
 impl Default for crate::providers::DefaultExpr { fn default()->Self{ Self } }
@@ -45,6 +61,7 @@ where G:IdGen, E:ExprEval, A:AggregatorStrategy, CX:CanonProvider, CD:CidProvide
   pub fn chip(mut self, c:SemanticChip)->Self{ self.chips.insert(c.id.clone(), c); self }
   pub fn chips(mut self, v:Vec<SemanticChip>)->Self{ for c in v { self.chips.insert(c.id.clone(), c); } self }
   pub fn default_mode(mut self, m:EngineMode)->Self{ self.default_mode = Some(m); self }
+  pub fn context_provider(mut self, name:&str, p:Arc<dyn crate::context::ContextProvider>)->Self{ self.providers.insert(name.into(), p); self }
   pub fn id(mut self, v:G)->Self{ self.id=Some(v); self }
   pub fn expr(mut self, v:E)->Self{ self.expr=Some(v); self }
   pub fn agg(mut self, v:A)->Self{ self.agg=Some(v); self }
@@ -60,8 +77,9 @@ where G:IdGen, E:ExprEval, A:AggregatorStrategy, CX:CanonProvider, CD:CidProvide
 
   pub fn build(self)->Engine<G,E,A,CX,CD,S,T> {
     Engine{
-      chips: self.chips,
+      chips: Arc::new(ArcSwap::from_pointee(ChipTable{ generation: 0, chips: self.chips })),
       default_mode: self.default_mode.unwrap_or_else(EngineMode::conservative),
+      providers: self.providers,
       id: self.id.expect("id provider"),
       expr: self.expr.expect("expr provider"),
       agg: self.agg.expect("agg provider"),
@@ -79,33 +97,59 @@ where
   G: IdGen, E: ExprEval, A: AggregatorStrategy,
   CX: CanonProvider, CD: CidProvider, S: Signer, T: ReceiptSink
 {
+  /// Shared handle to the chip table, for whoever drives reloads (e.g. a `UnitStore` activation hook).
+  pub fn chip_table(&self)->Arc<ArcSwap<ChipTable>> { self.chips.clone() }
+  pub fn swap_chips(&self, table:ChipTable) { self.chips.store(Arc::new(table)); }
+  pub fn chips_generation(&self)->u64 { self.chips.load().generation }
+
+  /// Runs against the table current at call time; the receipt records its generation.
   pub fn execute(&self, chip_id:&str, input: Json, mode: Option<EngineMode>) -> Result<ExecutionReceipt> {
+    self.run_id(chip_id, input, mode, None)
+  }
+
+  /// Run a chip that is not in the table (e.g. resolved from the registry by CID); no generation is recorded.
+  pub fn execute_chip(&self, chip:&SemanticChip, input: Json, mode: Option<EngineMode>) -> Result<ExecutionReceipt> {
+    self.run_chip(chip, None, input, mode, None)
+  }
+
+  /// [`Engine::execute`] answering the ASK run `ask`; the receipt's `resolves` links back to it.
+  pub fn resume(&self, chip_id:&str, input: Json, mode: Option<EngineMode>, ask:&str) -> Result<ExecutionReceipt> {
+    self.run_id(chip_id, input, mode, Some(ask))
+  }
+
+  /// [`Engine::execute_chip`] answering the ASK run `ask`.
+  pub fn resume_chip(&self, chip:&SemanticChip, input: Json, mode: Option<EngineMode>, ask:&str) -> Result<ExecutionReceipt> {
+    self.run_chip(chip, None, input, mode, Some(ask))
+  }
+
+  fn run_id(&self, chip_id:&str, input: Json, mode: Option<EngineMode>, resolves:Option<&str>) -> Result<ExecutionReceipt> {
+    let table = self.chips.load_full();
+    let chip = table.chips.get(chip_id).ok_or_else(|| anyhow!("Chip not found: {chip_id}"))?;
+    self.run_chip(chip, Some(table.generation), input, mode, resolves)
+  }
+
+  fn run_chip(&self, chip:&SemanticChip, generation:Option<u64>, input: Json, mode: Option<EngineMode>, resolves:Option<&str>) -> Result<ExecutionReceipt> {
     let start = std::time::Instant::now();
-    let chip = self.chips.get(chip_id).ok_or_else(|| anyhow!("Chip not found: {chip_id}"))?;
     let mode = mode.unwrap_or_else(|| self.default_mode.clone());
 
     if !mode.allows_all(&chip.required_effects) {
-      return Ok(denied_receipt(chip, mode, input, "Effects not allowed".into()));
+      return Ok(ExecutionReceipt{ unit_generation: generation, resolves: resolves.map(String::from), ..denied_receipt(chip, mode, input, "Effects not allowed".into()) });
     }
 
+    let (input, context) = crate::context::gather(&self.providers, &chip.context, input, &mode, &|v| self.cid.cid(&self.canon.canon(v)));
+
     let input_canon_bytes = self.canon.canon(&input);
     let input_canon: Json = serde_json::from_slice(&input_canon_bytes)?;
     let input_cid = self.cid.cid(&input_canon_bytes);
 
     let mut decisions = vec![];
-    let mut hash_chain = vec![input_cid.clone()];
+    let mut chain = HashChain::new(&self.canon, &self.cid, &input_cid);
+    for f in &context { chain.fact(f); }
+    if let Some(ask) = resolves { chain.resolves(ask); }
 
     for p in &chip.policies {
       let d = eval_policy_with(&self.expr, p, &input_canon, &mode);
-      let h = self.cid.cid(&self.canon.canon(&json!({
-          "policy": p.id,
-          "policy_hash": p.hash,
-          "decision": d.decision,
-          "skipped": d.skipped,
-          "input_cid": input_cid
-      })));
-
-      hash_chain.push(h);
+      chain.policy(p, &d);
       decisions.push(d);
     }
 
@@ -119,11 +163,11 @@ where
     let output_canon_bytes = self.canon.canon(&output);
     let output_canon: Json = serde_json::from_slice(&output_canon_bytes)?;
     let output_cid = self.cid.cid(&output_canon_bytes);
-    hash_chain.push(output_cid.clone());
+    let to_sign = chain.finish(&output_cid);
+    let hash_chain = chain.entries;
 
-    let missing = build_missing(&decisions);
+    let poi = ProofOfIndecision::from_decisions(&decisions);
 
-    let to_sign = self.canon.canon(&json!({ "input": input_cid, "output": output_cid, "hash_chain": hash_chain }));
     let signature = self.signer.sign(&to_sign).map(|b| base64::encode(b));
 
     let receipt = ExecutionReceipt {
@@ -134,10 +178,13 @@ where
       policy_decisions: decisions,
       output: CanonSlot{ raw: output.clone(), canon: output_canon, cid: output_cid },
       decision: final_decision,
-      missing,
+      poi,
       proof: Proof { hash_chain, signature },
       timestamp: self.clock.now_rfc3339(),
       duration_ns: start.elapsed().as_nanos() as u64,
+      unit_generation: generation,
+      context,
+      resolves: resolves.map(String::from),
     };
 
     let _ = self.sink.emit(&receipt);
@@ -199,30 +246,34 @@ pub fn eval_policy_with(E: &dyn ExprEval, policy:&PolicyBit, ctx:&Json, mode:&En
     }
 }
 
-pub fn build_missing(decisions:&[PolicyDecision]) -> Option<MissingInfo> {
-    let missing_fields: Vec<_> = decisions.iter()
-        .filter(|d| d.decision == Decision::Doubt)
-        .flat_map(|d| d.missing_fields.clone())
-        .collect();
-    let doubt_policies: Vec<_> = decisions.iter()
-        .filter(|d| d.decision == Decision::Doubt || d.error.is_some())
-        .map(|d| d.policy_id.clone()).collect();
-
-    if missing_fields.is_empty() && doubt_policies.is_empty() { return None; }
-    let reason = if !missing_fields.is_empty() { "missing_fields" } else { "policy_doubt" };
-    let resolution_hint = if !missing_fields.is_empty() {
-        Some(format!("Provide missing fields: {}", missing_fields.join(", ")))
-    } else if !doubt_policies.is_empty() {
-        Some(format!("Review policies: {}", doubt_policies.join(", ")))
-    } else { None };
-
-    Some(MissingInfo{
-        id: ulid::Ulid::new().to_string(),
-        reason: reason.into(),
-        missing_fields,
-        missing_evidence: doubt_policies,
-        resolution_hint,
-    })
+/// The receipt's `proof.hash_chain`, built entry by entry in execution order. [`crate::verify`] rebuilds
+/// it with the same calls, so a run and its replay cannot drift apart.
+pub(crate) struct HashChain<'a> { canon:&'a dyn CanonProvider, cid:&'a dyn CidProvider, input_cid:String, pub(crate) entries:Vec<String> }
+
+impl<'a> HashChain<'a> {
+    /// Starts with the input's CID.
+    pub(crate) fn new(canon:&'a dyn CanonProvider, cid:&'a dyn CidProvider, input_cid:&str) -> Self {
+        Self{ canon, cid, input_cid: input_cid.into(), entries: vec![input_cid.into()] }
+    }
+    fn push(&mut self, v:&Json) { self.entries.push(self.cid.cid(&self.canon.canon(v))); }
+    /// Fact records (provider, params, source CID) are chained right after the input they were merged into.
+    pub(crate) fn fact(&mut self, f:&ContextFact) { self.push(&json!(f)); }
+    pub(crate) fn resolves(&mut self, ask:&str) { self.push(&json!({ "resolves": ask })); }
+    pub(crate) fn policy(&mut self, p:&PolicyBit, d:&PolicyDecision) {
+        let entry = json!({
+            "policy": p.id,
+            "policy_hash": p.hash,
+            "decision": d.decision,
+            "skipped": d.skipped,
+            "input_cid": self.input_cid
+        });
+        self.push(&entry);
+    }
+    /// Closes the chain with the output's CID; returns the payload a signer signs.
+    pub(crate) fn finish(&mut self, output_cid:&str) -> Vec<u8> {
+        self.entries.push(output_cid.into());
+        self.canon.canon(&json!({ "input": self.input_cid, "output": output_cid, "hash_chain": self.entries }))
+    }
 }
 
 pub fn denied_receipt(chip:&SemanticChip, mode:EngineMode, input:Json, reason:String)->ExecutionReceipt {
@@ -231,14 +282,16 @@ pub fn denied_receipt(chip:&SemanticChip, mode:EngineMode, input:Json, reason:St
     let out = json!({"error": reason, "decision":"deny"});
     let out_canon = crate::providers::DefaultCanon{}.canon(&out);
     let out_cid = crate::providers::DefaultCid{}.cid(&out_canon);
+    let mut chain = HashChain::new(&crate::providers::DefaultCanon, &crate::providers::DefaultCid, &input_cid);
+    chain.finish(&out_cid);
     ExecutionReceipt{
         chip_id: chip.id.clone(), chip_hash: chip.hash.clone().unwrap_or_default(),
         mode, input: CanonSlot{ raw: input, canon: serde_json::from_slice(&canon).unwrap(), cid: input_cid.clone() },
         policy_decisions: vec![],
         output: CanonSlot{ raw: out, canon: serde_json::from_slice(&out_canon).unwrap(), cid: out_cid.clone() },
-        decision: Decision::Deny, missing: None,
-        proof: Proof{ hash_chain: vec![input_cid, out_cid], signature: None },
-        timestamp: chrono::Utc::now().to_rfc3339(), duration_ns: 0
+        decision: Decision::Deny, poi: None,
+        proof: Proof{ hash_chain: chain.entries, signature: None },
+        timestamp: chrono::Utc::now().to_rfc3339(), duration_ns: 0, unit_generation: None, context: vec![], resolves: None
     }
 }
 
@@ -0,0 +1,163 @@
+//! Offline replay of a receipt against the unit it names.
+//!
+//! [`replay`] redoes what [`crate::runtime::Engine`] did for the run, in the same order: canonical
+//! input and its CID, context fact records, the `resolves` link, every policy (evaluated again under
+//! the receipt's mode) and its chain entry, the aggregation, the output and its CID, the Proof of
+//! Indecision, and finally the payload the signer signed. It stops at the first step whose result
+//! differs from what the receipt records and reports it as a [`Divergence`] with a stable [`Code`].
+//! Context facts are not fetched again: the receipt's input already carries them under `$context`,
+//! and each must hash to its recorded `value_cid`.
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+
+use crate::context::CONTEXT_NS;
+use crate::model::*;
+use crate::providers::*;
+use crate::runtime::{eval_policy_with, HashChain};
+
+/// Stable identifiers for where a receipt departs from its replay; never renamed, only added to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Code {
+    /// `chip_id` or `chip_hash` is not the unit replayed.
+    UnitMismatch,
+    InputCanonMismatch,
+    InputCidMismatch,
+    /// Fact records differ from the unit's declarations, or a fact value from its `value_cid`.
+    ContextMismatch,
+    /// Policy ids or order differ from the unit's.
+    PolicySetMismatch,
+    PolicyDecisionMismatch,
+    PolicyHashMismatch,
+    AggregationMismatch,
+    OutputMismatch,
+    OutputCidMismatch,
+    PoiMismatch,
+    /// An entry, or the length, of `proof.hash_chain`.
+    HashChainMismatch,
+    /// Reported by callers that check `proof.signature` over [`Verdict::payload`], unless [`Verdict::unsigned`].
+    SignatureMissing,
+    SignatureInvalid,
+    /// Reported by callers that could not parse the receipt or the unit.
+    Malformed,
+}
+
+/// First point where the receipt and its replay disagree. `at` is a JSON pointer into the receipt.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Divergence { pub code: Code, pub at: String, pub expected: Json, pub got: Json }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Verdict {
+    pub kind: String,                      // "receipt.verdict.v1"
+    pub ok: bool,
+    pub chip_id: String,
+    pub input_cid: String,
+    pub output_cid: String,
+    /// CID of the replayed signed payload; set when the replay got that far.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signed_payload_cid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub divergence: Option<Divergence>,
+    /// The receipt is an effects denial, which the engine issues without a signature: there is none to check.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unsigned: bool,
+    /// Bytes the engine's signer signed, for checking `proof.signature`.
+    #[serde(skip)]
+    pub payload: Vec<u8>,
+}
+
+impl Verdict {
+    /// Records a divergence found outside the replay (e.g. a bad signature), unless one is already set.
+    pub fn fail(&mut self, code:Code, at:&str, expected:Json, got:Json) {
+        if self.divergence.is_none() { self.divergence = Some(Divergence{ code, at: at.into(), expected, got }); }
+        self.ok = false;
+    }
+}
+
+fn check(code:Code, at:impl Into<String>, expected:Json, got:Json) -> Result<(), Divergence> {
+    if expected == got { Ok(()) } else { Err(Divergence{ code, at: at.into(), expected, got }) }
+}
+
+/// [`replay_with`] using the default providers, i.e. what a stock engine runs.
+pub fn replay(chip:&SemanticChip, receipt:&ExecutionReceipt) -> Verdict {
+    replay_with(&DefaultExpr, &DefaultAggregator, &DefaultCanon, &DefaultCid, chip, receipt)
+}
+
+pub fn replay_with(expr:&dyn ExprEval, agg:&dyn AggregatorStrategy, canon:&dyn CanonProvider, cid:&dyn CidProvider, chip:&SemanticChip, receipt:&ExecutionReceipt) -> Verdict {
+    let mut v = Verdict{
+        kind: "receipt.verdict.v1".into(), ok: false, chip_id: receipt.chip_id.clone(),
+        input_cid: receipt.input.cid.clone(), output_cid: receipt.output.cid.clone(),
+        signed_payload_cid: None, divergence: None, unsigned: false, payload: vec![],
+    };
+    match replay_steps(expr, agg, canon, cid, chip, receipt) {
+        Ok(payload) => {
+            v.ok = true;
+            v.unsigned = !receipt.mode.allows_all(&chip.required_effects);
+            v.signed_payload_cid = Some(cid.cid(&payload));
+            v.payload = payload;
+        }
+        Err(d) => v.divergence = Some(d),
+    }
+    v
+}
+
+fn replay_steps(expr:&dyn ExprEval, agg:&dyn AggregatorStrategy, canon:&dyn CanonProvider, cid:&dyn CidProvider, chip:&SemanticChip, r:&ExecutionReceipt) -> Result<Vec<u8>, Divergence> {
+    let cid_of = |v:&Json| cid.cid(&canon.canon(v));
+    let parse = |b:&[u8]| serde_json::from_slice::<Json>(b).unwrap_or(Json::Null);
+    check(Code::UnitMismatch, "/chip_id", json!(chip.id), json!(r.chip_id))?;
+    check(Code::UnitMismatch, "/chip_hash", json!(chip.hash.clone().unwrap_or_default()), json!(r.chip_hash))?;
+
+    let input_canon = canon.canon(&r.input.raw);
+    check(Code::InputCanonMismatch, "/input/canon", parse(&input_canon), r.input.canon.clone())?;
+    let input_cid = cid.cid(&input_canon);
+    check(Code::InputCidMismatch, "/input/cid", json!(input_cid), json!(r.input.cid))?;
+    let mut chain = HashChain::new(canon, cid, &input_cid);
+
+    // A unit whose effects the mode did not allow gets a fixed denial and no evaluation.
+    if !r.mode.allows_all(&chip.required_effects) {
+        let output = json!({"error": "Effects not allowed", "decision": "deny"});
+        check(Code::PolicySetMismatch, "/policy_decisions", json!([]), json!(r.policy_decisions.iter().map(|d| &d.policy_id).collect::<Vec<_>>()))?;
+        check(Code::AggregationMismatch, "/decision", json!(Decision::Deny), json!(r.decision))?;
+        return finish(canon, cid, r, output, chain, None);
+    }
+
+    check(Code::ContextMismatch, "/context", json!(chip.context.iter().map(|d| (&d.name, &d.provider, &d.params)).collect::<Vec<_>>()),
+        json!(r.context.iter().map(|f| (&f.name, &f.provider, &f.params)).collect::<Vec<_>>()))?;
+    for (i, f) in r.context.iter().enumerate() {
+        let value = r.input.canon.get(CONTEXT_NS).and_then(|c| c.get(&f.name));
+        check(Code::ContextMismatch, format!("/context/{i}/value_cid"), json!(value.map(cid_of)), json!(f.value_cid))?;
+        chain.fact(f);
+    }
+    if let Some(ask) = &r.resolves { chain.resolves(ask); }
+
+    check(Code::PolicySetMismatch, "/policy_decisions", json!(chip.policies.iter().map(|p| &p.id).collect::<Vec<_>>()),
+        json!(r.policy_decisions.iter().map(|d| &d.policy_id).collect::<Vec<_>>()))?;
+    let mut decisions = Vec::with_capacity(chip.policies.len());
+    for (i, (p, got)) in chip.policies.iter().zip(&r.policy_decisions).enumerate() {
+        let d = eval_policy_with(expr, p, &r.input.canon, &r.mode);
+        check(Code::PolicyDecisionMismatch, format!("/policy_decisions/{i}"), json!({"decision": d.decision, "skipped": d.skipped}), json!({"decision": got.decision, "skipped": got.skipped}))?;
+        check(Code::PolicyHashMismatch, format!("/policy_decisions/{i}/policy_hash"), json!(d.policy_hash), json!(got.policy_hash))?;
+        chain.policy(p, &d);
+        decisions.push(d);
+    }
+
+    let decision = agg.aggregate(&chip.wiring, &decisions);
+    check(Code::AggregationMismatch, "/decision", json!(decision), json!(r.decision))?;
+    let output = json!({ "chip_id": chip.id, "decision": decision, "policy_count": decisions.len() });
+    finish(canon, cid, r, output, chain, ProofOfIndecision::from_decisions(&decisions))
+}
+
+/// Output, PoI and the full chain, then the signed payload.
+fn finish(canon:&dyn CanonProvider, cid:&dyn CidProvider, r:&ExecutionReceipt, output:Json, mut chain:HashChain, poi:Option<ProofOfIndecision>) -> Result<Vec<u8>, Divergence> {
+    check(Code::OutputMismatch, "/output/raw", output.clone(), r.output.raw.clone())?;
+    let output_cid = cid.cid(&canon.canon(&output));
+    check(Code::OutputCidMismatch, "/output/cid", json!(output_cid), json!(r.output.cid))?;
+    check(Code::PoiMismatch, "/poi", json!(poi), json!(r.poi))?;
+    let payload = chain.finish(&output_cid);
+    let got = &r.proof.hash_chain;
+    for (i, h) in chain.entries.iter().enumerate() {
+        check(Code::HashChainMismatch, format!("/proof/hash_chain/{i}"), json!(h), json!(got.get(i)))?;
+    }
+    check(Code::HashChainMismatch, "/proof/hash_chain", json!(chain.entries.len()), json!(got.len()))?;
+    Ok(payload)
+}
@@ -0,0 +1,105 @@
+use anyhow::{bail, Result};
+use engine_core::context::{ContextProvider, Fact, LookupDir, FETCH_WORKERS};
+use engine_core::model::*;
+use engine_core::runtime::Engine;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+struct Slow;
+impl ContextProvider for Slow {
+    fn effects(&self) -> &[Effect] { &[Effect::Read] }
+    fn fetch(&self, _:&Value, _:&Value) -> Result<Fact> {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        Ok(Fact{ value: json!(true), source_cid: "b3:slow".into() })
+    }
+}
+
+struct Remote;
+impl ContextProvider for Remote {
+    fn effects(&self) -> &[Effect] { &[Effect::Network] }
+    fn fetch(&self, _:&Value, _:&Value) -> Result<Fact> { Ok(Fact{ value: json!(true), source_cid: "b3:remote".into() }) }
+}
+
+fn decl(name:&str, provider:&str, params:Value) -> ContextDecl {
+    ContextDecl{ name: name.into(), provider: provider.into(), params, timeout_ms: Some(50) }
+}
+
+fn tables() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("apps.json"), r#"{"app-1": {"tier": "gold"}}"#).unwrap();
+    std::fs::write(dir.path().join("revoked.csv"), "token,reason\n\"t-9\",\"leaked, rotated\"\n").unwrap();
+    dir
+}
+
+#[test]
+fn facts_are_merged_and_recorded_with_their_source() {
+    let dir = tables();
+    let policy = PolicyBit::new("gold", "gold tier")
+        .requires(&["$context", "app", "tier"])
+        .condition(Expression::eq(Expression::context(&["$context", "app", "tier"]), Expression::literal("gold")));
+    let chip = SemanticChip::builder("gated").policy(policy).wiring(Wiring::All{ policies: vec!["gold".into()] })
+        .context(decl("app", "lookup", json!({"table": "apps", "key_from": ["app_id"]})))
+        .context(decl("revoked", "lookup", json!({"table": "revoked", "key": "t-9"})))
+        .build();
+    let engine = Engine::default().context_provider("lookup", Arc::new(LookupDir::new(dir.path()))).chip(chip).build();
+
+    let r = engine.execute("gated", json!({"app_id": "app-1", "$context": {"app": {"tier": "gold"}}}), None).unwrap();
+    assert_eq!(r.decision, Decision::Allow);
+    assert_eq!(r.input.raw["$context"]["revoked"]["reason"], "leaked, rotated");
+    let apps = std::fs::read(dir.path().join("apps.json")).unwrap();
+    assert_eq!(r.context[0].source_cid.as_deref(), Some(format!("b3:{}", blake3::hash(&apps).to_hex()).as_str()));
+    assert_eq!(r.proof.hash_chain.len(), 1 + 2 + 1 + 1);
+
+    // A caller-supplied `$context` never reaches the policy; an unknown key is a `null` fact, so `tier` is missing.
+    let r = engine.execute("gated", json!({"app_id": "app-2", "$context": {"app": {"tier": "gold"}}}), None).unwrap();
+    assert!(r.input.raw["$context"]["app"].is_null());
+    assert_eq!(r.decision, Decision::Doubt);
+}
+
+#[test]
+fn slow_or_disallowed_providers_leave_the_fact_out() {
+    let chip = SemanticChip::builder("c").wiring(Wiring::All{ policies: vec![] })
+        .context(decl("slow", "slow", Value::Null))
+        .context(decl("remote", "remote", Value::Null))
+        .context(decl("nope", "missing", Value::Null))
+        .build();
+    let engine = Engine::default().context_provider("slow", Arc::new(Slow)).context_provider("remote", Arc::new(Remote)).build();
+    let r = engine.execute_chip(&chip, json!({}), None).unwrap();
+    assert_eq!(r.input.raw["$context"], json!({}));
+    let errors: Vec<_> = r.context.iter().map(|f| f.error.clone().unwrap()).collect();
+    assert!(errors[0].contains("timed out"), "{errors:?}");
+    assert!(errors[1].contains("not allowed"), "{errors:?}");
+    assert!(errors[2].contains("unknown provider"), "{errors:?}");
+
+    let mut mode = EngineMode::conservative();
+    mode.enabled_effects.insert(Effect::Network);
+    let r = engine.execute_chip(&chip, json!({}), Some(mode)).unwrap();
+    assert_eq!(r.input.raw["$context"]["remote"], true);
+    assert_eq!(r.context[1].source_cid.as_deref(), Some("b3:remote"));
+}
+
+/// Sleeps past every deadline and records how many of its fetches run at once.
+#[derive(Default)]
+struct Stuck { running: std::sync::atomic::AtomicUsize, peak: std::sync::atomic::AtomicUsize }
+impl ContextProvider for Stuck {
+    fn effects(&self) -> &[Effect] { &[Effect::Read] }
+    fn fetch(&self, _:&Value, _:&Value) -> Result<Fact> {
+        use std::sync::atomic::Ordering::SeqCst;
+        self.peak.fetch_max(self.running.fetch_add(1, SeqCst) + 1, SeqCst);
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        self.running.fetch_sub(1, SeqCst);
+        bail!("too late")
+    }
+}
+
+#[test]
+fn overrunning_providers_hold_a_bounded_number_of_threads() {
+    let stuck = Arc::new(Stuck::default());
+    let mut builder = SemanticChip::builder("stuck").wiring(Wiring::All{ policies: vec![] });
+    for i in 0..3 * FETCH_WORKERS { builder = builder.context(decl(&format!("f{i}"), "stuck", Value::Null)); }
+    let chip = builder.build();
+    let engine = Engine::default().context_provider("stuck", stuck.clone()).build();
+    let r = engine.execute_chip(&chip, json!({}), None).unwrap();
+    assert!(r.context.iter().all(|f| f.error.as_deref().is_some_and(|e| e.contains("timed out"))), "{:?}", r.context);
+    assert!(stuck.peak.load(std::sync::atomic::Ordering::SeqCst) <= FETCH_WORKERS);
+}
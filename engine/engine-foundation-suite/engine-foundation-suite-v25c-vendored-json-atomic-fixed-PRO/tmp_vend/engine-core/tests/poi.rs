@@ -0,0 +1,37 @@
+use engine_core::model::*;
+use engine_core::runtime::Engine;
+use serde_json::json;
+
+fn chip() -> SemanticChip {
+    let role = PolicyBit::new("has_role", "actor role")
+        .requires(&["actor", "role"])
+        .condition(Expression::eq(Expression::context(&["actor", "role"]), Expression::literal("admin")));
+    SemanticChip::builder("gated").policy(role).wiring(Wiring::All{ policies: vec!["has_role".into()] }).build()
+}
+
+#[test]
+fn doubt_carries_a_typed_poi() {
+    let engine = Engine::default().chip(chip()).build();
+    let r = engine.execute("gated", json!({}), None).unwrap();
+    assert_eq!(r.decision, Decision::Doubt);
+    let poi = r.poi.unwrap();
+    assert_eq!(poi.reason, PoiReason::MissingFields);
+    assert_eq!(poi.missing, vec!["actor.role".to_string()]);
+    assert_eq!(poi.policies, vec!["has_role".to_string()]);
+    assert_eq!(serde_json::to_value(&poi).unwrap()["reason"], "missing_fields");
+
+    let r = engine.execute("gated", json!({"actor": {"role": "admin"}}), None).unwrap();
+    assert!(r.poi.is_none() && r.resolves.is_none());
+}
+
+#[test]
+fn resumed_receipts_chain_to_the_ask() {
+    let engine = Engine::default().chip(chip()).build();
+    let input = json!({"actor": {"role": "admin"}});
+    let plain = engine.execute("gated", input.clone(), None).unwrap();
+    let resumed = engine.resume("gated", input, None, "b3:ask").unwrap();
+    assert_eq!(resumed.decision, Decision::Allow);
+    assert_eq!(resumed.resolves.as_deref(), Some("b3:ask"));
+    assert_eq!(resumed.proof.hash_chain.len(), plain.proof.hash_chain.len() + 1);
+    assert_ne!(resumed.proof.hash_chain[1], plain.proof.hash_chain[1]);
+}
@@ -0,0 +1,68 @@
+use engine_core::model::*;
+use engine_core::runtime::Engine;
+use engine_core::verify::{replay, Code};
+use serde_json::json;
+
+fn chip() -> SemanticChip {
+    let role = PolicyBit::new("has_role", "actor role")
+        .requires(&["actor", "role"])
+        .condition(Expression::eq(Expression::context(&["actor", "role"]), Expression::literal("admin")));
+    let small = PolicyBit::new("small", "amount").condition(Expression::not(Expression::gt(Expression::context(&["amount"]), Expression::literal(100))));
+    let mut c = SemanticChip::builder("gated").policy(role).policy(small)
+        .wiring(Wiring::All{ policies: vec!["has_role".into(), "small".into()] }).build();
+    c.hash = Some("b3:gated".into());
+    c
+}
+
+#[test]
+fn untouched_receipts_replay_to_the_signed_payload() {
+    let chip = chip();
+    let engine = Engine::default().build();
+    for input in [json!({"actor": {"role": "admin"}, "amount": 5}), json!({"amount": 500})] {
+        let r = engine.execute_chip(&chip, input, None).unwrap();
+        let v = replay(&chip, &r);
+        assert!(v.ok, "{:?}", v.divergence);
+        assert_eq!(v.payload, serde_json::to_vec(&json!({"input": r.input.cid, "output": r.output.cid, "hash_chain": r.proof.hash_chain})).unwrap());
+    }
+    let r = engine.resume_chip(&chip, json!({"actor": {"role": "admin"}, "amount": 5}), None, "b3:ask").unwrap();
+    assert!(replay(&chip, &r).ok);
+}
+
+#[test]
+fn the_first_divergence_is_reported() {
+    let chip = chip();
+    let r = Engine::default().build().execute_chip(&chip, json!({"actor": {"role": "user"}, "amount": 5}), None).unwrap();
+    let code = |r:&ExecutionReceipt| replay(&chip, r).divergence.map(|d| (d.code, d.at));
+
+    let mut t = r.clone();
+    t.input.raw["actor"]["role"] = json!("admin");
+    assert_eq!(code(&t), Some((Code::InputCanonMismatch, "/input/canon".into())));
+
+    // Consistent input, but the recorded decision is not what the policy yields.
+    let mut t = r.clone();
+    t.policy_decisions[0].decision = Decision::Allow;
+    assert_eq!(code(&t), Some((Code::PolicyDecisionMismatch, "/policy_decisions/0".into())));
+
+    let mut t = r.clone();
+    t.decision = Decision::Allow;
+    assert_eq!(code(&t), Some((Code::AggregationMismatch, "/decision".into())));
+
+    let mut t = r.clone();
+    t.proof.hash_chain.swap(1, 2);
+    assert_eq!(code(&t), Some((Code::HashChainMismatch, "/proof/hash_chain/1".into())));
+
+    let mut other = chip.clone();
+    other.hash = Some("b3:other".into());
+    assert_eq!(replay(&other, &r).divergence.unwrap().code, Code::UnitMismatch);
+}
+
+#[test]
+fn effects_denials_replay_as_unsigned() {
+    let chip = chip().with_required_effects(vec![Effect::Network]);
+    let r = Engine::default().build().execute_chip(&chip, json!({"amount": 5}), None).unwrap();
+    assert_eq!((&r.decision, r.proof.signature.as_deref()), (&Decision::Deny, None));
+    let v = replay(&chip, &r);
+    assert!(v.ok && v.unsigned, "{:?}", v.divergence);
+    let plain = self::chip();
+    assert!(!replay(&plain, &Engine::default().build().execute_chip(&plain, json!({"amount": 5}), None).unwrap()).unsigned);
+}
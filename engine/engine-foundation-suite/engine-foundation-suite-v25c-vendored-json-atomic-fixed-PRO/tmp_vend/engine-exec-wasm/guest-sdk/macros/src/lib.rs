@@ -0,0 +1,104 @@
+//! `#[tdln_unit]`: generates the deterministic ABI exports for a guest unit. See `guest-sdk`.
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, visit::Visit, Error, FnArg, Item, ItemFn, ItemImpl};
+
+/// `std` modules that reach outside the sandbox (or only make sense there).
+const FORBIDDEN_STD: &[&str] = &["time", "env", "fs", "net", "process", "thread"];
+/// Crates whose whole purpose is entropy.
+const FORBIDDEN_CRATES: &[&str] = &["rand", "getrandom", "fastrand", "uuid", "chrono"];
+
+/// Best-effort compile-time guard over the annotated item. The conformance scan in
+/// `engine-exec-wasm` is authoritative; this just fails earlier with a pointer at the line.
+#[derive(Default)]
+struct Nondet { errors: Vec<Error> }
+
+impl<'ast> Visit<'ast> for Nondet {
+    fn visit_path(&mut self, p: &'ast syn::Path) {
+        let segs: Vec<String> = p.segments.iter().map(|s| s.ident.to_string()).collect();
+        let hit = match segs.as_slice() {
+            [root, m, ..] if (root == "std" || root == "core") && FORBIDDEN_STD.contains(&m.as_str()) => Some(format!("{root}::{m}")),
+            [root, _, ..] if FORBIDDEN_CRATES.contains(&root.as_str()) => Some(root.clone()),
+            [ty] if ty == "f32" || ty == "f64" => Some(format!("{ty} (use det::fixed)")),
+            _ => None,
+        };
+        if let Some(what) = hit { self.errors.push(Error::new_spanned(p, format!("nondeterministic in a tdln unit: {what}"))); }
+        syn::visit::visit_path(self, p);
+    }
+    fn visit_lit_float(&mut self, l: &'ast syn::LitFloat) {
+        self.errors.push(Error::new_spanned(l, "float literal in a tdln unit (use det::fixed)"));
+    }
+}
+
+#[proc_macro_attribute]
+pub fn tdln_unit(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let fold = match attr.to_string().trim() {
+        "" => false,
+        "fold" => true,
+        other => return Error::new(Span::call_site(), format!("unknown tdln_unit option `{other}` (expected `fold`)")).to_compile_error().into(),
+    };
+    let item = parse_macro_input!(item as Item);
+    let mut scan = Nondet::default();
+    scan.visit_item(&item);
+    if let Some(mut err) = scan.errors.pop() {
+        for e in scan.errors { err.combine(e); }
+        return err.to_compile_error().into();
+    }
+    let out = match (fold, item) {
+        (false, Item::Fn(f)) => run_unit(f),
+        (true, Item::Impl(i)) => fold_unit(i),
+        (false, other) => Err(Error::new_spanned(other, "#[tdln_unit] expects `fn(Input) -> UnitResult<Output>`")),
+        (true, other) => Err(Error::new_spanned(other, "#[tdln_unit(fold)] expects `impl Fold for T`")),
+    };
+    out.unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn common_exports() -> proc_macro2::TokenStream {
+    quote! {
+        #[no_mangle]
+        pub extern "C" fn alloc(len: i32) -> i32 { ::guest_sdk::abi::alloc(len) }
+        #[no_mangle]
+        pub extern "C" fn dealloc(ptr: i32, len: i32) { unsafe { ::guest_sdk::abi::dealloc(ptr, len) } }
+        #[no_mangle]
+        pub extern "C" fn tdln_abi_version() -> i32 { ::guest_sdk::ABI_VERSION }
+    }
+}
+
+fn run_unit(f: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let sig = &f.sig;
+    if let Some(a) = &sig.asyncness { return Err(Error::new_spanned(a, "tdln units are synchronous")); }
+    if !sig.generics.params.is_empty() { return Err(Error::new_spanned(&sig.generics, "tdln units cannot be generic")); }
+    if sig.inputs.len() != 1 || matches!(sig.inputs.first(), Some(FnArg::Receiver(_))) {
+        return Err(Error::new_spanned(&sig.inputs, "tdln units take exactly one input argument"));
+    }
+    let name = &sig.ident;
+    let common = common_exports();
+    Ok(quote! {
+        #f
+        #common
+        #[no_mangle]
+        pub extern "C" fn run(ptr: i32, len: i32) -> (i32, i32) { unsafe { ::guest_sdk::abi::run(ptr, len, #name) } }
+    })
+}
+
+fn fold_unit(i: ItemImpl) -> syn::Result<proc_macro2::TokenStream> {
+    let is_fold = i.trait_.as_ref().and_then(|(_, p, _)| p.segments.last()).is_some_and(|s| s.ident == "Fold");
+    if !is_fold { return Err(Error::new_spanned(&i.self_ty, "#[tdln_unit(fold)] expects `impl Fold for T`")); }
+    if !i.generics.params.is_empty() { return Err(Error::new_spanned(&i.generics, "tdln units cannot be generic")); }
+    let ty = &i.self_ty;
+    let common = common_exports();
+    Ok(quote! {
+        #i
+        #common
+        ::std::thread_local! {
+            static __TDLN_FOLD: ::std::cell::RefCell<::guest_sdk::abi::FoldCell<#ty>> = ::std::default::Default::default();
+        }
+        #[no_mangle]
+        pub extern "C" fn fold_init(ptr: i32, len: i32) { __TDLN_FOLD.with(|c| unsafe { c.borrow_mut().init(ptr, len) }) }
+        #[no_mangle]
+        pub extern "C" fn fold_step(ptr: i32, len: i32) -> (i32, i32) { __TDLN_FOLD.with(|c| unsafe { c.borrow_mut().step(ptr, len) }) }
+        #[no_mangle]
+        pub extern "C" fn fold_finish() -> (i32, i32) { __TDLN_FOLD.with(|c| c.borrow_mut().finish()) }
+    })
+}
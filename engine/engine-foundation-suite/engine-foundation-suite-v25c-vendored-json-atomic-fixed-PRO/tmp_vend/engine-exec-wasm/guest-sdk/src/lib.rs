@@ -0,0 +1,168 @@
+//! Authoring kit for deterministic Engine units (wasm32-unknown-unknown).
+//!
+//! `#[tdln_unit]` on `fn(I) -> UnitResult<O>` generates `alloc`, `dealloc`, `tdln_abi_version` and `run`;
+//! `#[tdln_unit(fold)]` on an `impl Fold for T` generates the streaming exports instead. One unit per crate.
+//! Output goes back as `{"ok": O}` / `{"error": {"code","message"}}` (ABI v2), so bad input is a typed
+//! error instead of a silent `null`. Helpers in [`det`] never touch time, randomness or floats.
+pub use guest_sdk_macros::tdln_unit;
+pub use serde_json::{self, json, Value as Json};
+
+use serde::{Serialize, Deserialize};
+
+/// Must match `engine_exec_wasm::GUEST_ABI_VERSION`.
+pub const ABI_VERSION: i32 = 2;
+
+/// Typed failure sent back to the host; surfaces as a Doubt with `poi.reason = "guest_error"`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnitError { pub code: String, pub message: String }
+impl UnitError {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self { Self{ code: code.into(), message: message.into() } }
+    pub fn invalid_input(message: impl Into<String>) -> Self { Self::new("invalid_input", message) }
+    pub fn missing(field: &str) -> Self { Self::new("missing_field", field) }
+}
+
+pub type UnitResult<T = Json> = Result<T, UnitError>;
+
+/// Streaming fold: one record in, at most one record out; `finish` yields the summary.
+/// The first error latches: later records are skipped and `fold_finish` reports it.
+pub trait Fold: Default {
+    fn init(&mut self, _params: Json) -> UnitResult<()> { Ok(()) }
+    fn step(&mut self, record: Json) -> UnitResult<Option<Json>>;
+    fn finish(&mut self) -> UnitResult<Json>;
+}
+
+/// Deterministic helpers. Everything here is a pure function of its arguments.
+pub mod det {
+    use super::{Json, UnitError, UnitResult};
+
+    /// JSON✯Atomic bytes (sorted keys, minified) — same bytes the host hashes.
+    pub fn canonical_bytes(v: &Json) -> Vec<u8> {
+        // serde_json's default map is a BTreeMap, so keys already serialize sorted.
+        serde_json::to_vec(v).expect("Value always serializes")
+    }
+
+    /// `b3:<hex>` of the canonical bytes (engine-core CID format).
+    pub fn cid(v: &Json) -> String { format!("b3:{}", blake3::hash(&canonical_bytes(v)).to_hex()) }
+
+    /// Dotted-path lookup (`"actor.role"`); array indices are plain numbers (`"items.0"`).
+    pub fn get<'a>(v: &'a Json, path: &str) -> Option<&'a Json> {
+        path.split('.').try_fold(v, |cur, seg| match cur {
+            Json::Object(m) => m.get(seg),
+            Json::Array(a) => seg.parse::<usize>().ok().and_then(|i| a.get(i)),
+            _ => None,
+        })
+    }
+
+    /// Like [`get`], but a missing path is a `missing_field` error.
+    pub fn require<'a>(v: &'a Json, path: &str) -> UnitResult<&'a Json> { get(v, path).ok_or_else(|| UnitError::missing(path)) }
+
+    /// Decimal number (or numeric string) as a scaled integer: `fixed("12.5", 2) == 1250`.
+    /// Numbers keep their source text (`arbitrary_precision`), so no float ever enters the unit.
+    pub fn fixed(v: &Json, scale: u32) -> UnitResult<i128> {
+        let text = match v { Json::Number(n) => n.to_string(), Json::String(s) => s.clone(), _ => return Err(UnitError::invalid_input("expected a decimal")) };
+        let bad = || UnitError::invalid_input(format!("not a decimal with at most {scale} places: {text}"));
+        let (neg, body) = match text.strip_prefix('-') { Some(b) => (true, b), None => (false, text.as_str()) };
+        let (int, frac) = body.split_once('.').unwrap_or((body, ""));
+        if int.is_empty() || frac.len() > scale as usize || !(int.bytes().chain(frac.bytes())).all(|b| b.is_ascii_digit()) { return Err(bad()); }
+        let digits = format!("{int}{frac:0<width$}", width = scale as usize);
+        let n: i128 = digits.parse().map_err(|_| bad())?;
+        Ok(if neg { -n } else { n })
+    }
+
+    /// Seeded PRNG (SplitMix64) for sampling/shuffling. Seed it from the input (e.g. `Rng::from_value(&input)`)
+    /// so replays draw the same sequence; there is deliberately no entropy source.
+    pub struct Rng(u64);
+    impl Rng {
+        pub fn new(seed: u64) -> Self { Self(seed) }
+        pub fn from_value(v: &Json) -> Self {
+            let h = blake3::hash(&canonical_bytes(v));
+            Self(u64::from_le_bytes(h.as_bytes()[..8].try_into().expect("32-byte hash")))
+        }
+        pub fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        }
+        /// Uniform in `0..n` (`n > 0`), without modulo bias.
+        pub fn below(&mut self, n: u64) -> u64 {
+            let zone = u64::MAX - (u64::MAX % n);
+            loop { let x = self.next_u64(); if x < zone { return x % n; } }
+        }
+    }
+}
+
+/// Glue used by the generated exports. Not a stable API.
+#[doc(hidden)]
+pub mod abi {
+    use super::{Fold, Json, UnitError, UnitResult};
+    use serde::{Serialize, de::DeserializeOwned};
+
+    pub fn alloc(len: i32) -> i32 {
+        let mut buf = Vec::<u8>::with_capacity(len as usize);
+        let ptr = buf.as_mut_ptr();
+        core::mem::forget(buf);
+        ptr as i32
+    }
+
+    /// # Safety
+    /// `ptr`/`len` must come from [`alloc`] or [`emit`].
+    pub unsafe fn dealloc(ptr: i32, len: i32) { drop(Vec::from_raw_parts(ptr as *mut u8, len as usize, len as usize)); }
+
+    /// Borrow host-written bytes (the host frees them) and decode them; parse errors become `invalid_input`.
+    ///
+    /// # Safety
+    /// `ptr`/`len` must describe a live region written by the host.
+    pub unsafe fn input<I: DeserializeOwned>(ptr: i32, len: i32) -> UnitResult<I> {
+        let bytes = core::slice::from_raw_parts(ptr as *const u8, len as usize);
+        serde_json::from_slice(bytes).map_err(|e| UnitError::invalid_input(e.to_string()))
+    }
+
+    /// Hand bytes to the host; capacity == len so the host's `dealloc(ptr,len)` frees them exactly.
+    pub fn emit(bytes: Vec<u8>) -> (i32, i32) {
+        if bytes.is_empty() { return (0, 0); }
+        let b = bytes.into_boxed_slice();
+        let len = b.len() as i32;
+        (Box::into_raw(b) as *mut u8 as i32, len)
+    }
+
+    pub fn envelope<O: Serialize>(r: UnitResult<O>) -> Vec<u8> {
+        let env = match r.and_then(|o| serde_json::to_value(o).map_err(|e| UnitError::new("output_encoding", e.to_string()))) {
+            Ok(v) => serde_json::json!({ "ok": v }),
+            Err(e) => serde_json::json!({ "error": e }),
+        };
+        serde_json::to_vec(&env).expect("Value always serializes")
+    }
+
+    /// # Safety
+    /// See [`input`].
+    pub unsafe fn run<I: DeserializeOwned, O: Serialize>(ptr: i32, len: i32, f: fn(I) -> UnitResult<O>) -> (i32, i32) {
+        emit(envelope(input(ptr, len).and_then(f)))
+    }
+
+    #[derive(Default)]
+    pub struct FoldCell<T> { state: T, error: Option<UnitError> }
+
+    impl<T: Fold> FoldCell<T> {
+        /// # Safety
+        /// See [`input`].
+        pub unsafe fn init(&mut self, ptr: i32, len: i32) {
+            if let Err(e) = input::<Json>(ptr, len).and_then(|p| self.state.init(p)) { self.error.get_or_insert(e); }
+        }
+        /// # Safety
+        /// See [`input`].
+        pub unsafe fn step(&mut self, ptr: i32, len: i32) -> (i32, i32) {
+            if self.error.is_some() { return (0, 0); }
+            match input::<Json>(ptr, len).and_then(|r| self.state.step(r)) {
+                Ok(Some(out)) => emit(serde_json::to_vec(&out).expect("Value always serializes")),
+                Ok(None) => (0, 0),
+                Err(e) => { self.error = Some(e); (0, 0) }
+            }
+        }
+        pub fn finish(&mut self) -> (i32, i32) {
+            let r = match self.error.take() { Some(e) => Err(e), None => self.state.finish() };
+            emit(envelope(r))
+        }
+    }
+}
@@ -0,0 +1,49 @@
+// Deterministic unit template in C (guest ABI v2), no libc.
+// Build:
+//   clang --target=wasm32-unknown-unknown -O2 -nostdlib -mmultivalue -Xclang -target-abi -Xclang experimental-mv \
+//         -Wl,--no-entry -Wl,--export-dynamic -o unit.wasm unit.c
+// Rules: no imports (no WASI), no float/double, output wrapped as {"ok":...} or {"error":{"code","message"}}.
+// Check with engine_exec_wasm::conformance::check before registering.
+
+typedef unsigned char u8;
+typedef int i32;
+typedef struct { i32 ptr; i32 len; } slice;
+
+#define EXPORT(name) __attribute__((export_name(name)))
+
+// Bump allocator over linear memory; a unit runs once per instance, so nothing is ever reused.
+extern u8 __heap_base;
+static i32 heap_top = 0;
+
+EXPORT("alloc") i32 alloc(i32 len) {
+    if (!heap_top) heap_top = (i32)&__heap_base;
+    i32 p = heap_top;
+    i32 need = ((len + 7) & ~7);
+    i32 have = __builtin_wasm_memory_size(0) * 65536;
+    if (p + need > have && __builtin_wasm_memory_grow(0, (p + need - have + 65535) / 65536) < 0) __builtin_trap();
+    heap_top = p + need;
+    return p;
+}
+
+EXPORT("dealloc") void dealloc(i32 ptr, i32 len) { (void)ptr; (void)len; }
+
+EXPORT("tdln_abi_version") i32 tdln_abi_version(void) { return 2; }
+
+static void copy(u8 *dst, const u8 *src, i32 n) { for (i32 i = 0; i < n; i++) dst[i] = src[i]; }
+
+static slice emit(const char *a, i32 alen, const u8 *body, i32 blen, const char *z, i32 zlen) {
+    i32 n = alen + blen + zlen;
+    u8 *out = (u8 *)alloc(n);
+    copy(out, (const u8 *)a, alen); copy(out + alen, body, blen); copy(out + alen + blen, (const u8 *)z, zlen);
+    return (slice){ (i32)out, n };
+}
+
+// Replace with domain logic. Input is canonical JSON; this template echoes it back.
+EXPORT("run") slice run(i32 ptr, i32 len) {
+    const u8 *in = (const u8 *)ptr;
+    if (len == 0 || in[0] != '{') {
+        static const char err[] = "{\"error\":{\"code\":\"invalid_input\",\"message\":\"expected a JSON object\"}}";
+        return emit(err, sizeof err - 1, 0, 0, "", 0);
+    }
+    return emit("{\"ok\":", 6, in, len, "}", 1);
+}
@@ -0,0 +1,172 @@
+use anyhow::{Result, bail};
+use serde::{Serialize, Deserialize};
+use wasmparser::{Operator, Parser, Payload, TypeRef};
+
+use crate::ExecConfig;
+
+/// Import module names that are nondeterministic by construction (WASI clocks, randomness, environment).
+/// `env` is only the toolchains' default module name; its imports are host functions like any other.
+const NONDET_MODULES: &[&str] = &["wasi_snapshot_preview1", "wasi_unstable", "wasi"];
+const PAGE: u64 = 65_536;
+/// Operator-name prefixes of the SIMD proposal (lanes make NaN/rounding host-dependent).
+const SIMD_PREFIXES: &[&str] = &["V128", "I8x16", "I16x8", "I32x4", "I64x2", "F32x4", "F64x2"];
+
+/// What a unit must (not) contain to be admitted.
+/// `strict` makes float ops a finding: NaN bit patterns and fused ops differ across hosts, so float
+/// code cannot back a reproducible receipt. Besides `required_exports`, a unit needs an entry point:
+/// `run`, or `fold_step` + `fold_finish`; `dealloc` is optional, as in the executor.
+/// Every import is a finding: the executor links no host functions, so an importing unit cannot instantiate.
+#[derive(Clone, Debug)]
+pub struct Profile {
+    pub strict: bool,
+    pub required_exports: Vec<String>,
+    /// Linear-memory budget; declared minimum and constant `memory.grow` targets must fit.
+    pub memory_budget_bytes: u64,
+    pub max_data_segment_bytes: u64,
+}
+impl Default for Profile { fn default()->Self{ Self{
+    strict: true,
+    required_exports: ["memory", "alloc"].into_iter().map(String::from).collect(),
+    memory_budget_bytes: ExecConfig::default().memory_limit_bytes as u64,
+    max_data_segment_bytes: 1024 * 1024,
+} }}
+impl Profile {
+    /// Registration profile matching what the executor will enforce at run time.
+    pub fn for_exec(cfg:&ExecConfig)->Self {
+        Self{ memory_budget_bytes: cfg.memory_limit_bytes as u64, ..Default::default() }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Finding { pub code: String, pub detail: String }
+
+/// Lint result; stored next to the registry entry as `wasm.lint.report.v1`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ConformanceReport {
+    pub kind: String,
+    pub module_cid: String,
+    pub strict: bool,
+    pub ok: bool,
+    pub findings: Vec<Finding>,
+}
+
+impl ConformanceReport {
+    /// `Err` listing every finding; use before registering a unit.
+    pub fn ensure(&self)->Result<()>{
+        if self.ok { return Ok(()); }
+        let list: Vec<String> = self.findings.iter().map(|f| format!("{}: {}", f.code, f.detail)).collect();
+        bail!("unit is not deterministic-conformant: {}", list.join("; "))
+    }
+}
+
+/// Per-category operator tally; only the first offender is named in the finding.
+#[derive(Default)]
+struct Tally { count: usize, first: Option<String> }
+impl Tally {
+    fn hit(&mut self, op:&str){ self.count += 1; self.first.get_or_insert_with(|| op.to_string()); }
+    fn finding(self, code:&str, what:&str)->Option<Finding>{
+        let first = self.first?;
+        Some(Finding{ code: code.into(), detail: format!("{} {what} instruction(s), first: {first}", self.count) })
+    }
+}
+
+/// Scalar float instructions: arithmetic, comparisons, loads/stores, consts, conversions (incl. saturating) and reinterprets.
+/// SIMD float lanes are reported as `simd`.
+fn is_float(op:&Operator)->bool {
+    use Operator::*;
+    matches!(op,
+        F32Load{..} | F64Load{..} | F32Store{..} | F64Store{..} | F32Const{..} | F64Const{..}
+        | F32Eq | F32Ne | F32Lt | F32Gt | F32Le | F32Ge | F64Eq | F64Ne | F64Lt | F64Gt | F64Le | F64Ge
+        | F32Abs | F32Neg | F32Ceil | F32Floor | F32Trunc | F32Nearest | F32Sqrt
+        | F32Add | F32Sub | F32Mul | F32Div | F32Min | F32Max | F32Copysign
+        | F64Abs | F64Neg | F64Ceil | F64Floor | F64Trunc | F64Nearest | F64Sqrt
+        | F64Add | F64Sub | F64Mul | F64Div | F64Min | F64Max | F64Copysign
+        | I32TruncF32S | I32TruncF32U | I32TruncF64S | I32TruncF64U | I64TruncF32S | I64TruncF32U | I64TruncF64S | I64TruncF64U
+        | I32TruncSatF32S | I32TruncSatF32U | I32TruncSatF64S | I32TruncSatF64U
+        | I64TruncSatF32S | I64TruncSatF32U | I64TruncSatF64S | I64TruncSatF64U
+        | F32ConvertI32S | F32ConvertI32U | F32ConvertI64S | F32ConvertI64U | F32DemoteF64
+        | F64ConvertI32S | F64ConvertI32U | F64ConvertI64S | F64ConvertI64U | F64PromoteF32
+        | I32ReinterpretF32 | I64ReinterpretF64 | F32ReinterpretI32 | F64ReinterpretI64)
+}
+
+/// Statically scan a module. Unlike engine validation this does not stop at the first problem,
+/// so authors get the whole list at once. Malformed wasm is still an `Err`.
+pub fn check(bytes:&[u8], profile:&Profile)->Result<ConformanceReport>{
+    let mut findings = Vec::new();
+    let mut exports = Vec::new();
+    let (mut floats, mut simd, mut atomics) = (Tally::default(), Tally::default(), Tally::default());
+    let budget_pages = profile.memory_budget_bytes / PAGE;
+    let mut initial_pages = 0u64;
+    for p in Parser::new(0).parse_all(bytes) {
+        match p? {
+            Payload::ImportSection(r) => for imp in r {
+                let imp = imp?;
+                let nondet = NONDET_MODULES.contains(&imp.module);
+                let kind = match imp.ty { TypeRef::Func(_) => "func", TypeRef::Memory(_) => "memory", TypeRef::Table(_) => "table", TypeRef::Global(_) => "global", _ => "other" };
+                findings.push(Finding{
+                    code: if nondet { "nondeterministic_import" } else { "import_not_allowed" }.into(),
+                    detail: format!("{}::{} ({kind})", imp.module, imp.name),
+                });
+            },
+            Payload::MemorySection(r) => for mem in r {
+                let mem = mem?;
+                initial_pages = initial_pages.max(mem.initial);
+                if mem.shared { findings.push(Finding{ code: "threads".into(), detail: "shared memory".into() }); }
+                if mem.initial > budget_pages {
+                    findings.push(Finding{ code: "memory_over_budget".into(), detail: format!("declares {} pages, budget is {budget_pages}", mem.initial) });
+                }
+            },
+            Payload::StartSection{ func, .. } => {
+                // Runs during instantiation, before the host has written input or armed the ABI.
+                findings.push(Finding{ code: "start_function".into(), detail: format!("func {func}") });
+            }
+            Payload::DataSection(r) => for (i, seg) in r.into_iter().enumerate() {
+                let len = seg?.data.len() as u64;
+                if len > profile.max_data_segment_bytes {
+                    findings.push(Finding{ code: "data_segment_oversized".into(), detail: format!("segment {i}: {len} bytes > {}", profile.max_data_segment_bytes) });
+                }
+            },
+            Payload::ExportSection(r) => for exp in r { exports.push(exp?.name.to_string()); },
+            Payload::CodeSectionEntry(body) => {
+                let mut ops = body.get_operators_reader()?;
+                let mut last_const = None;
+                while !ops.eof() {
+                    let op = ops.read()?;
+                    if let (Operator::MemoryGrow{ .. }, Some(delta)) = (&op, last_const) {
+                        if initial_pages + u64::from(delta) > budget_pages {
+                            findings.push(Finding{
+                                code: "memory_grow_over_budget".into(),
+                                detail: format!("memory.grow {delta} from {initial_pages} pages exceeds budget {budget_pages}"),
+                            });
+                        }
+                    }
+                    last_const = match op { Operator::I32Const{ value } if value >= 0 => Some(value as u32), _ => None };
+                    let dbg = format!("{op:?}");
+                    let name = dbg.split([' ', '{']).next().unwrap_or_default();
+                    if SIMD_PREFIXES.iter().any(|p| name.starts_with(p)) { simd.hit(name); }
+                    else if name.contains("Atomic") { atomics.hit(name); }
+                    else if profile.strict && is_float(&op) { floats.hit(name); }
+                }
+            }
+            _ => {}
+        }
+    }
+    findings.extend(floats.finding("float_ops", "float"));
+    findings.extend(simd.finding("simd", "SIMD"));
+    findings.extend(atomics.finding("threads", "atomic"));
+    let has = |n:&str| exports.iter().any(|e| e == n);
+    for want in &profile.required_exports {
+        if !has(want) {
+            findings.push(Finding{ code: "missing_export".into(), detail: want.clone() });
+        }
+    }
+    if !(has("run") || has("fold_step") && has("fold_finish")) {
+        findings.push(Finding{ code: "missing_export".into(), detail: "run (or fold_step + fold_finish)".into() });
+    }
+    Ok(ConformanceReport{
+        kind: "wasm.lint.report.v1".into(),
+        module_cid: format!("cid:b3:{}", blake3::hash(bytes).to_hex()),
+        strict: profile.strict,
+        ok: findings.is_empty(), findings,
+    })
+}
@@ -1,55 +1,256 @@
-
 use anyhow::{anyhow, Result, bail};
+use serde::{Serialize, Deserialize};
 use serde_json::Value as Json;
-use wasmtime::{Engine, Module, Store, Config, Linker, TypedFunc};
-use wasmparser::{Parser, Payload};
+use wasmtime::{Engine, Module, Store, Config, Linker, TypedFunc, Instance, Memory};
+
+pub mod limits;
+pub use limits::{ExecReport, LimitKind};
+use limits::{GuestAbiError, TrackedLimits};
+pub mod stream;
+use stream::StreamCid;
+pub mod conformance;
 
 #[derive(Clone, Debug)]
-pub struct ExecConfig { pub fuel_limit: u64, pub memory_limit_bytes: usize, pub allow_imports: bool }
-impl Default for ExecConfig { fn default()->Self{ Self{ fuel_limit: 50_000_000, memory_limit_bytes: 33554432, allow_imports:false } }}
+pub struct ExecConfig {
+    pub fuel_limit: u64,
+    pub memory_limit_bytes: usize,
+    pub table_elements_limit: u32,
+    pub instance_limit: usize,
+    pub wall_clock_ms: u64,
+    pub max_wasm_stack_bytes: usize,
+}
+impl Default for ExecConfig { fn default()->Self{ Self{
+    fuel_limit: 50_000_000, memory_limit_bytes: 33554432,
+    table_elements_limit: 10_000, instance_limit: 1, wall_clock_ms: 2_000, max_wasm_stack_bytes: 512 * 1024,
+} }}
+
+/// Guests built with `guest-sdk` export `tdln_abi_version() -> 2` and wrap their output as
+/// `{"ok": <value>}` or `{"error": {"code", "message"}}`; older guests return the bare value.
+pub const GUEST_ABI_VERSION: i32 = 2;
+
+/// Typed failure reported by the guest itself (bad input, domain rule), as opposed to a trap.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GuestError { pub code: String, pub message: String }
+
+/// Result of a guest run: `output` is `None` when the guest trapped, tripped a limit or reported `guest_error`.
+#[derive(Clone, Debug)]
+pub struct ExecOutcome { pub output: Option<Vec<u8>>, pub guest_error: Option<GuestError>, pub report: ExecReport }
+
+/// Result of a streaming (fold) run. Records emitted by the guest went to the caller's sink;
+/// only CIDs and the final summary are kept here. `summary` is `None` when the guest trapped
+/// or reported `guest_error` from `fold_finish`. `input_cid` always covers every record, also those a
+/// trapped run never reached; `records_in` counts the ones the guest was fed.
+#[derive(Clone, Debug)]
+pub struct StreamOutcome {
+    pub input_cid: String,
+    pub records_in: usize,
+    pub records_out: usize,
+    pub summary: Option<Json>,
+    pub guest_error: Option<GuestError>,
+    pub output_cid: Option<String>,
+    pub report: ExecReport,
+}
+
+type Guest = Store<TrackedLimits>;
+
+/// Period of the engine's epoch ticker; wall-clock deadlines are counted in ticks.
+const EPOCH_TICK_MS: u64 = 10;
 
 pub struct WasmExecutor{ engine: Engine, cfg: ExecConfig }
 impl WasmExecutor{
     pub fn new(cfg: ExecConfig)->Result<Self>{
         let mut c = Config::new();
-        c.consume_fuel(true).cranelift_nan_canonicalization(true).wasm_multi_value(true).wasm_simd(false).wasm_threads(false);
-        let engine = Engine::new(&c)?; Ok(Self{engine,cfg})
+        c.consume_fuel(true).epoch_interruption(true).max_wasm_stack(cfg.max_wasm_stack_bytes)
+            .cranelift_nan_canonicalization(true).wasm_multi_value(true).wasm_simd(false).wasm_relaxed_simd(false).wasm_threads(false);
+        let engine = Engine::new(&c)?;
+        // One ticker per engine, stopping with it. Each store sets its own deadline in ticks, so a run
+        // that times out interrupts nothing but itself.
+        let weak = engine.weak();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(std::time::Duration::from_millis(EPOCH_TICK_MS));
+            match weak.upgrade() { Some(e) => e.increment_epoch(), None => break }
+        });
+        Ok(Self{engine,cfg})
     }
-    fn validate(&self, bytes:&[u8])->Result<()>{
-        if !self.cfg.allow_imports {
-            for p in Parser::new(0).parse_all(bytes){
-                if let Payload::ImportSection(_) = p? { bail!("imports not allowed in deterministic mode"); }
-            }
-        }
-        Ok(())
+    pub fn config(&self)->&ExecConfig { &self.cfg }
+    /// Admission lint for registration (strict: floats are findings) against this executor's budget.
+    /// Runs at publish time only: at run time the engine config (no SIMD, threads or imports) and the
+    /// limits enforce what the lint checks statically.
+    pub fn conformance(&self, bytes:&[u8])->Result<conformance::ConformanceReport>{
+        conformance::check(bytes, &conformance::Profile::for_exec(&self.cfg))
     }
+    /// Run and return canonical output bytes; any trap is an error. See [`Self::exec_with_report`].
     pub fn exec(&self, unit:&[u8], input:&Json)->Result<Vec<u8>>{
-        self.validate(unit)?;
-        let in_canon = canon(input); let in_bytes = serde_json::to_vec(&in_canon)?;
+        let out = self.exec_with_report(unit, input)?;
+        match out.output {
+            Some(bytes) => Ok(bytes),
+            None => match out.guest_error {
+                Some(e) => bail!("guest error {}: {}", e.code, e.message),
+                None => bail!("guest trapped: {}", out.report.trap.unwrap_or_default()),
+            },
+        }
+    }
+    /// Run under all limits. Host/ABI errors are `Err`; guest traps come back as `Ok` with a report.
+    pub fn exec_with_report(&self, unit:&[u8], input:&Json)->Result<ExecOutcome>{
+        let in_bytes = serde_json::to_vec(&canon(input))?;
+        if in_bytes.len() > self.cfg.memory_limit_bytes { bail!("input exceeds limit"); }
+        let (out, report) = self.harness(unit, |store, instance, memory| {
+            let run: TypedFunc<(i32,i32),(i32,i32)> = instance.get_typed_func(&mut *store, "run").map_err(|_| anyhow!("export run required"))?;
+            let abi = guest_abi(store, instance)?;
+            let (in_ptr, in_len) = write_guest(store, instance, memory, &in_bytes)?;
+            let (out_ptr, out_len) = run.call(&mut *store, (in_ptr, in_len))?;
+            let out = read_guest(store, memory, out_ptr, out_len)?;
+            free_guest(store, instance, &[(in_ptr, in_len), (out_ptr, out_len)]);
+            Ok((abi, out))
+        })?;
+        let (output, guest_error) = match out {
+            Some((abi, bytes)) => {
+                let j: Json = serde_json::from_slice(&bytes)?;
+                match abi {
+                    1 => (Some(serde_json::to_vec(&canon(&j))?), None),
+                    GUEST_ABI_VERSION => unwrap_envelope(j)?,
+                    v => bail!("unsupported guest ABI version {v}"),
+                }
+            }
+            None => (None, None),
+        };
+        Ok(ExecOutcome{ output, guest_error, report })
+    }
+
+    /// Streaming ABI for large inputs: the host feeds one canonical record at a time and the guest folds.
+    ///
+    /// Guest exports: `alloc`, `dealloc`, optional `fold_init(ptr,len)` (receives `params`),
+    /// `fold_step(ptr,len)->(ptr,len)` (may emit one record; len 0 = nothing) and `fold_finish()->(ptr,len)` (summary).
+    /// Only one record is ever resident in guest memory, so the budget bounds the record size, not the input.
+    /// Emitted records are canonicalised and handed to `sink`; the output CID is that of
+    /// `{"records":[...],"summary":...}`, computed incrementally.
+    pub fn exec_stream<I>(&self, unit:&[u8], params:&Json, records:I, sink:&mut dyn FnMut(&[u8])->Result<()>)->Result<StreamOutcome>
+    where I: IntoIterator<Item=Json> {
+        let mut records = records.into_iter();
+        let mut input_cid = StreamCid::array();
+        let mut output_cid = StreamCid::envelope();
+        let (summary, report) = self.harness(unit, |store, instance, memory| {
+            let step: TypedFunc<(i32,i32),(i32,i32)> = instance.get_typed_func(&mut *store, "fold_step").map_err(|_| anyhow!("export fold_step required"))?;
+            let finish: TypedFunc<(),(i32,i32)> = instance.get_typed_func(&mut *store, "fold_finish").map_err(|_| anyhow!("export fold_finish required"))?;
+            let abi = guest_abi(store, instance)?;
+            if let Ok(init) = instance.get_typed_func::<(i32,i32),()>(&mut *store, "fold_init") {
+                let p = serde_json::to_vec(&canon(params))?;
+                let (ptr, len) = write_guest(store, instance, memory, &p)?;
+                init.call(&mut *store, (ptr, len))?;
+                free_guest(store, instance, &[(ptr, len)]);
+            }
+            for rec in records.by_ref() {
+                let rb = serde_json::to_vec(&canon(&rec))?;
+                if rb.len() > self.cfg.memory_limit_bytes { bail!("record {} exceeds limit", input_cid.count()); }
+                input_cid.push(&rb);
+                let (ptr, len) = write_guest(store, instance, memory, &rb)?;
+                let (out_ptr, out_len) = step.call(&mut *store, (ptr, len))?;
+                free_guest(store, instance, &[(ptr, len)]);
+                if out_len > 0 {
+                    let emitted = read_guest(store, memory, out_ptr, out_len)?;
+                    free_guest(store, instance, &[(out_ptr, out_len)]);
+                    let j: Json = serde_json::from_slice(&emitted)?;
+                    let eb = serde_json::to_vec(&canon(&j))?;
+                    output_cid.push(&eb);
+                    sink(&eb)?;
+                }
+            }
+            let (s_ptr, s_len) = finish.call(&mut *store, ())?;
+            let sb = read_guest(store, memory, s_ptr, s_len)?;
+            free_guest(store, instance, &[(s_ptr, s_len)]);
+            let j: Json = serde_json::from_slice(&sb)?;
+            match abi {
+                1 => Ok((Some(canon(&j)), None)),
+                GUEST_ABI_VERSION => {
+                    let (ok, err) = unwrap_envelope(j)?;
+                    Ok((ok.map(|b| serde_json::from_slice(&b)).transpose()?, err))
+                }
+                v => bail!("unsupported guest ABI version {v}"),
+            }
+        })?;
+        let (summary, guest_error) = summary.unwrap_or((None, None));
+        let records_in = input_cid.count(); let records_out = output_cid.count();
+        for rec in records { input_cid.push(&serde_json::to_vec(&canon(&rec))?); }
+        let output_cid = match &summary { Some(s) => Some(output_cid.finish_envelope(&serde_json::to_vec(s)?)), None => None };
+        Ok(StreamOutcome{ input_cid: input_cid.finish_array(), records_in, records_out, summary, guest_error, output_cid, report })
+    }
+
+    /// Compile, instantiate and run `body` under every limit (fuel, memory/table/instance caps, stack,
+    /// wall clock). Guest traps yield `(None, report)`; host/ABI errors propagate as `Err`.
+    fn harness<R>(&self, unit:&[u8], body: impl FnOnce(&mut Guest, &Instance, &Memory)->Result<R>)->Result<(Option<R>, ExecReport)>{
         let module = Module::new(&self.engine, unit)?;
-        let mut store = Store::new(&self.engine,());
-        store.add_fuel(self.cfg.fuel_limit)?;
+        let mut store = Store::new(&self.engine, TrackedLimits::new(&self.cfg));
+        store.limiter(|l| l);
+        store.set_fuel(self.cfg.fuel_limit)?;
+        // The guest traps with Interrupt once the deadline passes; one extra tick since the current one is partly spent.
+        store.set_epoch_deadline(self.cfg.wall_clock_ms.div_ceil(EPOCH_TICK_MS) + 1);
+        let start = std::time::Instant::now();
+        let res = self.instantiate(&mut store, &module).and_then(|(instance, memory)| body(&mut store, &instance, &memory));
+
+        let mut report = ExecReport{
+            fuel_limit: self.cfg.fuel_limit,
+            fuel_consumed: self.cfg.fuel_limit.saturating_sub(store.get_fuel().unwrap_or(0)),
+            memory_limit_bytes: self.cfg.memory_limit_bytes,
+            peak_memory_bytes: store.data().peak_memory_bytes,
+            wall_clock_ms: self.cfg.wall_clock_ms,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+            limit_hit: None, trap: None,
+        };
+        match res {
+            Ok(r) => Ok((Some(r), report)),
+            Err(e) if limits::is_guest_fault(&e, store.data()) => {
+                report.limit_hit = limits::classify(&e, store.data());
+                report.trap = Some(e.to_string());
+                Ok((None, report))
+            }
+            Err(e) => Err(e),
+        }
+    }
+    fn instantiate(&self, store:&mut Guest, module:&Module)->Result<(Instance, Memory)>{
         let linker = Linker::new(&self.engine);
-        let instance = linker.instantiate(&mut store, &module)?;
-        let memory = instance.get_memory(&mut store, "memory").ok_or_else(|| anyhow!("export memory required"))?;
-        let alloc: TypedFunc<i32,i32> = instance.get_typed_func(&mut store, "alloc").map_err(|_| anyhow!("export alloc required"))?;
-        let dealloc: TypedFunc<(i32,i32),()> = instance.get_typed_func(&mut store, "dealloc").map_err(|_| anyhow!("export dealloc required"))?;
-        let run: TypedFunc<(i32,i32),(i32,i32)> = instance.get_typed_func(&mut store, "run").map_err(|_| anyhow!("export run required"))?;
-        if memory.data_size(&store) > self.cfg.memory_limit_bytes { bail!("guest memory exceeds limit"); }
-        if in_bytes.len() > self.cfg.memory_limit_bytes { bail!("input exceeds limit"); }
-        let in_ptr = alloc.call(&mut store, in_bytes.len() as i32)?;
-        let data = memory.data_mut(&mut store);
-        let s = in_ptr as usize; let e = s + in_bytes.len(); if e > data.len(){ bail!("bad alloc OOB"); }
-        data[s:e].copy_from_slice(&in_bytes);
-        let (out_ptr, out_len) = run.call(&mut store, (in_ptr, in_bytes.len() as i32))?;
-        let data2 = memory.data(&store);
-        let s2 = out_ptr as usize; let e2 = s2 + (out_len as usize); if e2 > data2.len(){ bail!("guest OOB"); }
-        let out = data2[s2:e2].to_vec();
-        let _ = dealloc.call(&mut store, (in_ptr, in_bytes.len() as i32));
-        let _ = dealloc.call(&mut store, (out_ptr, out_len));
-        let j: Json = serde_json::from_slice(&out)?;
-        let out_canon = canon(&j);
-        Ok(serde_json::to_vec(&out_canon)?)
+        let instance = linker.instantiate(&mut *store, module)?;
+        let memory = instance.get_memory(&mut *store, "memory").ok_or_else(|| anyhow!("export memory required"))?;
+        Ok((instance, memory))
+    }
+}
+
+/// `tdln_abi_version()`, or 1 when not exported. An export of that name with another signature is an ABI
+/// error, not a v1 guest: reading a v2 envelope as a bare value would hand `{"ok":..}` back as the output.
+fn guest_abi(store:&mut Guest, instance:&Instance)->Result<i32>{
+    if instance.get_export(&mut *store, "tdln_abi_version").is_none() { return Ok(1); }
+    let f: TypedFunc<(),i32> = instance.get_typed_func(&mut *store, "tdln_abi_version").map_err(|e| anyhow!("export tdln_abi_version must be () -> i32: {e}"))?;
+    f.call(&mut *store, ())
+}
+
+fn write_guest(store:&mut Guest, instance:&Instance, memory:&Memory, bytes:&[u8])->Result<(i32,i32)>{
+    let alloc: TypedFunc<i32,i32> = instance.get_typed_func(&mut *store, "alloc").map_err(|_| anyhow!("export alloc required"))?;
+    let ptr = alloc.call(&mut *store, bytes.len() as i32)?;
+    let data = memory.data_mut(&mut *store);
+    // Guest-controlled: read the pointer as unsigned and never index past the memory.
+    let s = ptr as u32 as usize;
+    let dst = s.checked_add(bytes.len()).and_then(|e| data.get_mut(s..e))
+        .ok_or_else(|| GuestAbiError(format!("alloc({}) returned {ptr}, outside guest memory", bytes.len())))?;
+    dst.copy_from_slice(bytes);
+    Ok((ptr, bytes.len() as i32))
+}
+
+fn read_guest(store:&Guest, memory:&Memory, ptr:i32, len:i32)->Result<Vec<u8>>{
+    let s = ptr as u32 as usize;
+    s.checked_add(len as u32 as usize).and_then(|e| memory.data(store).get(s..e)).map(<[u8]>::to_vec)
+        .ok_or_else(|| GuestAbiError(format!("region {ptr}+{len} is outside guest memory")).into())
+}
+
+fn free_guest(store:&mut Guest, instance:&Instance, regions:&[(i32,i32)]){
+    let Ok(dealloc) = instance.get_typed_func::<(i32,i32),()>(&mut *store, "dealloc") else { return };
+    for r in regions { let _ = dealloc.call(&mut *store, *r); }
+}
+
+fn unwrap_envelope(j:Json)->Result<(Option<Vec<u8>>, Option<GuestError>)>{
+    let Json::Object(mut m) = j else { bail!("guest envelope must be an object") };
+    if let Some(ok) = m.remove("ok") { return Ok((Some(serde_json::to_vec(&canon(&ok))?), None)); }
+    match m.remove("error") {
+        Some(e) => Ok((None, Some(serde_json::from_value(e).map_err(|e| anyhow!("bad guest error: {e}"))?))),
+        None => bail!("guest envelope needs `ok` or `error`"),
     }
 }
 
@@ -0,0 +1,87 @@
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use wasmtime::{ResourceLimiter, StoreLimits, StoreLimitsBuilder, Trap};
+
+use crate::ExecConfig;
+
+/// Which host limit stopped the guest (if any). `instance_limit` has no kind: a run instantiates one
+/// module, so only a limit below 1 trips it, and that is a setup error.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all="snake_case")]
+pub enum LimitKind { Fuel, Memory, Table, WallClock, Stack }
+
+/// Structured accounting for one execution; lands in the receipt EER.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExecReport {
+    pub fuel_limit: u64,
+    pub fuel_consumed: u64,
+    pub memory_limit_bytes: usize,
+    pub peak_memory_bytes: usize,
+    pub wall_clock_ms: u64,
+    pub elapsed_ms: u64,
+    pub limit_hit: Option<LimitKind>,
+    pub trap: Option<String>,
+}
+
+/// StoreLimits plus peak/denial bookkeeping (StoreLimits alone does not tell us *why* a grow failed).
+pub struct TrackedLimits {
+    inner: StoreLimits,
+    pub peak_memory_bytes: usize,
+    pub denied: Option<LimitKind>,
+}
+
+impl TrackedLimits {
+    pub fn new(cfg:&ExecConfig)->Self {
+        let inner = StoreLimitsBuilder::new()
+            .memory_size(cfg.memory_limit_bytes)
+            .table_elements(cfg.table_elements_limit)
+            .instances(cfg.instance_limit)
+            .trap_on_grow_failure(true)
+            .build();
+        Self{ inner, peak_memory_bytes: 0, denied: None }
+    }
+}
+
+impl ResourceLimiter for TrackedLimits {
+    /// Also called for the module's initial memory (from 0), so peak covers it and an oversized
+    /// declaration fails instantiation as a Memory limit.
+    fn memory_growing(&mut self, current:usize, desired:usize, maximum:Option<usize>)->Result<bool>{
+        let ok = self.inner.memory_growing(current, desired, maximum);
+        if matches!(ok, Ok(true)) { self.peak_memory_bytes = self.peak_memory_bytes.max(desired); }
+        else { self.denied.get_or_insert(LimitKind::Memory); }
+        ok
+    }
+    fn table_growing(&mut self, current:u32, desired:u32, maximum:Option<u32>)->Result<bool>{
+        let ok = self.inner.table_growing(current, desired, maximum);
+        if !matches!(ok, Ok(true)) { self.denied.get_or_insert(LimitKind::Table); }
+        ok
+    }
+    fn instances(&self)->usize { self.inner.instances() }
+    fn tables(&self)->usize { self.inner.tables() }
+    fn memories(&self)->usize { self.inner.memories() }
+}
+
+/// Map a guest error to the limit that caused it. The limiter's own record wins over the trap code,
+/// since a refused `memory.grow` surfaces as a generic trap.
+pub fn classify(err:&anyhow::Error, limits:&TrackedLimits)->Option<LimitKind>{
+    if let Some(k) = limits.denied { return Some(k); }
+    match err.downcast_ref::<Trap>()? {
+        Trap::OutOfFuel => Some(LimitKind::Fuel),
+        Trap::Interrupt => Some(LimitKind::WallClock),
+        Trap::StackOverflow => Some(LimitKind::Stack),
+        _ => None,
+    }
+}
+
+/// The guest handed the host a region outside its own memory (from `alloc` or as its output).
+#[derive(Debug)]
+pub struct GuestAbiError(pub String);
+impl std::fmt::Display for GuestAbiError {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>)->std::fmt::Result { write!(f, "guest ABI: {}", self.0) }
+}
+impl std::error::Error for GuestAbiError {}
+
+/// True when the error came from the guest (trap, tripped limit, bad region) rather than from the host.
+pub fn is_guest_fault(err:&anyhow::Error, limits:&TrackedLimits)->bool {
+    limits.denied.is_some() || err.downcast_ref::<Trap>().is_some() || err.downcast_ref::<GuestAbiError>().is_some()
+}
@@ -0,0 +1,25 @@
+/// Incremental `cid:b3:` CID (the certified runtime's format) over a canonical JSON document whose only unbounded part is one array.
+/// Elements are hashed as they pass, so the array never has to be materialised in one buffer.
+/// Produces the same CID as hashing the whole canonical document at once.
+pub struct StreamCid { h: blake3::Hasher, n: usize }
+
+impl StreamCid {
+    /// Hash of a bare canonical array: `[e1,e2,...]`.
+    pub fn array()->Self { Self::with_prefix(b"[") }
+    /// Hash of the streaming output envelope `{"records":[...],"summary":S}` (keys already canonical).
+    pub fn envelope()->Self { Self::with_prefix(b"{\"records\":[") }
+    fn with_prefix(prefix:&[u8])->Self { let mut h = blake3::Hasher::new(); h.update(prefix); Self{ h, n: 0 } }
+
+    /// `elem` must already be canonical JSON bytes.
+    pub fn push(&mut self, elem:&[u8]) {
+        if self.n > 0 { self.h.update(b","); }
+        self.h.update(elem); self.n += 1;
+    }
+    pub fn count(&self)->usize { self.n }
+    pub fn finish_array(mut self)->String { self.h.update(b"]"); format!("cid:b3:{}", self.h.finalize().to_hex()) }
+    /// `summary` must already be canonical JSON bytes.
+    pub fn finish_envelope(mut self, summary:&[u8])->String {
+        self.h.update(b"],\"summary\":"); self.h.update(summary); self.h.update(b"}");
+        format!("cid:b3:{}", self.h.finalize().to_hex())
+    }
+}
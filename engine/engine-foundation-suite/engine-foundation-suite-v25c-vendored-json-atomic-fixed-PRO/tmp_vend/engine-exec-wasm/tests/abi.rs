@@ -0,0 +1,40 @@
+use engine_exec_wasm::{ExecConfig, WasmExecutor};
+use serde_json::json;
+
+/// Echo guest exporting `extra` next to the ABI.
+fn guest(extra: &str) -> Vec<u8> {
+    wat::parse_str(format!(r#"(module
+        (memory (export "memory") 1)
+        (func (export "alloc") (param i32) (result i32) i32.const 0)
+        (func (export "dealloc") (param i32 i32))
+        (func (export "run") (param i32 i32) (result i32 i32) local.get 0 local.get 1)
+        {extra})"#)).unwrap()
+}
+
+#[test]
+fn abi_version_export_must_have_the_right_signature() {
+    let ex = WasmExecutor::new(ExecConfig::default()).unwrap();
+    assert_eq!(ex.exec(&guest(""), &json!({"a": 1})).unwrap(), br#"{"a":1}"#);
+    for bad in [r#"(func (export "tdln_abi_version") (param i32) (result i32) i32.const 2)"#,
+                r#"(func (export "tdln_abi_version") (result i64) i64.const 2)"#,
+                r#"(global (export "tdln_abi_version") i32 (i32.const 2))"#] {
+        let err = ex.exec_with_report(&guest(bad), &json!({"ok": 1})).unwrap_err();
+        assert!(err.to_string().contains("tdln_abi_version must be () -> i32"), "{bad}: {err}");
+    }
+    let v2 = r#"(func (export "tdln_abi_version") (result i32) i32.const 2)"#;
+    assert_eq!(ex.exec(&guest(v2), &json!({"ok": {"b": 2}})).unwrap(), br#"{"b":2}"#);
+}
+
+#[test]
+fn regions_outside_guest_memory_are_guest_faults() {
+    let ex = WasmExecutor::new(ExecConfig::default()).unwrap();
+    for (alloc, out) in [("i32.const 0", "i32.const -1 i32.const 8"), ("i32.const 0", "i32.const 65530 i32.const -1"), ("i32.const -16", "local.get 0 local.get 1")] {
+        let unit = wat::parse_str(format!(r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) {alloc})
+            (func (export "run") (param i32 i32) (result i32 i32) {out}))"#)).unwrap();
+        let r = ex.exec_with_report(&unit, &json!({})).unwrap();
+        assert_eq!((r.output, r.report.limit_hit), (None, None), "{alloc} / {out}");
+        assert!(r.report.trap.unwrap().contains("outside guest memory"));
+    }
+}
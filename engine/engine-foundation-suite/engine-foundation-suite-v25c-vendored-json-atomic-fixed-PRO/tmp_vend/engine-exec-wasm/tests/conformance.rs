@@ -0,0 +1,94 @@
+use engine_exec_wasm::conformance::{check, Profile};
+
+const ABI: &str = r#"(memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) i32.const 0)
+  (func (export "dealloc") (param i32 i32))"#;
+
+fn codes(wat: &str) -> Vec<String> {
+    let bytes = wat::parse_str(wat).unwrap();
+    check(&bytes, &Profile::default()).unwrap().findings.into_iter().map(|f| f.code).collect()
+}
+
+#[test]
+fn integer_only_unit_passes() {
+    let run = r#"(func (export "run") (param i32 i32) (result i32 i32) local.get 0 local.get 1)"#;
+    assert!(codes(&format!("(module {ABI} {run})")).is_empty());
+    let fold = r#"(func (export "fold_step") (param i32 i32) (result i32 i32) i32.const 0 i32.const 0)
+      (func (export "fold_finish") (result i32 i32) i32.const 0 i32.const 0)"#;
+    assert!(codes(&format!("(module {ABI} {fold})")).is_empty());
+}
+
+#[test]
+fn clock_import_and_floats_are_rejected() {
+    let wat = format!(r#"(module
+      (import "wasi_snapshot_preview1" "clock_time_get" (func (param i32 i64 i32) (result i32)))
+      {ABI}
+      (func (export "run") (param i32 i32) (result i32 i32)
+        f64.const 1.5 i32.trunc_f64_s local.get 1))"#);
+    assert_eq!(codes(&wat), ["nondeterministic_import", "float_ops"]);
+    let report = check(&wat::parse_str(&wat).unwrap(), &Profile::default()).unwrap();
+    assert!(report.ensure().unwrap_err().to_string().contains("clock_time_get"));
+}
+
+#[test]
+fn every_float_family_is_caught_and_integer_lookalikes_are_not() {
+    for body in ["i32.const 0 f32.load drop", "i64.const 1 f64.reinterpret_i64 drop", "i32.const 1 f32.convert_i32_u drop",
+                 "f64.const 1 i64.trunc_sat_f64_s drop", "f32.const 1 f64.promote_f32 drop", "f64.const 1 f64.const 2 f64.copysign drop"] {
+        let wat = format!(r#"(module {ABI} (func (export "run") (param i32 i32) (result i32 i32) {body} local.get 0 local.get 1))"#);
+        assert_eq!(codes(&wat), ["float_ops"], "{body}");
+    }
+    let ints = r#"i64.const 1 i32.wrap_i64 i64.extend_i32_s i64.const 2 i64.rotl drop"#;
+    assert!(codes(&format!(r#"(module {ABI} (func (export "run") (param i32 i32) (result i32 i32) {ints} local.get 0 local.get 1))"#)).is_empty());
+}
+
+#[test]
+fn missing_entry_point_is_reported() {
+    assert_eq!(codes(&format!("(module {ABI})")), ["missing_export"]);
+}
+
+#[test]
+fn floats_only_matter_in_strict_mode() {
+    let wat = format!(r#"(module {ABI} (func (export "run") (param i32 i32) (result i32 i32) f64.const 1 i32.trunc_f64_s local.get 1))"#);
+    let bytes = wat::parse_str(&wat).unwrap();
+    assert!(check(&bytes, &Profile{ strict: false, ..Profile::default() }).unwrap().ok);
+    assert!(!check(&bytes, &Profile::default()).unwrap().ok);
+}
+
+#[test]
+fn reproducibility_threats_are_all_reported() {
+    let wat = r#"(module
+      (memory (export "memory") 1)
+      (data (i32.const 0) "0123456789")
+      (func $init)
+      (start $init)
+      (func (export "alloc") (param i32) (result i32) i32.const 600 memory.grow)
+      (func (export "dealloc") (param i32 i32))
+      (func (export "run") (param i32 i32) (result i32 i32)
+        v128.const i64x2 0 0 i64x2.extract_lane 0 drop
+        i32.const 0 i32.const 1 i32.atomic.rmw.add drop
+        local.get 0 local.get 1))"#;
+    let bytes = wat::parse_str(wat).unwrap();
+    let profile = Profile{ memory_budget_bytes: 512 * 65_536, max_data_segment_bytes: 8, ..Profile::default() };
+    let report = check(&bytes, &profile).unwrap();
+    let codes: Vec<_> = report.findings.iter().map(|f| f.code.as_str()).collect();
+    assert_eq!(codes, ["start_function", "memory_grow_over_budget", "data_segment_oversized", "simd", "threads"]);
+    assert_eq!(report.kind, "wasm.lint.report.v1");
+    assert_eq!(report.module_cid, format!("cid:b3:{}", blake3::hash(&bytes).to_hex()));
+}
+
+#[test]
+fn env_imports_are_plain_host_imports() {
+    let wat = format!(r#"(module
+      (import "env" "lookup" (func (param i32) (result i32)))
+      {ABI}
+      (func (export "run") (param i32 i32) (result i32 i32) local.get 0 local.get 1))"#);
+    assert_eq!(codes(&wat), ["import_not_allowed"]);
+}
+
+#[test]
+fn dealloc_is_optional() {
+    let wat = r#"(module (memory (export "memory") 1)
+      (func (export "alloc") (param i32) (result i32) i32.const 0)
+      (func (export "run") (param i32 i32) (result i32 i32) local.get 0 local.get 1))"#;
+    assert!(codes(wat).is_empty());
+}
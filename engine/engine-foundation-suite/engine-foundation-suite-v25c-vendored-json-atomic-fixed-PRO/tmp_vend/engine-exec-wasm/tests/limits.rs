@@ -0,0 +1,55 @@
+use engine_exec_wasm::limits::LimitKind;
+use engine_exec_wasm::{ExecConfig, WasmExecutor};
+use serde_json::json;
+
+/// A guest with `pages` of memory whose `run` executes `body` and echoes its input.
+fn guest(pages: u32, body: &str) -> Vec<u8> {
+    wat::parse_str(format!(r#"(module
+        (memory (export "memory") {pages})
+        (func (export "alloc") (param i32) (result i32) i32.const 0)
+        (func (export "dealloc") (param i32 i32))
+        (func (export "run") (param i32 i32) (result i32 i32) {body} local.get 0 local.get 1))"#)).unwrap()
+}
+const SPIN: &str = "(loop br 0)";
+
+fn executor(cfg: ExecConfig) -> WasmExecutor { WasmExecutor::new(cfg).unwrap() }
+
+#[test]
+fn a_run_within_limits_reports_its_initial_memory() {
+    let out = executor(ExecConfig::default()).exec_with_report(&guest(2, ""), &json!({"a": 1})).unwrap();
+    assert_eq!(out.output.as_deref(), Some(br#"{"a":1}"#.as_slice()));
+    assert_eq!((out.report.limit_hit, out.report.peak_memory_bytes), (None, 2 * 65536));
+}
+
+#[test]
+fn fuel_runs_out() {
+    let out = executor(ExecConfig{ fuel_limit: 100_000, ..Default::default() }).exec_with_report(&guest(1, SPIN), &json!({})).unwrap();
+    assert_eq!((out.output, out.report.limit_hit), (None, Some(LimitKind::Fuel)));
+    assert_eq!(out.report.fuel_consumed, 100_000);
+}
+
+#[test]
+fn memory_is_capped_on_grow_and_at_instantiation() {
+    let ex = executor(ExecConfig{ memory_limit_bytes: 4 * 65536, ..Default::default() });
+    let out = ex.exec_with_report(&guest(1, "(drop (memory.grow (i32.const 2)))"), &json!({})).unwrap();
+    assert_eq!((out.report.limit_hit, out.report.peak_memory_bytes), (None, 3 * 65536));
+    let out = ex.exec_with_report(&guest(1, "(drop (memory.grow (i32.const 8)))"), &json!({})).unwrap();
+    assert_eq!((out.output, out.report.limit_hit, out.report.peak_memory_bytes), (None, Some(LimitKind::Memory), 65536));
+    let out = ex.exec_with_report(&guest(8, ""), &json!({})).unwrap();
+    assert_eq!((out.output, out.report.limit_hit), (None, Some(LimitKind::Memory)));
+}
+
+#[test]
+fn a_timed_out_run_does_not_cut_others_short() {
+    let ex = executor(ExecConfig{ fuel_limit: u64::MAX, wall_clock_ms: 300, ..Default::default() });
+    let unit = guest(1, SPIN);
+    std::thread::scope(|s| {
+        let first = s.spawn(|| ex.exec_with_report(&unit, &json!({})).unwrap());
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        let second = s.spawn(|| ex.exec_with_report(&unit, &json!({})).unwrap());
+        for out in [first.join().unwrap(), second.join().unwrap()] {
+            assert_eq!((out.output, out.report.limit_hit), (None, Some(LimitKind::WallClock)));
+            assert!(out.report.elapsed_ms >= 300, "interrupted after {}ms", out.report.elapsed_ms);
+        }
+    });
+}
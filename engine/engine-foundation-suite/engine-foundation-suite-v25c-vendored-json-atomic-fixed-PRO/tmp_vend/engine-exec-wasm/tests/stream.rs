@@ -0,0 +1,65 @@
+use engine_exec_wasm::limits::LimitKind;
+use engine_exec_wasm::{ExecConfig, StreamOutcome, WasmExecutor};
+use serde_json::{json, Value};
+
+/// A fold guest: every record lands at offset 1024, `fold_step` runs `body` and then echoes odd-numbered
+/// records (the 1st, 3rd, ...), and `fold_finish` returns `{"done":true}`.
+fn fold_guest(body: &str) -> Vec<u8> {
+    wat::parse_str(format!(r#"(module
+        (memory (export "memory") 1)
+        (global $n (mut i32) (i32.const 0))
+        (data (i32.const 0) "{{\"done\":true}}")
+        (func (export "alloc") (param i32) (result i32) i32.const 1024)
+        (func (export "dealloc") (param i32 i32))
+        (func (export "fold_step") (param i32 i32) (result i32 i32)
+            {body}
+            (global.set $n (i32.add (global.get $n) (i32.const 1)))
+            local.get 0
+            (select (local.get 1) (i32.const 0) (i32.and (global.get $n) (i32.const 1))))
+        (func (export "fold_finish") (result i32 i32) i32.const 0 i32.const 13))"#)).unwrap()
+}
+
+fn cid(v: &Value) -> String { format!("cid:b3:{}", blake3::hash(&serde_json::to_vec(v).unwrap()).to_hex()) }
+
+fn run(cfg: ExecConfig, unit: &[u8], records: Vec<Value>) -> (anyhow::Result<StreamOutcome>, Vec<Value>) {
+    let mut emitted = Vec::new();
+    let out = WasmExecutor::new(cfg).unwrap().exec_stream(unit, &json!({}), records, &mut |b: &[u8]| { emitted.push(serde_json::from_slice(b)?); Ok(()) });
+    (out, emitted)
+}
+
+#[test]
+fn records_are_folded_and_cids_cover_the_whole_stream() {
+    let recs = vec![json!({"b":1,"a":2}), json!([1,2]), json!("x")];
+    let (out, emitted) = run(ExecConfig::default(), &fold_guest(""), recs.clone());
+    let out = out.unwrap();
+    assert_eq!(emitted, vec![recs[0].clone(), recs[2].clone()]);
+    assert_eq!((out.records_in, out.records_out, out.summary.clone()), (3, 2, Some(json!({"done": true}))));
+    assert_eq!(out.input_cid, cid(&json!(recs)));
+    assert_eq!(out.output_cid, Some(cid(&json!({"records": emitted, "summary": {"done": true}}))));
+    assert_eq!(serde_json::to_vec(&emitted[0]).unwrap(), br#"{"a":2,"b":1}"#);
+}
+
+#[test]
+fn memory_is_bounded_by_the_largest_record_not_the_stream() {
+    let cfg = ExecConfig{ memory_limit_bytes: 2 * 65536, ..Default::default() };
+    let recs: Vec<Value> = (0..2_000).map(|i| json!({"i": i, "pad": "x".repeat(1_000)})).collect();
+    let (out, emitted) = run(cfg.clone(), &fold_guest(""), recs);
+    let out = out.unwrap();
+    assert_eq!((out.records_in, emitted.len(), out.report.peak_memory_bytes), (2_000, 1_000, 65536));
+
+    let (out, _) = run(cfg, &fold_guest(""), vec![json!("x".repeat(3 * 65536))]);
+    assert!(out.unwrap_err().to_string().contains("exceeds limit"));
+}
+
+#[test]
+fn a_trap_midstream_leaves_no_summary_or_output_cid() {
+    let (out, emitted) = run(ExecConfig::default(), &fold_guest("(if (i32.eq (global.get $n) (i32.const 1)) (then unreachable))"), vec![json!(1), json!(2), json!(3)]);
+    let out = out.unwrap();
+    assert_eq!((emitted, out.summary, out.output_cid), (vec![json!(1)], None, None));
+    assert_eq!((out.records_in, out.report.limit_hit), (2, None));
+    assert_eq!(out.input_cid, cid(&json!([1, 2, 3])));
+    assert!(out.report.trap.is_some());
+
+    let (out, _) = run(ExecConfig{ fuel_limit: 100_000, ..Default::default() }, &fold_guest("(loop br 0)"), vec![json!(1)]);
+    assert_eq!(out.unwrap().report.limit_hit, Some(LimitKind::Fuel));
+}
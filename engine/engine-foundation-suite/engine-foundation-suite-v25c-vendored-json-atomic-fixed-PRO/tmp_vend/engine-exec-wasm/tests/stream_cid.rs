@@ -0,0 +1,28 @@
+use engine_exec_wasm::stream::StreamCid;
+use serde_json::json;
+
+fn b3(bytes: &[u8]) -> String { format!("cid:b3:{}", blake3::hash(bytes).to_hex()) }
+
+#[test]
+fn incremental_cids_match_whole_document() {
+    let recs = [json!({"a":1}), json!([1,2]), json!("x")];
+
+    let mut arr = StreamCid::array();
+    let mut env = StreamCid::envelope();
+    for r in &recs {
+        let b = serde_json::to_vec(r).unwrap();
+        arr.push(&b);
+        env.push(&b);
+    }
+    let summary = json!({"count":3});
+    assert_eq!(arr.finish_array(), b3(&serde_json::to_vec(&json!(recs)).unwrap()));
+    assert_eq!(
+        env.finish_envelope(&serde_json::to_vec(&summary).unwrap()),
+        b3(&serde_json::to_vec(&json!({"records": recs, "summary": summary})).unwrap())
+    );
+}
+
+#[test]
+fn empty_stream() {
+    assert_eq!(StreamCid::array().finish_array(), b3(b"[]"));
+}
@@ -0,0 +1,108 @@
+//! Open ASKs, so a client can answer a Proof of Indecision instead of starting over.
+//!
+//! Every `/run` that ends in ASK leaves a [`AskRecord`] (the unit it ran and the caller's input,
+//! keyed by run CID) in the tenant's `receipts/asks/`. `/run/resume` merges the answers into that
+//! input, re-runs the same unit and marks the record resolved by the new run, so each ASK is
+//! answered at most once and the receipts form a chain through `resolves`. Only the caller who got the
+//! ASK ([`AskRecord::asked_by`]) or a reviewer ([`REVIEWER_ROLE`]) may answer it.
+use anyhow::{anyhow, bail, Result};
+use engine_core::model::ProofOfIndecision;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Actor role (tenants file) that may answer any ASK of its tenant, not only its own.
+pub const REVIEWER_ROLE: &str = "reviewer";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AskRecord {
+    pub kind: String,                      // "run.ask.v1"
+    pub run_cid: String,
+    pub unit_ref: String,
+    pub realm: String,
+    /// What the caller sent, before `$server`/`$context` were added.
+    pub input: Value,
+    pub poi: ProofOfIndecision,
+    /// Receipt input CID, for matching the record against the ASK receipt.
+    pub input_cid: String,
+    /// [`crate::tenant::Identity::label`] of the caller the run answered with ASK.
+    #[serde(default)]
+    pub asked_by: String,
+    pub ts: String,
+    /// Run CID of the resume that answered this ASK.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_by: Option<String>,
+}
+
+impl AskRecord {
+    pub fn new(run_cid:&str, unit_ref:&str, realm:&str, input:Value, poi:ProofOfIndecision, input_cid:&str, asked_by:&str) -> Self {
+        Self{
+            kind: "run.ask.v1".into(), run_cid: run_cid.into(), unit_ref: unit_ref.into(), realm: realm.into(), input, poi,
+            input_cid: input_cid.into(), asked_by: asked_by.into(), ts: chrono::Utc::now().to_rfc3339(), resolved_by: None,
+        }
+    }
+
+    /// `who` may answer this ASK: it is the caller who got it, or a reviewer.
+    pub fn may_resume(&self, who:&crate::tenant::Identity) -> bool {
+        who.label() == self.asked_by || who.role.as_deref() == Some(REVIEWER_ROLE)
+    }
+}
+
+/// `{dir}/<hex>.json` per ASK.
+#[derive(Debug, Clone)]
+pub struct AskStore { dir: PathBuf, lock: Arc<Mutex<()>> }
+
+fn file_name(run_cid:&str) -> Result<String> {
+    let hex = run_cid.strip_prefix("b3:").ok_or_else(|| anyhow!("not a b3 cid: {run_cid}"))?;
+    if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) { bail!("not a b3 cid: {run_cid}"); }
+    Ok(format!("{}.json", hex.to_ascii_lowercase()))
+}
+
+impl AskStore {
+    pub fn new(dir:impl Into<PathBuf>) -> Self { Self{ dir: dir.into(), lock: Arc::default() } }
+
+    /// Writes `rec` through a temp file of its own; `replace` false keeps a record already there.
+    fn write(&self, rec:&AskRecord, replace:bool) -> Result<bool> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(file_name(&rec.run_cid)?);
+        let tmp = path.with_extension(format!("{}.tmp", ulid::Ulid::new()));
+        std::fs::write(&tmp, serde_json::to_vec_pretty(rec)?)?;
+        if replace { std::fs::rename(tmp, path)?; return Ok(true); }
+        let linked = std::fs::hard_link(&tmp, &path);
+        let _ = std::fs::remove_file(&tmp);
+        match linked {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Records a new ASK; `false` when `rec.run_cid` already has one, which is kept (answered or not).
+    pub fn create(&self, rec:&AskRecord) -> Result<bool> { self.write(rec, false) }
+
+    pub fn get(&self, run_cid:&str) -> Result<Option<AskRecord>> {
+        let path = self.dir.join(file_name(run_cid)?);
+        if !path.exists() { return Ok(None); }
+        Ok(Some(serde_json::from_slice(&std::fs::read(path)?)?))
+    }
+
+    /// Close `run_cid` as answered by `by`; fails if it was already answered. Check and write hold the
+    /// store's lock, so of two concurrent resolves exactly one succeeds.
+    pub fn resolve(&self, run_cid:&str, by:&str) -> Result<AskRecord> {
+        let _g = self.lock.lock().unwrap();
+        let mut rec = self.get(run_cid)?.ok_or_else(|| anyhow!("no open ask {run_cid}"))?;
+        if let Some(prev) = &rec.resolved_by { bail!("ask {run_cid} was already resolved by {prev}"); }
+        rec.resolved_by = Some(by.into());
+        self.write(&rec, true)?;
+        Ok(rec)
+    }
+}
+
+/// `base` with `answers` merged in: objects merge key by key, anything else is replaced.
+pub fn merge_answers(base:&mut Value, answers:Value) {
+    match (base, answers) {
+        (Value::Object(b), Value::Object(a)) => for (k, v) in a { merge_answers(b.entry(k).or_insert(Value::Null), v) },
+        (b, a) => *b = a,
+    }
+}
@@ -0,0 +1,51 @@
+//! Server-side facts under the reserved `$server` input key.
+//!
+//! Anything in `actor` is a claim by the caller. Policies that gate on identity or quota should
+//! read `$server.actor.role`, `$server.quota.remaining`, ... instead: the server fills `$server` from
+//! the bearer token and the quota store, and refuses inputs that already carry the key.
+use serde::Serialize;
+use serde_json::Value;
+
+pub const SERVER_NS: &str = "$server";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActorFacts {
+    /// Actor id from `tenants.<id>.actors`; `null` for tenant-wide tokens and anonymous calls.
+    pub id: Option<String>,
+    pub role: Option<String>,
+    pub authenticated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaFacts {
+    /// Runs left before this one; a run is only spent when it is `ACK`ed.
+    pub remaining: u64,
+    pub per_minute: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerFacts {
+    pub tenant: String,
+    pub actor: ActorFacts,
+    pub quota: QuotaFacts,
+}
+
+#[derive(Debug)]
+pub enum EnrichError { NotObject, Reserved }
+impl std::fmt::Display for EnrichError {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotObject => f.write_str("input must be a JSON object"),
+            Self::Reserved => write!(f, "{SERVER_NS} is reserved for server-side facts"),
+        }
+    }
+}
+impl std::error::Error for EnrichError {}
+
+/// `input` plus `{"$server": facts}`; `null` counts as `{}`.
+pub fn enrich(input:Value, facts:&ServerFacts) -> Result<Value, EnrichError> {
+    let mut obj = match input { Value::Object(m) => m, Value::Null => Default::default(), _ => return Err(EnrichError::NotObject) };
+    if obj.contains_key(SERVER_NS) { return Err(EnrichError::Reserved); }
+    obj.insert(SERVER_NS.into(), serde_json::to_value(facts).expect("facts serialize"));
+    Ok(Value::Object(obj))
+}
@@ -0,0 +1,191 @@
+//! The engine's Grant Revocation List: `revoked_grants/grl.json`, sealed by the engine key.
+//!
+//! Local revocations ([`revoke`]) and merged upstream ones share one serial sequence, and the list is
+//! re-sealed and written back after every change. With `GRL_REMOTE_URL`, upstream revocations are
+//! pulled as deltas (`?since=<last merged serial>`) at most every `GRL_TTL_MS` (default 60 s) and
+//! merged only when sealed by a key in `GRL_TRUSTED_KEYS` (comma-separated base64 ed25519); the
+//! remote's issuer is kept in `revoked_grants/grl.remote.json` so a restart resumes with a delta.
+//! [`init`] loads all of it when the router is built; every grant use goes through [`revoked`].
+use anyhow::{Context, Result};
+use ed25519_dalek::VerifyingKey;
+use engine_auth::grl::Grl;
+use once_cell::sync::OnceCell;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::signer;
+
+/// Per-grant markers checked alongside the list: `revoked_grants/<grant_id>.json` revokes at once.
+const DIR: &str = "revoked_grants";
+
+#[derive(Default)]
+struct Stats { checks: AtomicU64, hits: AtomicU64, applied: AtomicU64, unchanged: AtomicU64, rejected: AtomicU64, fetch_errors: AtomicU64 }
+
+struct State {
+    list: RwLock<Grl>,
+    path: PathBuf,
+    trusted: Vec<VerifyingKey>,
+    remote: Option<String>,
+    ttl: Duration,
+    last_sync: Mutex<Option<Instant>>,
+    /// Issuer of `remote`, once a list from it was merged; picks the `since` of the next pull.
+    remote_issuer: Mutex<Option<String>>,
+    stats: Stats,
+}
+
+static GRL: OnceCell<State> = OnceCell::new();
+
+/// Loads the list and the sync settings; a bad `grl.json` or setting is an error here, not in a request.
+pub fn init() -> Result<()> { GRL.get_or_try_init(State::from_env).map(|_| ()) }
+
+fn grl() -> &'static State { GRL.get().expect("grl::init runs when the router is built") }
+
+/// `remote_issuer` as persisted, for the remote it was learnt from.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Remote { url: String, issuer: String }
+
+impl State {
+    fn from_env() -> Result<Self> {
+        signer::init_signer();
+        let path = Path::new(DIR).join("grl.json");
+        let remote = std::env::var("GRL_REMOTE_URL").ok();
+        let key = signer::signing_key();
+        let list = match std::fs::read(&path) {
+            Ok(b) => {
+                let l: Grl = serde_json::from_slice(&b).with_context(|| format!("{}", path.display()))?;
+                l.verify(&[key.verifying_key()]).with_context(|| format!("{} is not sealed by the engine key", path.display()))?;
+                l
+            }
+            Err(_) => { let mut l = Grl::default(); l.sign(&key)?; l }
+        };
+        let trusted = std::env::var("GRL_TRUSTED_KEYS").unwrap_or_default().split(',').map(str::trim).filter(|k| !k.is_empty())
+            .map(engine_auth::signing::verifying_key_b64).collect::<Result<Vec<_>>>().context("GRL_TRUSTED_KEYS")?;
+        let remote_path = path.with_file_name("grl.remote.json");
+        let issuer = match std::fs::read(&remote_path) {
+            Ok(b) => serde_json::from_slice::<Remote>(&b).with_context(|| format!("{}", remote_path.display()))?,
+            Err(_) => Remote{ url: String::new(), issuer: String::new() },
+        };
+        let remote_issuer = Some(issuer).filter(|r| remote.as_deref() == Some(r.url.as_str()) && !r.issuer.is_empty()).map(|r| r.issuer);
+        let ttl = match std::env::var("GRL_TTL_MS") { Ok(ms) => ms.parse().context("GRL_TTL_MS")?, Err(_) => 60_000 };
+        Ok(Self{
+            list: RwLock::new(list), path, trusted, remote, ttl: Duration::from_millis(ttl),
+            last_sync: Mutex::new(None), remote_issuer: Mutex::new(remote_issuer), stats: Stats::default(),
+        })
+    }
+
+    /// Records (and persists) the issuer of `remote`; `None` forgets it, so the next pull is a full list.
+    fn set_remote_issuer(&self, issuer:Option<String>) -> Result<()> {
+        let mut cur = self.remote_issuer.lock().unwrap();
+        if *cur == issuer { return Ok(()); }
+        let p = self.path.with_file_name("grl.remote.json");
+        match (&issuer, &self.remote) {
+            (Some(i), Some(url)) => {
+                let tmp = p.with_extension("json.tmp");
+                std::fs::write(&tmp, serde_json::to_vec_pretty(&Remote{ url: url.clone(), issuer: i.clone() })?)?;
+                std::fs::rename(tmp, &p)?;
+            }
+            _ => { let _ = std::fs::remove_file(&p); }
+        }
+        *cur = issuer;
+        Ok(())
+    }
+
+    /// Seals `next` with the engine key, writes it and makes it current.
+    fn commit(&self, list:&mut Grl, mut next:Grl) -> Result<()> {
+        next.sign(&signer::signing_key())?;
+        std::fs::create_dir_all(self.path.parent().unwrap_or(Path::new(".")))?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&next)?)?;
+        std::fs::rename(tmp, &self.path)?;
+        *list = next;
+        Ok(())
+    }
+}
+
+/// Whether `grant_id` is revoked, by the list or a per-grant marker.
+pub fn revoked(grant_id:&str) -> bool {
+    let s = &grl().stats;
+    s.checks.fetch_add(1, Ordering::Relaxed);
+    let hit = grl().list.read().unwrap().is_revoked(grant_id) || Path::new(DIR).join(format!("{grant_id}.json")).exists();
+    if hit { s.hits.fetch_add(1, Ordering::Relaxed); }
+    hit
+}
+
+/// Revokes `grant_id` locally; returns the re-sealed list.
+pub fn revoke(grant_id:&str, reason:Option<String>) -> Result<Grl> {
+    let g = grl();
+    let mut list = g.list.write().unwrap();
+    let mut next = list.clone();
+    if next.revoke(grant_id, reason) { g.commit(&mut list, next)?; }
+    Ok(list.clone())
+}
+
+/// The sealed list, or its delta after serial `since` (sealed on the fly).
+pub fn current(since:Option<u64>) -> Result<Grl> {
+    let list = grl().list.read().unwrap();
+    let Some(since) = since else { return Ok(list.clone()) };
+    let mut d = list.delta(since);
+    d.sign(&signer::signing_key())?;
+    Ok(d)
+}
+
+/// Pulls and merges `GRL_REMOTE_URL` when the last pull is older than `GRL_TTL_MS`. Failures keep
+/// the current list (and are counted); they never make a revoked grant usable again.
+pub async fn sync() {
+    let g = grl();
+    let Some(url) = &g.remote else { return };
+    {
+        let mut last = g.last_sync.lock().unwrap();
+        if last.is_some_and(|t| t.elapsed() < g.ttl) { return; }
+        *last = Some(Instant::now());
+    }
+    let issuer = g.remote_issuer.lock().unwrap().clone();
+    let since = issuer.and_then(|i| g.list.read().unwrap().upstream.get(&i).copied()).unwrap_or(0);
+    let fetched = async {
+        reqwest::Client::new().get(url).query(&[("since", since)]).timeout(Duration::from_secs(10))
+            .send().await?.error_for_status()?.json::<Grl>().await
+    }.await;
+    let up = match fetched {
+        Ok(up) => up,
+        Err(e) => { g.stats.fetch_errors.fetch_add(1, Ordering::Relaxed); tracing::warn!(%url, "grl: fetch: {e}"); return; }
+    };
+    let mut list = g.list.write().unwrap();
+    let mut next = list.clone();
+    match next.merge(&up, &g.trusted).and_then(|n| g.commit(&mut list, next).map(|_| n)) {
+        Ok(n) => {
+            if let Err(e) = g.set_remote_issuer(Some(up.issuer)) { tracing::warn!("grl: {e:#}"); }
+            let c = if n > 0 { &g.stats.applied } else { &g.stats.unchanged };
+            c.fetch_add(1, Ordering::Relaxed);
+        }
+        Err(e) => {
+            g.stats.rejected.fetch_add(1, Ordering::Relaxed);
+            // A gap (e.g. the upstream was rebuilt) is closed by pulling the full list next time.
+            if let Err(e) = g.set_remote_issuer(None) { tracing::warn!("grl: {e:#}"); }
+            tracing::warn!(%url, "grl: merge: {e:#}");
+        }
+    }
+}
+
+/// Prometheus text exposition of the list and its sync counters.
+pub fn metrics() -> String {
+    let s = &grl().stats;
+    let (serial, entries) = { let l = grl().list.read().unwrap(); (l.serial, l.entries.len()) };
+    let mut out = String::new();
+    out.push_str("# TYPE engine_grl_serial gauge\n");
+    out.push_str(&format!("engine_grl_serial {serial}\n"));
+    out.push_str("# TYPE engine_grl_entries gauge\n");
+    out.push_str(&format!("engine_grl_entries {entries}\n"));
+    out.push_str("# TYPE engine_grl_checks_total counter\n");
+    out.push_str(&format!("engine_grl_checks_total {}\n", s.checks.load(Ordering::Relaxed)));
+    out.push_str("# TYPE engine_grl_revoked_hits_total counter\n");
+    out.push_str(&format!("engine_grl_revoked_hits_total {}\n", s.hits.load(Ordering::Relaxed)));
+    out.push_str("# TYPE engine_grl_merges_total counter\n");
+    for (outcome, c) in [("applied", &s.applied), ("unchanged", &s.unchanged), ("rejected", &s.rejected)] {
+        out.push_str(&format!("engine_grl_merges_total{{outcome=\"{outcome}\"}} {}\n", c.load(Ordering::Relaxed)));
+    }
+    out.push_str("# TYPE engine_grl_fetch_errors_total counter\n");
+    out.push_str(&format!("engine_grl_fetch_errors_total {}\n", s.fetch_errors.load(Ordering::Relaxed)));
+    out
+}
@@ -1,5 +1,16 @@
 use once_cell::sync::Lazy;
 
+pub mod server;
+pub mod presign;
+pub mod presign_s3;
+pub mod presign_fs;
+pub mod signer;
+pub mod asks;
+pub mod enrich;
+pub mod quota;
+pub mod tenant;
+pub mod grl;
+
 use axum::{Router, routing::{get, post}};
 use tower_http::trace::TraceLayer;
 
@@ -7,8 +18,11 @@ pub struct EngineHttpConfig {
     pub enable_metrics: bool,
 }
 
-pub fn engine_router(cfg: EngineHttpConfig) -> Router {
+/// Fails when the revocation list (`revoked_grants/grl.json`) or its sync settings do not load.
+pub fn engine_router(cfg: EngineHttpConfig) -> anyhow::Result<Router> {
+    grl::init()?;
     let mut r = Router::new().route("/.well-known/logline/grl.json", axum::routing::get(well_known_grl))
+        .route("/grl/revoke", post(grl_revoke))
         .route("/health", get(|| async { "ok" }))
         .route("/ready", get(|| async { "ok" }))
         .route("/version", get(|| async { env!("CARGO_PKG_VERSION") }))
@@ -17,9 +31,9 @@ pub fn engine_router(cfg: EngineHttpConfig) -> Router {
         .route("/acquire_presigned_url", post(|| async { axum::Json(serde_json::json!({"url":"stub"})) }))
         .layer(TraceLayer::new_for_http());
     if cfg.enable_metrics {
-        r = r.route("/metrics", get(|| async { "# HELP engine 1\nengine 1\n" }));
+        r = r.route("/metrics", get(|| async { format!("# HELP engine 1\nengine 1\n{}", grl::metrics()) }));
     }
-    r
+    Ok(r)
 }
 
 
@@ -41,7 +55,10 @@ struct RunWasmReq {
 
 #[derive(Serialize)]
 struct RunWasmResp {
+    decision: engine_core::model::Decision,
     output: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poi: Option<Poi>,
     meta: serde_json::Value,
 }
 
@@ -53,28 +70,171 @@ async fn run_wasm_handler(State(state): State<Arc<EngineState>>, Json(req): Json
         Ok(e) => e,
         Err(_) => return Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
     };
-    let out_bytes = exec.exec(&wasm_bytes, &req.input).map_err(|_| axum::http::StatusCode::BAD_REQUEST)?;
-    let output: serde_json::Value = serde_json::from_slice(&out_bytes).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Malformed modules / ABI violations are still the caller's fault (400); guest traps are a Doubt.
+    let outcome = exec.exec_with_report(&wasm_bytes, &req.input).map_err(|_| axum::http::StatusCode::BAD_REQUEST)?;
     let meta = serde_json::json!({
         "fuel_limit": state.wasm_cfg.fuel_limit,
         "memory_limit_bytes": state.wasm_cfg.memory_limit_bytes,
-        "deterministic": true
+        "wall_clock_ms": state.wasm_cfg.wall_clock_ms,
+        "deterministic": true,
+        "report": outcome.report
     });
-    Ok(Json(RunWasmResp{ output, meta }))
+    let Some(out_bytes) = outcome.output else {
+        let poi = match &outcome.guest_error { Some(e) => guest_error_poi(e), None => trap_poi(&outcome.report) };
+        return Ok(Json(RunWasmResp{ decision: engine_core::model::Decision::Doubt, output: serde_json::Value::Null, poi: Some(poi), meta }));
+    };
+    let output: serde_json::Value = serde_json::from_slice(&out_bytes).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(RunWasmResp{ decision: engine_core::model::Decision::Allow, output, poi: None, meta }))
+}
+
+/// First line of a `/run-wasm/stream` body; every following line is one input record (NDJSON).
+#[derive(Deserialize)]
+struct RunWasmStreamHead {
+    wasm_b64: String,
+    #[serde(default)]
+    params: serde_json::Value,
 }
 
+/// Last line of a `/run-wasm/stream` response; the lines before it are the records the guest emitted.
+#[derive(Serialize)]
+struct RunWasmStreamTrailer {
+    decision: engine_core::model::Decision,
+    summary: serde_json::Value,
+    input_cid: String,
+    output_cid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poi: Option<Poi>,
+    meta: serde_json::Value,
 }
 
-/// Build a router with deterministic WASM enabled (default limits).
-pub fn engine_router_with_wasm(cfg: EngineHttpConfig) -> Router {
-    use axum::Router;
+/// Upper bound for the head line (module + params); records are bounded by `memory_limit_bytes`.
+const STREAM_HEAD_LIMIT: usize = 16 * 1024 * 1024;
+/// Caps on what one request may spool to disk (`413` beyond them).
+const STREAM_BODY_LIMIT: u64 = 1 << 30;
+const STREAM_RECORDS_LIMIT: usize = 1_000_000;
+
+/// Temp file removed on drop: streamed bodies are spooled here instead of being held in memory.
+struct Spool(std::path::PathBuf);
+impl Spool {
+    fn new(tag:&str)->Self { Self(std::env::temp_dir().join(format!("engine-{tag}-{}", Ulid::new()))) }
+}
+impl Drop for Spool { fn drop(&mut self) { let _ = std::fs::remove_file(&self.0); } }
+
+/// One `\n`-terminated line without its terminator, at most `limit` bytes; `None` at the end of the body.
+async fn read_line<R: tokio::io::AsyncBufRead + Unpin>(r:&mut R, limit:usize) -> std::io::Result<Option<Vec<u8>>> {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+    let mut line = Vec::new();
+    if (&mut *r).take(limit as u64 + 1).read_until(b'\n', &mut line).await? == 0 { return Ok(None); }
+    if line.last() == Some(&b'\n') { line.pop(); if line.last() == Some(&b'\r') { line.pop(); } }
+    if line.len() > limit { return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("line exceeds {limit} bytes"))); }
+    Ok(Some(line))
+}
+
+/// Large-input variant of `/run-wasm`. The body is NDJSON: a [`RunWasmStreamHead`] line, then one record per line.
+/// Records are spooled to disk as they arrive (so a slow upload does not eat the guest's wall clock) and then
+/// folded one at a time through the guest's `fold_step`; host and guest memory are bounded by the largest record.
+/// The response is NDJSON too: each emitted record (canonical JSON), then a [`RunWasmStreamTrailer`] line.
+async fn run_wasm_stream_handler(State(state): State<Arc<EngineState>>, body: axum::body::Body) -> Result<axum::response::Response, axum::http::StatusCode> {
+    use axum::http::StatusCode;
+    use futures_util::{StreamExt, TryStreamExt};
+    use tokio::io::AsyncWriteExt;
+    let mut body = tokio::io::BufReader::new(tokio_util::io::StreamReader::new(body.into_data_stream().map_err(std::io::Error::other)));
+    let head = read_line(&mut body, STREAM_HEAD_LIMIT).await.map_err(|_| StatusCode::BAD_REQUEST)?.ok_or(StatusCode::BAD_REQUEST)?;
+    let head: RunWasmStreamHead = serde_json::from_slice(&head).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let wasm_bytes = general_purpose::STANDARD.decode(head.wasm_b64.as_bytes()).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let input = Spool::new("stream-in");
+    let mut w = tokio::io::BufWriter::new(tokio::fs::File::create(&input.0).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?);
+    let (mut spooled, mut count) = (0u64, 0usize);
+    while let Some(line) = read_line(&mut body, state.wasm_cfg.memory_limit_bytes).await.map_err(|_| StatusCode::BAD_REQUEST)? {
+        if line.iter().all(u8::is_ascii_whitespace) { continue; }
+        spooled += line.len() as u64 + 1; count += 1;
+        if spooled > STREAM_BODY_LIMIT || count > STREAM_RECORDS_LIMIT { return Err(StatusCode::PAYLOAD_TOO_LARGE); }
+        serde_json::from_slice::<serde::de::IgnoredAny>(&line).map_err(|_| StatusCode::BAD_REQUEST)?;
+        w.write_all(&line).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        w.write_all(b"\n").await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    w.flush().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let cfg = state.wasm_cfg.clone();
+    let output = tokio::task::spawn_blocking(move || -> anyhow::Result<Spool> {
+        use std::io::{BufRead, Write};
+        let exec = engine_exec_wasm::WasmExecutor::new(cfg.clone())?;
+        let output = Spool::new("stream-out");
+        let mut w = std::io::BufWriter::new(std::fs::File::create(&output.0)?);
+        let mut sink = |b: &[u8]| -> anyhow::Result<()> { w.write_all(b)?; w.write_all(b"\n")?; Ok(()) };
+        let mut spool_err = None;
+        let records = std::io::BufReader::new(std::fs::File::open(&input.0)?).lines()
+            .map_while(|l| l.map_err(anyhow::Error::from).and_then(|l| Ok(serde_json::from_str(&l)?)).map_err(|e| spool_err = Some(e)).ok());
+        let outcome = exec.exec_stream(&wasm_bytes, &head.params, records, &mut sink)?;
+        if let Some(e) = spool_err { return Err(e); }
+        serde_json::to_writer(&mut w, &stream_trailer(&cfg, outcome))?;
+        w.write_all(b"\n")?;
+        w.flush()?;
+        Ok(output)
+    }).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    // Malformed modules / ABI violations are the caller's fault (400); spool I/O is ours.
+    .map_err(|e| if e.is::<std::io::Error>() { StatusCode::INTERNAL_SERVER_ERROR } else { StatusCode::BAD_REQUEST })?;
+
+    let file = tokio::fs::File::open(&output.0).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // The spool lives as long as the body stream, so it is removed once the response is sent (or dropped).
+    let body = axum::body::Body::from_stream(tokio_util::io::ReaderStream::new(file).map(move |chunk| { let _spool = &output; chunk }));
+    Ok(([(axum::http::header::CONTENT_TYPE, "application/x-ndjson")], body).into_response())
+}
+
+fn stream_trailer(cfg:&engine_exec_wasm::ExecConfig, outcome:engine_exec_wasm::StreamOutcome) -> RunWasmStreamTrailer {
+    let meta = serde_json::json!({
+        "fuel_limit": cfg.fuel_limit,
+        "memory_limit_bytes": cfg.memory_limit_bytes,
+        "wall_clock_ms": cfg.wall_clock_ms,
+        "deterministic": true,
+        "abi": "fold_init?/fold_step/fold_finish",
+        "records_in": outcome.records_in,
+        "records_out": outcome.records_out,
+        "report": outcome.report
+    });
+    let (decision, poi) = match (&outcome.summary, &outcome.guest_error) {
+        (Some(_), _) => (engine_core::model::Decision::Allow, None),
+        (None, Some(e)) => (engine_core::model::Decision::Doubt, Some(guest_error_poi(e))),
+        (None, None) => (engine_core::model::Decision::Doubt, Some(trap_poi(&outcome.report))),
+    };
+    RunWasmStreamTrailer{
+        decision, summary: outcome.summary.unwrap_or(serde_json::Value::Null),
+        input_cid: outcome.input_cid, output_cid: outcome.output_cid, poi, meta,
+    }
+}
+
+/// The unit itself declined (typed error via guest-sdk): surface its code so the caller can fix the input.
+fn guest_error_poi(e: &engine_exec_wasm::GuestError) -> Poi {
+    Poi::new(PoiReason::GuestError).violation(e.code.clone()).hint(e.message.clone())
+}
+
+fn trap_poi(report: &engine_exec_wasm::ExecReport) -> Poi {
+    use engine_exec_wasm::LimitKind::*;
+    let (violation, hint) = match report.limit_hit {
+        Some(Fuel) => ("limit_exceeded:fuel", "raise fuel_limit or reduce the work per input"),
+        Some(Memory) => ("limit_exceeded:memory", "raise memory_limit_bytes or shrink the input"),
+        Some(Table) => ("limit_exceeded:table", "raise table_elements_limit"),
+        Some(WallClock) => ("limit_exceeded:wall_clock", "raise wall_clock_ms or reduce the work per input"),
+        Some(Stack) => ("limit_exceeded:stack", "reduce recursion depth in the unit"),
+        None => ("guest_trap", "inspect meta.report.trap and fix the unit"),
+    };
+    Poi::new(PoiReason::WasmTrap).violation(violation).hint(hint)
+}
+
+}
+
+/// Build a router with deterministic WASM enabled (default limits). `/registry/presign` is not here: it runs the
+/// caller tenant's policy unit, so it lives in [`server::build_router`].
+pub fn engine_router_with_wasm(cfg: EngineHttpConfig) -> anyhow::Result<Router> {
     use std::sync::Arc;
-    let mut r = engine_router(cfg);
+    let mut r = engine_router(cfg)?;
     let state = Arc::new(EngineState{ wasm_cfg: engine_exec_wasm::ExecConfig::default() });
-    r = r.route("/run-wasm", post(run_wasm_handler)).with_state(state);
-        r = r.route("/registry/presign", post(presign_handler));
+    r = r.route("/run-wasm", post(run_wasm_handler))
+        .route("/run-wasm/stream", post(run_wasm_stream_handler))
+        .with_state(state);
         r = r.route("/s3/proxy", get(s3_proxy_handler));
-    r
+    Ok(r.merge(presign_fs::router()))
 }
 
 
@@ -114,247 +274,88 @@ async fn emit_audit_report(base_dir: &str, who: &str, input_json: &serde_json::V
 
 use serde::{Serialize, Deserialize};
 
-#[derive(Deserialize)]
-struct PresignReq {
+/// Body of `POST /registry/presign`; the policy unit sees it as `presign`.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PresignReq {
     backend: String,
     bucket: String,
     key: String,
     verb: String,
-    ttl_secs: u64,
+    pub(crate) ttl_secs: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
     who: Option<String>,
-    // constraints (optional)
-    ip_hash: Option<String>,
+    // constraints (optional): `client_ip` binds the grant to that address; the unit and the audit see only its keyed hash
+    #[serde(default, skip_serializing)]
+    pub(crate) client_ip: Option<String>,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub(crate) ip_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     byte_range_max: Option<u64>
 }
 
 #[derive(Serialize)]
-struct PresignResp {
+pub(crate) struct PresignResp {
     url: String,
     meta: serde_json::Value
 }
 
 
-#[derive(Serialize)]
-struct Poi {
-    reason: String,
-    missing: Vec<String>,
-    violations: Vec<String>,
-    hints: Vec<String>,
-}
-
-#[derive(Serialize)]
-#[serde(tag = "decision")]
-enum PolicyDecision {
-    ACK,
-    ASK { poi: Poi },
-    NACK { error: String },
-}
-
-fn eval_presign_policy(req: &PresignReq) -> PolicyDecision {
-    // Hard limits (representing TDLN policy)
-    let allowed_verbs = ["GET","PUT"];
-    if !allowed_verbs.contains(&req.verb.as_str()) {
-        return PolicyDecision::NACK { error: "verb_not_allowed".into() };
-    }
-
-    let mut violations = Vec::new();
-    let mut missing = Vec::new();
-    let mut hints = Vec::new();
+use engine_core::model::{Decision, PoiReason, ProofOfIndecision as Poi};
 
-    // TTL policy: <= 600s
-    if req.ttl_secs > 600 {
-        violations.push(format!("ttl_secs_exceeds_max:{}", req.ttl_secs));
-        hints.push("use ttl_secs <= 600".into());
-    }
+/// Unit `/registry/presign` requests are evaluated against unless `PRESIGN_POLICY_UNIT` names another
+/// (chip id, CID or `name@range`); the default ships in `units/presign.policy.v1.json`.
+pub const PRESIGN_POLICY_UNIT: &str = "presign.policy.v1";
 
-    // Key/prefix policy: must not contain `..`
-    if req.key.contains("..") {
-        violations.push("key_invalid_path_traversal".into());
-        hints.push("remove '..' from key".into());
-    }
+/// Policies of the presign unit a caller can satisfy by changing the request (TTL, byte range, backend):
+/// denied by these alone, the answer is `ASK` with their descriptions as `poi.hints` rather than `NACK`.
+pub const PRESIGN_ADJUSTABLE: &[&str] = &["ttl_max", "byte_range_max", "backend_allowed"];
 
-    // Byte range policy: <= 100 MB if provided
-    if let Some(br) = req.byte_range_max {
-        if br > 100 * 1024 * 1024 {
-            violations.push(format!("byte_range_max_too_large:{}", br));
-            hints.push("set byte_range_max <= 104857600".into());
-        }
-    }
+use engine_auth::grant::{AccessGrant, GrantConstraints, GrantPolicy, GrantResource, GrantSeal, GRANT_KIND};
+use engine_auth::verifier::{Access, Denied, GrantVerifier};
 
-    // Backend policy: restrict to fs/s3 only in this demo
-    if req.backend != "fs" && req.backend != "s3" {
-        violations.push("backend_not_allowed".into());
-        hints.push("use backend 's3' or 'fs'".into());
+/// Checks grants presented to the proxy. Trusted issuers: the engine key and `GRANT_TRUSTED_KEYS`
+/// (comma-separated base64 ed25519); `iat`/`exp` tolerate `GRANT_CLOCK_SKEW_SECS` (default 30).
+/// Legacy blake3 PASETO tokens are accepted until `GRANT_LEGACY_TOKENS_UNTIL` (RFC 3339; anything
+/// else, e.g. `off`, refuses them), by default [`engine_auth::verifier::LEGACY_TOKENS_UNTIL`].
+/// `ip_hash` constraints are keyed with [`signer::ip_hash_key`].
+static GRANTS: Lazy<GrantVerifier> = Lazy::new(|| {
+    signer::init_signer();
+    let mut trusted = vec![signer::signing_key().verifying_key()];
+    for k in std::env::var("GRANT_TRUSTED_KEYS").unwrap_or_default().split(',').map(str::trim).filter(|k| !k.is_empty()) {
+        trusted.push(engine_auth::signing::verifying_key_b64(k).expect("GRANT_TRUSTED_KEYS"));
     }
-
-    if violations.is_empty() && missing.is_empty() {
-        PolicyDecision::ACK
-    } else {
-        PolicyDecision::ASK { poi: Poi {
-            reason: "constraints_need_adjustment".into(),
-            missing,
-            violations,
-            hints
-        }}
+    let skew = std::env::var("GRANT_CLOCK_SKEW_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(engine_auth::verifier::DEFAULT_SKEW_SECS);
+    let v = GrantVerifier::new(trusted).with_skew(chrono::Duration::seconds(skew)).with_ip_key(signer::ip_hash_key());
+    match std::env::var("GRANT_LEGACY_TOKENS_UNTIL") {
+        Ok(until) => v.with_legacy_until(chrono::DateTime::parse_from_rfc3339(&until).ok().map(|t| t.with_timezone(&chrono::Utc))),
+        Err(_) => v,
     }
-}
-
-
-use ed25519_dalek::{Signer, Verifier, SigningKey, VerifyingKey, Signature, SECRET_KEY_LENGTH, PUBLIC_KEY_LENGTH};
-use base64::{engine::general_purpose, Engine as _};
-
-#[derive(Serialize, Deserialize, Clone)]
-struct AccessGrant {
-    kind: String,             // "access.grant.v1"
-    grant_id: String,         // ULID
-    sub: String,              // who
-    resource: serde_json::Value, // {store,bucket,prefix,object,verbs,constraints}
-    exp: String,
-    iat: String,
-    nonce: String,
-    seal: GrantSeal
-}
-
-#[derive(Serialize, Deserialize, Clone)]
-struct GrantSeal {
-    alg: String,   // "ed25519-blake3"
-    kid: String,   // "dev-2026-key-1"
-    sig: String    // base64
-}
-
-fn sign_grant(mut grant: AccessGrant, sk_b64: &str, kid: &str) -> Result<AccessGrant, axum::http::StatusCode> {
-    let sk_bytes = general_purpose::STANDARD.decode(sk_b64).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
-    let sk = SigningKey::from_bytes(&sk_bytes.try_into().map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?);
-    let mut to_sign = grant.clone(); to_sign.seal.sig = "".into();
-    let bytes = serde_json::to_vec(&to_sign).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
-    let digest = blake3::hash(&bytes);
-    let sig = sk.sign(digest.as_bytes());
-    grant.seal = GrantSeal{ alg:"ed25519-blake3".into(), kid: kid.into(), sig: general_purpose::STANDARD.encode(sig.to_bytes()) };
-    Ok(grant)
-}
-
-fn verify_grant(grant: &AccessGrant, pk_b64: &str) -> Result<(), axum::http::StatusCode> {
-    if grant.kind != "access.grant.v1" { return Err(axum::http::StatusCode::UNAUTHORIZED); }
-    let pk_bytes = general_purpose::STANDARD.decode(pk_b64).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
-    let vk = VerifyingKey::from_bytes(&pk_bytes.try_into().map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
-    let mut to_verify = grant.clone(); 
-    let sig_b = general_purpose::STANDARD.decode(to_verify.seal.sig.as_bytes()).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
-    let sig = Signature::from_bytes(&sig_b.try_into().map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?);
-    to_verify.seal.sig = "".into();
-    let bytes = serde_json::to_vec(&to_verify).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
-    let digest = blake3::hash(&bytes);
-    vk.verify(digest.as_bytes(), &sig).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)
-}
-
-
-fn b64url_nopad(data: &[u8]) -> String {
-    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(data)
-}
-fn b64url_decode(s: &str) -> Result<Vec<u8>, axum::http::StatusCode> {
-    base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(s.as_bytes()).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)
-}
-
-// Minimal PASETO v4.public (Ed25519, detached footer = kid)
-// token = "v4.public." + b64url(msg || sig || footer_json)
-#[derive(Serialize, Deserialize, Clone)]
-struct PasetoGrant {
-    token: String,        // v4.public....
-    payload: AccessGrant  // same grant payload (for convenience/debug)
-}
-
-fn paseto_sign(grant: &AccessGrant, sk_b64: &str) -> Result<String, axum::http::StatusCode> {
-    let sk_bytes = base64::engine::general_purpose::STANDARD.decode(sk_b64).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
-    let sk = ed25519_dalek::SigningKey::from_bytes(&sk_bytes.try_into().map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?);
-    let msg = serde_json::to_vec(grant).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
-    let digest = blake3::hash(&msg); // v4.public usa Ed25519 direto; aqui mantemos ed25519(blake3(msg)) por coerência com seal
-    let sig  = sk.sign(digest.as_bytes());
-    let body = [msg.as_slice(), sig.as_bytes()].concat();
-    let token = format!("v4.public.{}", b64url_nopad(&body));
-    Ok(token)
-}
-
-fn paseto_verify(token: &str, pk_b64: &str) -> Result<AccessGrant, axum::http::StatusCode> {
-    let prefix = "v4.public.";
-    if !token.starts_with(prefix) { return Err(axum::http::StatusCode::UNAUTHORIZED); }
-    let body = b64url_decode(&token[prefix.len()..])?;
-    if body.len() < 64 { return Err(axum::http::StatusCode::UNAUTHORIZED); }
-    let (msg, sigb) = body.split_at(body.len()-64);
-    let grant: AccessGrant = serde_json::from_slice(msg).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
-    let vk_bytes = base64::engine::general_purpose::STANDARD.decode(pk_b64).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
-    let vk = ed25519_dalek::VerifyingKey::from_bytes(&vk_bytes.try_into().map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
-    let digest = blake3::hash(msg);
-    let sig = ed25519_dalek::Signature::from_bytes(sigb.try_into().map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?);
-    vk.verify(digest.as_bytes(), &sig).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
-    Ok(grant)
-}
-
-
-
-static GRL_CACHE: once_cell::sync::Lazy<std::sync::Mutex<(std::time::Instant, Vec<u8>)>> = once_cell::sync::Lazy::new(|| std::sync::Mutex::new((std::time::Instant::now(), Vec::new())));
-
+});
 
-fn bump_grl_merge_metric(applied: bool) {
-    if !applied { return; }
-    let p = std::path::Path::new("metrics").join("grl_merge_applied.count");
-    if let Ok(prev) = std::fs::read_to_string(&p) {
-        if let Ok(n) = prev.trim().parse::<u64>() {
-            let _ = std::fs::write(&p, format!("{}", n+1));
-            return;
-        }
+#[derive(Deserialize)]
+struct GrlQuery { since: Option<u64> }
+
+/// `GET /.well-known/logline/grl.json[?since=<serial>]` — the sealed list, or the sealed delta after `since`.
+/// `ETag` is the body's CID; a matching `If-None-Match` gets `304`.
+async fn well_known_grl(headers: HeaderMap, Query(q): Query<GrlQuery>) -> Result<axum::response::Response, axum::http::StatusCode> {
+    grl::sync().await;
+    let doc = grl::current(q.since).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    let body = serde_json::to_vec(&doc).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    let etag = format!("\"b3:{}\"", blake3::hash(&body).to_hex());
+    if headers.get(axum::http::header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) == Some(etag.as_str()) {
+        return Ok((axum::http::StatusCode::NOT_MODIFIED, [(axum::http::header::ETAG, etag)]).into_response());
     }
-    let _ = std::fs::create_dir_all("metrics");
-    let _ = std::fs::write(&p, "1");
+    Ok(([(axum::http::header::CONTENT_TYPE, "application/json".to_string()), (axum::http::header::ETAG, etag)], body).into_response())
 }
 
-async fn well_known_grl() -> Result<impl IntoResponse, axum::http::StatusCode> {
-    // 1) Start from local manifest if exists
-    let local_path = std::path::Path::new("revoked_grants").join("manifest.json");
-    let mut merged: serde_json::Value = if local_path.exists() {
-        let data = std::fs::read(&local_path).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
-        serde_json::from_slice(&data).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?
-    } else {
-        serde_json::json!({ "kind":"revocation.manifest.v1", "updated_at": chrono::Utc::now().to_rfc3339(), "grants": [], "sig": "" })
-    };
-
-    // 2) Optional remote fetch + TTL cache
-    if let Ok(remote) = std::env::var("GRL_REMOTE_URL") {
-        let ttl_ms: u64 = std::env::var("GRL_TTL_MS").ok().and_then(|s| s.parse().ok()).unwrap_or(60000);
-        let mut guard = GRL_CACHE.lock().unwrap();
-        let (last, buf) = &mut *guard;
-        if buf.is_empty() || last.elapsed().as_millis() as u64 >= ttl_ms {
-            drop(guard);
-            // fetch
-            let client = reqwest::Client::new();
-            if let Ok(resp) = client.get(remote).send().await {
-                if let Ok(bytes) = resp.bytes().await {
-                    let mut g = GRL_CACHE.lock().unwrap();
-                    *g = (std::time::Instant::now(), bytes.to_vec());
-                }
-            }
-        }
-        let (_, data) = &*GRL_CACHE.lock().unwrap();
-        if !data.is_empty() {
-            if let Ok(remote_v) = serde_json::from_slice::<serde_json::Value>(data) {
-                // naive merge: concatenate unique grant ids, prefer remote sig/updated_at if newer
-                if let (Some(local_g), Some(remote_g)) = (merged.get_mut("grants"), remote_v.get("grants")) {
-                    if let (Some(local_arr), Some(remote_arr)) = (local_g.as_array_mut(), remote_g.as_array()) {
-                        for r in remote_arr {
-                            if !local_arr.contains(r) { local_arr.push(r.clone()); }
-                        }
-                    }
-                }
-                if let Some(ru) = remote_v.get("updated_at").and_then(|x| x.as_str()) {
-                    merged["updated_at"] = serde_json::Value::String(ru.to_string());
-                }
-                if let Some(rs) = remote_v.get("sig") {
-                    merged["sig"] = rs.clone();
-                }
-            }
-        }
-    }
+#[derive(Deserialize)]
+struct RevokeReq { grant_id: String, reason: Option<String> }
 
-    let body = serde_json::to_vec(&merged).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(([(axum::http::header::CONTENT_TYPE, "application/json")], body))
+/// `POST /grl/revoke` (`ENGINE_ADMIN_TOKEN`) — adds the grant under the next serial; returns the re-sealed list.
+async fn grl_revoke(headers: HeaderMap, Json(req): Json<RevokeReq>) -> Result<Json<engine_auth::grl::Grl>, (axum::http::StatusCode, Json<serde_json::Value>)> {
+    server::admin_guard(&headers)?;
+    grl::revoke(&req.grant_id, req.reason).map(Json)
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": format!("{e:#}")}))))
 }
 
 async fn health_handler() -> Result<impl IntoResponse, axum::http::StatusCode> {
@@ -366,52 +367,55 @@ async fn health_handler() -> Result<impl IntoResponse, axum::http::StatusCode> {
         "proxy_enabled": proxy,
         "ts": chrono::Utc::now().to_rfc3339()
     });
-    let _ = emit_audit_report("./tenants/_public", "health", &serde_json::json!({}), &status, &serde_json::json!({"intent":"health"})).await;
+    let _ = emit_audit_report(&tenant::Directory::global().audit_dir(&tenant::TenantId::public()).display().to_string(), "health", &serde_json::json!({}), &status, &serde_json::json!({"intent":"health"})).await;
     Ok(([(axum::http::header::CONTENT_TYPE, "application/json")], serde_json::to_vec(&status).unwrap()))
 }
 
-async fn presign_handler(Json(req): Json<PresignReq>) -> Result<Json<PresignResp>, axum::http::StatusCode> {
-    if std::env::var("PRESIGN_DISABLE").ok().as_deref() == Some("1") { return Err(axum::http::StatusCode::SERVICE_UNAVAILABLE); }
-
-// 0) Evaluate policy with constraints
-let pd = eval_presign_policy(&req);
-match &pd {
-    PolicyDecision::NACK { error } => {
-        let meta = serde_json::json!({
-            "backend": req.backend, "bucket": req.bucket, "key": req.key,
-            "verb": req.verb, "ttl_secs": req.ttl_secs,
-            "constraints": { "ip_hash": req.ip_hash, "byte_range_max": req.byte_range_max }
-        });
-        let out = serde_json::json!({ "decision":"NACK","error": error });
-        let _ = emit_audit_report("./tenants/_public", req.who.as_deref().unwrap_or("anonymous"), &meta, &out, &serde_json::json!({"intent":"presign"})).await;
-        return Ok(Json(PresignResp{ url: "".into(), meta: out }));
-    },
-    PolicyDecision::ASK { poi } => {
-        // Emit audit with PoI; do NOT issue a url
-        let meta = serde_json::json!({
-            "backend": req.backend, "bucket": req.bucket, "key": req.key,
-            "verb": req.verb, "ttl_secs": req.ttl_secs,
-            "constraints": { "ip_hash": req.ip_hash, "byte_range_max": req.byte_range_max }
-        });
-        let out = serde_json::to_value(poi).unwrap_or(serde_json::json!({}));
-        let resp = serde_json::json!({ "decision":"ASK", "poi": out });
-        let _ = emit_audit_report("./tenants/_public", req.who.as_deref().unwrap_or("anonymous"), &meta, &resp, &serde_json::json!({"intent":"presign"})).await;
-        return Ok(Json(PresignResp{ url: "".into(), meta: resp }));
-    },
-    PolicyDecision::ACK => { /* proceed to presign */ }
+/// Audit base dir of the caller's tenant (`401` when the tenants directory rejects the credentials).
+fn tenant_audit_dir(headers: &HeaderMap) -> Result<String, axum::http::StatusCode> {
+    let dir = tenant::Directory::global();
+    let who = dir.authenticate(headers).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
+    Ok(dir.audit_dir(&who.tenant).display().to_string())
 }
 
+/// Acts on the policy unit's receipt for `req` (see `server::registry_presign`): `ACK` issues the URL and a
+/// grant linked to the receipt (`grant.policy`); `ASK`/`NACK` issue nothing. A `Deny` from
+/// [`PRESIGN_ADJUSTABLE`] policies only is answered `ASK`, hinted with their `descriptions` (policy id → text).
+/// `card` is the receipt card with its SIRP refs; every outcome is audited under `audit_dir` with it.
+pub(crate) async fn issue_presign(tenant: &tenant::TenantId, audit_dir: &str, req: &PresignReq, receipt: &engine_core::model::ExecutionReceipt, card: serde_json::Value, link: GrantPolicy, descriptions: &std::collections::BTreeMap<String, String>) -> Result<PresignResp, axum::http::StatusCode> {
+    let who = req.who.as_deref().unwrap_or("anonymous");
+    let request = serde_json::json!({
+        "backend": req.backend, "bucket": req.bucket, "key": req.key,
+        "verb": req.verb, "ttl_secs": req.ttl_secs,
+        "constraints": { "ip_hash": req.ip_hash, "byte_range_max": req.byte_range_max }
+    });
+    let refused = match receipt.decision {
+        Decision::Allow => None,
+        Decision::Deny => {
+            let denied: Vec<_> = receipt.policy_decisions.iter().filter(|d| d.decision == Decision::Deny).map(|d| d.policy_id.clone()).collect();
+            if !denied.is_empty() && denied.iter().all(|p| PRESIGN_ADJUSTABLE.contains(&p.as_str())) {
+                let hints = denied.iter().map(|p| descriptions.get(p).cloned().unwrap_or_else(|| format!("satisfy {p}"))).collect();
+                let poi = Poi{ violations: denied, hints, ..Poi::new(PoiReason::ConstraintsNeedAdjustment) };
+                Some(serde_json::json!({ "decision":"ASK", "poi": poi, "receipt": card }))
+            } else {
+                Some(serde_json::json!({ "decision":"NACK", "error":"policy_denied", "violations": denied, "receipt": card }))
+            }
+        }
+        _ => Some(serde_json::json!({ "decision":"ASK", "poi": receipt.poi, "receipt": card })),
+    };
+    if let Some(out) = refused {
+        let _ = emit_audit_report(audit_dir, who, &request, &out, &serde_json::json!({"intent":"presign"})).await;
+        return Ok(PresignResp{ url: "".into(), meta: out });
+    }
+
+    // The grant's id is known before the URL so the fs URL can be bound to it (and die with its revocation).
+    let grant_id = Ulid::new().to_string();
     // Choose provider
     let url = match req.backend.as_str() {
         "fs" => {
-            #[cfg(feature="fs")]
-            {
-                let reg = engine_registry::fs_registry::FsRegistry::new("./.dev-registry");
-                if req.verb == "GET" { reg.presign_get(&req.bucket, &req.key, req.ttl_secs).await }
-                else { reg.presign_put(&req.bucket, &req.key, req.ttl_secs).await }
-            }
-            #[cfg(not(feature="fs"))]
-            { Err(anyhow::anyhow!("fs backend not compiled")).map_err(|_| axum::http::StatusCode::BAD_REQUEST)? }
+            // Served by this engine under /fs/<tenant>; the URL caps the bytes like the grant does.
+            engine_registry::fs_registry::check_object(&req.bucket, &req.key)
+                .map(|_| presign_fs::registry(tenant).presign(&req.verb, &req.bucket, &req.key, req.ttl_secs, req.byte_range_max, Some(&grant_id)))
         },
         "s3" => {
             #[cfg(feature="s3")]
@@ -431,46 +435,43 @@ match &pd {
         "bucket": req.bucket,
         "key": req.key,
         "verb": req.verb,
-        "ttl_secs": req.ttl_secs
+        "ttl_secs": req.ttl_secs,
+        "policy": card
     });
 
 
-// Build resource descriptor
-let res = serde_json::json!({
-    "store": if req.backend=="s3" { "S3" } else { "FS" },
-    "bucket": req.bucket,
-    "prefix": "",
-    "object": req.key,
-    "verbs": [req.verb],
-    "constraints": {
-        "ip_hash": req.ip_hash,
-        "byte_range_max": req.byte_range_max
-    }
-});
-
-// Minimal signed grant (dev keys)
-let audit_who = req.who.as_deref().unwrap_or("anonymous").to_string();
-let grant = AccessGrant {
-    kind: "access.grant.v1".into(),
-    grant_id: ulid::Ulid::new().to_string(),
-    sub: audit_who.clone(),
-    resource: res,
+// Grant for the proxy, bound to the caller's tenant and to the policy receipt, sealed by the engine key
+let constraints = (req.ip_hash.is_some() || req.byte_range_max.is_some())
+    .then(|| GrantConstraints{ ip_hash: req.ip_hash.clone(), byte_range_max: req.byte_range_max });
+signer::init_signer();
+let sk = signer::signing_key();
+let mut grant = AccessGrant {
+    kind: GRANT_KIND.into(),
+    grant_id,
+    sub: who.to_string(),
+    tenants: vec![tenant.to_string()],
+    resource: GrantResource{
+        store: if req.backend=="s3" { "S3" } else { "FS" }.into(),
+        bucket: req.bucket.clone(), prefix: String::new(), object: Some(req.key.clone()),
+        verbs: vec![req.verb.clone()], constraints,
+    },
     exp: (chrono::Utc::now() + chrono::Duration::seconds(req.ttl_secs as i64)).to_rfc3339(),
     iat: chrono::Utc::now().to_rfc3339(),
-    nonce: format!("n-{}", ulid::Ulid::new()),
-    seal: GrantSeal{ alg:"ed25519-blake3".into(), kid:"dev-2026-key-1".into(), sig:"".into() }
+    nonce: format!("n-{}", Ulid::new()),
+    policy: Some(link),
+    seal: GrantSeal{ alg: engine_auth::signing::SEAL_ALG.into(), kid: engine_auth::signing::key_id(&sk.verifying_key()), sig: String::new() },
 };
-let sk_b64 = std::fs::read_to_string("secrets/dev/ed25519.private.b64").unwrap_or_default();
-let grant = sign_grant(grant, &sk_b64, "dev-2026-key-1").map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+engine_auth::signing::sign_grant(&sk, &mut grant).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
 let grant_json = serde_json::to_value(&grant).unwrap();
-    let paseto_token = paseto_sign(&grant, &sk_b64).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    let paseto_token = engine_auth::paseto::sign_grant(&sk, &grant).map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
 
 // Emit audit span for grant issuance
+    let _ = emit_audit_report(audit_dir, who, &meta, &serde_json::json!({"presigned_url": url, "grant": grant_json, "paseto": paseto_token}), &serde_json::json!({"intent":"presign"})).await;
 
-    let who = req.who.as_deref().unwrap_or("anonymous");
-    let _ = emit_audit_report("./tenants/_public", who, &meta, &serde_json::json!({"presigned_url": url, "grant": grant_json, "paseto": paseto_token}), &serde_json::json!({"intent":"presign"})).await;
-
-    Ok(Json(PresignResp{ url, meta }))
+    let mut meta = meta;
+    meta["grant"] = grant_json;
+    meta["paseto"] = paseto_token.into();
+    Ok(PresignResp{ url, meta })
 }
 
 use axum::{extract::Query, http::HeaderMap, response::IntoResponse};
@@ -478,43 +479,6 @@ use std::collections::HashMap;
 
 
 
-#[derive(Serialize, Deserialize)]
-struct RevocationManifest {
-    kind: String,                 // "revocation.manifest.v1"
-    updated_at: String,
-    grants: Vec<String>,          // grant_ids
-    sig: String                   // ed25519 over blake3(json without sig)
-}
-
-fn check_revocation_signed(grant_id: &str) -> bool {
-    let p = std::path::Path::new("revoked_grants").join("manifest.json");
-    if !p.exists() { return false; }
-    let data = std::fs::read(&p).ok()?;
-    let mf: RevocationManifest = serde_json::from_slice(&data).ok()?;
-    if mf.kind != "revocation.manifest.v1" { return false; }
-
-    // Verify signature
-    let mut v: serde_json::Value = serde_json::from_slice(&data).ok()?;
-    if let Some(obj) = v.as_object_mut() {
-        obj.remove("sig");
-    }
-    let msg = serde_json::to_vec(&v).ok()?;
-    let digest = blake3::hash(&msg);
-    let pk_b64 = std::fs::read_to_string("secrets/dev/ed25519.public.b64").ok()?;
-    let pk_bytes = base64::engine::general_purpose::STANDARD.decode(pk_b64.trim()).ok()?;
-    let vk = ed25519_dalek::VerifyingKey::from_bytes(&pk_bytes.try_into().ok()?).ok()?;
-    let sig_bytes = base64::engine::general_purpose::STANDARD.decode(mf.sig).ok()?;
-    let sig = ed25519_dalek::Signature::from_bytes(&sig_bytes.try_into().ok()?);
-    if vk.verify(digest.as_bytes(), &sig).is_err() { return false; }
-
-    mf.grants.iter().any(|g| g == grant_id)
-}
-
-fn check_revocation(grant_id: &str) -> bool {
-    let path = std::path::Path::new("revoked_grants").join(format!("{}.json", grant_id));
-    path.exists()
-}
-
 #[derive(Serialize)]
 struct ProxyPoi {
     reason: String,
@@ -522,44 +486,104 @@ struct ProxyPoi {
     hints: Vec<String>,
 }
 
-fn hash_ip(ip: &str) -> String {
-    format!("iphash:{}", blake3::hash(ip.as_bytes()).to_hex())
+/// Proxies whose `X-Forwarded-For` is believed: `ENGINE_TRUSTED_PROXIES` (comma-separated addresses).
+static TRUSTED_PROXIES: Lazy<Vec<std::net::IpAddr>> = Lazy::new(|| {
+    std::env::var("ENGINE_TRUSTED_PROXIES").unwrap_or_default().split(',').map(str::trim).filter(|p| !p.is_empty())
+        .map(|p| p.parse().expect("ENGINE_TRUSTED_PROXIES"))
+        .collect()
+});
+
+/// Client address the `ip_hash` constraint is checked against: the peer of the connection, unless it is a
+/// trusted proxy; then the last `X-Forwarded-For` hop that is not one. Nothing else the client sends counts.
+fn client_ip(peer: Option<std::net::SocketAddr>, headers: &HeaderMap) -> Option<std::net::IpAddr> {
+    let peer = peer?.ip();
+    if !TRUSTED_PROXIES.contains(&peer) { return Some(peer); }
+    let xff = headers.get_all("X-Forwarded-For").iter().filter_map(|v| v.to_str().ok()).flat_map(|v| v.split(',')).collect::<Vec<_>>();
+    // Walking back from the proxy: the first hop it did not add itself is the client.
+    xff.iter().rev().map(|h| h.trim().parse::<std::net::IpAddr>()).find(|ip| !matches!(ip, Ok(ip) if TRUSTED_PROXIES.contains(ip))).and_then(Result::ok)
+}
+
+fn denied_status(e: &Denied) -> axum::http::StatusCode {
+    match e {
+        Denied::Scope(_) => axum::http::StatusCode::FORBIDDEN,
+        Denied::Range(_) => axum::http::StatusCode::RANGE_NOT_SATISFIABLE,
+        _ => axum::http::StatusCode::UNAUTHORIZED,
+    }
 }
 
-async fn s3_proxy_handler(headers: HeaderMap, Query(q): Query<HashMap<String,String>>) -> Result<impl IntoResponse, axum::http::StatusCode> {
+/// Needs the router served with `into_make_service_with_connect_info::<SocketAddr>()` for `ip_hash` grants to pass.
+async fn s3_proxy_handler(peer: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>, headers: HeaderMap, Query(q): Query<HashMap<String,String>>) -> Result<axum::response::Response, axum::http::StatusCode> {
         if std::env::var("PROXY_DISABLE").ok().as_deref() == Some("1") { return Err(axum::http::StatusCode::SERVICE_UNAVAILABLE); }
-    // Expect header X-LogLine-Grant: base64(JSON)
-    let grant_b64 = headers.get("X-LogLine-Grant").ok_or(axum::http::StatusCode::UNAUTHORIZED)?;
-    let grant_bytes = base64::engine::general_purpose::STANDARD.decode(grant_b64.as_bytes()).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
-    let grant: AccessGrant = serde_json::from_slice(&grant_bytes).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
-
-    // Verify signature
-    let pk_b64 = std::fs::read_to_string("secrets/dev/ed25519.public.b64").unwrap_or_default();
-    verify_grant(&grant, &pk_b64)?;
-
-    // Basic expiry check
-    let now = chrono::Utc::now();
-    let exp = chrono::DateTime::parse_from_rfc3339(&grant.exp).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?.with_timezone(&chrono::Utc);
-    if now > exp { return Err(axum::http::StatusCode::UNAUTHORIZED); }
+    // Expect X-LogLine-Paseto: <v4.public token> or X-LogLine-Grant: base64(JSON)
+    let token = headers.get("X-LogLine-Paseto").and_then(|v| v.to_str().ok());
+    let grant: AccessGrant = match token {
+        // Unverified: only names the grant in audit records and the revocation check until `verify_token` opens it.
+        Some(t) => engine_auth::paseto::peek_grant(t).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?,
+        None => {
+            let grant_b64 = headers.get("X-LogLine-Grant").ok_or(axum::http::StatusCode::UNAUTHORIZED)?;
+            let grant_bytes = base64::engine::general_purpose::STANDARD.decode(grant_b64.as_bytes()).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?;
+            serde_json::from_slice(&grant_bytes).map_err(|_| axum::http::StatusCode::UNAUTHORIZED)?
+        }
+    };
 
     // Extract bucket/key from query (?bucket=...&key=...)
     let bucket = q.get("bucket").ok_or(axum::http::StatusCode::BAD_REQUEST)?;
     let key    = q.get("key").ok_or(axum::http::StatusCode::BAD_REQUEST)?;
 
-    // Policy check: object matches grant.resource.object
-    let obj = grant.resource.get("object").and_then(|v| v.as_str()).ok_or(axum::http::StatusCode::UNAUTHORIZED)?;
-    if obj != key { return Err(axum::http::StatusCode::UNAUTHORIZED); }
+    // The bearer token (if any) names the caller's tenant, checked against `grant.tenants`, and picks the audit tenant.
+    let directory = tenant::Directory::global();
+    let caller = directory.authenticate(&headers).ok();
+    let audit_dir = directory.audit_dir(caller.as_ref().map_or(&tenant::TenantId::public(), |c| &c.tenant)).display().to_string();
+    let range = headers.get(axum::http::header::RANGE).and_then(|v| v.to_str().ok());
+    let input = serde_json::json!({"bucket":bucket,"key":key,"grant_id":grant.grant_id,"range":range});
+
+    // One range per response: anything else is refused before the grant is checked, so it keeps its nonce.
+    if let Some(r) = range.filter(|r| !matches!(engine_auth::verifier::parse_range(r).as_deref(), Some([_]))) {
+        let error = format!("unsupported Range {r}: one range per request");
+        let _ = emit_audit_report(&audit_dir, "proxy", &input, &serde_json::json!({"decision":"DENY","error": error}), &serde_json::json!({"intent":"proxy_get"})).await;
+        return Ok((axum::http::StatusCode::RANGE_NOT_SATISFIABLE, Json(serde_json::json!({"error": error}))).into_response());
+    }
+
+    // Revoked grants stop working on their next use (checked first: a refused request keeps its nonce).
+    grl::sync().await;
+    let tenant = caller.as_ref().map(|c| c.tenant.to_string());
+    let check = if grl::revoked(&grant.grant_id) { Err((axum::http::StatusCode::UNAUTHORIZED, "grant revoked".to_string())) } else {
+        let ip = client_ip(peer.map(|p| p.0), &headers).map(|ip| ip.to_string());
+        let access = Access{ verb: "GET", bucket, key, tenant: tenant.as_deref(), client_ip: ip.as_deref(), range };
+        match token { Some(t) => GRANTS.verify_token(t, &access).map(|g| g.seal.alg), None => GRANTS.verify(&grant, &access).map(|_| grant.seal.alg.clone()) }
+            .map_err(|e| (denied_status(&e), e.to_string()))
+    };
+    let alg = match check {
+        Ok(alg) => alg,
+        Err((status, error)) => {
+            let _ = emit_audit_report(&audit_dir, "proxy", &input, &serde_json::json!({"decision":"DENY","error": error}), &serde_json::json!({"intent":"proxy_get"})).await;
+            return Ok((status, Json(serde_json::json!({"error": error}))).into_response());
+        }
+    };
 
     // Backend: only s3 supported here
     #[cfg(feature="s3")]
     {
         let reg = engine_registry::s3_registry::S3Registry::new_from_env().await.map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
         let data = reg.get_bytes(bucket, key).await.map_err(|_| axum::http::StatusCode::BAD_GATEWAY)?;
+        let total = data.len() as u64;
+        // One range per response; a multi-range request is not served.
+        let span = match range.map(engine_auth::verifier::parse_range) {
+            None => None,
+            Some(Some(specs)) if specs.len() == 1 => Some(specs[0].resolve(total).ok_or(axum::http::StatusCode::RANGE_NOT_SATISFIABLE)?),
+            Some(_) => return Err(axum::http::StatusCode::RANGE_NOT_SATISFIABLE),
+        };
         let meta = serde_json::json!({
-            "proxy":"s3", "bucket":bucket, "key":key, "bytes": data.len()
+            "proxy":"s3", "bucket":bucket, "key":key, "bytes": data.len(), "grant_alg": alg,
+            "range_start": span.map(|s| s.0), "range_end": span.map(|s| s.1)
+        });
+        let _ = emit_audit_report(&audit_dir, "proxy", &input, &meta, &serde_json::json!({"intent":"proxy_get"})).await;
+        return Ok(match span {
+            Some((a, b)) => (axum::http::StatusCode::PARTIAL_CONTENT,
+                [(axum::http::header::CONTENT_TYPE, "application/octet-stream".to_string()), (axum::http::header::CONTENT_RANGE, format!("bytes {a}-{b}/{total}"))],
+                data[a as usize..=b as usize].to_vec()).into_response(),
+            None => ([(axum::http::header::CONTENT_TYPE, "application/octet-stream")], data).into_response(),
         });
-        let _ = emit_audit_report("./tenants/_public", "proxy", &serde_json::json!({"bucket":bucket,"key":key}), &meta, &serde_json::json!({"intent":"proxy_get"})).await;
-        return Ok(([(axum::http::header::CONTENT_TYPE, "application/octet-stream")], data));
     }
     #[cfg(not(feature="s3"))]
     {
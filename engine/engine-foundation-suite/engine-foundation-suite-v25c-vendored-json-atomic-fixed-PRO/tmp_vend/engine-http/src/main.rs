@@ -1,26 +1,31 @@
 
 #[tokio::main]
-async fn main() {
+async fn main() -> anyhow::Result<()> {
     use engine_http::server::build_router_with_flavors;
     use engine_http::presign::StubPresigner;
+    tracing_subscriber::fmt::init();
+    // `ENGINE_HTTP_ADDR` lets several engines share a host (e.g. two SIRP peers on localhost).
+    // Served with the peer address: grants bound to a client address are checked against it.
+    let addr = std::env::var("ENGINE_HTTP_ADDR").unwrap_or_else(|_| "0.0.0.0:8080".into());
 
     #[cfg(feature = "s3")]
     {
         use engine_http::presign_s3::S3Presigner;
         match S3Presigner::from_env().await {
             Ok(p) => {
-                let app = build_router_with_flavors("./out", "./registry", 2, p).await;
-                let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
-                println!("🚀 engine-http (S3 presigner) on :8080");
-                axum::serve(listener, app).await.unwrap();
-                return;
+                let app = build_router_with_flavors("./out", "./registry", 2, p).await?;
+                let listener = tokio::net::TcpListener::bind(&addr).await?;
+                println!("🚀 engine-http (S3 presigner) on {addr}");
+                axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await?;
+                return Ok(());
             }
             Err(e) => eprintln!("S3 presigner init error: {e}. Falling back to stub..."),
         }
     }
 
-    let app = build_router_with_flavors("./out", "./registry", 2, StubPresigner).await;
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
-    println!("🚀 engine-http (stub presigner) on :8080");
-    axum::serve(listener, app).await.unwrap();
+    let app = build_router_with_flavors("./out", "./registry", 2, StubPresigner).await?;
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    println!("🚀 engine-http (stub presigner) on {addr}");
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await?;
+    Ok(())
 }
@@ -1,6 +1,9 @@
 
 use serde::{Serialize, Deserialize};
 
+/// Longest TTL any presigned URL gets (S3's own ceiling, 7 days); longer requests are clamped to it.
+pub const PRESIGN_TTL_MAX_SECS: u64 = 7 * 24 * 3600;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PresignIntent {
     pub actor: String,
@@ -0,0 +1,106 @@
+//! The fs backend's presigned URLs, issued and served by the engine itself.
+//!
+//! Objects live under `FS_REGISTRY_ROOT/<tenant>` (default `./.dev-registry`). URLs point at
+//! `FS_PRESIGN_BASE_URL/<tenant>` (default `http://127.0.0.1:8080/fs`) and are signed with a per-tenant key
+//! derived from `FS_PRESIGN_KEY`, or else from the engine key, so every engine sharing the seed accepts them
+//! and no tenant's URL opens another's objects.
+//! `GET|PUT /fs/:tenant/:bucket/*key` ([`router`]) checks the URL and streams the object (`Range` → `206`);
+//! keys must be relative paths that stay in their bucket (`400` otherwise). Every URL is bound to the grant
+//! issued with it and stops working (`403`) once that grant is on the revocation list, as the proxy's do.
+use axum::{Router, routing::get, body::Body, extract::{Path, Query}, http::{HeaderMap, StatusCode, header}, response::{IntoResponse, Response}};
+use engine_auth::verifier::parse_range;
+use engine_registry::{RegistryProvider, fs_registry::{FsRegistry, InvalidObject}, url_signer::{UrlClaims, UrlError, UrlQuery, UrlSigner}};
+use once_cell::sync::Lazy;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::{grl, signer};
+use crate::tenant::TenantId;
+
+static FS: Lazy<FsRegistry> = Lazy::new(|| {
+    let key = match std::env::var("FS_PRESIGN_KEY") {
+        Ok(k) => k.into_bytes(),
+        Err(_) => { signer::init_signer(); blake3::derive_key("engine-http fs presign v1", &signer::signing_key().to_bytes()).to_vec() }
+    };
+    let base = std::env::var("FS_PRESIGN_BASE_URL").unwrap_or_else(|_| "http://127.0.0.1:8080/fs".into());
+    FsRegistry::new(std::env::var("FS_REGISTRY_ROOT").unwrap_or_else(|_| "./.dev-registry".into())).with_signer(UrlSigner::new(key, base))
+});
+
+/// `tenant`'s fs registry, the one its URLs are issued for.
+pub fn registry(tenant:&TenantId) -> FsRegistry { FS.scoped(tenant.as_str()).expect("tenant ids are path segments") }
+
+fn now() -> u64 { chrono::Utc::now().timestamp().max(0) as u64 }
+
+fn url_err(e:UrlError) -> (StatusCode, String) {
+    let status = match e { UrlError::WrongVerb{ .. } => StatusCode::METHOD_NOT_ALLOWED, _ => StatusCode::FORBIDDEN };
+    (status, e.to_string())
+}
+
+/// Invalid objects (absolute keys, `..`, symlinks out of the bucket) → 400; anything else is a missing object.
+fn object_err<'a>(bucket:&'a str, key:&'a str) -> impl Fn(anyhow::Error) -> (StatusCode, String) + 'a {
+    move |e| match e.downcast_ref::<InvalidObject>() {
+        Some(_) => (StatusCode::BAD_REQUEST, e.to_string()),
+        None => (StatusCode::NOT_FOUND, format!("no object {bucket}/{key}")),
+    }
+}
+
+/// The tenant's registry and what its URL for `verb` grants, once the object name is known to be safe
+/// and the URL's grant is not revoked.
+async fn checked(tenant:&str, verb:&str, bucket:&str, key:&str, q:&UrlQuery) -> Result<(FsRegistry, UrlClaims), (StatusCode, String)> {
+    let tenant = TenantId::parse(tenant).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    engine_registry::fs_registry::check_object(bucket, key).map_err(object_err(bucket, key))?;
+    let reg = registry(&tenant);
+    let c = reg.signer().expect("fs registry has a signer").check(verb, bucket, key, q, now()).map_err(url_err)?;
+    let grant = c.grant.as_deref().ok_or((StatusCode::FORBIDDEN, "presigned URL carries no grant".to_string()))?;
+    grl::init().map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+    grl::sync().await;
+    if grl::revoked(grant) { return Err((StatusCode::FORBIDDEN, "grant revoked".into())); }
+    Ok((reg, c))
+}
+
+pub fn router<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new().route("/fs/:tenant/:bucket/*key", get(fs_get).put(fs_put))
+}
+
+async fn fs_get(Path((tenant, bucket, key)): Path<(String, String, String)>, Query(q): Query<UrlQuery>, headers: HeaderMap) -> Result<Response, (StatusCode, String)> {
+    let (reg, c) = checked(&tenant, "GET", &bucket, &key, &q).await?;
+    let path = reg.existing_path(&bucket, &key).await.map_err(object_err(&bucket, &key))?;
+    let mut file = tokio::fs::File::open(&path).await.map_err(|_| (StatusCode::NOT_FOUND, format!("no object {bucket}/{key}")))?;
+    let total = file.metadata().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?.len();
+    let unsatisfiable = |why:String| (StatusCode::RANGE_NOT_SATISFIABLE, why);
+    // One range per response, as the grant proxy does.
+    let span = match headers.get(header::RANGE).and_then(|v| v.to_str().ok()) {
+        None => None,
+        Some(r) => match parse_range(r).as_deref() {
+            Some([one]) => Some(one.resolve(total).ok_or_else(|| unsatisfiable(format!("{r} is outside {total} bytes")))?),
+            _ => return Err(unsatisfiable(format!("unsupported Range {r}"))),
+        },
+    };
+    let (start, end) = span.unwrap_or((0, total.saturating_sub(1)));
+    let len = if total == 0 { 0 } else { end - start + 1 };
+    if let Some(max) = c.max.filter(|m| len > *m) {
+        return Err(unsatisfiable(format!("{len} bytes exceed the URL's limit of {max}; ask for a Range")));
+    }
+    file.seek(std::io::SeekFrom::Start(start)).await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let body = Body::from_stream(tokio_util::io::ReaderStream::new(file.take(len)));
+    let mut resp = (
+        [(header::CONTENT_TYPE, "application/octet-stream".to_string()), (header::CONTENT_LENGTH, len.to_string()), (header::ACCEPT_RANGES, "bytes".to_string())],
+        body,
+    ).into_response();
+    if span.is_some() {
+        *resp.status_mut() = StatusCode::PARTIAL_CONTENT;
+        resp.headers_mut().insert(header::CONTENT_RANGE, format!("bytes {start}-{end}/{total}").parse().unwrap());
+    }
+    Ok(resp)
+}
+
+async fn fs_put(Path((tenant, bucket, key)): Path<(String, String, String)>, Query(q): Query<UrlQuery>, body: Body) -> Result<StatusCode, (StatusCode, String)> {
+    let (reg, c) = checked(&tenant, "PUT", &bucket, &key, &q).await?;
+    let limit = c.max.map_or(usize::MAX, |m| m as usize);
+    let bytes = axum::body::to_bytes(body, limit).await
+        .map_err(|_| (StatusCode::PAYLOAD_TOO_LARGE, format!("body exceeds the URL's limit of {} bytes", c.max.unwrap_or_default())))?;
+    reg.put_bytes(&bucket, &key, &bytes).await.map_err(|e| match e.downcast_ref::<InvalidObject>() {
+        Some(_) => (StatusCode::BAD_REQUEST, e.to_string()),
+        None => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")),
+    })?;
+    Ok(StatusCode::CREATED)
+}
@@ -0,0 +1,78 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Refill rate and burst size of a [`TokenBucket`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Quota {
+    #[serde(default = "default_per_minute")]
+    pub per_minute: u32,
+    /// Bucket size; defaults to one minute's worth.
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+fn default_per_minute() -> u32 { 600 }
+impl Default for Quota { fn default() -> Self { Self{ per_minute: default_per_minute(), burst: None } } }
+
+#[derive(Debug)]
+pub struct TokenBucket { capacity: f64, per_sec: f64, tokens: f64, last: Instant }
+
+impl TokenBucket {
+    pub fn new(q:Quota) -> Self {
+        let capacity = f64::from(q.burst.unwrap_or(q.per_minute).max(1));
+        Self{ capacity, per_sec: f64::from(q.per_minute) / 60.0, tokens: capacity, last: Instant::now() }
+    }
+    fn refill(&mut self, now:Instant) {
+        self.tokens = (self.tokens + now.duration_since(self.last).as_secs_f64() * self.per_sec).min(self.capacity);
+        self.last = now;
+    }
+    /// Whole tokens left right now.
+    pub fn remaining(&mut self) -> u64 { self.refill(Instant::now()); self.tokens.floor() as u64 }
+    /// Take one token if there is one.
+    pub fn try_take(&mut self) -> bool {
+        self.refill(Instant::now());
+        if self.tokens < 1.0 { return false; }
+        self.tokens -= 1.0;
+        true
+    }
+    /// Return a token taken with [`TokenBucket::try_take`] that was not spent after all.
+    pub fn give_back(&mut self) { self.tokens = (self.tokens + 1.0).min(self.capacity); }
+}
+
+/// One bucket per key (actor id), created full on first use.
+#[derive(Debug, Default)]
+pub struct QuotaStore { buckets: Mutex<HashMap<String, TokenBucket>> }
+
+impl QuotaStore {
+    pub fn remaining(&self, key:&str, q:Quota) -> u64 {
+        self.buckets.lock().unwrap().entry(key.into()).or_insert_with(|| TokenBucket::new(q)).remaining()
+    }
+    /// Set one token aside for `key`. It is only spent by [`Hold::commit`]; dropping the hold returns it,
+    /// so concurrent requests never see the same last token.
+    pub fn hold(&self, key:&str, q:Quota) -> Hold<'_> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let b = buckets.entry(key.into()).or_insert_with(|| TokenBucket::new(q));
+        let remaining = b.remaining();
+        let held = b.try_take();
+        Hold{ store: self, key: key.into(), remaining, held }
+    }
+}
+
+/// A token reserved by [`QuotaStore::hold`].
+#[derive(Debug)]
+pub struct Hold<'a> { store: &'a QuotaStore, key: String, remaining: u64, held: bool }
+
+impl Hold<'_> {
+    /// Tokens the key had when the hold was taken (0: nothing was held).
+    pub fn remaining(&self) -> u64 { self.remaining }
+    /// Spend the held token.
+    pub fn commit(mut self) { self.held = false; }
+}
+
+impl Drop for Hold<'_> {
+    fn drop(&mut self) {
+        if !self.held { return; }
+        if let Some(b) = self.store.buckets.lock().unwrap().get_mut(&self.key) { b.give_back(); }
+    }
+}
@@ -16,6 +16,7 @@ use once_cell::sync::Lazy;
 use std::sync::Mutex;
 use std::collections::HashMap;
 use axum::{routing::{get, post}, Router, Json};
+use anyhow::Context as _;
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 use std::collections::HashMap;
@@ -207,16 +208,46 @@ use serde_json::json;
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 use std::collections::HashMap;
-use engine_loader::{UnitStore, load_units_from_dir, watch_units};
+use engine_loader::watch_units;
+
+pub type TenantEngine = Engine<UlidGen, crate::DefaultExprWrap, KOfN, DefaultCanon, DefaultCid, NoopSigner, FsSink>;
 
-#[derive(Clone)]
 pub struct AppState<P: Presigner> {
-    pub units: UnitStore,
-    pub engine: Engine<UlidGen, crate::DefaultExprWrap, KOfN, DefaultCanon, DefaultCid, NoopSigner, FsSink>,
+    /// Units, engine, registry and keys per tenant; handlers get theirs through [`TenantCtx`].
+    pub tenants: std::sync::Arc<crate::tenant::Tenants<TenantEngine>>,
     pub k: usize,
-    pub reg: FileRegistry,
+    /// Lint profile `/registry/put` applies to wasm units.
+    pub lint: engine_exec_wasm::conformance::Profile,
+    /// When set (`REGISTRY_TRUST_POLICY`), `/registry/put` only accepts entries sealed by an allowed publisher.
+    pub trust: Option<std::sync::Arc<engine_registry::provenance::TrustPolicy>>,
+    /// Peer DIDs for SIRP (`SIRP_DIDS`, `sirp.dids.v1`); tenants' and the engine's own DIDs need no entry.
+    pub dids: std::sync::Arc<tdln_sirp::DidKeys>,
+    /// Tenant whose units run the intents peers post to `/sirp/inbox` (`SIRP_INBOX_TENANT`, default `_public`).
+    pub inbox: crate::tenant::TenantId,
+    /// Posts outbox capsules to peer inboxes.
+    pub http: reqwest::Client,
     pub presigner: std::sync::Arc<P>,
 }
+// By hand: a derive would require `P: Clone`, and the presigner is shared through its `Arc`.
+impl<P: Presigner> Clone for AppState<P> {
+    fn clone(&self) -> Self {
+        Self{
+            tenants: self.tenants.clone(), k: self.k, lint: self.lint.clone(), trust: self.trust.clone(),
+            dids: self.dids.clone(), inbox: self.inbox.clone(), http: self.http.clone(), presigner: self.presigner.clone(),
+        }
+    }
+}
+/// The caller's tenant and identity, resolved from its bearer token. Unit, registry and key access goes through it.
+pub struct TenantCtx(pub std::sync::Arc<crate::tenant::Tenant<TenantEngine>>, pub crate::tenant::Identity);
+#[axum::async_trait]
+impl<P: Presigner> axum::extract::FromRequestParts<AppState<P>> for TenantCtx {
+    type Rejection = (StatusCode, Json<serde_json::Value>);
+    async fn from_request_parts(parts:&mut axum::http::request::Parts, state:&AppState<P>) -> Result<Self, Self::Rejection> {
+        state.tenants.identify(&parts.headers).map(|(t, who)| TenantCtx(t, who))
+            .map_err(|e| (StatusCode::UNAUTHORIZED, Json(json!({"error": e.to_string()}))))
+    }
+}
+
 pub struct DefaultExprWrap;
 impl Default for DefaultExprWrap { fn default()->Self { Self } }
 impl ExprEval for DefaultExprWrap {
@@ -252,7 +283,10 @@ pub struct RunBody {
     #[serde(default)]
     pub options: Option<RunOptions>,
     pub input: serde_json::Value,
+    /// `b3:<hex>` CID, `name@range` (`quota@^1.2`), `name@channel` (`quota@stable`) or a bare chip id.
     pub unit_ref: Option<String>,
+    #[serde(default)]
+    pub realm: Option<String>,
 
     #[serde(default)]
     pub k: Option<usize>,
@@ -261,52 +295,97 @@ pub struct RunBody {
 pub struct RunResp {
     pub receipt: engine_core::model::ExecutionReceipt,
     pub card: serde_json::Value,
+    /// `GET` path of the run's `bundle.zip` (`options.offline_bundle`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle_url: Option<String>,
+    /// Why no bundle could be sealed (e.g. a unit built in code has no spec); the run itself stands.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle_error: Option<String>,
+}
+impl RunResp {
+    fn new(receipt:engine_core::model::ExecutionReceipt, card:serde_json::Value) -> Self { Self{ receipt, card, bundle_url: None, bundle_error: None } }
 }
 
-pub async fn build_router<P: Presigner>(outdir:&str, regdir:&str, k:usize, presigner:P) -> Router {
-    let policy_a = PolicyBit::new("has_role","actor has role")
-        .requires(&["actor","role"])
-        .condition(Expression::eq(Expression::context(&["actor","role"]), Expression::literal("admin"))).build();
-    let policy_b = PolicyBit::new("has_quota","quota > 0")
-        .requires(&["actor","quota"])
-        .condition(Expression::gt(Expression::context(&["actor","quota"]), Expression::literal(0))).build();
-    let policy_c = PolicyBit::new("resource_ok","resource not restricted")
-        .requires(&["resource","restricted"])
-        .condition(Expression::not(Expression::context(&["resource","restricted"]))).build();
+/// Fails when a configured file (`REGISTRY_TRUST_POLICY`, `SIRP_DIDS`, tenant keys and reviewers) cannot be loaded.
+pub async fn build_router<P: Presigner>(outdir:&str, regdir:&str, k:usize, presigner:P) -> anyhow::Result<Router> {
     let units_dir = std::env::var("UNITS_DIR").ok();
-    let store = UnitStore::new(units_dir.clone().unwrap_or_else(|| "./units".into()));
-    if let Some(dir) = &units_dir { let _ = tokio::spawn(watch_units(store.clone())); }
-
-    let default_unit = if let Some(dir) = &units_dir {
-        if let Ok(list) = load_units_from_dir(dir).await { list.into_iter().next() }
-        else { None }
-    } else { None };
-
-    let selected = default_unit;
-let engine = Engine::default()
-        .chips(store.list())
+    // Single-tenant deployments (and anonymous callers) keep the `outdir`/`regdir`/`UNITS_DIR` layout;
+    // named tenants get `{ENGINE_TENANTS_ROOT}/<id>/{units,registry,receipts,keys}`.
+    let dir = crate::tenant::Directory::global().clone();
+    let public = crate::tenant::TenantPaths{
+        units: units_dir.clone().unwrap_or_else(|| "./units".into()).into(),
+        registry: regdir.into(), receipts: outdir.into(),
+        audit: dir.audit_dir(&crate::tenant::TenantId::public()),
+        key: std::env::var("ENGINE_SIGNING_KEY_ED25519_FILE").unwrap_or_else(|_| "var/keys/ed25519.seed".into()).into(),
+        context: std::env::var("CONTEXT_DIR").unwrap_or_else(|_| "./context".into()).into(),
+        reviewers: std::env::var("REVIEWERS_POLICY").unwrap_or_else(|_| "./reviewers.json".into()).into(),
+    };
+    // Context providers units may declare: `lookup` (tenant tables) and `cas` (tenant registry objects).
+    let tenants = crate::tenant::Tenants::build(dir, Some(public), |_, paths| Engine::default()
+        .context_provider("lookup", std::sync::Arc::new(engine_core::context::LookupDir::new(&paths.context)))
+        .context_provider("cas", std::sync::Arc::new(paths.cas()))
         .agg(KOfN{ k })
         .expr(ExtensibleExpr{ reg: BasicRegistry::new() })
-        .sink(FsSink::new(outdir))
-        .build();
+        .sink(FsSink::new(&paths.receipts))
+        .build())?;
+    for t in tenants.all() {
+        // Hot-reloaded generations replace the tenant engine's chip table; running executions keep their snapshot.
+        let table = t.engine.chip_table();
+        t.units.on_activate(move |g| table.store(std::sync::Arc::new(engine_core::runtime::ChipTable::new(g.number, g.units.values().cloned()))));
+        if units_dir.is_some() || t.id.as_str() != crate::tenant::PUBLIC { let _ = tokio::spawn(watch_units(t.units.clone())); }
+    }
 
+    // The engine key signs SIRP receipts (`signer::did`).
+    signer::init_signer();
     let state = AppState {
-        engine, k, units: store.clone(),
-        reg: FileRegistry::new(regdir),
+        tenants: std::sync::Arc::new(tenants), k,
+        lint: engine_exec_wasm::conformance::Profile::for_exec(&engine_exec_wasm::ExecConfig::default()),
+        trust: std::env::var("REGISTRY_TRUST_POLICY").ok()
+            .map(|p| engine_registry::provenance::TrustPolicy::load(&p).with_context(|| format!("REGISTRY_TRUST_POLICY {p}")))
+            .transpose()?.map(std::sync::Arc::new),
+        dids: std::sync::Arc::new(std::env::var("SIRP_DIDS").ok()
+            .map(|p| tdln_sirp::DidKeys::load(&p).with_context(|| format!("SIRP_DIDS {p}")))
+            .transpose()?.unwrap_or_default()),
+        inbox: crate::tenant::TenantId::parse(&std::env::var("SIRP_INBOX_TENANT").unwrap_or_else(|_| crate::tenant::PUBLIC.into())).context("SIRP_INBOX_TENANT")?,
+        http: reqwest::Client::builder().timeout(SIRP_TIMEOUT).build()?,
         presigner: std::sync::Arc::new(presigner),
     };
+    let _ = tokio::spawn(sirp_worker(state.clone()));
 
-    Router::new()
+    Ok(Router::new()
             .route("/v1/apps/register", post(register_app))
-            .route("/r/:run", get(handle_run_cid))
+            .route("/r/:run", get(handle_run_cid::<P>))
         .route("/health", get(|| async { "ok" }))
         .route("/run", post(run::<P>))
+        .route("/run/resume", post(run_resume::<P>))
+        .route("/bundles/:run_cid", get(bundle_get::<P>))
+        .route("/sirp/verify", post(sirp_verify::<P>))
+        .route("/sirp/inbox", post(sirp_inbox::<P>))
+        .route("/sirp/send", post(sirp_send::<P>))
+        .route("/sirp/outbox", get(sirp_outbox::<P>))
+        .route("/sirp/:cid", get(sirp_get::<P>))
+        .route("/reviews", get(review_list::<P>))
+        .route("/reviews/:run_cid", get(review_get::<P>))
+        .route("/reviews/:run_cid/decision", post(review_decide::<P>))
         .route("/registry/put", post(registry_put::<P>))
+        .route("/registry/list", get(registry_list::<P>))
+        .route("/registry/resolve/:name/:req", get(registry_resolve::<P>))
+        .route("/registry/yank", post(registry_yank::<P>))
+        .route("/registry/channel", post(registry_channel::<P>))
+        .route("/registry/index", get(registry_index::<P>).post(registry_index_publish::<P>))
+        .route("/admin/units", get(admin_units::<P>))
+        .route("/admin/units/reload", post(admin_units_reload::<P>))
+        .route("/admin/units/rollback", post(admin_units_rollback::<P>))
+        .route("/metrics", get(|State(state): State<AppState<P>>| async move {
+            state.tenants.all().map(|t| t.units.metrics_labeled(&format!("tenant=\"{}\"", t.id))).collect::<String>()
+        }))
         .route("/acquire_presigned_url", post(acquire_presigned_url::<P>))
-        .with_state(state)
+        .route("/registry/presign", post(registry_presign::<P>))
+        .merge(crate::presign_fs::router())
+        .with_state(state))
 }
 
-async fn run<P: Presigner>(State(state): State<AppState<P>>, Json(body): Json<RunBody>) -> Json<RunResp> {
+async fn run<P: Presigner>(State(state): State<AppState<P>>, TenantCtx(t, who): TenantCtx, Json(body): Json<RunBody>) -> axum::response::Response {
     use once_cell::sync::Lazy;
 use std::sync::Mutex;
 use std::collections::HashMap;
@@ -322,16 +401,20 @@ use serde_json::json;
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 use std::collections::HashMap;
-use engine_core::model::{EngineMode, CanonSlot, Decision, MissingInfo, Proof};
+use engine_core::model::{EngineMode, CanonSlot, Decision, ProofOfIndecision, Proof};
     let _k = body.k.unwrap_or(state.k);
-    let input_json = body.input;
+    let opts = body.options.unwrap_or_default();
+    let realm = body.realm.unwrap_or_else(|| "trust".into());
+    let caller_input = body.input.clone();
+    // One of the actor's runs is held while the unit executes and only spent on ACK.
+    let hold = t.hold_run(&who);
+    let input_json = match crate::enrich::enrich(body.input, &t.server_facts(&who, &hold)) {
+        Ok(v) => v,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()}))).into_response(),
+    };
 
     // Enforce unit_ref: if missing, return ASK (Doubt) with PoI: missing unit_ref
-    if body.unit_ref.is_none() {
-        let poi = ProofOfIndecision::missing(vec!["unit_ref"]);
-        let card = make_card(Decision::ASK, None, None, None, Some(poi, run_cid.clone(), extra_refs.clone()), false, None, vec![]);
-        return let json = serde_json::to_value(&card).unwrap(); RUN_INDEX.lock().unwrap().insert(run_cid.clone(), json.clone()); Json(card).into_response();
-    
+    let Some(unit_ref) = body.unit_ref else {
         let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
         let receipt = engine_core::model::ExecutionReceipt{
             chip_id: "MISSING_UNIT_REF".into(),
@@ -341,63 +424,723 @@ use engine_core::model::{EngineMode, CanonSlot, Decision, MissingInfo, Proof};
             policy_decisions: Vec::new(),
             output: CanonSlot{ raw: serde_json::json!({}), canon: serde_json::json!({}), cid: "b3:missing".into() },
             decision: Decision::Doubt,
-            missing: Some(MissingInfo{ id:"unit_ref".into(), reason:"required".into(), missing_fields: vec!["unit_ref".into()], missing_evidence: vec![], resolution_hint: Some("include unit_ref (CID or registry id)".into()) }),
+            poi: Some(ProofOfIndecision::missing(&["unit_ref"]).hint("include unit_ref (CID or registry id)")),
             proof: Proof{ hash_chain: Vec::new(), signature: None },
             timestamp: now,
             duration_ns: 0,
+            unit_generation: None,
+            context: Vec::new(),
+            resolves: None,
         };
-        let card = serde_json::json!({
-            "kind":"receipt.card.v1",
-            "realm":"trust",
-            "decision":"ASK",
-            "poi":{"present":true,"missing":["unit_ref"]},
-            "proof": receipt.proof,
-            "ts": receipt.timestamp
-        });
-        return Json(RunResp{ receipt, card });
+        let card = receipt_card(&receipt, &realm, None);
+        return Json(RunResp::new(receipt, card)).into_response();
+    };
+
+    if hold.remaining() == 0 {
+        return over_quota(&t, &who).into_response();
+    }
+    let receipt = match execute_ref(&t, &unit_ref, input_json, None).await {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+    if receipt.decision == Decision::Allow { hold.commit(); }
+    let run_cid = compute_run_cid(&unit_ref, Some(&realm), &caller_input, &opts);
+    if let Err(e) = record_ask(&t, &who, &receipt, &run_cid, &unit_ref, &realm, caller_input) { return reg_err(e).into_response(); }
+    let mut card = receipt_card(&receipt, &realm, Some(&run_cid));
+    let sirp = match seal_sirp(&t, &receipt, &run_cid, &mut card) {
+        Ok(c) => c,
+        Err(e) => return reg_err(e).into_response(),
+    };
+    if !opts.no_hitl {
+        if let Err(e) = queue_review(&t, &who, &receipt, &run_cid, &unit_ref, &realm, &mut card) { return reg_err(e).into_response(); }
+    }
+    let mut resp = RunResp::new(receipt, card);
+    if opts.offline_bundle {
+        match write_bundle(&t, &resp.receipt, &resp.card, &sirp, &run_cid, opts.redact_input).await {
+            Ok(url) => resp.bundle_url = Some(url),
+            Err(e) => resp.bundle_error = Some(format!("{e:#}")),
+        }
+    }
+    Json(resp).into_response()
+},
+        "output": { "cid": receipt.output.cid },
+        "proof": receipt.proof,
+        "ts": receipt.timestamp
+    });
+    Json(RunResp::new(receipt, card))
+}
+
+#[derive(Deserialize)]
+struct RegPutBody {
+    name:String, version:String, cid:String,
+    /// Signed entries are published verbatim: the seal covers `id` and `meta` too.
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    meta: Option<serde_json::Value>,
+    #[serde(default)]
+    seal: Option<engine_auth::grant::GrantSeal>,
+    /// WASM units: the module itself, linted before the entry is written.
+    #[serde(default)]
+    wasm_b64: Option<String>,
+    /// JSON units: the `UnitSpec`; `cid` must be its canonical CID. Stored so `/run` can load it by CID.
+    #[serde(default)]
+    spec: Option<engine_loader::UnitSpec>,
+}
+#[derive(Serialize)]
+struct RegPutResp {
+    path:String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lint: Option<engine_exec_wasm::conformance::ConformanceReport>,
+}
+async fn registry_put<P: Presigner>(State(state): State<AppState<P>>, TenantCtx(t, _): TenantCtx, Json(b): Json<RegPutBody>) -> Result<Json<RegPutResp>, (StatusCode, Json<serde_json::Value>)> {
+    let bad = |code:StatusCode, v:serde_json::Value| (code, Json(v));
+    let lint = match &b.wasm_b64 {
+        Some(w) => {
+            let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, w).map_err(|_| bad(StatusCode::BAD_REQUEST, json!({"error":"wasm_b64 is not base64"})))?;
+            let report = engine_exec_wasm::conformance::check(&bytes, &state.lint)
+                .map_err(|e| bad(StatusCode::BAD_REQUEST, json!({"error": format!("malformed wasm: {e}")})))?;
+            if report.module_cid != b.cid {
+                return Err(bad(StatusCode::BAD_REQUEST, json!({"error":"cid does not match wasm bytes", "expected": report.module_cid})));
+            }
+            if !report.ok { return Err(bad(StatusCode::UNPROCESSABLE_ENTITY, json!({"error":"wasm lint failed", "lint": report}))); }
+            Some(report)
+        }
+        None => None,
+    };
+    let spec = match &b.spec {
+        Some(spec) => {
+            let got = engine_loader::spec_cid(spec).map_err(|e| bad(StatusCode::BAD_REQUEST, json!({"error": e.to_string()})))?;
+            if got != b.cid { return Err(bad(StatusCode::BAD_REQUEST, json!({"error":"cid does not match canonical spec", "expected": got}))); }
+            Some(engine_loader::spec_bytes(spec).map_err(|e| bad(StatusCode::BAD_REQUEST, json!({"error": e.to_string()})))?)
+        }
+        None => None,
+    };
+    let e = EngineRegistryEntry{
+        kind:"engine.registry.entry.v1".into(),
+        id: b.id.unwrap_or_else(|| ulid::Ulid::new().to_string()),
+        name: b.name, version: b.version, cid: b.cid,
+        meta: lint_meta(b.meta, lint.is_some(), b.seal.is_some()).map_err(|err| bad(StatusCode::BAD_REQUEST, json!({"error": err})))?,
+        seal: b.seal,
+    };
+    if let Some(policy) = &state.trust {
+        engine_registry::provenance::verify_entry(&e, policy).map_err(|err| bad(StatusCode::FORBIDDEN, json!({"error": format!("{err:#}")})))?;
     }
+    if let Some(bytes) = &spec {
+        t.cas.put(bytes).await.map_err(reg_err)?;
+    }
+    let p = t.reg.put(&e).map_err(reg_err)?;
+    if let Some(report) = &lint {
+        t.reg.put_attachment(&e.name, &e.version, "lint", &json!(report)).map_err(reg_err)?;
+    }
+    Ok(Json(RegPutResp{ path: p.display().to_string(), lint }))
+}
 
-    let unit_id = body.unit_ref.unwrap();
-    let receipt = state.engine.execute(&unit_id, input_json, None).expect("execute");
-    let card = serde_json::json!({
+/// 429 for a caller whose run quota (the actor's, or the tenant's shared one) is spent.
+fn over_quota(t:&crate::tenant::Tenant<TenantEngine>, who:&crate::tenant::Identity) -> (StatusCode, Json<serde_json::Value>) {
+    let whom = match &who.actor { Some(a) => format!("actor {a} of tenant {}", t.id), None => format!("tenant {}", t.id) };
+    (StatusCode::TOO_MANY_REQUESTS, Json(json!({"error": format!("{whom} is over its run quota")})))
+}
+
+/// Registry errors → HTTP: republishing a version is a conflict, unknown name/range a 404.
+fn reg_err(e:anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    use engine_registry::file_registry::RegistryError::*;
+    let code = match e.downcast_ref() {
+        Some(VersionExists{ .. }) => StatusCode::CONFLICT,
+        Some(NotFound(_)) => StatusCode::NOT_FOUND,
+        Some(InvalidVersion(_) | InvalidName(_)) => StatusCode::BAD_REQUEST,
+        Some(ChannelYanked{ .. }) => StatusCode::GONE,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (code, Json(json!({"error": e.to_string()})))
+}
+
+/// Chip ids run from the engine's current table (the receipt records its generation); CIDs and
+/// `name@range|channel` are fetched from the registry, checked against their canonical CID and cached.
+/// `resolves` is the ASK run this execution answers.
+async fn execute_ref(t:&crate::tenant::Tenant<TenantEngine>, unit_ref:&str, input:serde_json::Value, resolves:Option<&str>) -> Result<engine_core::model::ExecutionReceipt, (StatusCode, Json<serde_json::Value>)> {
+    match engine_registry::file_registry::UnitRef::parse(unit_ref) {
+        engine_registry::file_registry::UnitRef::Id(id) => {
+            if !t.engine.chip_table().load().chips.contains_key(&id) { return Err(unit_err(engine_loader::ResolveError::NotFound(id).into())); }
+            let r = match resolves { Some(ask) => t.engine.resume(&id, input, None, ask), None => t.engine.execute(&id, input, None) };
+            r.map_err(reg_err)
+        }
+        _ => {
+            let unit = t.units.resolve(unit_ref).await.map_err(unit_err)?;
+            let r = match resolves { Some(ask) => t.engine.resume_chip(&unit, input, None, ask), None => t.engine.execute_chip(&unit, input, None) };
+            r.map_err(reg_err)
+        }
+    }
+}
+
+/// ASK receipts leave a record `/run/resume` can answer, for `who`. Re-running the same manifest keeps the record
+/// it already has, answered or not.
+fn record_ask(t:&crate::tenant::Tenant<TenantEngine>, who:&crate::tenant::Identity, receipt:&engine_core::model::ExecutionReceipt, run_cid:&str, unit_ref:&str, realm:&str, input:serde_json::Value) -> anyhow::Result<()> {
+    let (engine_core::model::Decision::Doubt, Some(poi)) = (&receipt.decision, &receipt.poi) else { return Ok(()) };
+    t.asks.create(&crate::asks::AskRecord::new(run_cid, unit_ref, realm, input, poi.clone(), &receipt.input.cid, &who.label())).map(|_| ())
+}
+
+fn receipt_card(receipt:&engine_core::model::ExecutionReceipt, realm:&str, run_cid:Option<&str>) -> serde_json::Value {
+    use engine_core::model::Decision;
+    json!({
         "kind":"receipt.card.v1",
-        "realm":"trust",
+        "realm": realm,
+        "run_cid": run_cid,
         "unit_id": receipt.chip_id,
+        "unit_generation": receipt.unit_generation,
         "decision": match receipt.decision { Decision::Allow => "ACK", Decision::Deny => "NACK", _ => "ASK" },
+        "poi": receipt.poi,
+        "resolves": receipt.resolves,
         "input": { "cid": receipt.input.cid },
         "output": { "cid": receipt.output.cid },
         "proof": receipt.proof,
         "ts": receipt.timestamp
+    })
+}
+
+#[derive(Deserialize)]
+pub struct ResumeBody {
+    /// Run CID of the ASK being answered (`card.run_cid`).
+    pub run_cid: String,
+    /// Missing fields, merged into the original input.
+    pub input: serde_json::Value,
+}
+
+/// Answer an ASK: same unit, original input plus `input`; the receipt `resolves` the ASK's run CID.
+/// Only the caller who got the ASK, or a reviewer of the tenant, may answer it (403 otherwise).
+async fn run_resume<P: Presigner>(TenantCtx(t, who): TenantCtx, Json(b): Json<ResumeBody>) -> Result<Json<RunResp>, (StatusCode, Json<serde_json::Value>)> {
+    let ask = match t.asks.get(&b.run_cid) {
+        Ok(Some(a)) => a,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, Json(json!({"error": format!("no ask {}", b.run_cid)})))),
+        Err(e) => return Err((StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()})))),
+    };
+    if !ask.may_resume(&who) {
+        return Err((StatusCode::FORBIDDEN, Json(json!({"error": format!("ask {} belongs to another caller", ask.run_cid)}))));
+    }
+    if let Some(by) = &ask.resolved_by {
+        return Err((StatusCode::CONFLICT, Json(json!({"error": format!("ask {} was already resolved", ask.run_cid), "resolved_by": by}))));
+    }
+    not_reviewed(&t, &ask.run_cid)?;
+    let mut caller_input = ask.input.clone();
+    crate::asks::merge_answers(&mut caller_input, b.input);
+    let hold = t.hold_run(&who);
+    let input = crate::enrich::enrich(caller_input.clone(), &t.server_facts(&who, &hold))
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()}))))?;
+    if hold.remaining() == 0 {
+        return Err(over_quota(&t, &who));
+    }
+    let receipt = execute_ref(&t, &ask.unit_ref, input, Some(&ask.run_cid)).await?;
+    let run_cid = compute_run_cid(&ask.unit_ref, Some(&ask.realm), &caller_input, &RunOptions::default());
+    {
+        // Whoever closes the ask first wins; a concurrent resume, or one racing a reviewer's decision, gets 409.
+        let _settle = t.settle.lock().unwrap();
+        not_reviewed(&t, &ask.run_cid)?;
+        t.asks.resolve(&ask.run_cid, &run_cid).map_err(|e| (StatusCode::CONFLICT, Json(json!({"error": e.to_string()}))))?;
+    }
+    if receipt.decision == engine_core::model::Decision::Allow { hold.commit(); }
+    record_ask(&t, &who, &receipt, &run_cid, &ask.unit_ref, &ask.realm, caller_input).map_err(reg_err)?;
+    let mut card = receipt_card(&receipt, &ask.realm, Some(&run_cid));
+    seal_sirp(&t, &receipt, &run_cid, &mut card).map_err(reg_err)?;
+    queue_review(&t, &who, &receipt, &run_cid, &ask.unit_ref, &ask.realm, &mut card).map_err(reg_err)?;
+    Ok(Json(RunResp::new(receipt, card)))
+}
+
+/// A reviewer's approve/deny is final; the ask can no longer be answered by resuming (409).
+fn not_reviewed(t:&crate::tenant::Tenant<TenantEngine>, run_cid:&str) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    match t.reviews.get(run_cid).map_err(reg_err)?.filter(|i| !i.status.is_open()) {
+        Some(item) => Err((StatusCode::CONFLICT, Json(json!({"error": format!("ask {run_cid} was decided by review"), "override": item.override_cid})))),
+        None => Ok(()),
+    }
+}
+
+fn bundle_path(t:&crate::tenant::Tenant<TenantEngine>, run_cid:&str) -> anyhow::Result<std::path::PathBuf> {
+    let hex = run_cid.strip_prefix("b3:").filter(|h| h.len() == 64 && h.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or_else(|| anyhow::anyhow!("not a b3 cid: {run_cid}"))?;
+    Ok(t.paths.receipts.join("bundles").join(format!("{}.zip", hex.to_ascii_lowercase())))
+}
+
+/// Seal the run's `bundle.zip` with the tenant key under `<receipts>/bundles/`; returns its URL path.
+async fn write_bundle(t:&crate::tenant::Tenant<TenantEngine>, receipt:&engine_core::model::ExecutionReceipt, card:&serde_json::Value, sirp:&tdln_sirp::Chain, run_cid:&str, redact_input:bool) -> anyhow::Result<String> {
+    let spec = t.units.spec(&receipt.chip_hash).await?
+        .ok_or_else(|| anyhow::anyhow!("{}: unit has no spec to bundle", receipt.chip_id))?;
+    let mut bundle = engine_bundle::Bundle::new(run_cid, receipt, card).unit(serde_json::to_value(spec)?).redact_input(redact_input);
+    for (name, doc) in sirp.docs()? { bundle = bundle.sirp(name, doc); }
+    let (path, key) = (bundle_path(t, run_cid)?, t.signing_key().clone());
+    tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+        let zip = bundle.seal(&key)?;
+        std::fs::create_dir_all(path.parent().unwrap_or(std::path::Path::new(".")))?;
+        let tmp = path.with_extension("zip.tmp");
+        std::fs::write(&tmp, zip)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }).await??;
+    Ok(format!("/bundles/{run_cid}"))
+}
+
+/// `GET /bundles/:run_cid` — the caller's tenant only.
+async fn bundle_get<P: Presigner>(TenantCtx(t, _): TenantCtx, Path(run_cid): Path<String>) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let path = bundle_path(&t, &run_cid).map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()}))))?;
+    let zip = tokio::fs::read(&path).await.map_err(|_| (StatusCode::NOT_FOUND, Json(json!({"error": format!("no bundle for {run_cid}")}))))?;
+    Ok(([(axum::http::header::CONTENT_TYPE, "application/zip")], zip))
+}
+
+/// SIRP for a run on this engine: an engine-attested INTENT for the tenant, then the engine's DELIVERY,
+/// EXECUTION and RESULT pointing to the receipt. All four are stored under the tenant and listed in the card's `refs`.
+fn seal_sirp(t:&crate::tenant::Tenant<TenantEngine>, receipt:&engine_core::model::ExecutionReceipt, run_cid:&str, card:&mut serde_json::Value) -> anyhow::Result<tdln_sirp::Chain> {
+    // Callers hold a token, not a key: the engine seals the INTENT itself, attested for the tenant.
+    let (engine_did, engine_key) = (signer::did(), signer::signing_key());
+    let chain = tdln_sirp::Chain::attested(
+        tdln_sirp::Party{ did: &engine_did, key: &engine_key },
+        &t.did(), run_cid, &engine_core::json_atomic::compute_cid(receipt)?,
+    )?;
+    card["refs"] = json!(t.sirp.put_chain(&chain)?);
+    Ok(chain)
+}
+
+/// Keys SIRP documents are checked against: the tenant's, the engine's, and peers from `SIRP_DIDS`.
+fn sirp_keys<P: Presigner>(state:&AppState<P>, t:&crate::tenant::Tenant<TenantEngine>) -> tdln_sirp::DidKeys {
+    (*state.dids).clone()
+        .with(&t.did(), &t.signing_key().verifying_key())
+        .with(&signer::did(), &signer::signing_key().verifying_key())
+}
+
+/// `GET /sirp/:cid` — a capsule or receipt of the caller's tenant.
+async fn sirp_get<P: Presigner>(TenantCtx(t, _): TenantCtx, Path(cid): Path<String>) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    match t.sirp.get(&cid) {
+        Ok(Some(doc)) => Ok(Json(doc)),
+        Ok(None) => Err((StatusCode::NOT_FOUND, Json(json!({"error": format!("no sirp document {cid}")})))),
+        Err(e) => Err((StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()})))),
+    }
+}
+
+/// `POST /sirp/verify` — seals and links of an intent → delivery → execution → result chain.
+async fn sirp_verify<P: Presigner>(State(state): State<AppState<P>>, TenantCtx(t, _): TenantCtx, Json(chain): Json<tdln_sirp::Chain>) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let unprocessable = |e:anyhow::Error| (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"ok": false, "error": e.to_string()})));
+    chain.verify(&sirp_keys(&state, &t)).map_err(unprocessable)?;
+    Ok(Json(json!({"ok": true, "refs": chain.refs().map_err(unprocessable)?, "attested_for": chain.attested_for()})))
+}
+
+fn sirp_err(e:anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    let code = match e.downcast_ref::<tdln_sirp::SirpError>() {
+        Some(tdln_sirp::SirpError::UnknownDid(_) | tdln_sirp::SirpError::BadSignature(_)) => StatusCode::FORBIDDEN,
+        Some(tdln_sirp::SirpError::Broken(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+        None => return reg_err(e),
+    };
+    (code, Json(json!({"error": e.to_string()})))
+}
+
+/// Run request an INTENT carries in its `aad`; it must hash to the intent's `run.manifest` ref.
+#[derive(Deserialize)]
+struct IntentAad {
+    unit_ref: String,
+    #[serde(default)]
+    realm: Option<String>,
+    input: serde_json::Value,
+}
+
+/// `/run` for an admitted intent, as `who` in the inbox tenant.
+async fn run_intent(t:&crate::tenant::Tenant<TenantEngine>, who:&crate::tenant::Identity, intent:&tdln_sirp::Capsule) -> Result<(engine_core::model::ExecutionReceipt, serde_json::Value), (StatusCode, Json<serde_json::Value>)> {
+    let bad = |e:String| (StatusCode::BAD_REQUEST, Json(json!({"error": e})));
+    let b: IntentAad = serde_json::from_value(intent.aad.clone()).map_err(|e| bad(format!("aad: {e}")))?;
+    let realm = b.realm.unwrap_or_else(|| "trust".into());
+    let run_cid = compute_run_cid(&b.unit_ref, Some(&realm), &b.input, &RunOptions::default());
+    if intent.find(tdln_sirp::RUN_MANIFEST) != Some(run_cid.as_str()) { return Err(bad(format!("aad is the run {run_cid}, not the intent's"))); }
+    let hold = t.hold_run(who);
+    let input = crate::enrich::enrich(b.input.clone(), &t.server_facts(who, &hold)).map_err(|e| bad(e.to_string()))?;
+    if hold.remaining() == 0 {
+        return Err(over_quota(t, who));
+    }
+    let receipt = execute_ref(t, &b.unit_ref, input, None).await?;
+    if receipt.decision == engine_core::model::Decision::Allow { hold.commit(); }
+    record_ask(t, who, &receipt, &run_cid, &b.unit_ref, &realm, b.input).map_err(reg_err)?;
+    let mut card = receipt_card(&receipt, &realm, Some(&run_cid));
+    queue_review(t, who, &receipt, &run_cid, &b.unit_ref, &realm, &mut card).map_err(reg_err)?;
+    Ok((receipt, card))
+}
+
+/// `POST /sirp/inbox` — a peer engine's sealed INTENT, addressed to this engine's DID and sealed by a DID in
+/// `SIRP_DIDS`. It runs under the inbox tenant; the reply is the whole chain plus the receipt and its card.
+/// Intents that cannot run (bad `aad`, unknown unit, ...) get a sealed REJECTED delivery (422); quota and
+/// server errors are left unsealed so the sender retries. A capsule delivered twice gets the first reply.
+async fn sirp_inbox<P: Presigner>(State(state): State<AppState<P>>, Json(intent): Json<tdln_sirp::Capsule>) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    use tdln_sirp::Signed;
+    let t = state.tenants.get(&state.inbox)
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Json(json!({"error": format!("no inbox tenant {}", state.inbox)}))))?;
+    tdln_sirp::Chain::admit(&intent, &signer::did(), &sirp_keys(&state, &t)).map_err(sirp_err)?;
+    let cid = intent.cid().map_err(reg_err)?;
+    let replied = |reply:serde_json::Value| if reply.get("result").is_some() { Ok(Json(reply)) } else { Err((StatusCode::UNPROCESSABLE_ENTITY, Json(reply))) };
+    // Held from the answer check to `put_answer`: a capsule delivered twice at once runs once.
+    let answering = Answering::new(&cid);
+    let _answering = answering.lock.lock().await;
+    if let Some(reply) = t.sirp.answer(&cid).map_err(reg_err)? { return replied(reply); }
+    // The peer is not one of the tenant's actors; it runs on the tenant's shared quota.
+    let who = crate::tenant::Identity{ tenant: t.id.clone(), actor: Some(intent.from.clone()), role: None, anonymous: false };
+    let key = signer::signing_key();
+    let reply = match run_intent(&t, &who, &intent).await {
+        Ok((receipt, mut card)) => {
+            let chain = tdln_sirp::Chain::answer(intent, &key, &engine_core::json_atomic::compute_cid(&receipt).map_err(reg_err)?).map_err(reg_err)?;
+            card["refs"] = json!(t.sirp.put_chain(&chain).map_err(reg_err)?);
+            let mut reply = serde_json::to_value(&chain).map_err(|e| reg_err(e.into()))?;
+            reply["receipt"] = json!(receipt);
+            reply["card"] = card;
+            reply
+        }
+        Err((code, err)) if code == StatusCode::TOO_MANY_REQUESTS || code.is_server_error() => return Err((code, err)),
+        Err((_, err)) => {
+            let why = err.0.get("error").and_then(|e| e.as_str()).unwrap_or("rejected").to_string();
+            let delivery = tdln_sirp::Chain::reject(&intent, &key, &why).map_err(reg_err)?;
+            t.sirp.put(&intent).and_then(|_| t.sirp.put(&delivery)).map_err(reg_err)?;
+            json!({"intent": intent, "delivery": delivery})
+        }
+    };
+    t.sirp.put_answer(&cid, &reply).map_err(reg_err)?;
+    replied(reply)
+}
+
+/// Per-capsule locks of `/sirp/inbox`, by intent CID.
+static ANSWERING: Lazy<Mutex<HashMap<String, std::sync::Arc<tokio::sync::Mutex<()>>>>> = Lazy::new(Default::default);
+
+/// The inbox lock of one capsule; its map entry goes with the last holder.
+struct Answering { cid: String, lock: std::sync::Arc<tokio::sync::Mutex<()>> }
+impl Answering {
+    fn new(cid:&str) -> Self {
+        let lock = ANSWERING.lock().unwrap().entry(cid.into()).or_default().clone();
+        Self{ cid: cid.into(), lock }
+    }
+}
+impl Drop for Answering {
+    fn drop(&mut self) {
+        let mut m = ANSWERING.lock().unwrap();
+        if std::sync::Arc::strong_count(&self.lock) == 2 { m.remove(&self.cid); }
+    }
+}
+
+/// Timeout of one post to a peer inbox.
+const SIRP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+/// Outbox claims outlive the post they cover, so a capsule is in flight at most once.
+fn sirp_lease() -> chrono::Duration { chrono::Duration::seconds(2 * SIRP_TIMEOUT.as_secs() as i64) }
+/// Posts in flight to one peer inbox, at most.
+const SIRP_PEER_CONCURRENCY: usize = 4;
+
+#[derive(Deserialize)]
+struct SirpSendBody {
+    /// Peer engine DID; its inbox URL comes from `SIRP_DIDS.endpoints`.
+    to: String,
+    unit_ref: String,
+    #[serde(default)]
+    realm: Option<String>,
+    input: serde_json::Value,
+}
+
+/// `POST /sirp/send` — ask a peer engine to run `unit_ref` on `input`. The engine seals an INTENT carrying
+/// the request, queues it in the tenant's outbox and tries it once (claimed, so the worker does not post it
+/// meanwhile); the outbox worker retries it.
+async fn sirp_send<P: Presigner>(State(state): State<AppState<P>>, TenantCtx(t, _): TenantCtx, Json(b): Json<SirpSendBody>) -> Result<Json<tdln_sirp::outbox::Entry>, (StatusCode, Json<serde_json::Value>)> {
+    use tdln_sirp::Signed;
+    let endpoint = state.dids.endpoint(&b.to)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, Json(json!({"error": format!("no inbox endpoint for {}", b.to)}))))?;
+    let realm = b.realm.unwrap_or_else(|| "trust".into());
+    let run_cid = compute_run_cid(&b.unit_ref, Some(&realm), &b.input, &RunOptions::default());
+    let mut intent = tdln_sirp::Capsule::intent(&signer::did(), &b.to, &run_cid);
+    intent.aad = json!({"unit_ref": b.unit_ref, "realm": realm, "input": b.input});
+    intent.sign(&signer::signing_key()).map_err(reg_err)?;
+    let entry = t.outbox.enqueue(intent, endpoint).map_err(reg_err)?;
+    match t.outbox.claim(&entry.capsule_cid, chrono::Utc::now(), sirp_lease()).map_err(reg_err)? {
+        Some(claimed) => Ok(Json(sirp_deliver(&state, &t, claimed).await)),
+        // Settled already, or in flight.
+        None => Ok(Json(entry)),
+    }
+}
+
+#[derive(Deserialize)]
+struct OutboxQuery {
+    #[serde(default)]
+    status: Option<tdln_sirp::outbox::Status>,
+}
+
+/// `GET /sirp/outbox?status=pending|delivered|dead` — the caller's tenant only (default `pending`).
+async fn sirp_outbox<P: Presigner>(TenantCtx(t, _): TenantCtx, axum::extract::Query(q): axum::extract::Query<OutboxQuery>) -> Result<Json<Vec<tdln_sirp::outbox::Entry>>, (StatusCode, Json<serde_json::Value>)> {
+    t.outbox.list(q.status.unwrap_or(tdln_sirp::outbox::Status::Pending)).map(Json).map_err(reg_err)
+}
+
+/// One attempt at a claimed outbox entry. A verified answer (whose receipt matches its EXECUTION) is delivered and
+/// its documents stored under the tenant; a sealed rejection dead-letters the entry; anything else is retried.
+async fn sirp_deliver<P: Presigner>(state:&AppState<P>, t:&crate::tenant::Tenant<TenantEngine>, entry:tdln_sirp::outbox::Entry) -> tdln_sirp::outbox::Entry {
+    let cid = entry.capsule_cid.clone();
+    let keys = sirp_keys(state, t);
+    let attempt = async {
+        let resp = state.http.post(&entry.endpoint).json(&entry.capsule).send().await?;
+        let status = resp.status();
+        anyhow::Ok((status, resp.json::<serde_json::Value>().await.unwrap_or_default()))
+    }.await;
+    let settled = attempt.and_then(|(status, body)| {
+        if status.is_success() {
+            let chain: tdln_sirp::Chain = serde_json::from_value(body.clone())?;
+            let receipt = body.get("receipt").cloned().unwrap_or_default();
+            if engine_core::json_atomic::compute_cid(&receipt)? != chain.execution.result_cid { anyhow::bail!("answer's receipt is not the one its execution names"); }
+            let done = t.outbox.delivered(&cid, chain.clone(), &keys)?;
+            t.sirp.put_chain(&chain)?;
+            t.sirp.put(&receipt)?;
+            return Ok(done);
+        }
+        match body.get("delivery").filter(|_| status == StatusCode::UNPROCESSABLE_ENTITY) {
+            Some(d) => t.outbox.rejected(&cid, serde_json::from_value(d.clone())?, &keys),
+            None => anyhow::bail!("{status}: {}", body.get("error").and_then(|e| e.as_str()).unwrap_or_default()),
+        }
     });
-    Json(RunResp{ receipt, card })
-},
-        "output": { "cid": receipt.output.cid },
-        "proof": receipt.proof,
-        "ts": receipt.timestamp
-    });
-    Json(RunResp{ receipt, card })
+    let settled = settled.or_else(|e| t.outbox.failed(&cid, &format!("{e:#}"), chrono::Utc::now()));
+    match settled {
+        Ok(Some(e)) => e,
+        // Settled meanwhile by another attempt.
+        Ok(None) => t.outbox.get(&cid).ok().flatten().unwrap_or(entry),
+        Err(e) => { tracing::warn!(capsule = %cid, "sirp outbox: {e:#}"); entry }
+    }
+}
+
+/// Retries every tenant's due outbox entries, once a second. Entries are posted concurrently, at most
+/// [`SIRP_PEER_CONCURRENCY`] per peer inbox; the rest wait for a later tick.
+async fn sirp_worker<P: Presigner>(state:AppState<P>) {
+    let mut peers: HashMap<String, std::sync::Arc<tokio::sync::Semaphore>> = HashMap::new();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let now = chrono::Utc::now();
+        for t in state.tenants.all() {
+            for e in t.outbox.due(now).unwrap_or_default() {
+                let peer = peers.entry(e.endpoint.clone()).or_insert_with(|| std::sync::Arc::new(tokio::sync::Semaphore::new(SIRP_PEER_CONCURRENCY)));
+                let Ok(permit) = peer.clone().try_acquire_owned() else { continue };
+                let claimed = match t.outbox.claim(&e.capsule_cid, now, sirp_lease()) {
+                    Ok(Some(claimed)) => claimed,
+                    Ok(None) => continue,
+                    Err(err) => { tracing::warn!(capsule = %e.capsule_cid, "sirp outbox: {err:#}"); continue }
+                };
+                let (state, t) = (state.clone(), t.clone());
+                tokio::spawn(async move { sirp_deliver(&state, &t, claimed).await; drop(permit); });
+            }
+        }
+    }
+}
+
+/// ASK receipts open a review item; the card says where to follow it.
+fn queue_review(t:&crate::tenant::Tenant<TenantEngine>, who:&crate::tenant::Identity, receipt:&engine_core::model::ExecutionReceipt, run_cid:&str, unit_ref:&str, realm:&str, card:&mut serde_json::Value) -> anyhow::Result<()> {
+    let (engine_core::model::Decision::Doubt, Some(poi)) = (&receipt.decision, &receipt.poi) else { return Ok(()) };
+    let receipt_cid = engine_core::json_atomic::compute_cid(receipt)?;
+    // Re-running the same manifest keeps the item (and any reviews) already open for it.
+    t.reviews.enqueue(&engine_audit::review::ReviewItem::new(run_cid, unit_ref, realm, &receipt_cid, poi.clone(), card.clone()), &who.label())?;
+    let status = t.reviews.get(run_cid)?.map(|i| i.status);
+    card["review"] = json!({"status": status, "href": format!("/reviews/{run_cid}")});
+    Ok(())
+}
+
+/// Queue errors → HTTP: unknown run 404, closed item 409, untrusted or bad seal 403.
+fn review_err(e:anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    use engine_audit::review::ReviewError::*;
+    let code = match e.downcast_ref() {
+        Some(NotFound(_)) => StatusCode::NOT_FOUND,
+        Some(Closed(_)) => StatusCode::CONFLICT,
+        Some(Forbidden(_)) => StatusCode::FORBIDDEN,
+        None => StatusCode::BAD_REQUEST,
+    };
+    (code, Json(json!({"error": e.to_string()})))
 }
 
 #[derive(Deserialize)]
-struct RegPutBody { name:String, version:String, cid:String }
-#[derive(Serialize)]
-struct RegPutResp { path:String }
-async fn registry_put<P: Presigner>(State(state): State<AppState<P>>, Json(b): Json<RegPutBody>) -> Json<RegPutResp> {
-    let e = EngineRegistryEntry{
-        kind:"engine.registry.entry.v1".into(),
-        id: ulid::Ulid::new().to_string(),
-        name: b.name, version: b.version, cid: b.cid, meta: serde_json::json!({})
+struct ReviewListQuery { status: Option<engine_audit::review::ReviewStatus> }
+
+/// `GET /reviews?status=pending` — the tenant's review items, oldest first.
+async fn review_list<P: Presigner>(TenantCtx(t, _): TenantCtx, axum::extract::Query(q): axum::extract::Query<ReviewListQuery>) -> Result<Json<Vec<engine_audit::review::ReviewItem>>, (StatusCode, Json<serde_json::Value>)> {
+    t.reviews.list(q.status).map(Json).map_err(reg_err)
+}
+
+/// `GET /reviews/:run_cid` — the item, its review receipts and, once decided, the final card.
+async fn review_get<P: Presigner>(TenantCtx(t, _): TenantCtx, Path(run_cid): Path<String>) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let item = t.reviews.get(&run_cid).map_err(review_err)?
+        .ok_or_else(|| review_err(engine_audit::review::ReviewError::NotFound(run_cid.clone()).into()))?;
+    let mut reviews = vec![];
+    for cid in &item.reviews { reviews.extend(t.reviews.receipt(cid).map_err(reg_err)?); }
+    let card = reviews.last().and_then(|r| item.final_card(r));
+    Ok(Json(json!({"item": item, "reviews": reviews, "card": card})))
+}
+
+/// `POST /reviews/:run_cid/decision` with a sealed `review.receipt.v1`, checked against the tenant's reviewers.
+/// Approve/deny close the item and publish the final card (`/r/:run`), which points to the review receipt as its `override`.
+async fn review_decide<P: Presigner>(TenantCtx(t, _): TenantCtx, Path(run_cid): Path<String>, Json(r): Json<engine_audit::review::ReviewReceipt>) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let Some(reviewers) = &t.reviewers else {
+        return Err((StatusCode::SERVICE_UNAVAILABLE, Json(json!({"error": format!("tenant {} has no reviewers configured", t.id)}))));
     };
-    let p = state.reg.put(&e).expect("registry put");
-    Json(RegPutResp{ path: p.display().to_string() })
+    if r.run_cid != run_cid { return Err((StatusCode::BAD_REQUEST, Json(json!({"error": "review is for another run"})))); }
+    let _settle = t.settle.lock().unwrap();
+    if let Some(by) = t.asks.get(&run_cid).map_err(review_err)?.and_then(|a| a.resolved_by) {
+        return Err((StatusCode::CONFLICT, Json(json!({"error": format!("ask {run_cid} was already resolved"), "resolved_by": by}))));
+    }
+    let (item, review_cid) = t.reviews.decide(&r, reviewers).map_err(review_err)?;
+    let card = item.final_card(&r);
+    Ok(Json(json!({"review_cid": review_cid, "item": item, "card": card})))
+}
+
+/// Unit resolution errors → HTTP: missing is 404, a spec that is corrupt or fails its CID check is 422, an object
+/// store that cannot be read is 502; registry errors as [`reg_err`].
+fn unit_err(e:anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    use engine_loader::ResolveError::*;
+    let code = match e.downcast_ref() {
+        Some(NotFound(_)) => StatusCode::NOT_FOUND,
+        Some(CidMismatch{ .. } | Corrupt{ .. }) => StatusCode::UNPROCESSABLE_ENTITY,
+        Some(Unavailable{ .. }) => StatusCode::BAD_GATEWAY,
+        None => return reg_err(e),
+    };
+    (code, Json(json!({"error": e.to_string()})))
+}
+
+/// Admin routes need `X-Admin-Token: <ENGINE_ADMIN_TOKEN>` (compared in constant time); `Authorization` stays the
+/// caller's tenant token. Without a token configured they are closed (`503`), never open.
+pub(crate) fn admin_guard(headers:&axum::http::HeaderMap) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    use subtle::ConstantTimeEq;
+    let token = std::env::var("ENGINE_ADMIN_TOKEN").unwrap_or_default();
+    if token.is_empty() { return Err((StatusCode::SERVICE_UNAVAILABLE, Json(json!({"error":"admin routes are disabled: ENGINE_ADMIN_TOKEN is not set"})))); }
+    let given = headers.get("x-admin-token").and_then(|v| v.to_str().ok()).unwrap_or_default();
+    if bool::from(given.as_bytes().ct_eq(token.as_bytes())) { Ok(()) } else { Err((StatusCode::UNAUTHORIZED, Json(json!({"error":"admin token required"})))) }
+}
+
+/// `GET /admin/units` — serving generation, its units, rollback candidates and recent reload events.
+async fn admin_units<P: Presigner>(TenantCtx(t, _): TenantCtx, headers: axum::http::HeaderMap) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    admin_guard(&headers)?;
+    let gen = t.units.snapshot();
+    let units: Vec<_> = gen.units.iter().map(|(src, u)| json!({"source": src, "id": u.id, "hash": u.hash})).collect();
+    let history: Vec<_> = t.units.history().iter().map(|g| json!({"generation": g.number, "origin": g.origin, "activated_at": g.activated_at, "units": g.units.len()})).collect();
+    Ok(Json(json!({
+        "generation": gen.number, "origin": gen.origin, "activated_at": gen.activated_at,
+        "units": units, "history": history, "events": t.units.events(),
+    })))
+}
+
+/// `POST /admin/units/reload` — rescan `UNITS_DIR` now instead of waiting for a file event.
+async fn admin_units_reload<P: Presigner>(TenantCtx(t, _): TenantCtx, headers: axum::http::HeaderMap) -> Result<Json<engine_loader::ReloadEvent>, (StatusCode, Json<serde_json::Value>)> {
+    admin_guard(&headers)?;
+    let store = t.units.clone();
+    let ev = tokio::task::spawn_blocking(move || store.reload()).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": e.to_string()}))))?;
+    Ok(Json(ev))
+}
+
+#[derive(Deserialize)]
+struct RollbackBody { #[serde(default)] generation: Option<u64> }
+/// `POST /admin/units/rollback {generation?}` — re-activate an earlier generation (default: the previous one).
+async fn admin_units_rollback<P: Presigner>(TenantCtx(t, _): TenantCtx, headers: axum::http::HeaderMap, Json(b): Json<RollbackBody>) -> Result<Json<engine_loader::ReloadEvent>, (StatusCode, Json<serde_json::Value>)> {
+    admin_guard(&headers)?;
+    t.units.rollback(b.generation).map(Json).map_err(|e| (StatusCode::CONFLICT, Json(json!({"error": e.to_string()}))))
+}
+
+/// `GET /registry/index` — latest signed snapshot (`registry.index.v1`) for offline verification.
+/// The entry's `meta` with `lint` pointing at the lint attachment when the unit was linted. Sealed entries are
+/// stored as signed, so their publisher must have put that marker in `meta` already.
+fn lint_meta(meta:Option<serde_json::Value>, linted:bool, sealed:bool) -> Result<serde_json::Value, String> {
+    let mut meta = meta.unwrap_or_else(|| json!({}));
+    if !linted { return Ok(meta); }
+    let kind = json!("wasm.lint.report.v1");
+    let fields = meta.as_object_mut().ok_or("meta must be an object")?;
+    match fields.get("lint") {
+        Some(v) if *v == kind => {}
+        None if !sealed => { fields.insert("lint".into(), kind); }
+        _ => return Err(format!("meta.lint must be {kind} for wasm units (sealed entries carry it themselves)")),
+    }
+    Ok(meta)
+}
+async fn registry_index<P: Presigner>(TenantCtx(t, _): TenantCtx) -> Result<Json<engine_registry::provenance::RegistryIndex>, (StatusCode, Json<serde_json::Value>)> {
+    match engine_registry::provenance::read_index(&t.reg).map_err(reg_err)? {
+        Some(index) => Ok(Json(index)),
+        None => Err((StatusCode::NOT_FOUND, Json(json!({"error":"no index published yet"})))),
+    }
+}
+
+#[derive(Deserialize)]
+struct RegIndexBody { #[serde(default = "default_index_ttl")] ttl_secs: u64 }
+fn default_index_ttl() -> u64 { 7 * 24 * 3600 }
+/// `POST /registry/index` — admin only: re-snapshot and seal with the tenant key (must be in the policy's `index_keys`).
+async fn registry_index_publish<P: Presigner>(TenantCtx(t, _): TenantCtx, headers: axum::http::HeaderMap, Json(b): Json<RegIndexBody>) -> Result<Json<engine_registry::provenance::RegistryIndex>, (StatusCode, Json<serde_json::Value>)> {
+    admin_guard(&headers)?;
+    let key = t.signing_key();
+    let kid = engine_auth::signing::key_id(&key.verifying_key());
+    engine_registry::provenance::publish_index(&t.reg, key, &kid, b.ttl_secs).map(Json).map_err(reg_err)
+}
+
+#[derive(Deserialize)]
+struct RegListQuery { name: Option<String>, cursor: Option<String>, #[serde(default = "default_page")] limit: usize }
+fn default_page() -> usize { 50 }
+async fn registry_list<P: Presigner>(TenantCtx(t, _): TenantCtx, axum::extract::Query(q): axum::extract::Query<RegListQuery>) -> Result<Json<engine_registry::file_registry::Page>, (StatusCode, Json<serde_json::Value>)> {
+    t.reg.list(q.name.as_deref(), q.cursor.as_deref(), q.limit.min(500)).map(Json).map_err(reg_err)
+}
+
+/// `GET /registry/resolve/:name/:req` — `req` is a channel, `latest` or a semver range (URL-encoded).
+async fn registry_resolve<P: Presigner>(TenantCtx(t, _): TenantCtx, axum::extract::Path((name, req)): axum::extract::Path<(String, String)>) -> Result<Json<EngineRegistryEntry>, (StatusCode, Json<serde_json::Value>)> {
+    t.reg.resolve(&name, &req).map(Json).map_err(reg_err)
+}
+
+#[derive(Deserialize)]
+struct RegYankBody { name:String, version:String, #[serde(default)] reason:String, #[serde(default)] undo:bool }
+/// `POST /registry/yank` (admin) — hides a version from range resolution, or restores it with `undo`.
+async fn registry_yank<P: Presigner>(TenantCtx(t, _): TenantCtx, headers: axum::http::HeaderMap, Json(b): Json<RegYankBody>) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    admin_guard(&headers)?;
+    if b.undo { t.reg.unyank(&b.name, &b.version) } else { t.reg.yank(&b.name, &b.version, &b.reason) }.map_err(reg_err)?;
+    Ok(Json(json!({"name": b.name, "version": b.version, "yanked": !b.undo})))
+}
+
+#[derive(Deserialize)]
+struct RegChannelBody { name:String, channel:String, version:String }
+/// `POST /registry/channel` (admin) — repoints a channel such as `stable`.
+async fn registry_channel<P: Presigner>(TenantCtx(t, _): TenantCtx, headers: axum::http::HeaderMap, Json(b): Json<RegChannelBody>) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    admin_guard(&headers)?;
+    t.reg.set_channel(&b.name, &b.channel, &b.version).map_err(|e| match e.downcast_ref::<engine_registry::file_registry::RegistryError>() {
+        Some(_) => reg_err(e),
+        None => (StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()}))),
+    })?;
+    Ok(Json(json!({"name": b.name, "channel": b.channel, "version": b.version})))
 }
 
 #[derive(Deserialize)]
 struct PresignBody { actor:String, resource: crate::presign::PresignResource, ttl_seconds: u64 }
-async fn acquire_presigned_url<P: Presigner>(State(state): State<AppState<P>>, Json(b): Json<PresignBody>) -> Json<PresignResponse> {
-    let intent = PresignIntent{ actor: b.actor, resource: b.resource, ttl_seconds: b.ttl_seconds };
-    let resp = state.presigner.presign(intent).await.expect("presign");
-    Json(resp)
+/// `POST /acquire_presigned_url` — the configured [`Presigner`] (stub or S3), without a policy unit. The fs backend
+/// is only issued through `/registry/presign`, which runs the tenant's presign policy.
+async fn acquire_presigned_url<P: Presigner>(State(state): State<AppState<P>>, Json(b): Json<PresignBody>) -> Result<Json<PresignResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let intent = PresignIntent{ actor: b.actor, resource: b.resource, ttl_seconds: b.ttl_seconds.min(crate::presign::PRESIGN_TTL_MAX_SECS) };
+    state.presigner.presign(intent).await.map(Json).map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({"error": format!("{e:#}")}))))
+}
+
+/// `POST /registry/presign` — the request runs through the presign policy unit (`PRESIGN_POLICY_UNIT`, default
+/// [`crate::PRESIGN_POLICY_UNIT`]) as `{presign: …}` plus `$server`: the tenant's own, or the public tenant's when
+/// the tenant has none. The receipt is sealed like a run's (`card.refs`) and only an `ACK` yields a URL, whose grant
+/// links the receipt.
+async fn registry_presign<P: Presigner>(State(state): State<AppState<P>>, TenantCtx(t, who): TenantCtx, Json(mut req): Json<crate::PresignReq>) -> Result<Json<crate::PresignResp>, (StatusCode, Json<serde_json::Value>)> {
+    if std::env::var("PRESIGN_DISABLE").ok().as_deref() == Some("1") {
+        return Err((StatusCode::SERVICE_UNAVAILABLE, Json(json!({"error":"presign is disabled"}))));
+    }
+    req.ip_hash = req.client_ip.as_deref().map(|ip| engine_auth::grant::ip_hash(&crate::signer::ip_hash_key(), ip));
+    // Clamped before the unit sees it: whatever a unit allows, no URL outlives the presigner ceiling.
+    req.ttl_secs = req.ttl_secs.min(crate::presign::PRESIGN_TTL_MAX_SECS);
+    let unit_ref = std::env::var("PRESIGN_POLICY_UNIT").unwrap_or_else(|_| crate::PRESIGN_POLICY_UNIT.into());
+    let caller_input = json!({ "presign": req });
+    // Presigning spends no runs: the hold only lets the unit see the actor's quota.
+    let hold = t.hold_run(&who);
+    let input = crate::enrich::enrich(caller_input.clone(), &t.server_facts(&who, &hold))
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()}))))?;
+    let public = state.tenants.get(&crate::tenant::TenantId::public()).filter(|p| p.id != t.id);
+    let (unit, units) = match t.units.resolve(&unit_ref).await {
+        Ok(u) => (u, &t.units),
+        Err(e) => match &public {
+            Some(p) if matches!(e.downcast_ref(), Some(engine_loader::ResolveError::NotFound(_))) => (p.units.resolve(&unit_ref).await.map_err(unit_err)?, &p.units),
+            _ => return Err(unit_err(e)),
+        },
+    };
+    let descriptions = units.spec(unit.hash.as_deref().unwrap_or_default()).await.map_err(reg_err)?
+        .map(|s| s.policies.into_iter().filter_map(|p| Some((p.id, p.description?))).collect()).unwrap_or_default();
+    let receipt = t.engine.execute_chip(&unit, input, None).map_err(reg_err)?;
+    let run_cid = compute_run_cid(&unit_ref, Some("presign"), &caller_input, &RunOptions::default());
+    let mut card = receipt_card(&receipt, "presign", Some(&run_cid));
+    seal_sirp(&t, &receipt, &run_cid, &mut card).map_err(reg_err)?;
+    let link = engine_auth::grant::GrantPolicy{
+        unit: receipt.chip_id.clone(), unit_cid: receipt.chip_hash.clone(), run_cid,
+        receipt_cid: engine_core::json_atomic::compute_cid(&receipt).map_err(reg_err)?,
+    };
+    crate::issue_presign(&t.id, &t.paths.audit.display().to_string(), &req, &receipt, card, link, &descriptions).await
+        .map(Json).map_err(|code| (code, Json(json!({"error": format!("presign failed for backend {}", req.backend)}))))
 }
 
 
@@ -470,22 +1213,25 @@ pub struct SubmitResp {
     pub card: serde_json::Value,
 }
 
-pub async fn build_router_with_flavors<P: Presigner>(outdir:&str, regdir:&str, k:usize, presigner:P) -> Router {
-    let base = build_router::<P>(outdir, regdir, k, presigner).await;
+pub async fn build_router_with_flavors<P: Presigner>(outdir:&str, regdir:&str, k:usize, presigner:P) -> anyhow::Result<Router> {
+    let base = build_router::<P>(outdir, regdir, k, presigner).await?;
     // Allow larger bodies for code/data submit (adjust as needed)
-    base.layer(DefaultBodyLimit::max(16 * 1024 * 1024))
+    Ok(base.layer(DefaultBodyLimit::max(16 * 1024 * 1024))
         .route("/submit-code", ax_post(submit_code::<P>))
-        .route("/submit-data", ax_post(submit_data::<P>))
+        .route("/submit-data", ax_post(submit_data::<P>)))
 }
 
-async fn submit_code<P: Presigner>(State(state): State<AppState<P>>, Json(b): Json<SubmitCodeBody>) -> Result<Json<SubmitResp>, StatusCode> {
+async fn submit_code<P: Presigner>(TenantCtx(t, who): TenantCtx, Json(b): Json<SubmitCodeBody>) -> Result<Json<SubmitResp>, StatusCode> {
     // Minimal placeholder: treat "code" as context for the same example unit.
+    let hold = t.hold_run(&who);
     let input = serde_json::json!({
-        "actor": { "role": "admin", "quota": 5, "id": b.actor },
+        "actor": { "id": b.actor },
         "resource": { "restricted": false },
         "artifact": { "kind":"code", "present": b.code.is_some() || b.url.is_some(), "meta": b.meta }
     });
-    let receipt = state.engine.execute("", input, None).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let input = crate::enrich::enrich(input, &t.server_facts(&who, &hold)).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let receipt = t.engine.execute("", input, None).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if receipt.decision == engine_core::model::Decision::Allow { hold.commit(); }
     let card = serde_json::json!({
         "kind":"receipt.card.v1",
         "unit_id": receipt.chip_id,
@@ -498,14 +1244,17 @@ async fn submit_code<P: Presigner>(State(state): State<AppState<P>>, Json(b): Js
     Ok(Json(SubmitResp{ receipt, card }))
 }
 
-async fn submit_data<P: Presigner>(State(state): State<AppState<P>>, Json(b): Json<SubmitDataBody>) -> Result<Json<SubmitResp>, StatusCode> {
+async fn submit_data<P: Presigner>(TenantCtx(t, who): TenantCtx, Json(b): Json<SubmitDataBody>) -> Result<Json<SubmitResp>, StatusCode> {
+    let hold = t.hold_run(&who);
     let input = serde_json::json!({
-        "actor": { "role": "admin", "quota": 5, "id": b.actor },
+        "actor": { "id": b.actor },
         "resource": { "restricted": false },
         "payload": b.data,
         "meta": b.meta
     });
-    let receipt = state.engine.execute("", input, None).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let input = crate::enrich::enrich(input, &t.server_facts(&who, &hold)).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let receipt = t.engine.execute("", input, None).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if receipt.decision == engine_core::model::Decision::Allow { hold.commit(); }
     let card = serde_json::json!({
         "kind":"receipt.card.v1",
         "unit_id": receipt.chip_id,
@@ -523,8 +1272,12 @@ async fn submit_data<P: Presigner>(State(state): State<AppState<P>>, Json(b): Js
 pub struct RunOptions {
     #[serde(default)]
     pub require_certified_runtime: bool,
+    /// Seal a `bundle.zip` for offline verification (`RunResp.bundle_url`).
     #[serde(default)]
     pub offline_bundle: bool,
+    /// Bundle per-field input commitments instead of the input.
+    #[serde(default)]
+    pub redact_input: bool,
     #[serde(default)]
     pub no_hitl: bool,
 }
@@ -563,100 +1316,18 @@ fn resolve_url(run_cid: &str) -> String {
 }
 
 
-fn sirp_capsule_intent(from: &str, to: &str, run_cid: &str) -> serde_json::Value {
-    json!({
-      "kind":"sirp.capsule.v1",
-      "type":"INTENT",
-      "from": from,
-      "to": to,
-      "ts": chrono::Utc::now().to_rfc3339(),
-      "refs":[{"kind":"run.manifest","cid": run_cid}],
-      "aad":{},
-      "signature": signer::sign_bytes(&cap_canon.as_bytes()) // assinatura real deve ser aplicada no signer do engine
-    })
-}
-fn sirp_capsule_result(from: &str, to: &str, card_cid: &str) -> serde_json::Value {
-    json!({
-      "kind":"sirp.capsule.v1",
-      "type":"RESULT",
-      "from": from,
-      "to": to,
-      "ts": chrono::Utc::now().to_rfc3339(),
-      "refs":[{"kind":"receipt.card","cid": card_cid}],
-      "aad":{},
-      "signature": signer::sign_bytes(&cap_canon.as_bytes())
-    })
-}
-fn sirp_delivery(capsule_cid: &str, sender_did: &str, receiver_did: &str, outcome: &str) -> serde_json::Value {
-    json!({
-      "kind":"sirp.receipt.delivery.v1",
-      "capsule_cid": capsule_cid,
-      "sender_did": sender_did,
-      "receiver_did": receiver_did,
-      "ts_received": chrono::Utc::now().to_rfc3339(),
-      "outcome": outcome,
-      "signature": signer::sign_bytes(&cap_canon.as_bytes())
-    })
-}
-fn sirp_execution(capsule_cid: &str, executor_did: &str, card_cid: &str, runtime_used: bool, eer_cid: Option<&str>) -> serde_json::Value {
-    json!({
-      "kind":"sirp.receipt.execution.v1",
-      "capsule_cid": capsule_cid,
-      "executor_did": executor_did,
-      "ts_done": chrono::Utc::now().to_rfc3339(),
-      "result_cid": card_cid,
-      "runtime_used": runtime_used,
-      "eer_cid": eer_cid.unwrap_or(""),
-      "signature": signer::sign_bytes(&cap_canon.as_bytes())
-    })
-}
-
-fn emit_sirp_intent_and_delivery(run_cid: &str) -> (String, String) {
-    let from = "did:tdln:issuer:m1";
-    let to = "did:tdln:engine:exec";
-    let cap = sirp_capsule_intent(from, to, run_cid);
-    let cap_canon = json_atomic_stringify(&cap);
-    let cap_cid = blake3_cid(&cap_canon);
-    let del = sirp_delivery(&cap_cid, from, to, "DELIVERED");
-    let del_canon = json_atomic_stringify(&del);
-    let del_cid = blake3_cid(&del_canon);
-    (cap_cid, del_cid)
-}
-
-fn emit_sirp_result_and_execution(card_payload: &serde_json::Value, runtime_used: bool, eer_cid: Option<&str>, intent_cid: &str) -> (String, String) {
-    let card_canon = json_atomic_stringify(card_payload);
-    let card_cid = blake3_cid(&card_canon);
-    let from = "did:tdln:engine:exec";
-    let to = "did:tdln:issuer:m1";
-    let cap_res = sirp_capsule_result(from, to, &card_cid);
-    let cap_res_canon = json_atomic_stringify(&cap_res);
-    let cap_res_cid = blake3_cid(&cap_res_canon);
-    let exe = sirp_execution(intent_cid, from, &card_cid, runtime_used, eer_cid);
-    let exe_canon = json_atomic_stringify(&exe);
-    let exe_cid = blake3_cid(&exe_canon);
-    (cap_res_cid, exe_cid)
-}
-
-fn ref_cid(kind: &str, cid: &str) -> RefLink {
-    RefLink{ kind: kind.into(), cid: cid.into(), media_type: None, hrefs: vec![] }
-}
-
-
-static RUN_INDEX: Lazy<Mutex<HashMap<String, serde_json::Value>>> = Lazy::new(|| Mutex::new(HashMap::new()));
-
-
 use axum::{extract::Path, http::{HeaderMap, StatusCode}, response::{IntoResponse, Redirect}};
 
-pub async fn handle_run_cid(Path(run): Path<String>, headers: HeaderMap) -> impl IntoResponse {
+/// `GET /r/:run` — the final card of a run the caller's tenant had reviewed (kept with the review queue).
+pub async fn handle_run_cid<P: Presigner>(TenantCtx(t, _): TenantCtx, Path(run): Path<String>, headers: HeaderMap) -> impl IntoResponse {
     let wants_json = headers.get(axum::http::header::ACCEPT)
         .and_then(|h| h.to_str().ok())
         .map(|s| s.contains("application/json"))
         .unwrap_or(false);
 
-    let store = RUN_INDEX.lock().unwrap();
-    if let Some(card) = store.get(&run) {
+    if let Ok(Some(card)) = t.reviews.card(&run) {
         if wants_json {
-            return (StatusCode::OK, axum::Json(card.clone())).into_response();
+            return (StatusCode::OK, axum::Json(card)).into_response();
         } else {
             // best-effort pull realm/did; if absent, redirect to a generic UI route
             let realm = card.get("realm").and_then(|v| v.as_str()).unwrap_or("trust");
@@ -43,3 +43,27 @@ pub fn sign_bytes(data: &[u8]) -> String {
     let sig = sk.sign(data);
     format!("ed25519:{}", base64::encode(sig.to_bytes()))
 }
+
+/// DID the engine signs SIRP receipts as: `ENGINE_DID`, else derived from its key.
+pub fn did() -> String {
+    std::env::var("ENGINE_DID").unwrap_or_else(|_| {
+        let kid = engine_auth::signing::key_id(&signing_key().verifying_key());
+        format!("did:tdln:engine:{}", kid.trim_start_matches("ed25519:"))
+    })
+}
+
+/// Key of grant `ip_hash` constraints: derived from `ENGINE_IP_HASH_KEY`, else from the engine key,
+/// so engines sharing a seed agree on it and nobody else can match a hash to an address.
+pub fn ip_hash_key() -> [u8; 32] {
+    const CONTEXT: &str = "engine-http grant ip_hash v1";
+    match std::env::var("ENGINE_IP_HASH_KEY") {
+        Ok(k) => blake3::derive_key(CONTEXT, k.as_bytes()),
+        Err(_) => { init_signer(); blake3::derive_key(CONTEXT, &signing_key().to_bytes()) }
+    }
+}
+
+/// The engine key itself, for documents sealed with `engine_auth::signing` (e.g. the registry index).
+pub fn signing_key() -> SigningKey {
+    let guard = SIGNER.lock().unwrap();
+    guard.as_ref().expect("Signer not initialized. Call init_signer().").clone()
+}
@@ -0,0 +1,326 @@
+//! Tenants: who a request belongs to, and the resources only that tenant can reach.
+//!
+//! The tenant comes from the request's bearer token, never from its body or path. Every tenant
+//! owns a directory tree under the tenants root (`units/`, `registry/`, `receipts/`, `audit/`,
+//! `keys/`, `context/`), and a [`Tenant`] only holds handles into its own tree: a handler that got its
+//! `Tenant` from [`Tenants::for_request`] has no way to name another tenant's units, registry
+//! entries, receipts or key.
+//!
+//! A token may also name an actor inside the tenant (`tenants.<id>.actors`); the actor's id, role and
+//! per-actor quota are what [`crate::enrich`] hands policies under `$server`.
+use anyhow::{anyhow, bail, Context, Result};
+use axum::http::HeaderMap;
+use ed25519_dalek::SigningKey;
+use engine_audit::review::{ReviewQueue, Reviewers};
+use engine_audit::sink_fs::FsAudit;
+use engine_loader::UnitStore;
+use engine_registry::cas::Cas;
+use engine_registry::file_registry::FileRegistry;
+use engine_registry::fs_registry::FsRegistry;
+use tdln_sirp::outbox::{Backoff, Outbox};
+use tdln_sirp::store::SirpStore;
+use once_cell::sync::Lazy;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::asks::AskStore;
+use crate::enrich::{ActorFacts, QuotaFacts, ServerFacts};
+use crate::quota::{Hold, Quota, QuotaStore};
+
+/// Tenant of anonymous requests when the directory allows them (and of everything when there is no directory).
+pub const PUBLIC: &str = "_public";
+
+/// Validated tenant name: `[a-z0-9][a-z0-9_-]{0,62}`, or [`PUBLIC`]. Safe to use as a path component.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct TenantId(String);
+impl TenantId {
+    pub fn parse(s:&str) -> Result<Self> {
+        let ok = s == PUBLIC || (!s.is_empty() && s.len() <= 63
+            && s.bytes().next().is_some_and(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+            && s.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_' || b == b'-'));
+        if !ok { bail!("invalid tenant id {s:?}"); }
+        Ok(Self(s.into()))
+    }
+    pub fn public() -> Self { Self(PUBLIC.into()) }
+    pub fn as_str(&self) -> &str { &self.0 }
+}
+impl std::fmt::Display for TenantId {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(&self.0) }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenantSpec {
+    /// `b3:<hex>` of each bearer token; the tokens themselves are never stored.
+    pub tokens: Vec<String>,
+    #[serde(default)]
+    pub quota: Quota,
+    /// Actor id → credentials, role and run quota of one caller within the tenant.
+    #[serde(default)]
+    pub actors: BTreeMap<String, ActorSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActorSpec {
+    /// `b3:<hex>` of each bearer token, like [`TenantSpec::tokens`].
+    pub tokens: Vec<String>,
+    #[serde(default)]
+    pub role: Option<String>,
+    /// Runs this actor may get `ACK`ed; defaults to the tenant's quota.
+    #[serde(default)]
+    pub quota: Option<Quota>,
+}
+
+/// Who is calling, as established by the server (never by the request body).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub tenant: TenantId,
+    /// Set when the token belongs to an entry of `tenants.<id>.actors`.
+    pub actor: Option<String>,
+    pub role: Option<String>,
+    /// No bearer token (only possible with `"anonymous": true`).
+    pub anonymous: bool,
+}
+impl Identity {
+    fn public() -> Self { Self{ tenant: TenantId::public(), actor: None, role: None, anonymous: true } }
+    /// `tenant` or `tenant/actor`, for audit records.
+    pub fn label(&self) -> String {
+        match &self.actor { Some(a) => format!("{}/{a}", self.tenant), None => self.tenant.to_string() }
+    }
+}
+
+#[derive(Debug)]
+pub enum AuthError { Missing, Unknown }
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self { Self::Missing => f.write_str("bearer token required"), Self::Unknown => f.write_str("unknown bearer token") }
+    }
+}
+impl std::error::Error for AuthError {}
+
+#[derive(Debug, Deserialize)]
+struct DirectoryFile {
+    kind: String,                          // "engine.tenants.v1"
+    /// Requests without a token run as [`PUBLIC`].
+    #[serde(default)]
+    anonymous: bool,
+    #[serde(default)]
+    public_quota: Quota,
+    tenants: BTreeMap<String, TenantSpec>,
+}
+
+/// Token → tenant mapping plus where tenant trees live.
+#[derive(Debug, Clone)]
+pub struct Directory {
+    root: PathBuf,
+    anonymous: bool,
+    specs: BTreeMap<TenantId, TenantSpec>,
+    by_token: HashMap<String, Identity>,
+}
+
+pub fn token_hash(token:&str) -> String { format!("b3:{}", blake3::hash(token.as_bytes()).to_hex()) }
+
+impl Directory {
+    /// No tenants file: single-tenant mode, every request is [`PUBLIC`].
+    pub fn open(root:impl Into<PathBuf>) -> Self {
+        let specs = BTreeMap::from([(TenantId::public(), TenantSpec{ tokens: vec![], quota: Quota::default(), actors: BTreeMap::new() })]);
+        Self{ root: root.into(), anonymous: true, specs, by_token: HashMap::new() }
+    }
+
+    pub fn load(path:impl AsRef<Path>, root:impl Into<PathBuf>) -> Result<Self> {
+        let p = path.as_ref();
+        let f: DirectoryFile = serde_json::from_slice(&std::fs::read(p).with_context(|| format!("read {}", p.display()))?)?;
+        if f.kind != "engine.tenants.v1" { bail!("{} is not an engine.tenants.v1 document", p.display()); }
+        let mut specs = BTreeMap::new();
+        let mut by_token = HashMap::new();
+        for (name, spec) in f.tenants {
+            let id = TenantId::parse(&name)?;
+            if id.as_str() == PUBLIC { bail!("{PUBLIC} is reserved; use \"anonymous\": true"); }
+            let actors = spec.actors.iter().flat_map(|(a, s)| s.tokens.iter().map(move |t| (t, Some(a.clone()), s.role.clone())));
+            for (t, actor, role) in spec.tokens.iter().map(|t| (t, None, None)).chain(actors) {
+                let who = Identity{ tenant: id.clone(), actor, role, anonymous: false };
+                if by_token.insert(t.clone(), who).is_some() { bail!("token {t} is assigned more than once"); }
+            }
+            specs.insert(id, spec);
+        }
+        if f.anonymous { specs.insert(TenantId::public(), TenantSpec{ tokens: vec![], quota: f.public_quota, actors: BTreeMap::new() }); }
+        Ok(Self{ root: root.into(), anonymous: f.anonymous, specs, by_token })
+    }
+
+    /// `ENGINE_TENANTS` (tenants file; unset = single-tenant) and `ENGINE_TENANTS_ROOT` (default `./tenants`).
+    pub fn from_env() -> Result<Self> {
+        let root = std::env::var("ENGINE_TENANTS_ROOT").unwrap_or_else(|_| "./tenants".into());
+        match std::env::var("ENGINE_TENANTS") { Ok(p) => Self::load(p, root), Err(_) => Ok(Self::open(root)) }
+    }
+
+    /// Process-wide directory for handlers without router state (audit paths in `lib.rs`).
+    pub fn global() -> &'static Directory {
+        static DIR: Lazy<Directory> = Lazy::new(|| Directory::from_env().expect("ENGINE_TENANTS"));
+        &DIR
+    }
+
+    pub fn authenticate(&self, headers:&HeaderMap) -> Result<Identity, AuthError> {
+        let token = headers.get("authorization").and_then(|v| v.to_str().ok()).and_then(|v| v.strip_prefix("Bearer "));
+        match token {
+            Some(t) => self.by_token.get(&token_hash(t)).cloned().ok_or(AuthError::Unknown),
+            None if self.anonymous => Ok(Identity::public()),
+            None => Err(AuthError::Missing),
+        }
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item=&TenantId> { self.specs.keys() }
+    pub fn spec(&self, id:&TenantId) -> Option<&TenantSpec> { self.specs.get(id) }
+    pub fn dir(&self, id:&TenantId) -> PathBuf { self.root.join(id.as_str()) }
+    /// Base dir for `audit.report.v1` files (written under `<dir>/audit/`).
+    pub fn audit_dir(&self, id:&TenantId) -> PathBuf { self.dir(id) }
+}
+
+/// Where one tenant's state lives.
+#[derive(Debug, Clone)]
+pub struct TenantPaths {
+    pub units: PathBuf, pub registry: PathBuf, pub receipts: PathBuf, pub audit: PathBuf, pub key: PathBuf,
+    /// Lookup tables (`<table>.json` / `<table>.csv`) for the `lookup` context provider.
+    pub context: PathBuf,
+    /// `review.reviewers.v1` of the keys that may decide this tenant's reviews; optional.
+    pub reviewers: PathBuf,
+}
+impl TenantPaths {
+    pub fn under(dir:&Path) -> Self {
+        Self{ units: dir.join("units"), registry: dir.join("registry"), receipts: dir.join("receipts"), audit: dir.to_path_buf(), key: dir.join("keys/ed25519.seed"), context: dir.join("context"),
+            reviewers: dir.join("reviewers.json") }
+    }
+    /// Object store behind the registry. Specs published with `/registry/put {spec}` live in `{registry}/.cas`
+    /// (dot-names are never packages).
+    pub fn cas(&self) -> Cas<FsRegistry> { Cas::new(FsRegistry::new(&self.registry), ".cas") }
+}
+
+/// A new seed is created owner-only (`0600`) and never over an existing one.
+fn load_or_create_key(path:&Path) -> Result<SigningKey> {
+    let load = || -> Result<SigningKey> {
+        let seed: [u8; 32] = std::fs::read(path)?.try_into().map_err(|_| anyhow!("{}: seed must be 32 bytes", path.display()))?;
+        Ok(SigningKey::from_bytes(&seed))
+    };
+    if path.exists() { return load(); }
+    std::fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    let mut open = std::fs::OpenOptions::new();
+    open.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut open, 0o600);
+    match open.open(path) {
+        Ok(mut f) => {
+            let seed: [u8; 32] = rand::random();
+            std::io::Write::write_all(&mut f, &seed)?;
+            f.sync_all()?;
+            Ok(SigningKey::from_bytes(&seed))
+        }
+        // Another process created it first: use that one.
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => load(),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Everything a request may touch, scoped to one tenant.
+pub struct Tenant<E> {
+    pub id: TenantId,
+    pub paths: TenantPaths,
+    pub engine: E,
+    pub units: UnitStore,
+    pub reg: FileRegistry,
+    /// Object store behind the registry; holds published unit specs by CID.
+    pub cas: Arc<Cas<FsRegistry>>,
+    /// Open ASKs (`receipts/asks/`) that `/run/resume` can answer.
+    pub asks: AskStore,
+    /// ASK runs waiting for a reviewer (`receipts/reviews/`); queue actions are audited under `audit/`.
+    pub reviews: ReviewQueue,
+    /// Who may decide this tenant's reviews; `None` when the tenant has no reviewers file.
+    pub reviewers: Option<Reviewers>,
+    /// Held while an ask is resolved by `/run/resume` or decided by a reviewer, so only one of them closes it.
+    pub settle: Mutex<()>,
+    /// SIRP capsules and receipts of this tenant's runs (`receipts/sirp/`), by CID.
+    pub sirp: SirpStore,
+    /// Intents this tenant sent to peer engines (`receipts/sirp/outbox/`), until answered or dead-lettered.
+    pub outbox: Outbox,
+    key: SigningKey,
+    quota: Quota,
+    actors: BTreeMap<String, ActorSpec>,
+    /// Per-actor run quota; spent only on `ACK`.
+    actor_runs: QuotaStore,
+}
+
+impl<E> Tenant<E> {
+    /// Signs this tenant's documents (registry index, ...).
+    pub fn signing_key(&self) -> &SigningKey { &self.key }
+    /// DID the tenant issues SIRP intents as; its key is [`Tenant::signing_key`].
+    pub fn did(&self) -> String { format!("did:tdln:tenant:{}", self.id) }
+
+    fn actor_quota<'a>(&self, who:&'a Identity) -> (&'a str, Quota) {
+        // Tenant-wide tokens and anonymous callers share one bucket.
+        match &who.actor {
+            Some(a) => (a, self.actors.get(a).and_then(|s| s.quota).unwrap_or(self.quota)),
+            None => ("*", self.quota),
+        }
+    }
+    /// Reserve one of `who`'s runs; commit it when the decision is `ACK`.
+    pub fn hold_run(&self, who:&Identity) -> Hold<'_> {
+        let (key, q) = self.actor_quota(who);
+        self.actor_runs.hold(key, q)
+    }
+    /// What the server vouches for about this request, for `$server`.
+    pub fn server_facts(&self, who:&Identity, hold:&Hold<'_>) -> ServerFacts {
+        ServerFacts{
+            tenant: self.id.to_string(),
+            actor: ActorFacts{ id: who.actor.clone(), role: who.role.clone(), authenticated: !who.anonymous },
+            quota: QuotaFacts{ remaining: hold.remaining(), per_minute: self.actor_quota(who).1.per_minute },
+        }
+    }
+}
+
+/// All tenants, reachable only through a request's credentials.
+pub struct Tenants<E> { dir: Directory, by_id: HashMap<TenantId, Arc<Tenant<E>>> }
+
+impl<E> Tenants<E> {
+    /// `public` overrides the [`PUBLIC`] tenant's layout (single-tenant deployments keep their `outdir`/`regdir`).
+    pub fn build(dir:Directory, public:Option<TenantPaths>, mut make_engine:impl FnMut(&TenantId, &TenantPaths) -> E) -> Result<Self> {
+        let mut by_id = HashMap::new();
+        for id in dir.ids() {
+            let paths = match (&public, id.as_str() == PUBLIC) {
+                (Some(p), true) => p.clone(),
+                _ => TenantPaths::under(&dir.dir(id)),
+            };
+            let quota = dir.spec(id).map(|s| s.quota).unwrap_or_default();
+            let actors = dir.spec(id).map(|s| s.actors.clone()).unwrap_or_default();
+            let cas = Arc::new(paths.cas());
+            let reg = FileRegistry::new(&paths.registry);
+            let units = UnitStore::new(&paths.units).with_registry(reg.clone(), cas.clone());
+            let key = load_or_create_key(&paths.key).with_context(|| format!("tenant {id} key"))?;
+            let asks = AskStore::new(paths.receipts.join("asks"));
+            let reviews = ReviewQueue::new(paths.receipts.join("reviews"), FsAudit::new(paths.audit.join("audit")));
+            let reviewers = paths.reviewers.exists().then(|| Reviewers::load(&paths.reviewers)).transpose()
+                .with_context(|| format!("tenant {id} reviewers"))?;
+            let sirp = SirpStore::new(paths.receipts.join("sirp"));
+            let outbox = Outbox::new(paths.receipts.join("sirp").join("outbox"), Backoff::default());
+            let engine = make_engine(id, &paths);
+            by_id.insert(id.clone(), Arc::new(Tenant{ id: id.clone(), paths, engine, units, reg, cas, asks, reviews, reviewers, settle: Mutex::new(()), sirp, outbox, key,
+                quota, actors, actor_runs: QuotaStore::default() }));
+        }
+        Ok(Self{ dir, by_id })
+    }
+
+    pub fn for_request(&self, headers:&HeaderMap) -> Result<Arc<Tenant<E>>, AuthError> {
+        self.identify(headers).map(|(t, _)| t)
+    }
+
+    /// The caller's tenant plus who within it is calling.
+    pub fn identify(&self, headers:&HeaderMap) -> Result<(Arc<Tenant<E>>, Identity), AuthError> {
+        let who = self.dir.authenticate(headers)?;
+        let t = self.by_id.get(&who.tenant).cloned().ok_or(AuthError::Unknown)?;
+        Ok((t, who))
+    }
+
+    /// A tenant chosen by configuration rather than a token: the one `/sirp/inbox` runs peers' intents under.
+    pub fn get(&self, id:&TenantId) -> Option<Arc<Tenant<E>>> { self.by_id.get(id).cloned() }
+
+    /// Startup wiring (watchers, metrics, the SIRP outbox worker) only; request handlers go through [`Tenants::for_request`].
+    pub fn all(&self) -> impl Iterator<Item=&Arc<Tenant<E>>> { self.by_id.values() }
+}
@@ -0,0 +1,45 @@
+use engine_core::model::ProofOfIndecision;
+use engine_http::asks::{merge_answers, AskRecord, AskStore};
+use engine_http::tenant::{Identity, TenantId};
+use serde_json::json;
+
+#[test]
+fn an_ask_is_answered_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = AskStore::new(dir.path());
+    let run = format!("b3:{}", "a".repeat(64));
+    let rec = AskRecord::new(&run, "allow_admin_quota", "trust", json!({"resource": {"restricted": false}}), ProofOfIndecision::missing(&["actor.role"]), "b3:in", "acme/alice");
+    assert!(store.create(&rec).unwrap());
+    assert_eq!(store.get(&run).unwrap().unwrap().poi.missing, vec!["actor.role".to_string()]);
+    assert!(store.get(&format!("b3:{}", "b".repeat(64))).unwrap().is_none());
+    assert!(store.get("../etc/passwd").is_err());
+
+    // Concurrent resolves: exactly one wins.
+    let wins = std::thread::scope(|s| {
+        let hs: Vec<_> = (0..8).map(|i| { let store = &store; let run = &run; s.spawn(move || store.resolve(run, &format!("b3:next-{i}")).is_ok()) }).collect();
+        hs.into_iter().map(|h| h.join().unwrap()).filter(|ok| *ok).count()
+    });
+    assert_eq!(wins, 1);
+    assert!(store.get(&run).unwrap().unwrap().resolved_by.unwrap().starts_with("b3:next-"));
+    assert!(store.resolve(&run, "b3:other").is_err());
+
+    // Re-running the same manifest keeps the answered record.
+    assert!(!store.create(&rec).unwrap());
+    assert!(store.get(&run).unwrap().unwrap().resolved_by.is_some());
+}
+
+#[test]
+fn only_the_asker_or_a_reviewer_resumes() {
+    let rec = AskRecord::new(&format!("b3:{}", "c".repeat(64)), "u", "trust", json!({}), ProofOfIndecision::missing(&["x"]), "b3:in", "acme/alice");
+    let who = |actor:&str, role:Option<&str>| Identity{ tenant: TenantId::parse("acme").unwrap(), actor: Some(actor.into()), role: role.map(Into::into), anonymous: false };
+    assert!(rec.may_resume(&who("alice", None)));
+    assert!(!rec.may_resume(&who("bob", Some("admin"))));
+    assert!(rec.may_resume(&who("carol", Some("reviewer"))));
+}
+
+#[test]
+fn answers_merge_into_the_original_input() {
+    let mut input = json!({"actor": {"id": "alice"}, "resource": {"restricted": false}});
+    merge_answers(&mut input, json!({"actor": {"role": "admin"}, "resource": {"restricted": true}}));
+    assert_eq!(input, json!({"actor": {"id": "alice", "role": "admin"}, "resource": {"restricted": true}}));
+}
@@ -0,0 +1,52 @@
+use engine_http::presign::StubPresigner;
+use engine_http::server::build_router;
+use engine_http::tenant::token_hash;
+use serde_json::{json, Value};
+
+async fn run(http: &reqwest::Client, url: &str, token: &str) -> (u16, Value) {
+    let r = http.post(format!("{url}/run")).bearer_auth(token)
+        .json(&json!({"unit_ref": "allow_admin_quota", "input": {"resource": {"restricted": false}}}))
+        .send().await.unwrap();
+    (r.status().as_u16(), r.json().await.unwrap())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn allow_admin_quota_acks_for_an_admin_actor_and_spends_one_run() {
+    let unit = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/../units/allow_admin_quota.json")).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_current_dir(dir.path()).unwrap();
+    std::fs::create_dir_all("tenants/acme/units").unwrap();
+    std::fs::write("tenants/acme/units/allow_admin_quota.json", unit).unwrap();
+    let doc = json!({
+        "kind": "engine.tenants.v1",
+        "tenants": { "acme": {
+            "tokens": [token_hash("acme-ci")],
+            "actors": { "alice": { "tokens": [token_hash("alice-secret")], "role": "admin", "quota": { "per_minute": 2 } } }
+        } }
+    });
+    std::fs::write("tenants.json", doc.to_string()).unwrap();
+    std::env::set_var("ENGINE_TENANTS", "tenants.json");
+    let l = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", l.local_addr().unwrap());
+    let app = build_router("out", "registry", 1, StubPresigner).await.unwrap();
+    tokio::spawn(async move { axum::serve(l, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await.unwrap() });
+    let http = reqwest::Client::new();
+    // The tenant's units are loaded by the watcher spawned in build_router.
+    for _ in 0..50 {
+        if run(&http, &url, "acme-ci").await.0 != 404 { break; }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    // A tenant-wide token has no role: NACK, and nothing is spent.
+    let (_, ci) = run(&http, &url, "acme-ci").await;
+    assert_eq!(ci["card"]["decision"], json!("NACK"), "{ci:#}");
+
+    // Two runs a minute: each ACK spends exactly one, then 429.
+    for _ in 0..2 {
+        let (status, ok) = run(&http, &url, "alice-secret").await;
+        assert_eq!((status, &ok["card"]["decision"]), (200, &json!("ACK")), "{ok:#}");
+    }
+    let (status, over) = run(&http, &url, "alice-secret").await;
+    assert_eq!(status, 429, "{over:#}");
+    assert!(over["error"].as_str().unwrap().contains("alice"));
+}
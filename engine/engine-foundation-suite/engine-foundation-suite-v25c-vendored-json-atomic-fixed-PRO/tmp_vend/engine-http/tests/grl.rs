@@ -0,0 +1,76 @@
+use axum::{extract::Query, routing::get, Json, Router};
+use base64::Engine as _;
+use ed25519_dalek::SigningKey;
+use engine_auth::grl::Grl;
+use engine_http::{engine_router, EngineHttpConfig};
+use reqwest::{header, StatusCode};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+async fn serve(app: Router) -> String {
+    let l = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", l.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(l, app).await.unwrap() });
+    url
+}
+
+#[tokio::test]
+async fn the_list_is_served_with_etags_and_pulled_by_delta() {
+    // `revoked_grants/` lives in the working directory.
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_current_dir(dir.path()).unwrap();
+
+    // Upstream: another issuer's list, served as deltas; remembers the `since` of every pull.
+    let up_key = SigningKey::from_bytes(&[9; 32]);
+    let up = Arc::new(Mutex::new(Grl::default()));
+    for g in ["up-1", "up-2"] { up.lock().unwrap().revoke(g, None); }
+    let pulls = Arc::new(Mutex::new(Vec::new()));
+    let upstream = {
+        let (up, pulls, key) = (up.clone(), pulls.clone(), up_key.clone());
+        Router::new().route("/grl.json", get(move |Query(q): Query<HashMap<String, u64>>| async move {
+            let since = q.get("since").copied().unwrap_or(0);
+            pulls.lock().unwrap().push(since);
+            let mut d = up.lock().unwrap().delta(since);
+            d.sign(&key).unwrap();
+            Json(d)
+        }))
+    };
+    let b64 = base64::engine::general_purpose::STANDARD;
+    std::env::set_var("GRL_REMOTE_URL", format!("{}/grl.json", serve(upstream).await));
+    std::env::set_var("GRL_TRUSTED_KEYS", b64.encode(up_key.verifying_key().as_bytes()));
+    std::env::set_var("GRL_TTL_MS", "0");
+    std::env::set_var("ENGINE_SIGNING_KEY_ED25519", b64.encode([1u8; 32]));
+    std::env::set_var("ENGINE_ADMIN_TOKEN", "adm");
+    let engine = serve(engine_router(EngineHttpConfig{ enable_metrics: false }).unwrap()).await;
+    let http = reqwest::Client::new();
+
+    let revoke = http.post(format!("{engine}/grl/revoke")).json(&serde_json::json!({"grant_id": "local-1"}));
+    assert_eq!(revoke.try_clone().unwrap().send().await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    let local: Grl = revoke.header("x-admin-token", "adm").send().await.unwrap().json().await.unwrap();
+    assert_eq!(local.serial, 1);
+
+    let list_url = format!("{engine}/.well-known/logline/grl.json");
+    let r = http.get(&list_url).send().await.unwrap();
+    let etag = r.headers()[header::ETAG].clone();
+    let list: Grl = r.json().await.unwrap();
+    let ids = |l:&Grl| l.entries.iter().map(|e| e.grant_id.clone()).collect::<Vec<_>>();
+    assert_eq!(ids(&list), ["local-1", "up-1", "up-2"]);
+
+    // Nothing new upstream: the next pull asks for the delta after serial 2, and the list is unchanged.
+    let r = http.get(&list_url).header(header::IF_NONE_MATCH, etag.clone()).send().await.unwrap();
+    assert_eq!(r.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(*pulls.lock().unwrap(), [0, 2]);
+
+    up.lock().unwrap().revoke("up-3", Some("leaked".into()));
+    let r = http.get(&list_url).header(header::IF_NONE_MATCH, etag.clone()).send().await.unwrap();
+    assert_eq!(r.status(), StatusCode::OK);
+    assert_ne!(r.headers()[header::ETAG], etag);
+    assert_eq!(pulls.lock().unwrap().last(), Some(&2));
+    let delta: Grl = http.get(format!("{list_url}?since={}", list.serial)).send().await.unwrap().json().await.unwrap();
+    assert_eq!((delta.since, ids(&delta)), (3, vec!["up-3".to_string()]));
+    delta.verify(&[SigningKey::from_bytes(&[1; 32]).verifying_key()]).unwrap();
+
+    // The remote's issuer survives a restart, so the next process resumes with a delta.
+    let remote: serde_json::Value = serde_json::from_slice(&std::fs::read("revoked_grants/grl.remote.json").unwrap()).unwrap();
+    assert_eq!(remote["issuer"], engine_auth::signing::key_id(&up_key.verifying_key()));
+}
@@ -0,0 +1,62 @@
+use engine_http::{presign_fs, tenant::TenantId};
+use engine_registry::url_signer::UrlSigner;
+use reqwest::{StatusCode, header};
+
+#[tokio::test]
+async fn fs_urls_are_presigned_then_fetched() {
+    let root = tempfile::tempdir().unwrap();
+    // `revoked_grants/` lives in the working directory.
+    std::env::set_current_dir(root.path()).unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/fs", listener.local_addr().unwrap());
+    // Read once, by the registry's first use below.
+    std::env::set_var("FS_REGISTRY_ROOT", root.path());
+    std::env::set_var("FS_PRESIGN_BASE_URL", &base);
+    std::env::set_var("FS_PRESIGN_KEY", "test-key");
+    tokio::spawn(async move { axum::serve(listener, presign_fs::router::<()>()).await.unwrap() });
+
+    let alice = TenantId::parse("alice").unwrap();
+    let reg = presign_fs::registry(&alice);
+    let http = reqwest::Client::new();
+
+    let put = reg.presign("PUT", "b", "dir/obj.bin", 60, Some(16), Some("g-put"));
+    assert!(put.starts_with(&format!("{base}/alice/b/dir/obj.bin?")));
+    assert_eq!(http.put(&put).body(vec![0u8; 17]).send().await.unwrap().status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(http.put(&put).body((0u8..16).collect::<Vec<_>>()).send().await.unwrap().status(), StatusCode::CREATED);
+    assert_eq!(http.get(&put).send().await.unwrap().status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert!(root.path().join("alice/b/dir/obj.bin").is_file());
+
+    let get = reg.presign("GET", "b", "dir/obj.bin", 60, None, Some("g-get"));
+    let r = http.get(&get).send().await.unwrap();
+    assert_eq!(r.status(), StatusCode::OK);
+    assert_eq!(r.bytes().await.unwrap().len(), 16);
+    let r = http.get(&get).header(header::RANGE, "bytes=4-7").send().await.unwrap();
+    assert_eq!(r.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(r.headers()[header::CONTENT_RANGE], "bytes 4-7/16");
+    assert_eq!(&r.bytes().await.unwrap()[..], &[4, 5, 6, 7]);
+    assert_eq!(http.get(&get).header(header::RANGE, "bytes=0-1,4-5").send().await.unwrap().status(), StatusCode::RANGE_NOT_SATISFIABLE);
+
+    let capped = reg.presign("GET", "b", "dir/obj.bin", 60, Some(8), Some("g-capped"));
+    assert_eq!(http.get(&capped).send().await.unwrap().status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(http.get(&capped).header(header::RANGE, "bytes=0-7").send().await.unwrap().status(), StatusCode::PARTIAL_CONTENT);
+
+    // Expired, tampered, grant-less, or another tenant's: all 403.
+    assert_eq!(http.get(reg.presign("GET", "b", "dir/obj.bin", 60, None, None)).send().await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert_eq!(http.get(get.replace("grant=g-get", "grant=g-put")).send().await.unwrap().status(), StatusCode::FORBIDDEN);
+    let expired = UrlSigner::new(b"test-key".to_vec(), base.clone()).scoped("alice").presign("GET", "b", "dir/obj.bin", 1_000, 60, None);
+    assert_eq!(http.get(&expired).send().await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert_eq!(http.get(get.replace("verb=GET", "verb=PUT")).send().await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert_eq!(http.get(get.replace("/alice/", "/bob/")).send().await.unwrap().status(), StatusCode::FORBIDDEN);
+
+    // Revoking the grant kills its URL on the next request; the others keep working.
+    engine_http::grl::revoke("g-get", None).unwrap();
+    assert_eq!(http.get(&get).send().await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert_eq!(http.get(&capped).header(header::RANGE, "bytes=0-7").send().await.unwrap().status(), StatusCode::PARTIAL_CONTENT);
+
+    // Keys must stay inside their bucket, whatever the signature says.
+    for key in ["%2Fetc%2Fpasswd", "dir/..%2F..%2Fsecret", "dir//obj.bin"] {
+        let r = http.get(format!("{base}/alice/b/{key}?verb=GET&exp=9999999999&sig=00")).send().await.unwrap();
+        assert_eq!(r.status(), StatusCode::BAD_REQUEST, "{key}");
+    }
+    assert_eq!(http.get(format!("{base}/..%2F/b/obj?verb=GET&exp=9999999999&sig=00")).send().await.unwrap().status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,66 @@
+use engine_http::presign::{StubPresigner, PRESIGN_TTL_MAX_SECS};
+use engine_http::server::build_router;
+use serde_json::{json, Value};
+
+async fn presign(http: &reqwest::Client, url: &str, change: Value) -> Value {
+    let mut req = json!({"backend": "fs", "bucket": "b", "key": "dir/obj.json", "verb": "GET", "ttl_secs": 60});
+    for (k, v) in change.as_object().unwrap() { req[k] = v.clone(); }
+    http.post(format!("{url}/registry/presign")).json(&req).send().await.unwrap().json().await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn presign_runs_the_policy_unit_and_the_grant_links_its_receipt() {
+    let unit = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/../units/presign.policy.v1.json")).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_current_dir(dir.path()).unwrap();
+    std::fs::create_dir_all("units").unwrap();
+    std::fs::write("units/presign.policy.v1.json", unit).unwrap();
+    std::fs::write("units/closed.json",
+        r#"{"id":"presign.closed","policies":[{"id":"closed","condition":{"kind":"literal","value":false}}],"wiring":{"type":"all","policies":["closed"]}}"#).unwrap();
+    std::env::set_var("UNITS_DIR", "units");
+    std::env::set_var("FS_REGISTRY_ROOT", "objects");
+    std::env::set_var("FS_PRESIGN_KEY", "test-key");
+    let l = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", l.local_addr().unwrap());
+    let app = build_router("out", "registry", 1, StubPresigner).await.unwrap();
+    tokio::spawn(async move { axum::serve(l, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await.unwrap() });
+    let http = reqwest::Client::new();
+
+    // ACK: the grant names the unit and the receipt the engine sealed in the run's SIRP chain.
+    let ok = presign(&http, &url, json!({})).await;
+    assert!(!ok["url"].as_str().unwrap().is_empty(), "{ok:#}");
+    let link = &ok["meta"]["grant"]["policy"];
+    assert_eq!(link["unit"], json!("presign.policy.v1"));
+    let refs = ok["meta"]["policy"]["refs"].as_array().unwrap();
+    let execution = refs.iter().find(|r| r["kind"] == json!(tdln_sirp::EXECUTION)).unwrap();
+    let execution: Value = http.get(format!("{url}/sirp/{}", execution["cid"].as_str().unwrap())).send().await.unwrap().json().await.unwrap();
+    assert_eq!(execution["result_cid"], link["receipt_cid"]);
+
+    // Limits the caller can adjust: ASK with hints, no URL.
+    let ask = presign(&http, &url, json!({"ttl_secs": 9999, "backend": "gcs"})).await;
+    assert_eq!((ask["url"].as_str(), ask["meta"]["decision"].as_str()), (Some(""), Some("ASK")), "{ask:#}");
+    assert_eq!(ask["meta"]["poi"]["violations"], json!(["ttl_max", "backend_allowed"]));
+    assert_eq!(ask["meta"]["poi"]["hints"], json!(["use ttl_secs <= 600", "use backend 's3' or 'fs'"]));
+
+    // Huge TTLs are clamped, not overflowed: the unit sees the ceiling and asks for less.
+    let huge = presign(&http, &url, json!({"ttl_secs": u64::MAX})).await;
+    assert_eq!((huge["meta"]["decision"].as_str(), &huge["meta"]["poi"]["violations"]), (Some("ASK"), &json!(["ttl_max"])), "{huge:#}");
+    // The policy-less route only reaches the configured presigner (here the stub), never the fs store.
+    let r = http.post(format!("{url}/acquire_presigned_url"))
+        .json(&json!({"actor": "a", "resource": {"store": "fs", "bucket": "b", "prefix": "", "object": "o", "verb": "PUT"}, "ttl_seconds": u64::MAX}))
+        .send().await.unwrap();
+    assert_eq!(r.status(), 200);
+    assert!(r.json::<Value>().await.unwrap()["url"].as_str().unwrap().starts_with(&format!("stub://b//o?verb=PUT&exp={PRESIGN_TTL_MAX_SECS}")));
+
+    // Anything else is NACK, including absolute keys and traversal.
+    for (change, policy) in [(json!({"key": "/etc/passwd"}), "key_no_traversal"), (json!({"key": "a/../b"}), "key_no_traversal"), (json!({"verb": "DELETE"}), "verb_allowed")] {
+        let nack = presign(&http, &url, change).await;
+        assert_eq!(nack["meta"]["decision"], json!("NACK"), "{nack:#}");
+        assert_eq!(nack["meta"]["violations"], json!([policy]));
+    }
+
+    // The decision is the unit's: another unit, another answer.
+    std::env::set_var("PRESIGN_POLICY_UNIT", "presign.closed");
+    let closed = presign(&http, &url, json!({})).await;
+    assert_eq!((closed["meta"]["decision"].as_str(), &closed["meta"]["violations"]), (Some("NACK"), &json!(["closed"])));
+}
@@ -0,0 +1,65 @@
+use axum::http::HeaderMap;
+use engine_http::enrich::{enrich, EnrichError, SERVER_NS};
+use engine_http::quota::{Quota, QuotaStore};
+use engine_http::tenant::{token_hash, Directory, Tenants};
+
+fn bearer(token: &str) -> HeaderMap {
+    let mut h = HeaderMap::new();
+    h.insert("authorization", format!("Bearer {token}").parse().unwrap());
+    h
+}
+
+fn setup() -> (tempfile::TempDir, Tenants<()>) {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let doc = serde_json::json!({
+        "kind": "engine.tenants.v1",
+        "tenants": {
+            "acme": {
+                "tokens": [token_hash("acme-ci")],
+                "actors": {
+                    "alice": { "tokens": [token_hash("alice-secret")], "role": "admin", "quota": { "per_minute": 1 } }
+                }
+            }
+        }
+    });
+    std::fs::write(root.join("tenants.json"), doc.to_string()).unwrap();
+    let tenants = Tenants::build(Directory::load(root.join("tenants.json"), root).unwrap(), None, |_, _| ()).unwrap();
+    (dir, tenants)
+}
+
+#[test]
+fn server_facts_come_from_the_token_not_the_input() {
+    let (_dir, tenants) = setup();
+    let (t, who) = tenants.identify(&bearer("alice-secret")).unwrap();
+    assert_eq!((who.actor.as_deref(), who.role.as_deref()), (Some("alice"), Some("admin")));
+    let hold = t.hold_run(&who);
+    let input = serde_json::json!({ "actor": { "role": "admin", "quota": 99 } });
+    let v = enrich(input, &t.server_facts(&who, &hold)).unwrap();
+    assert_eq!(v[SERVER_NS]["actor"]["id"], "alice");
+    assert_eq!(v[SERVER_NS]["quota"]["remaining"], 1);
+    assert_eq!(v["actor"]["quota"], 99);
+
+    let (t, ci) = tenants.identify(&bearer("acme-ci")).unwrap();
+    let v = enrich(serde_json::Value::Null, &t.server_facts(&ci, &t.hold_run(&ci))).unwrap();
+    assert!(v[SERVER_NS]["actor"]["role"].is_null());
+
+    let spoofed = serde_json::json!({ "$server": { "actor": { "role": "admin" } } });
+    assert!(matches!(enrich(spoofed, &t.server_facts(&ci, &hold)), Err(EnrichError::Reserved)));
+    assert!(matches!(enrich(serde_json::json!([1]), &t.server_facts(&ci, &hold)), Err(EnrichError::NotObject)));
+}
+
+#[test]
+fn quota_is_spent_only_on_commit() {
+    let store = QuotaStore::default();
+    let q = Quota{ per_minute: 1, burst: None };
+    drop(store.hold("alice", q));
+    assert_eq!(store.remaining("alice", q), 1);
+    let first = store.hold("alice", q);
+    let second = store.hold("alice", q);
+    assert_eq!((first.remaining(), second.remaining()), (1, 0));
+    first.commit();
+    drop(second);
+    assert_eq!(store.remaining("alice", q), 0);
+    assert_eq!(store.remaining("bob", q), 1);
+}
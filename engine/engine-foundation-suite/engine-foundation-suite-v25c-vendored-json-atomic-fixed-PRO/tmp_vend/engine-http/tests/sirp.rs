@@ -0,0 +1,49 @@
+use base64::Engine as _;
+use ed25519_dalek::SigningKey;
+use engine_http::presign::StubPresigner;
+use engine_http::server::build_router;
+use serde_json::{json, Value};
+use tdln_sirp::{Capsule, DidKeys, Signed};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn intents_run_once_and_the_answer_lands_in_the_outbox() {
+    // Keys, units and receipts live in the working directory.
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_current_dir(dir.path()).unwrap();
+    std::fs::create_dir_all("units").unwrap();
+    std::fs::write("units/allow.json",
+        r#"{"id":"allow","policies":[{"id":"p","condition":{"kind":"literal","value":true}}],"wiring":{"type":"all","policies":["p"]}}"#).unwrap();
+
+    // The engine is its own peer: the DID it sends to is its own, served by its own inbox.
+    let l = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", l.local_addr().unwrap());
+    let did = "did:tdln:engine:self";
+    std::fs::write("dids.json", serde_json::to_vec(&DidKeys::default().at(did, &format!("{url}/sirp/inbox"))).unwrap()).unwrap();
+    let key = SigningKey::from_bytes(&[1; 32]);
+    std::env::set_var("ENGINE_SIGNING_KEY_ED25519", base64::engine::general_purpose::STANDARD.encode(key.to_bytes()));
+    std::env::set_var("ENGINE_DID", did);
+    std::env::set_var("UNITS_DIR", "units");
+    std::env::set_var("SIRP_DIDS", "dids.json");
+    let app = build_router("out", "registry", 1, StubPresigner).await.unwrap();
+    tokio::spawn(async move { axum::serve(l, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await.unwrap() });
+    let http = reqwest::Client::new();
+
+    let entry: Value = http.post(format!("{url}/sirp/send")).json(&json!({"to": did, "unit_ref": "allow", "input": {"amount": 1}}))
+        .send().await.unwrap().json().await.unwrap();
+    assert_eq!((entry["status"].as_str(), entry["attempts"].as_u64()), (Some("delivered"), Some(1)), "{entry:#}");
+    assert!(entry["chain"]["execution"]["result_cid"].as_str().unwrap().starts_with("b3:"));
+    let outbox = |status:&'static str| http.get(format!("{url}/sirp/outbox?status={status}")).send();
+    assert_eq!(outbox("delivered").await.unwrap().json::<Vec<Value>>().await.unwrap().len(), 1);
+    assert!(outbox("pending").await.unwrap().json::<Vec<Value>>().await.unwrap().is_empty());
+
+    // The same request sealed anew, delivered several times at once: it runs once and every delivery gets that answer.
+    let mut intent: Capsule = serde_json::from_value(entry["capsule"].clone()).unwrap();
+    intent.ts = chrono::Utc::now().to_rfc3339();
+    intent.sign(&key).unwrap();
+    let posts: Vec<_> = (0..8).map(|_| { let req = http.post(format!("{url}/sirp/inbox")).json(&intent); tokio::spawn(async move { req.send().await.unwrap().json::<Value>().await.unwrap() }) }).collect();
+    let mut replies = vec![];
+    for p in posts { replies.push(p.await.unwrap()); }
+    assert_eq!(replies[0]["receipt"]["decision"], json!("Allow"), "{:#}", replies[0]);
+    assert!(replies.iter().all(|r| *r == replies[0]));
+    assert_ne!(replies[0]["execution"], entry["chain"]["execution"]);
+}
@@ -0,0 +1,93 @@
+use axum::http::HeaderMap;
+use engine_http::tenant::{token_hash, AuthError, Directory, TenantId, Tenants};
+use engine_registry::schema::EngineRegistryEntry;
+
+fn bearer(token: &str) -> HeaderMap {
+    let mut h = HeaderMap::new();
+    h.insert("authorization", format!("Bearer {token}").parse().unwrap());
+    h
+}
+
+fn setup(anonymous: bool) -> (tempfile::TempDir, Tenants<()>) {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let doc = serde_json::json!({
+        "kind": "engine.tenants.v1",
+        "anonymous": anonymous,
+        "tenants": {
+            "acme": { "tokens": [token_hash("acme-secret")], "quota": { "per_minute": 2 } },
+            "globex": { "tokens": [token_hash("globex-secret")] }
+        }
+    });
+    std::fs::write(root.join("tenants.json"), doc.to_string()).unwrap();
+    let tenants = Tenants::build(Directory::load(root.join("tenants.json"), root).unwrap(), None, |_, _| ()).unwrap();
+    (dir, tenants)
+}
+
+#[test]
+fn credentials_pick_the_tenant() {
+    let (_dir, tenants) = setup(false);
+    assert_eq!(tenants.for_request(&bearer("acme-secret")).unwrap().id.as_str(), "acme");
+    assert_eq!(tenants.for_request(&bearer("globex-secret")).unwrap().id.as_str(), "globex");
+    assert!(matches!(tenants.for_request(&HeaderMap::new()), Err(AuthError::Missing)));
+    assert!(matches!(tenants.for_request(&bearer("acme")), Err(AuthError::Unknown)));
+
+    let (_dir, open) = setup(true);
+    assert_eq!(open.for_request(&HeaderMap::new()).unwrap().id.as_str(), "_public");
+}
+
+#[test]
+fn tenant_ids_are_path_safe() {
+    for bad in ["", "../acme", "acme/units", "ACME", "-acme", ".acme"] {
+        assert!(TenantId::parse(bad).is_err(), "{bad:?}");
+    }
+    assert!(TenantId::parse("acme-2_eu").is_ok());
+}
+
+#[test]
+fn units_registry_keys_and_quota_are_isolated() {
+    let (_dir, tenants) = setup(false);
+    let acme = tenants.for_request(&bearer("acme-secret")).unwrap();
+    let globex = tenants.for_request(&bearer("globex-secret")).unwrap();
+    assert_ne!(acme.paths.units, globex.paths.units);
+    assert_ne!(acme.signing_key().verifying_key(), globex.signing_key().verifying_key());
+    #[cfg(unix)]
+    assert_eq!(std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(&acme.paths.key).unwrap().permissions()) & 0o777, 0o600);
+
+    std::fs::create_dir_all(&acme.paths.units).unwrap();
+    std::fs::write(acme.paths.units.join("only_acme.json"),
+        r#"{"id":"only_acme","policies":[{"id":"p","condition":{"kind":"literal","value":true}}],"wiring":{"type":"all","policies":["p"]}}"#).unwrap();
+    acme.units.reload();
+    globex.units.reload();
+    assert!(acme.units.get("only_acme").is_some());
+    assert!(globex.units.get("only_acme").is_none());
+
+    let e = EngineRegistryEntry{ kind: "engine.registry.entry.v1".into(), id: "1".into(), name: "quota".into(), version: "1.0.0".into(), cid: "b3:aa".into(), meta: serde_json::json!({}), seal: None };
+    acme.reg.put(&e).unwrap();
+    assert!(acme.reg.get("quota", "1.0.0").unwrap().is_some());
+    assert!(globex.reg.get("quota", "1.0.0").unwrap().is_none());
+    assert!(globex.reg.list(None, None, 10).unwrap().items.is_empty());
+
+    let (_, acme_ci) = tenants.identify(&bearer("acme-secret")).unwrap();
+    let (_, globex_ci) = tenants.identify(&bearer("globex-secret")).unwrap();
+    acme.hold_run(&acme_ci).commit();
+    acme.hold_run(&acme_ci).commit();
+    assert_eq!(acme.hold_run(&acme_ci).remaining(), 0);
+    assert!(globex.hold_run(&globex_ci).remaining() > 0);
+}
+
+#[test]
+fn reviewers_are_per_tenant() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("acme")).unwrap();
+    std::fs::write(root.join("acme/reviewers.json"), r#"{"kind":"review.reviewers.v1","keys":{"k1":"AAAA"}}"#).unwrap();
+    let doc = serde_json::json!({"kind": "engine.tenants.v1", "tenants": {"acme": {"tokens": [token_hash("a")]}, "globex": {"tokens": [token_hash("g")]}}});
+    std::fs::write(root.join("tenants.json"), doc.to_string()).unwrap();
+    let tenants = Tenants::build(Directory::load(root.join("tenants.json"), root).unwrap(), None, |_, _| ()).unwrap();
+    assert!(tenants.for_request(&bearer("a")).unwrap().reviewers.as_ref().is_some_and(|r| r.keys.contains_key("k1")));
+    assert!(tenants.for_request(&bearer("g")).unwrap().reviewers.is_none());
+
+    std::fs::write(root.join("globex/reviewers.json"), "{}").unwrap();
+    assert!(Tenants::build(Directory::load(root.join("tenants.json"), root).unwrap(), None, |_, _| ()).is_err());
+}
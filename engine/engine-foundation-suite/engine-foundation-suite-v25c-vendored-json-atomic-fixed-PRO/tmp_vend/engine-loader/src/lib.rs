@@ -1,26 +1,42 @@
 
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use parking_lot::RwLock;
 use std::sync::Arc;
 use engine_core::{AtomicUnit};
-use engine_core::model::{PolicyBit, Wiring, Expression, Aggregator};
+use engine_core::model::{ContextDecl, PolicyBit, Wiring, Expression, Aggregator};
+use engine_registry::cas::Cas;
+use engine_registry::file_registry::{FileRegistry, UnitRef};
+use engine_registry::RegistryProvider;
 
+/// A unit's identity is the CID of its canonical spec ([`spec_cid`]); absent optionals are
+/// omitted rather than `null`, so a spec round-trips to the same bytes.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnitSpec {
     pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub policies: Vec<PolicySpec>,
     pub wiring: WiringSpec,
+    /// Facts fetched by context providers before evaluation (`$context.<as>`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<ContextDecl>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolicySpec {
     pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub requires: Option<Vec<Vec<String>>>, // list of JSON pointer paths split
     pub condition: ExprSpec,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub fallback: Option<String>, // "Allow"|"Deny"|"Doubt"
 }
 
@@ -82,6 +98,11 @@ fn wiring_from_spec(w:&WiringSpec)->Result<Wiring>{
     })
 }
 
+/// JSON✯Atomic bytes of a spec; this is what gets stored in the object store.
+pub fn spec_bytes(spec:&UnitSpec)->Result<Vec<u8>>{ engine_core::json_atomic::to_json_atomic_bytes(spec) }
+/// `b3:` CID of the canonical spec; becomes `SemanticChip.hash` and so the receipt's `chip_hash`.
+pub fn spec_cid(spec:&UnitSpec)->Result<String>{ engine_core::json_atomic::compute_cid(spec) }
+
 pub fn unit_from_spec(spec:&UnitSpec)->Result<AtomicUnit>{
     let mut b = engine_core::model::SemanticChip::builder(&spec.id);
     for p in &spec.policies {
@@ -99,60 +120,413 @@ pub fn unit_from_spec(spec:&UnitSpec)->Result<AtomicUnit>{
         b = b.policy(pb.build());
     }
     b = b.wiring(wiring_from_spec(&spec.wiring)?);
-    Ok(b.build())
+    let mut unit = b.build();
+    unit.context = spec.context.clone();
+    unit.hash = Some(spec_cid(spec)?);
+    Ok(unit)
+}
+
+/// Where [`UnitStore::resolve`] fetches spec bytes by CID: `None` when the source has no such object,
+/// an error when it could not answer or answered with bytes that fail its own integrity check.
+#[async_trait]
+pub trait SpecSource: Send + Sync {
+    async fn fetch(&self, cid:&str)->Result<Option<Vec<u8>>>;
+}
+#[async_trait]
+impl<P: RegistryProvider> SpecSource for Cas<P> {
+    async fn fetch(&self, cid:&str)->Result<Option<Vec<u8>>>{ self.find(cid).await }
 }
 
+/// Resolution failures the HTTP layer maps to distinct statuses (404, 422, 502).
+#[derive(Debug)]
+pub enum ResolveError {
+    NotFound(String),
+    /// The fetched spec does not canonicalize to the requested CID.
+    CidMismatch { expected: String, got: String },
+    /// The source returned bytes that fail its integrity check or are not a unit spec.
+    Corrupt { cid: String, reason: String },
+    /// The source could not be read (I/O, network); the unit may well exist.
+    Unavailable { cid: String, reason: String },
+}
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(r) => write!(f, "unit not found: {r}"),
+            Self::CidMismatch{ expected, got } => write!(f, "unit spec canonicalizes to {got}, expected {expected}"),
+            Self::Corrupt{ cid, reason } => write!(f, "unit spec {cid} is corrupt: {reason}"),
+            Self::Unavailable{ cid, reason } => write!(f, "unit spec {cid} could not be fetched: {reason}"),
+        }
+    }
+}
+impl std::error::Error for ResolveError {}
+
+/// Debounce window for directory events: editors write temp file, rename, chmod in quick succession.
+pub const RELOAD_DEBOUNCE: Duration = Duration::from_millis(250);
+/// Activated generations kept for rollback.
+const KEEP_GENERATIONS: usize = 8;
+const KEEP_EVENTS: usize = 64;
+
+/// One activated unit set. Units are keyed by source: the file name for directory loads, the id otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct Generation {
+    pub number: u64,
+    pub units: BTreeMap<String, AtomicUnit>,
+    /// `reload`, `replace`, or `rollback:<n>`.
+    pub origin: String,
+    pub activated_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReloadOutcome { Activated, Unchanged, Rejected, RolledBack }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileError { pub file: String, pub error: String }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadEvent {
+    pub at: u64,
+    pub outcome: ReloadOutcome,
+    /// Generation serving after this event.
+    pub generation: u64,
+    pub units: usize,
+    /// Files that failed to compile (their previous version, if any, stays active) and validation failures.
+    pub errors: Vec<FileError>,
+}
+
+#[derive(Debug, Default)]
+struct ReloadStats { activated: AtomicU64, unchanged: AtomicU64, rejected: AtomicU64, rolled_back: AtomicU64, file_errors: AtomicU64 }
+
+type ActivateHook = Box<dyn Fn(&Generation) + Send + Sync>;
+
 #[derive(Clone)]
 pub struct UnitStore {
-    inner: Arc<RwLock<Vec<AtomicUnit>>>,
+    current: Arc<RwLock<Arc<Generation>>>,
+    history: Arc<RwLock<VecDeque<Arc<Generation>>>>,
+    events: Arc<RwLock<VecDeque<ReloadEvent>>>,
+    stats: Arc<ReloadStats>,
+    /// Serializes reload/rollback so two activations never interleave.
+    activate_lock: Arc<parking_lot::Mutex<()>>,
+    on_activate: Arc<RwLock<Vec<ActivateHook>>>,
+    /// CID → compiled unit, filled by [`UnitStore::resolve`]; content-addressed, so never stale, only evicted.
+    by_cid: Arc<parking_lot::Mutex<UnitCache>>,
+    reg: Option<FileRegistry>,
+    source: Option<Arc<dyn SpecSource>>,
     pub dir: PathBuf,
 }
 
-impl UnitStore {
-    pub fn new<P: AsRef<Path>>(dir:P)->Self {
-        Self{ inner: Arc::new(RwLock::new(Vec::new())), dir: dir.as_ref().into() }
+/// Units compiled from fetched specs kept by [`UnitStore::resolve`] (see [`UnitStore::cache_capacity`]).
+pub const UNIT_CACHE_CAPACITY: usize = 256;
+
+/// CID → unit, least recently used evicted past `cap`.
+struct UnitCache { cap: usize, tick: u64, units: HashMap<String, (u64, AtomicUnit)> }
+impl UnitCache {
+    fn new(cap:usize)->Self { Self{ cap, tick: 0, units: HashMap::new() } }
+    fn get(&mut self, cid:&str)->Option<AtomicUnit> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.units.get_mut(cid).map(|(used, u)| { *used = tick; u.clone() })
+    }
+    fn insert(&mut self, cid:String, unit:AtomicUnit) {
+        self.tick += 1;
+        self.units.insert(cid, (self.tick, unit));
+        while self.units.len() > self.cap {
+            let Some(oldest) = self.units.iter().min_by_key(|(_, (used, _))| *used).map(|(c, _)| c.clone()) else { break };
+            self.units.remove(&oldest);
+        }
     }
-    pub fn list(&self)->Vec<AtomicUnit>{ self.inner.read().clone() }
-    pub fn get(&self, id:&str)->Option<AtomicUnit>{ self.inner.read().iter().find(|u| u.id==id).cloned() }
-    pub fn replace_all(&self, units:Vec<AtomicUnit>) { *self.inner.write() = units; }
 }
 
-pub async fn load_units_from_dir<P: AsRef<Path>>(dir:P)->Result<Vec<AtomicUnit>>{
-    let mut units = Vec::new();
-    if !dir.as_ref().exists(){ return Ok(units) }
+fn now_unix()->u64 { std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) }
+
+/// Per-unit checks that need no other unit: wiring only names defined policies, ids are unique, weights line up,
+/// each context fact has its own `as`.
+pub fn validate_unit(u:&AtomicUnit)->Result<()>{
+    let mut seen = HashSet::new();
+    for p in &u.policies {
+        if !seen.insert(p.id.as_str()) { bail!("{}: duplicate policy id {}", u.id, p.id); }
+    }
+    for id in u.wiring.ids() {
+        if !seen.contains(id.as_str()) { bail!("{}: wiring references unknown policy {id}", u.id); }
+    }
+    if let Wiring::Weighted{ policies, weights, .. } = &u.wiring {
+        if policies.len() != weights.len() { bail!("{}: {} weights for {} policies", u.id, weights.len(), policies.len()); }
+    }
+    let mut names = HashSet::new();
+    for c in &u.context {
+        if !names.insert(c.name.as_str()) { bail!("{}: duplicate context fact {}", u.id, c.name); }
+    }
+    Ok(())
+}
+
+/// Checks across the whole candidate set; any failure rejects the generation.
+fn validate_set(units:&BTreeMap<String, AtomicUnit>)->Vec<FileError>{
+    let mut by_id: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (src, u) in units { by_id.entry(u.id.as_str()).or_default().push(src.as_str()); }
+    let mut errors: Vec<FileError> = by_id.into_iter().filter(|(_, srcs)| srcs.len() > 1)
+        .map(|(id, srcs)| FileError{ file: srcs.join(", "), error: format!("unit id {id} defined more than once") })
+        .collect();
+    errors.extend(units.iter().filter_map(|(src, u)| validate_unit(u).err().map(|e| FileError{ file: src.clone(), error: e.to_string() })));
+    errors.sort_by(|a, b| a.file.cmp(&b.file));
+    errors
+}
+
+/// Unit files are `*.json|*.yaml|*.yml`; dotfiles (editor swap/lock files) are ignored.
+fn is_unit_file(p:&Path)->bool {
+    let hidden = p.file_name().and_then(|n| n.to_str()).map(|n| n.starts_with('.')).unwrap_or(true);
+    !hidden && matches!(p.extension().and_then(|e| e.to_str()), Some("json" | "yaml" | "yml"))
+}
+
+/// A spec file: `.json`, otherwise YAML.
+pub fn read_spec(p:&Path)->Result<UnitSpec>{
+    let s = std::fs::read_to_string(p)?;
+    Ok(if p.extension().and_then(|e| e.to_str()) == Some("json") { serde_json::from_str(&s)? } else { serde_yaml::from_str(&s)? })
+}
+
+fn compile_file(p:&Path)->Result<AtomicUnit>{
+    let unit = unit_from_spec(&read_spec(p)?)?;
+    validate_unit(&unit)?;
+    Ok(unit)
+}
+
+/// Compile every unit file on its own: one bad file yields one error, not a failed directory.
+pub fn scan_dir<P: AsRef<Path>>(dir:P)->Result<(BTreeMap<String, AtomicUnit>, Vec<FileError>)>{
+    let (mut units, mut errors) = (BTreeMap::new(), Vec::new());
+    if !dir.as_ref().exists(){ return Ok((units, errors)) }
     for entry in std::fs::read_dir(dir.as_ref())? {
         let p = entry?.path();
-        if p.extension().and_then(|e| e.to_str()) == Some("json") {
-            let s = std::fs::read_to_string(&p)?;
-            let spec:UnitSpec = serde_json::from_str(&s)?;
-            units.push(unit_from_spec(&spec)?);
-        } else if p.extension().and_then(|e| e.to_str()) == Some("yaml") || p.extension().and_then(|e| e.to_str()) == Some("yml") {
-            let s = std::fs::read_to_string(&p)?;
-            let spec:UnitSpec = serde_yaml::from_str(&s)?;
-            units.push(unit_from_spec(&spec)?);
+        if !is_unit_file(&p) { continue; }
+        let file = p.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        match compile_file(&p) {
+            Ok(u) => { units.insert(file, u); }
+            Err(e) => errors.push(FileError{ file, error: format!("{e:#}") }),
+        }
+    }
+    Ok((units, errors))
+}
+
+impl UnitStore {
+    pub fn new<P: AsRef<Path>>(dir:P)->Self {
+        Self{
+            current: Arc::default(), history: Arc::default(), events: Arc::default(), stats: Arc::default(),
+            activate_lock: Arc::default(), on_activate: Arc::default(), by_cid: Arc::new(parking_lot::Mutex::new(UnitCache::new(UNIT_CACHE_CAPACITY))), reg: None, source: None, dir: dir.as_ref().into(),
+        }
+    }
+    /// Resolve `name@req` through `reg` and fetch specs by CID from `source`.
+    pub fn with_registry(mut self, reg:FileRegistry, source:Arc<dyn SpecSource>)->Self {
+        self.reg = Some(reg); self.source = Some(source); self
+    }
+    /// How many fetched units [`UnitStore::resolve`] keeps compiled (default [`UNIT_CACHE_CAPACITY`]).
+    pub fn cache_capacity(self, cap:usize)->Self {
+        *self.by_cid.lock() = UnitCache::new(cap.max(1)); self
+    }
+    pub fn list(&self)->Vec<AtomicUnit>{ self.current.read().units.values().cloned().collect() }
+    pub fn get(&self, id:&str)->Option<AtomicUnit>{ self.current.read().units.values().find(|u| u.id==id).cloned() }
+    /// The generation currently serving; `0` until the first activation.
+    pub fn generation(&self)->u64 { self.current.read().number }
+    pub fn snapshot(&self)->Arc<Generation>{ self.current.read().clone() }
+    /// Activated generations still available for [`UnitStore::rollback`], oldest first.
+    pub fn history(&self)->Vec<Arc<Generation>>{ self.history.read().iter().cloned().collect() }
+    pub fn events(&self)->Vec<ReloadEvent>{ self.events.read().iter().cloned().collect() }
+
+    /// Call `f` with the current generation now and with every generation activated later, in order.
+    /// Runs on the activating thread before `reload`/`rollback` return, so keep it cheap (e.g. an ArcSwap store).
+    pub fn on_activate(&self, f:impl Fn(&Generation) + Send + Sync + 'static) {
+        let _g = self.activate_lock.lock();
+        f(&self.snapshot());
+        self.on_activate.write().push(Box::new(f));
+    }
+
+    /// Activate `units` as-is (no directory, no validation).
+    pub fn replace_all(&self, units:Vec<AtomicUnit>) {
+        let _g = self.activate_lock.lock();
+        self.activate(units.into_iter().map(|u| (u.id.clone(), u)).collect(), "replace".into());
+    }
+
+    fn activate(&self, units:BTreeMap<String, AtomicUnit>, origin:String)->u64 {
+        let number = self.generation() + 1;
+        let gen = Arc::new(Generation{ number, units, origin, activated_at: now_unix() });
+        {
+            let mut h = self.history.write();
+            h.push_back(gen.clone());
+            while h.len() > KEEP_GENERATIONS { h.pop_front(); }
+        }
+        *self.current.write() = gen.clone();
+        for f in self.on_activate.read().iter() { f(&gen); }
+        number
+    }
+
+    fn record(&self, ev:ReloadEvent)->ReloadEvent {
+        let counter = match ev.outcome {
+            ReloadOutcome::Activated => &self.stats.activated, ReloadOutcome::Unchanged => &self.stats.unchanged,
+            ReloadOutcome::Rejected => &self.stats.rejected, ReloadOutcome::RolledBack => &self.stats.rolled_back,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.stats.file_errors.fetch_add(ev.errors.len() as u64, Ordering::Relaxed);
+        let mut evs = self.events.write();
+        evs.push_back(ev.clone());
+        while evs.len() > KEEP_EVENTS { evs.pop_front(); }
+        ev
+    }
+
+    /// Rescan `dir` and activate the result if it validates. A file that fails to compile keeps its
+    /// previous unit; a candidate set that fails validation is rejected and the current generation stays.
+    pub fn reload(&self)->ReloadEvent {
+        let _g = self.activate_lock.lock();
+        let cur = self.snapshot();
+        let (mut units, mut errors) = match scan_dir(&self.dir) {
+            Ok(r) => r,
+            Err(e) => (cur.units.clone(), vec![FileError{ file: self.dir.display().to_string(), error: format!("{e:#}") }]),
+        };
+        for fe in &errors {
+            if let Some(prev) = cur.units.get(&fe.file) { units.insert(fe.file.clone(), prev.clone()); }
+        }
+        let invalid = validate_set(&units);
+        let (outcome, generation) = if !invalid.is_empty() {
+            errors.extend(invalid);
+            (ReloadOutcome::Rejected, cur.number)
+        } else if cur.number > 0 && same_units(&cur.units, &units) {
+            (ReloadOutcome::Unchanged, cur.number)
+        } else {
+            let n = units.len();
+            let number = self.activate(units, "reload".into());
+            return self.record(ReloadEvent{ at: now_unix(), outcome: ReloadOutcome::Activated, generation: number, units: n, errors });
+        };
+        self.record(ReloadEvent{ at: now_unix(), outcome, generation, units: cur.units.len(), errors })
+    }
+
+    /// Re-activate an earlier generation (default: the one before the current) as a new generation.
+    pub fn rollback(&self, to:Option<u64>)->Result<ReloadEvent>{
+        let _g = self.activate_lock.lock();
+        let cur = self.generation();
+        let target = {
+            let h = self.history.read();
+            match to {
+                Some(n) => h.iter().find(|g| g.number == n).cloned().ok_or_else(|| anyhow!("generation {n} is not in history"))?,
+                None => h.iter().rev().find(|g| g.number < cur).cloned().ok_or_else(|| anyhow!("no earlier generation to roll back to"))?,
+            }
+        };
+        let number = self.activate(target.units.clone(), format!("rollback:{}", target.number));
+        Ok(self.record(ReloadEvent{ at: now_unix(), outcome: ReloadOutcome::RolledBack, generation: number, units: target.units.len(), errors: vec![] }))
+    }
+
+    /// Prometheus text exposition of the reload counters.
+    pub fn metrics(&self)->String { self.metrics_labeled("") }
+
+    /// [`UnitStore::metrics`] with extra labels (`tenant="acme"`) on every sample; no `# TYPE` lines,
+    /// so several stores can be concatenated.
+    pub fn metrics_labeled(&self, labels:&str)->String {
+        let s = &self.stats;
+        let typed = labels.is_empty();
+        let l = |extra:&str| match (labels.is_empty(), extra.is_empty()) {
+            (true, true) => String::new(),
+            (true, false) => format!("{{{extra}}}"),
+            (false, true) => format!("{{{labels}}}"),
+            (false, false) => format!("{{{labels},{extra}}}"),
+        };
+        let mut out = String::new();
+        if typed { out.push_str("# TYPE engine_units_generation gauge\n"); }
+        out.push_str(&format!("engine_units_generation{} {}\n", l(""), self.generation()));
+        if typed { out.push_str("# TYPE engine_units_loaded gauge\n"); }
+        out.push_str(&format!("engine_units_loaded{} {}\n", l(""), self.current.read().units.len()));
+        if typed { out.push_str("# TYPE engine_units_reloads_total counter\n"); }
+        for (outcome, c) in [("activated", &s.activated), ("unchanged", &s.unchanged), ("rejected", &s.rejected), ("rolled_back", &s.rolled_back)] {
+            out.push_str(&format!("engine_units_reloads_total{} {}\n", l(&format!("outcome=\"{outcome}\"")), c.load(Ordering::Relaxed)));
         }
+        if typed { out.push_str("# TYPE engine_units_reload_errors_total counter\n"); }
+        out.push_str(&format!("engine_units_reload_errors_total{} {}\n", l(""), s.file_errors.load(Ordering::Relaxed)));
+        out
+    }
+
+    /// `unit_ref` as accepted by `/run`: a `b3:`/`cid:b3:` CID, `name@range|channel`, or a local chip id.
+    /// CIDs are served from local units, then the cache, then fetched, checked and compiled.
+    pub async fn resolve(&self, unit_ref:&str)->Result<AtomicUnit>{
+        let cid = match UnitRef::parse(unit_ref) {
+            UnitRef::Id(id) => return self.get(&id).ok_or_else(|| ResolveError::NotFound(id).into()),
+            UnitRef::Cid(cid) => cid.strip_prefix("cid:").unwrap_or(&cid).to_string(),
+            UnitRef::Named{ name, req } => {
+                let reg = self.reg.as_ref().ok_or_else(|| ResolveError::NotFound(unit_ref.into()))?;
+                reg.resolve(&name, &req)?.cid
+            }
+        };
+        if let Some(u) = self.current.read().units.values().find(|u| u.hash.as_deref() == Some(cid.as_str())) { return Ok(u.clone()); }
+        if let Some(u) = self.by_cid.lock().get(&cid) { return Ok(u); }
+        let source = self.source.as_ref().ok_or_else(|| ResolveError::NotFound(cid.clone()))?;
+        let bytes = match source.fetch(&cid).await {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => return Err(ResolveError::NotFound(cid).into()),
+            Err(e) if e.downcast_ref::<engine_registry::cas::IntegrityError>().is_some() => return Err(ResolveError::Corrupt{ cid, reason: format!("{e:#}") }.into()),
+            Err(e) => return Err(ResolveError::Unavailable{ cid, reason: format!("{e:#}") }.into()),
+        };
+        let spec: UnitSpec = serde_json::from_slice(&bytes).map_err(|e| ResolveError::Corrupt{ cid: cid.clone(), reason: e.to_string() })?;
+        let got = spec_cid(&spec)?;
+        if got != cid { return Err(ResolveError::CidMismatch{ expected: cid, got }.into()); }
+        let unit = unit_from_spec(&spec)?;
+        validate_unit(&unit)?;
+        self.by_cid.lock().insert(cid, unit.clone());
+        Ok(unit)
     }
-    Ok(units)
+
+    /// The spec behind `chip_hash` (offline bundles): a unit file in `dir`, else the spec source.
+    /// `None` for units built in code, which have no spec.
+    pub async fn spec(&self, cid:&str)->Result<Option<UnitSpec>>{
+        let (dir, want) = (self.dir.clone(), cid.to_string());
+        let local = tokio::task::spawn_blocking(move || -> Result<Option<UnitSpec>> {
+            if !dir.exists() { return Ok(None); }
+            for entry in std::fs::read_dir(&dir)? {
+                let p = entry?.path();
+                if !is_unit_file(&p) { continue; }
+                let Ok(spec) = read_spec(&p) else { continue };
+                if spec_cid(&spec)? == want { return Ok(Some(spec)); }
+            }
+            Ok(None)
+        }).await??;
+        if local.is_some() { return Ok(local); }
+        let Some(source) = &self.source else { return Ok(None) };
+        let Some(bytes) = source.fetch(cid).await? else { return Ok(None) };
+        let spec: UnitSpec = serde_json::from_slice(&bytes)?;
+        Ok((spec_cid(&spec)? == cid).then_some(spec))
+    }
+}
+
+/// Same sources with the same spec CIDs.
+fn same_units(a:&BTreeMap<String, AtomicUnit>, b:&BTreeMap<String, AtomicUnit>)->bool {
+    a.len() == b.len() && a.iter().zip(b).all(|((ka, ua), (kb, ub))| ka == kb && ua.hash == ub.hash)
 }
 
+/// All-or-nothing load; the first bad file is an error. Use [`scan_dir`] for per-file results.
+pub async fn load_units_from_dir<P: AsRef<Path>>(dir:P)->Result<Vec<AtomicUnit>>{
+    let (units, errors) = scan_dir(dir)?;
+    if let Some(e) = errors.first() { bail!("{}: {}", e.file, e.error); }
+    Ok(units.into_values().collect())
+}
+
+/// Initial load, then a debounced [`UnitStore::reload`] after each burst of changes to unit files.
+/// Reloads read and compile units, so they run on the blocking pool.
 pub async fn watch_units(store:UnitStore)->Result<()>{
     use notify::{recommended_watcher, Event, RecursiveMode, Watcher};
+    let reload = |store:&UnitStore| { let s = store.clone(); tokio::task::spawn_blocking(move || s.reload()) };
     let dir = store.dir.clone();
     if !dir.exists(){ std::fs::create_dir_all(&dir)?; }
-    // initial load
-    if let Ok(u) = load_units_from_dir(&dir).await { store.replace_all(u) }
+    reload(&store).await?;
 
     let (tx, mut rx) = tokio::sync::mpsc::channel::<Event>(32);
+    let watch_dir = dir.clone();
     tokio::task::spawn_blocking(move || {
         let mut w = recommended_watcher(move |res: Result<Event, _>| {
             if let Ok(ev) = res { let _ = tx.blocking_send(ev); }
         }).expect("watcher");
-        w.watch(&dir, RecursiveMode::NonRecursive).expect("watch");
+        w.watch(&watch_dir, RecursiveMode::NonRecursive).expect("watch");
         std::thread::park(); // stay alive
     });
 
-    while let Some(_ev) = rx.recv().await {
-        if let Ok(u) = load_units_from_dir(&dir).await { store.replace_all(u) }
+    while let Some(ev) = rx.recv().await {
+        let mut relevant = ev.paths.iter().any(|p| is_unit_file(p));
+        // Quiet period: keep absorbing events until none arrives for RELOAD_DEBOUNCE.
+        while let Ok(Some(ev)) = tokio::time::timeout(RELOAD_DEBOUNCE, rx.recv()).await {
+            relevant |= ev.paths.iter().any(|p| is_unit_file(p));
+        }
+        if relevant { reload(&store).await?; }
     }
     Ok(())
 }
@@ -0,0 +1,56 @@
+use engine_loader::{ReloadOutcome, UnitStore};
+
+fn unit(id:&str, policy:&str, wired:&str) -> String {
+    format!(r#"{{"id":"{id}","policies":[{{"id":"{policy}","condition":{{"kind":"literal","value":true}}}}],"wiring":{{"type":"all","policies":["{wired}"]}}}}"#)
+}
+
+#[test]
+fn bad_file_keeps_previous_unit_and_rollback_restores() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    std::fs::write(dir.join("a.json"), unit("a", "p", "p")).unwrap();
+    std::fs::write(dir.join("b.json"), unit("b", "p", "p")).unwrap();
+    std::fs::write(dir.join(".a.json.swp"), "garbage").unwrap();
+    let store = UnitStore::new(dir);
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    store.on_activate(move |g| sink.lock().unwrap().push(g.number));
+
+    let ev = store.reload();
+    assert_eq!(ev.outcome, ReloadOutcome::Activated);
+    assert_eq!((ev.generation, ev.units), (1, 2));
+    let a1 = store.get("a").unwrap().hash;
+    assert!(a1.as_deref().unwrap().starts_with("b3:"));
+
+    // half-written file: a's previous unit stays, nothing else changes
+    std::fs::write(dir.join("a.json"), "{\"id\":").unwrap();
+    let ev = store.reload();
+    assert_eq!(ev.outcome, ReloadOutcome::Unchanged);
+    assert_eq!(ev.errors.len(), 1);
+    assert_eq!(ev.errors[0].file, "a.json");
+    assert_eq!(store.get("a").unwrap().hash, a1);
+
+    // wiring names an unknown policy: compile error isolated to that file
+    std::fs::write(dir.join("c.json"), unit("c", "p", "q")).unwrap();
+    assert_eq!(store.reload().errors.len(), 2);
+    assert!(store.get("c").is_none());
+
+    // duplicate id across files rejects the whole candidate set
+    std::fs::write(dir.join("c.json"), unit("b", "p", "p")).unwrap();
+    let ev = store.reload();
+    assert_eq!(ev.outcome, ReloadOutcome::Rejected);
+    assert_eq!(store.generation(), 1);
+
+    std::fs::remove_file(dir.join("c.json")).unwrap();
+    std::fs::write(dir.join("a.json"), unit("a", "p2", "p2")).unwrap();
+    assert_eq!(store.reload().generation, 2);
+    assert_ne!(store.get("a").unwrap().hash, a1);
+
+    let ev = store.rollback(None).unwrap();
+    assert_eq!(ev.outcome, ReloadOutcome::RolledBack);
+    assert_eq!(store.generation(), 3);
+    assert_eq!(store.get("a").unwrap().hash, a1);
+    assert_eq!(store.snapshot().origin, "rollback:1");
+    assert_eq!(*seen.lock().unwrap(), vec![0, 1, 2, 3]);
+    assert!(store.metrics().contains("engine_units_reloads_total{outcome=\"rejected\"} 1"));
+}
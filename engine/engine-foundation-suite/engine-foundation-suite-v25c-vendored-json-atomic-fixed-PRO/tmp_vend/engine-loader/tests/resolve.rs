@@ -0,0 +1,73 @@
+use engine_loader::{spec_bytes, spec_cid, ResolveError, SpecSource, UnitSpec, UnitStore};
+use engine_registry::file_registry::FileRegistry;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// In-memory spec source that counts fetches and can be taken down.
+#[derive(Default)]
+struct Source { objects: HashMap<String, Vec<u8>>, fetches: AtomicUsize, down: AtomicBool }
+#[async_trait::async_trait]
+impl SpecSource for Source {
+    async fn fetch(&self, cid:&str) -> anyhow::Result<Option<Vec<u8>>> {
+        if self.down.load(Ordering::SeqCst) { anyhow::bail!("connection refused"); }
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+        Ok(self.objects.get(cid).cloned())
+    }
+}
+
+fn spec(id:&str) -> UnitSpec {
+    serde_json::from_str(&format!(r#"{{"id":"{id}","policies":[{{"id":"p","condition":{{"kind":"literal","value":true}}}}],"wiring":{{"type":"all","policies":["p"]}}}}"#)).unwrap()
+}
+
+fn store(source:Arc<Source>, dir:&tempfile::TempDir) -> UnitStore {
+    UnitStore::new(dir.path().join("units")).with_registry(FileRegistry::new(dir.path().join("registry")), source)
+}
+
+fn kind(e:anyhow::Error) -> String {
+    match e.downcast_ref::<ResolveError>().unwrap() {
+        ResolveError::NotFound(_) => "not_found",
+        ResolveError::CidMismatch{ .. } => "cid_mismatch",
+        ResolveError::Corrupt{ .. } => "corrupt",
+        ResolveError::Unavailable{ .. } => "unavailable",
+    }.into()
+}
+
+#[tokio::test]
+async fn fetched_units_are_cached_least_recently_used_first_out() {
+    let (a, b) = (spec("a"), spec("b"));
+    let (ca, cb) = (spec_cid(&a).unwrap(), spec_cid(&b).unwrap());
+    let mut source = Source::default();
+    source.objects.insert(ca.clone(), spec_bytes(&a).unwrap());
+    source.objects.insert(cb.clone(), spec_bytes(&b).unwrap());
+    let source = Arc::new(source);
+    let dir = tempfile::tempdir().unwrap();
+    let units = store(source.clone(), &dir).cache_capacity(1);
+
+    let fetches = |r:&str| { let (units, source, r) = (units.clone(), source.clone(), r.to_string()); async move { units.resolve(&r).await.unwrap(); source.fetches.load(Ordering::SeqCst) } };
+    assert_eq!(fetches(&ca).await, 1);
+    assert_eq!(fetches(&format!("cid:{ca}")).await, 1);
+    assert_eq!(fetches(&cb).await, 2);
+    assert_eq!(fetches(&ca).await, 3, "a was evicted for b");
+    assert_eq!(units.resolve(&ca).await.unwrap().hash.as_deref(), Some(ca.as_str()));
+}
+
+#[tokio::test]
+async fn missing_corrupt_and_unreachable_specs_are_told_apart() {
+    let (a, b) = (spec("a"), spec("b"));
+    let ca = spec_cid(&a).unwrap();
+    let (garbage, swapped) = (format!("b3:{}", "0".repeat(64)), format!("b3:{}", "1".repeat(64)));
+    let mut source = Source::default();
+    source.objects.insert(garbage.clone(), b"{\"id\":".to_vec());
+    source.objects.insert(swapped.clone(), spec_bytes(&b).unwrap());
+    let source = Arc::new(source);
+    let dir = tempfile::tempdir().unwrap();
+    let units = store(source.clone(), &dir);
+
+    assert_eq!(kind(units.resolve(&ca).await.unwrap_err()), "not_found");
+    assert_eq!(kind(units.resolve("nope").await.unwrap_err()), "not_found");
+    assert_eq!(kind(units.resolve(&garbage).await.unwrap_err()), "corrupt");
+    assert_eq!(kind(units.resolve(&swapped).await.unwrap_err()), "cid_mismatch");
+    source.down.store(true, Ordering::SeqCst);
+    assert_eq!(kind(units.resolve(&ca).await.unwrap_err()), "unavailable");
+}
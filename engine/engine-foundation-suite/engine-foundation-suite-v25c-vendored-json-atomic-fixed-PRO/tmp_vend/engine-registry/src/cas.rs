@@ -0,0 +1,182 @@
+//! Content-addressed layer over any [`RegistryProvider`].
+//!
+//! Blobs live at `objects/b3/<aa>/<bb>/<hex>` (sharded by the first two bytes of the blake3 hash),
+//! so identical content is stored once. Caller keys are aliases: `refs/<bucket>/<key>` holds a small
+//! JSON pointer to a CID. Every read re-hashes the blob; a mismatch is an error, never silent data.
+use anyhow::{anyhow, bail, Result};
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use tokio::sync::RwLock;
+
+use crate::{ObjectMeta, RegistryProvider};
+
+const OBJECTS: &str = "objects/b3/";
+const REFS: &str = "refs/";
+const HREF: &str = "tdln://objects/";
+
+/// Alias document stored under `refs/<bucket>/<key>`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Alias { pub cid: String, pub size: u64 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GcReport { pub kept: usize, pub removed: Vec<String>, pub bytes_freed: u64 }
+
+pub struct Cas<P: RegistryProvider> {
+    inner: P,
+    bucket: String,
+    /// Writers share, GC is exclusive: a blob is never collected between its write and its alias.
+    gc_lock: RwLock<()>,
+}
+
+/// Accepts `b3:<hex>` (engine-core) and `cid:b3:<hex>` (receipts); returns the lowercase hex.
+pub fn parse_cid(cid:&str) -> Result<String> {
+    let hex = cid.strip_prefix("cid:").unwrap_or(cid).strip_prefix("b3:").ok_or_else(|| anyhow!("not a b3 cid: {cid}"))?;
+    if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) { bail!("not a b3 cid: {cid}"); }
+    Ok(hex.to_ascii_lowercase())
+}
+
+pub fn cid_of(bytes:&[u8]) -> String { format!("b3:{}", blake3::hash(bytes).to_hex()) }
+
+/// Stored bytes that do not hash to the CID they were read under.
+#[derive(Debug)]
+pub struct IntegrityError { pub cid: String, pub got: String }
+impl std::fmt::Display for IntegrityError {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "integrity check failed for {}: stored bytes hash to {}", self.cid, self.got)
+    }
+}
+impl std::error::Error for IntegrityError {}
+
+/// The provider had no such object (as opposed to failing to answer).
+fn is_missing(e:&anyhow::Error) -> bool {
+    #[cfg(feature="s3")]
+    if let Some(e) = e.downcast_ref::<aws_sdk_s3::error::SdkError<aws_sdk_s3::operation::get_object::GetObjectError>>() {
+        return e.as_service_error().is_some_and(|s| s.is_no_such_key());
+    }
+    e.chain().any(|c| c.downcast_ref::<std::io::Error>().is_some_and(|io| io.kind() == std::io::ErrorKind::NotFound))
+}
+
+fn blob_key(hex:&str) -> String { format!("{OBJECTS}{}/{}/{hex}", &hex[..2], &hex[2..4]) }
+fn alias_key(bucket:&str, key:&str) -> String { format!("{REFS}{bucket}/{key}") }
+
+impl<P: RegistryProvider> Cas<P> {
+    /// All CAS state (blobs and aliases) lives in `bucket` of the underlying provider.
+    pub fn new(inner:P, bucket:impl Into<String>) -> Self { Self{ inner, bucket: bucket.into(), gc_lock: RwLock::new(()) } }
+    pub fn provider(&self) -> &P { &self.inner }
+
+    /// Store a blob by content; a second put of the same bytes is a no-op.
+    pub async fn put(&self, bytes:&[u8]) -> Result<ObjectMeta> {
+        let _g = self.gc_lock.read().await;
+        self.put_blob(bytes).await
+    }
+
+    async fn put_blob(&self, bytes:&[u8]) -> Result<ObjectMeta> {
+        let cid = cid_of(bytes);
+        let key = blob_key(&cid[3..]);
+        if self.inner.head(&self.bucket, &key).await.is_err() {
+            self.inner.put_bytes(&self.bucket, &key, bytes).await?;
+        }
+        Ok(ObjectMeta{ bucket: self.bucket.clone(), key, size: Some(bytes.len() as u64), etag: None, cid_b3: Some(cid) })
+    }
+
+    /// Store a blob and point `bucket/key` at it (re-pointing an existing alias is allowed).
+    pub async fn put_keyed(&self, bucket:&str, key:&str, bytes:&[u8]) -> Result<ObjectMeta> {
+        let _g = self.gc_lock.read().await;
+        let meta = self.put_blob(bytes).await?;
+        let alias = Alias{ cid: meta.cid_b3.clone().unwrap_or_default(), size: bytes.len() as u64 };
+        self.inner.put_bytes(&self.bucket, &alias_key(bucket, key), &serde_json::to_vec(&alias)?).await?;
+        Ok(ObjectMeta{ bucket: bucket.into(), key: key.into(), ..meta })
+    }
+
+    /// Fetch by CID, verifying the content hash; a mismatch is an [`IntegrityError`].
+    pub async fn get(&self, cid:&str) -> Result<Vec<u8>> {
+        let hex = parse_cid(cid)?;
+        let bytes = self.inner.get_bytes(&self.bucket, &blob_key(&hex)).await?;
+        let got = blake3::hash(&bytes).to_hex();
+        if got.as_str() != hex { return Err(IntegrityError{ cid: format!("b3:{hex}"), got: format!("b3:{got}") }.into()); }
+        Ok(bytes)
+    }
+
+    /// [`Cas::get`], with an absent blob as `None`; transport and integrity failures stay errors.
+    pub async fn find(&self, cid:&str) -> Result<Option<Vec<u8>>> {
+        match self.get(cid).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if is_missing(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn alias(&self, bucket:&str, key:&str) -> Result<Alias> {
+        let raw = self.inner.get_bytes(&self.bucket, &alias_key(bucket, key)).await
+            .map_err(|e| anyhow!("no such key {bucket}/{key}: {e}"))?;
+        Ok(serde_json::from_slice(&raw)?)
+    }
+
+    pub async fn get_keyed(&self, bucket:&str, key:&str) -> Result<Vec<u8>> {
+        let a = self.alias(bucket, key).await?;
+        self.get(&a.cid).await
+    }
+
+    /// Unlike a raw provider `head`, always carries the CID.
+    pub async fn head_keyed(&self, bucket:&str, key:&str) -> Result<ObjectMeta> {
+        let a = self.alias(bucket, key).await?;
+        Ok(ObjectMeta{ bucket: bucket.into(), key: key.into(), size: Some(a.size), etag: None, cid_b3: Some(a.cid) })
+    }
+
+    pub async fn remove_key(&self, bucket:&str, key:&str) -> Result<()> {
+        self.inner.delete(&self.bucket, &alias_key(bucket, key)).await
+    }
+
+    /// Resolve a `tdln://objects/<cid>` href (as found in `RefItem.hrefs`).
+    pub async fn resolve_href(&self, href:&str) -> Result<Vec<u8>> {
+        let cid = href.strip_prefix(HREF).ok_or_else(|| anyhow!("not a tdln object href: {href}"))?;
+        self.get(cid).await
+    }
+
+    /// First `tdln://objects/` href of a ref that resolves; other schemes are skipped.
+    pub async fn resolve_hrefs(&self, hrefs:&[String]) -> Result<Vec<u8>> {
+        let mut last = anyhow!("no tdln://objects/ href");
+        for h in hrefs.iter().filter(|h| h.starts_with(HREF)) {
+            match self.resolve_href(h).await { Ok(b) => return Ok(b), Err(e) => last = e }
+        }
+        Err(last)
+    }
+
+    /// Delete blobs no alias points at. `pins` keeps extra CIDs alive (e.g. ones only referenced from receipts).
+    pub async fn gc(&self, pins:&[String]) -> Result<GcReport> {
+        let _g = self.gc_lock.write().await;
+        let mut live: HashSet<String> = pins.iter().map(|c| parse_cid(c)).collect::<Result<_>>()?;
+        for k in self.inner.list(&self.bucket, REFS).await? {
+            let a: Alias = serde_json::from_slice(&self.inner.get_bytes(&self.bucket, &k).await?)?;
+            live.insert(parse_cid(&a.cid)?);
+        }
+        let mut report = GcReport::default();
+        for k in self.inner.list(&self.bucket, OBJECTS).await? {
+            let hex = k.rsplit('/').next().unwrap_or_default();
+            if live.contains(hex) { report.kept += 1; continue; }
+            report.bytes_freed += self.inner.head(&self.bucket, &k).await?.size.unwrap_or(0);
+            self.inner.delete(&self.bucket, &k).await?;
+            report.removed.push(format!("b3:{hex}"));
+        }
+        Ok(report)
+    }
+}
+
+/// Registry objects as `$context` facts: params `{"cid": "b3:...", "pointer": "/optional/json/pointer"}`.
+/// The blob is hash-checked like any read, and its CID is the fact's source.
+impl<P: RegistryProvider> engine_core::context::ContextProvider for Cas<P> {
+    fn effects(&self) -> &[engine_core::model::Effect] { &[engine_core::model::Effect::Read] }
+    fn fetch(&self, params:&serde_json::Value, _input:&serde_json::Value) -> Result<engine_core::context::Fact> {
+        let cid = params.get("cid").and_then(|c| c.as_str()).ok_or_else(|| anyhow!("cas fact needs cid"))?;
+        // Providers run on the engine's fetch workers, outside any runtime; one runtime serves them all.
+        static RT: std::sync::OnceLock<tokio::runtime::Runtime> = std::sync::OnceLock::new();
+        let rt = RT.get_or_init(|| tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build().expect("cas context runtime"));
+        let bytes = rt.block_on(self.get(cid))?;
+        let doc: serde_json::Value = serde_json::from_slice(&bytes)?;
+        let value = match params.get("pointer").and_then(|p| p.as_str()) {
+            Some(p) => doc.pointer(p).cloned().unwrap_or(serde_json::Value::Null),
+            None => doc,
+        };
+        Ok(engine_core::context::Fact{ value, source_cid: format!("b3:{}", parse_cid(cid)?) })
+    }
+}
@@ -1,19 +1,163 @@
-
-use anyhow::Result;
+use anyhow::{Result, bail};
+use semver::{Version, VersionReq};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use crate::schema::EngineRegistryEntry;
 
+/// Layout under `dir`:
+/// - `{name}/{version}.json` — the entry; written once, never overwritten
+/// - `{name}/{version}/{tag}.json` — sidecars (lint report, signatures, ...)
+/// - `{name}/state.json` — mutable package state: channels and yanks (updated under `{name}/.state.lock`)
+#[derive(Clone, Debug)]
 pub struct FileRegistry { pub dir: PathBuf }
+
+/// Registry failures callers map to distinct responses (409 vs 404 vs 400).
+#[derive(Debug)]
+pub enum RegistryError {
+    InvalidVersion(String),
+    /// A package name or attachment tag that is not a single, plain path segment.
+    InvalidName(String),
+    /// Same name/version already published with different content.
+    VersionExists { name: String, version: String, cid: String },
+    NotFound(String),
+    /// A channel points at a version that has since been yanked.
+    ChannelYanked { name: String, channel: String, version: String },
+}
+impl std::fmt::Display for RegistryError {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidVersion(v) => write!(f, "not a semver version: {v}"),
+            Self::InvalidName(n) => write!(f, "invalid package name: {n:?}"),
+            Self::VersionExists{ name, version, cid } => write!(f, "{name}@{version} is already published as {cid}"),
+            Self::NotFound(what) => write!(f, "not found: {what}"),
+            Self::ChannelYanked{ name, channel, version } => write!(f, "channel {name}:{channel} points at yanked {version}"),
+        }
+    }
+}
+impl std::error::Error for RegistryError {}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageState {
+    #[serde(default)]
+    pub channels: BTreeMap<String, String>,
+    /// version → reason
+    #[serde(default)]
+    pub yanked: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo { pub name: String, pub version: String, pub cid: String, pub yanked: bool }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page {
+    pub items: Vec<VersionInfo>,
+    /// Pass back as `cursor` for the next page; `None` on the last page.
+    pub next: Option<String>,
+}
+
+/// What a caller asked to run: a content address, a `name@range|channel`, or a bare chip id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnitRef { Cid(String), Named { name: String, req: String }, Id(String) }
+impl UnitRef {
+    pub fn parse(s:&str) -> Self {
+        if s.starts_with("b3:") || s.starts_with("cid:b3:") { return Self::Cid(s.to_string()); }
+        match s.split_once('@') {
+            Some((name, req)) => Self::Named{ name: name.into(), req: req.into() },
+            None => Self::Id(s.to_string()),
+        }
+    }
+}
+
+fn parse_version(v:&str) -> Result<Version> { Version::parse(v).map_err(|_| RegistryError::InvalidVersion(v.into()).into()) }
+
+/// Every name joined into a path goes through here: one segment, no separators, not hidden (`.`, `..`).
+fn check_name(name:&str) -> Result<()> {
+    if name.is_empty() || name.contains(['/', '\\', '@', '\0']) || name.starts_with('.') {
+        return Err(RegistryError::InvalidName(name.into()).into());
+    }
+    Ok(())
+}
+/// `name` and a semver `version` (semver strings are path-safe).
+fn check_entry(name:&str, version:&str) -> Result<()> { check_name(name)?; parse_version(version).map(|_| ()) }
+
+/// Unique sibling of `path` to stage a write in; not a `*.json`, so listings skip it.
+fn tmp_path(path:&Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!(".{name}.{}.tmp", ulid::Ulid::new()))
+}
+
+/// Write via temp file + rename so readers never see a half-written document.
+fn write_atomic(path:&Path, bytes:&[u8]) -> Result<()> {
+    let tmp = tmp_path(path);
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path).inspect_err(|_| { let _ = std::fs::remove_file(&tmp); })?;
+    Ok(())
+}
+
+/// Like [`write_atomic`] but never replaces `path`: the staged file is hard-linked into place,
+/// which fails with `AlreadyExists` if another writer got there first.
+fn write_new(path:&Path, bytes:&[u8]) -> std::io::Result<()> {
+    let tmp = tmp_path(path);
+    std::fs::write(&tmp, bytes)?;
+    let linked = std::fs::hard_link(&tmp, path);
+    let _ = std::fs::remove_file(&tmp);
+    linked
+}
+
+/// A writer older than this crashed holding the lock; the next one breaks it.
+const STALE_LOCK: std::time::Duration = std::time::Duration::from_secs(30);
+const LOCK_WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Exclusive lock on one package's state: a file created with `create_new`, so like [`write_new`] it also
+/// holds across processes sharing the registry dir. Removed on drop.
+struct StateLock(PathBuf);
+impl StateLock {
+    fn acquire(path:PathBuf) -> Result<Self> {
+        let deadline = std::time::Instant::now() + LOCK_WAIT;
+        loop {
+            match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self(path)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let age = std::fs::metadata(&path).and_then(|m| m.modified()).ok().and_then(|t| t.elapsed().ok());
+                    if age.is_some_and(|a| a > STALE_LOCK) { let _ = std::fs::remove_file(&path); continue; }
+                    if std::time::Instant::now() > deadline { bail!("{} is held by another writer", path.display()); }
+                    std::thread::sleep(std::time::Duration::from_millis(2));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+impl Drop for StateLock { fn drop(&mut self) { let _ = std::fs::remove_file(&self.0); } }
+
 impl FileRegistry {
     pub fn new<P: AsRef<Path>>(dir:P)->Self { Self{ dir: dir.as_ref().into() } }
+
+    fn entry_path(&self, name:&str, version:&str) -> PathBuf { self.dir.join(name).join(format!("{version}.json")) }
+    fn state_path(&self, name:&str) -> PathBuf { self.dir.join(name).join("state.json") }
+
+    /// Publish an entry. Versions are immutable: re-publishing the same CID is a no-op,
+    /// a different CID is [`RegistryError::VersionExists`].
     pub fn put(&self, entry:&EngineRegistryEntry) -> Result<PathBuf> {
-        std::fs::create_dir_all(&self.dir)?;
-        let path = self.dir.join(format!("{}_{}.json", entry.name, entry.version));
-        std::fs::write(&path, serde_json::to_string_pretty(entry)?)?;
-        Ok(path)
+        check_entry(&entry.name, &entry.version)?;
+        let path = self.entry_path(&entry.name, &entry.version);
+        std::fs::create_dir_all(path.parent().expect("entry path has a parent"))?;
+        match write_new(&path, serde_json::to_string_pretty(entry)?.as_bytes()) {
+            Ok(()) => Ok(path),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let existing = self.get(&entry.name, &entry.version)?.expect("entry exists");
+                if existing.cid == entry.cid { return Ok(path); }
+                Err(RegistryError::VersionExists{ name: entry.name.clone(), version: entry.version.clone(), cid: existing.cid }.into())
+            }
+            Err(e) => Err(e.into()),
+        }
     }
+
+    /// Exact version (yanked ones included, so pinned callers keep working).
     pub fn get(&self, name:&str, version:&str) -> Result<Option<EngineRegistryEntry>> {
-        let path = self.dir.join(format!("{}_{}.json", name, version));
+        check_entry(name, version)?;
+        let path = self.entry_path(name, version);
         if path.exists() {
             let s = std::fs::read_to_string(&path)?;
             Ok(Some(serde_json::from_str(&s)?))
@@ -21,4 +165,132 @@ impl FileRegistry {
             Ok(None)
         }
     }
+
+    pub fn state(&self, name:&str) -> Result<PackageState> {
+        check_name(name)?;
+        let p = self.state_path(name);
+        if !p.exists() { return Ok(PackageState::default()); }
+        Ok(serde_json::from_str(&std::fs::read_to_string(p)?)?)
+    }
+    fn save_state(&self, name:&str, st:&PackageState) -> Result<()> {
+        write_atomic(&self.state_path(name), serde_json::to_string_pretty(st)?.as_bytes())
+    }
+
+    /// All published versions of `name`, ascending by semver precedence.
+    pub fn versions(&self, name:&str) -> Result<Vec<Version>> {
+        check_name(name)?;
+        let dir = self.dir.join(name);
+        if !dir.is_dir() { return Ok(vec![]); }
+        let mut out = Vec::new();
+        for ent in std::fs::read_dir(dir)? {
+            let ent = ent?;
+            let fname = ent.file_name(); let fname = fname.to_string_lossy();
+            let Some(stem) = fname.strip_suffix(".json") else { continue };
+            if !ent.file_type()?.is_file() { continue; }
+            if let Ok(v) = Version::parse(stem) { out.push(v); }
+        }
+        out.sort();
+        Ok(out)
+    }
+
+    /// Resolve `req`: a channel name (`stable`, `canary`, ...), `latest`, or a semver range (`^1.2`, `~1.4.1`, `=2.0.0`).
+    /// Ranges pick the highest non-yanked match; channels return what they point at.
+    pub fn resolve(&self, name:&str, req:&str) -> Result<EngineRegistryEntry> {
+        let st = self.state(name)?;
+        if let Some(version) = st.channels.get(req) {
+            if st.yanked.contains_key(version) {
+                return Err(RegistryError::ChannelYanked{ name: name.into(), channel: req.into(), version: version.clone() }.into());
+            }
+            return self.get(name, version)?.ok_or_else(|| RegistryError::NotFound(format!("{name}@{version}")).into());
+        }
+        let range = if req == "latest" { VersionReq::STAR } else {
+            VersionReq::parse(req).map_err(|_| RegistryError::InvalidVersion(req.into()))?
+        };
+        let best = self.versions(name)?.into_iter().rev()
+            .find(|v| range.matches(v) && !st.yanked.contains_key(&v.to_string()))
+            .ok_or_else(|| RegistryError::NotFound(format!("{name}@{req}")))?;
+        Ok(self.get(name, &best.to_string())?.expect("listed version exists"))
+    }
+
+    /// Read-modify-write of `state.json` under the package's [`StateLock`], so concurrent updates all land.
+    fn update_state(&self, name:&str, change:impl FnOnce(&mut PackageState) -> Result<()>) -> Result<()> {
+        check_name(name)?;
+        let _lock = StateLock::acquire(self.dir.join(name).join(".state.lock"))?;
+        let mut st = self.state(name)?;
+        change(&mut st)?;
+        self.save_state(name, &st)
+    }
+
+    /// Point `channel` at an existing, non-yanked version.
+    pub fn set_channel(&self, name:&str, channel:&str, version:&str) -> Result<()> {
+        if VersionReq::parse(channel).is_ok() || channel == "latest" { bail!("channel name {channel:?} would shadow a version range"); }
+        if self.get(name, version)?.is_none() { return Err(RegistryError::NotFound(format!("{name}@{version}")).into()); }
+        self.update_state(name, |st| {
+            if st.yanked.contains_key(version) { bail!("{name}@{version} is yanked"); }
+            st.channels.insert(channel.into(), version.into());
+            Ok(())
+        })
+    }
+
+    /// Hide a version from range resolution; exact gets still work.
+    pub fn yank(&self, name:&str, version:&str, reason:&str) -> Result<()> {
+        if self.get(name, version)?.is_none() { return Err(RegistryError::NotFound(format!("{name}@{version}")).into()); }
+        self.update_state(name, |st| { st.yanked.insert(version.into(), reason.into()); Ok(()) })
+    }
+    pub fn unyank(&self, name:&str, version:&str) -> Result<()> {
+        if self.get(name, version)?.is_none() { return Err(RegistryError::NotFound(format!("{name}@{version}")).into()); }
+        self.update_state(name, |st| { st.yanked.remove(version); Ok(()) })
+    }
+
+    /// Page through versions (of one package, or of all packages by name), `limit` at a time.
+    /// The cursor is the `name@version` of the last item returned.
+    pub fn list(&self, name:Option<&str>, cursor:Option<&str>, limit:usize) -> Result<Page> {
+        let limit = limit.max(1);
+        let mut names: Vec<String> = match name {
+            Some(n) => vec![n.to_string()],
+            None if self.dir.is_dir() => std::fs::read_dir(&self.dir)?
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                // Dot-dirs are never packages (`check_name`); the tenant's spec store lives in `.cas`.
+                .filter(|n| !n.starts_with('.'))
+                .collect(),
+            None => vec![],
+        };
+        names.sort();
+        let after = cursor.and_then(|c| c.rsplit_once('@')).map(|(n, v)| (n.to_string(), Version::parse(v).ok()));
+        let mut items = Vec::new();
+        for n in &names {
+            if matches!(&after, Some((an, _)) if n < an) { continue; }
+            let st = self.state(n)?;
+            for v in self.versions(n)? {
+                if let Some((an, Some(av))) = &after { if n == an && &v <= av { continue; } }
+                if items.len() == limit {
+                    let last: &VersionInfo = items.last().expect("limit > 0");
+                    let next = Some(format!("{}@{}", last.name, last.version));
+                    return Ok(Page{ items, next });
+                }
+                let version = v.to_string();
+                let cid = self.get(n, &version)?.map(|e| e.cid).unwrap_or_default();
+                items.push(VersionInfo{ name: n.clone(), yanked: st.yanked.contains_key(&version), version, cid });
+            }
+        }
+        Ok(Page{ items, next: None })
+    }
+
+    /// Sidecar document stored next to an entry as `{name}/{version}/{tag}.json` (e.g. the wasm lint report).
+    pub fn put_attachment(&self, name:&str, version:&str, tag:&str, doc:&serde_json::Value) -> Result<PathBuf> {
+        check_entry(name, version)?; check_name(tag)?;
+        let dir = self.dir.join(name).join(version);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{tag}.json"));
+        write_atomic(&path, serde_json::to_string_pretty(doc)?.as_bytes())?;
+        Ok(path)
+    }
+    pub fn get_attachment(&self, name:&str, version:&str, tag:&str) -> Result<Option<serde_json::Value>> {
+        check_entry(name, version)?; check_name(tag)?;
+        let path = self.dir.join(name).join(version).join(format!("{tag}.json"));
+        if !path.exists() { return Ok(None); }
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(&path)?)?))
+    }
 }
@@ -3,6 +3,12 @@ use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
 
+pub mod schema;
+pub mod file_registry;
+pub mod cas;
+pub mod provenance;
+pub mod url_signer;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObjectMeta {
     pub bucket: String,
@@ -19,24 +25,90 @@ pub trait RegistryProvider: Send + Sync {
     async fn head(&self, bucket: &str, key: &str) -> Result<ObjectMeta>;
     async fn presign_get(&self, bucket: &str, key: &str, ttl_secs: u64) -> Result<String>;
     async fn presign_put(&self, bucket: &str, key: &str, ttl_secs: u64) -> Result<String>;
+    /// Keys in `bucket` starting with `prefix` (any depth, `/`-separated).
+    async fn list(&self, bucket: &str, prefix: &str) -> Result<Vec<String>>;
+    async fn delete(&self, bucket: &str, key: &str) -> Result<()>;
 }
 
 #[cfg(feature="fs")]
 pub mod fs_registry {
     use super::*;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use tokio::fs;
     use blake3::Hasher;
+    use crate::url_signer::{UrlClaims, UrlSigner};
+
+    /// A bucket or key that would leave the registry root; servers map it to 400.
+    #[derive(Debug)]
+    pub struct InvalidObject(pub String);
+    impl std::fmt::Display for InvalidObject {
+        fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "invalid object {}", self.0) }
+    }
+    impl std::error::Error for InvalidObject {}
+
+    /// One path segment: not empty, `.` or `..`, no separators or NUL.
+    fn segment_ok(s:&str) -> bool { !matches!(s, "" | "." | "..") && !s.contains(['/', '\\', '\0']) }
+
+    /// `bucket` is one segment; `key` is relative and made of segments (no leading `/`, no `//`).
+    pub fn check_object(bucket:&str, key:&str) -> Result<()> {
+        if segment_ok(bucket) && key.split('/').all(segment_ok) { return Ok(()); }
+        Err(InvalidObject(format!("{bucket}/{key}")).into())
+    }
 
+    /// Fails unless `p`, symlinks resolved, lies under `base` (both must exist).
+    async fn contained(base:&Path, p:&Path) -> Result<()> {
+        let (base, real) = (fs::canonicalize(base).await?, fs::canonicalize(p).await?);
+        if real.starts_with(&base) { Ok(()) } else { Err(InvalidObject(p.display().to_string()).into()) }
+    }
+
+    /// With a [`UrlSigner`], `presign_*` issue signed HTTP URLs; without one, bare `fs://bucket/key` handles.
     #[derive(Clone)]
-    pub struct FsRegistry { pub root: PathBuf }
-    impl FsRegistry { pub fn new(root: impl Into<PathBuf>) -> Self { Self{ root: root.into() } } }
+    pub struct FsRegistry { pub root: PathBuf, signer: Option<UrlSigner> }
+    impl FsRegistry {
+        pub fn new(root: impl Into<PathBuf>) -> Self { Self{ root: root.into(), signer: None } }
+        pub fn with_signer(mut self, signer: UrlSigner) -> Self { self.signer = Some(signer); self }
+        pub fn signer(&self) -> Option<&UrlSigner> { self.signer.as_ref() }
+
+        /// The registry under `root/<scope>`, its URLs signed with a key derived for `scope`:
+        /// a URL issued for one scope (tenant) is refused by every other.
+        pub fn scoped(&self, scope:&str) -> Result<Self> {
+            if !segment_ok(scope) { return Err(InvalidObject(scope.into()).into()); }
+            Ok(Self{ root: self.root.join(scope), signer: self.signer.as_ref().map(|s| s.scoped(scope)) })
+        }
+
+        /// Path of `bucket/key`, checked with [`check_object`].
+        pub fn object_path(&self, bucket:&str, key:&str) -> Result<PathBuf> {
+            check_object(bucket, key)?;
+            Ok(self.root.join(bucket).join(key))
+        }
+
+        /// [`Self::object_path`] of an existing object whose real path (symlinks resolved) stays in its bucket.
+        pub async fn existing_path(&self, bucket:&str, key:&str) -> Result<PathBuf> {
+            let p = self.object_path(bucket, key)?;
+            contained(&self.root.join(bucket), &p).await?;
+            Ok(p)
+        }
+
+        /// URL for `verb` on `bucket/key`, valid `ttl_secs`, limited to `max` bytes, bound to `grant` if any.
+        pub fn presign(&self, verb:&str, bucket:&str, key:&str, ttl_secs:u64, max:Option<u64>, grant:Option<&str>) -> String {
+            match &self.signer {
+                Some(s) => {
+                    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+                    s.sign(&UrlClaims{ verb: verb.into(), bucket: bucket.into(), key: key.into(), exp: now.saturating_add(ttl_secs), max, grant: grant.map(str::to_string) })
+                }
+                None => format!("fs://{}/{}", bucket, key),
+            }
+        }
+    }
 
     #[async_trait]
     impl RegistryProvider for FsRegistry {
         async fn put_bytes(&self, bucket:&str, key:&str, bytes:&[u8]) -> Result<ObjectMeta> {
-            let p = self.root.join(bucket).join(key);
-            if let Some(dir) = p.parent() { fs::create_dir_all(dir).await?; }
+            let p = self.object_path(bucket, key)?;
+            let base = self.root.join(bucket);
+            if let Some(dir) = p.parent() { fs::create_dir_all(dir).await?; contained(&base, dir).await?; }
+            // An existing object may be a symlink; never write through one that leaves the bucket.
+            if fs::symlink_metadata(&p).await.is_ok() { contained(&base, &p).await?; }
             fs::write(&p, bytes).await?;
             let mut h = Hasher::new(); h.update(bytes);
             Ok(ObjectMeta{
@@ -48,19 +120,44 @@ pub mod fs_registry {
             })
         }
         async fn get_bytes(&self, bucket:&str, key:&str) -> Result<Vec<u8>> {
-            let p = self.root.join(bucket).join(key);
-            Ok(fs::read(p).await?)
+            Ok(fs::read(self.existing_path(bucket, key).await?).await?)
         }
         async fn head(&self, bucket:&str, key:&str) -> Result<ObjectMeta> {
-            let p = self.root.join(bucket).join(key);
-            let md = fs::metadata(&p).await?;
+            let md = fs::metadata(self.existing_path(bucket, key).await?).await?;
             Ok(ObjectMeta{ bucket: bucket.to_string(), key: key.to_string(), size: Some(md.len()), etag: None, cid_b3: None })
         }
-        async fn presign_get(&self, bucket:&str, key:&str, _ttl_secs:u64) -> Result<String> {
-            Ok(format!("fs://{}/{}", bucket, key))
+        async fn presign_get(&self, bucket:&str, key:&str, ttl_secs:u64) -> Result<String> {
+            Ok(self.presign("GET", bucket, key, ttl_secs, None, None))
         }
-        async fn presign_put(&self, bucket:&str, key:&str, _ttl_secs:u64) -> Result<String> {
-            Ok(format!("fs://{}/{}", bucket, key))
+        async fn presign_put(&self, bucket:&str, key:&str, ttl_secs:u64) -> Result<String> {
+            Ok(self.presign("PUT", bucket, key, ttl_secs, None, None))
+        }
+        async fn list(&self, bucket:&str, prefix:&str) -> Result<Vec<String>> {
+            let dir = prefix.rfind('/').map(|i| &prefix[..i]);
+            if !segment_ok(bucket) || dir.is_some_and(|d| !d.split('/').all(segment_ok)) {
+                return Err(InvalidObject(format!("{bucket}/{prefix}")).into());
+            }
+            let base = self.root.join(bucket);
+            // Only descend into the directory part of the prefix; filter the rest by name.
+            let start = match dir { Some(d) => base.join(d), None => base.clone() };
+            let mut out = Vec::new(); let mut stack = vec![start];
+            while let Some(dir) = stack.pop() {
+                let mut rd = match fs::read_dir(&dir).await { Ok(rd) => rd, Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue, Err(e) => return Err(e.into()) };
+                while let Some(ent) = rd.next_entry().await? {
+                    let p = ent.path();
+                    if ent.file_type().await?.is_dir() { stack.push(p); continue; }
+                    let key = p.strip_prefix(&base)?.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+                    if key.starts_with(prefix) { out.push(key); }
+                }
+            }
+            out.sort();
+            Ok(out)
+        }
+        async fn delete(&self, bucket:&str, key:&str) -> Result<()> {
+            match fs::remove_file(self.object_path(bucket, key)?).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
         }
     }
 }
@@ -118,5 +215,19 @@ pub mod s3_registry {
         async fn presign_put(&self, bucket:&str, key:&str, _ttl_secs:u64) -> Result<String> {
             Ok(format!("s3://{}/{}", bucket, key))
         }
+        async fn list(&self, bucket:&str, prefix:&str) -> Result<Vec<String>> {
+            let mut out = Vec::new(); let mut token = None;
+            loop {
+                let page = self.client.list_objects_v2().bucket(bucket).prefix(prefix)
+                    .set_continuation_token(token).send().await?;
+                out.extend(page.contents().iter().filter_map(|o| o.key().map(|k| k.to_string())));
+                match page.next_continuation_token() { Some(t) => token = Some(t.to_string()), None => break }
+            }
+            Ok(out)
+        }
+        async fn delete(&self, bucket:&str, key:&str) -> Result<()> {
+            self.client.delete_object().bucket(bucket).key(key).send().await?;
+            Ok(())
+        }
     }
 }
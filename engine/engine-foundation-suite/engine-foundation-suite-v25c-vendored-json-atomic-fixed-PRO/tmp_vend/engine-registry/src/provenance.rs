@@ -0,0 +1,177 @@
+//! Who may publish what, and a signed snapshot of the whole registry.
+//!
+//! Entries carry a publisher seal (ADR-0001: ed25519 over blake3(JSON✯Atomic(entry)) with `seal.sig`
+//! blanked, exactly like grant seals). A [`TrustPolicy`] — distributed out of band, like a TUF root —
+//! maps key ids to public keys, says which keys may publish which names, and which keys may sign
+//! the [`RegistryIndex`]. The index (TUF targets + snapshot in one document) pins every
+//! `name@version` to its artifact CID and to the CID of the signed entry, so a client holding the
+//! policy and the index can verify an entry offline before loading the unit.
+use anyhow::{anyhow, bail, Context, Result};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use engine_auth::grant::GrantSeal;
+use engine_auth::signing::{sign_doc, verify_doc, verifying_key_b64, SEAL_ALG};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::file_registry::FileRegistry;
+use crate::schema::EngineRegistryEntry;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishRule {
+    /// Exact names, `prefix*`, or `*`.
+    pub names: Vec<String>,
+    pub kids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustPolicy {
+    pub kind: String,                      // "registry.trust.v1"
+    /// kid → base64 ed25519 public key
+    pub keys: BTreeMap<String, String>,
+    pub publishers: Vec<PublishRule>,
+    /// Keys allowed to sign the index snapshot.
+    pub index_keys: Vec<String>,
+}
+
+fn name_matches(pattern:&str, name:&str) -> bool {
+    match pattern.strip_suffix('*') { Some(prefix) => name.starts_with(prefix), None => pattern == name }
+}
+
+impl TrustPolicy {
+    pub fn load(path:impl AsRef<Path>) -> Result<Self> {
+        let p = path.as_ref();
+        let policy: Self = serde_json::from_slice(&std::fs::read(p).with_context(|| format!("read {}", p.display()))?)?;
+        if policy.kind != "registry.trust.v1" { bail!("{} is not a registry.trust.v1 document", p.display()); }
+        Ok(policy)
+    }
+    pub fn key(&self, kid:&str) -> Result<VerifyingKey> {
+        verifying_key_b64(self.keys.get(kid).ok_or_else(|| anyhow!("unknown key {kid}"))?)
+    }
+    pub fn may_publish(&self, kid:&str, name:&str) -> bool {
+        self.publishers.iter().any(|r| r.kids.iter().any(|k| k == kid) && r.names.iter().any(|p| name_matches(p, name)))
+    }
+}
+
+fn unsigned_seal(kid:&str) -> GrantSeal { GrantSeal{ alg: SEAL_ALG.into(), kid: kid.into(), sig: String::new() } }
+
+fn check_seal(seal:Option<&GrantSeal>, policy:&TrustPolicy) -> Result<(GrantSeal, VerifyingKey)> {
+    let seal = seal.ok_or_else(|| anyhow!("unsigned"))?.clone();
+    if seal.alg != SEAL_ALG { bail!("unsupported seal alg {}", seal.alg); }
+    let vk = policy.key(&seal.kid)?;
+    Ok((seal, vk))
+}
+
+/// Seal `entry` as publisher `kid`.
+pub fn sign_entry(key:&SigningKey, kid:&str, entry:&mut EngineRegistryEntry) -> Result<()> {
+    entry.seal = Some(unsigned_seal(kid));
+    let sig = sign_doc(key, entry)?;
+    entry.seal.as_mut().expect("seal just set").sig = sig;
+    Ok(())
+}
+
+/// Signature valid and the signer may publish `entry.name`; returns the publisher kid.
+pub fn verify_entry(entry:&EngineRegistryEntry, policy:&TrustPolicy) -> Result<String> {
+    let (seal, vk) = check_seal(entry.seal.as_ref(), policy).with_context(|| format!("{}@{}", entry.name, entry.version))?;
+    if !policy.may_publish(&seal.kid, &entry.name) { bail!("key {} may not publish {}", seal.kid, entry.name); }
+    let mut unsigned = entry.clone();
+    unsigned.seal = Some(unsigned_seal(&seal.kid));
+    verify_doc(&vk, &unsigned, &seal.sig).with_context(|| format!("{}@{}", entry.name, entry.version))?;
+    Ok(seal.kid)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexTarget {
+    pub cid: String,
+    /// CID of the signed entry document; binds publisher, meta and seal, not just the artifact.
+    pub entry_cid: String,
+    pub yanked: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryIndex {
+    pub kind: String,                      // "registry.index.v1"
+    /// Strictly increasing; clients refuse anything older than what they last accepted (rollback).
+    pub version: u64,
+    /// Unix seconds; clients refuse a stale snapshot (freeze).
+    pub expires_at: u64,
+    /// "name@version" → target
+    pub targets: BTreeMap<String, IndexTarget>,
+    /// name → channel → version
+    pub channels: BTreeMap<String, BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seal: Option<GrantSeal>,
+}
+
+impl RegistryIndex {
+    /// The entry is the one the index pinned (compare after [`verify_index`]).
+    pub fn check_entry(&self, entry:&EngineRegistryEntry) -> Result<&IndexTarget> {
+        let key = format!("{}@{}", entry.name, entry.version);
+        let t = self.targets.get(&key).ok_or_else(|| anyhow!("{key} is not in index v{}", self.version))?;
+        if t.cid != entry.cid { bail!("{key}: artifact cid {} differs from index {}", entry.cid, t.cid); }
+        let got = engine_core::json_atomic::compute_cid(entry)?;
+        if t.entry_cid != got { bail!("{key}: entry cid {got} differs from index {}", t.entry_cid); }
+        Ok(t)
+    }
+}
+
+/// Unsigned snapshot of everything in `reg`.
+pub fn snapshot(reg:&FileRegistry, version:u64, expires_at:u64) -> Result<RegistryIndex> {
+    let mut targets = BTreeMap::new();
+    let mut channels = BTreeMap::new();
+    let mut cursor = None;
+    loop {
+        let page = reg.list(None, cursor.as_deref(), 500)?;
+        for v in &page.items {
+            let entry = reg.get(&v.name, &v.version)?.ok_or_else(|| anyhow!("{}@{} vanished", v.name, v.version))?;
+            targets.insert(format!("{}@{}", v.name, v.version), IndexTarget{
+                cid: entry.cid.clone(), entry_cid: engine_core::json_atomic::compute_cid(&entry)?, yanked: v.yanked,
+            });
+            if !channels.contains_key(&v.name) {
+                let st = reg.state(&v.name)?;
+                if !st.channels.is_empty() { channels.insert(v.name.clone(), st.channels); }
+            }
+        }
+        match page.next { Some(c) => cursor = Some(c), None => break }
+    }
+    Ok(RegistryIndex{ kind: "registry.index.v1".into(), version, expires_at, targets, channels, seal: None })
+}
+
+pub fn sign_index(key:&SigningKey, kid:&str, index:&mut RegistryIndex) -> Result<()> {
+    index.seal = Some(unsigned_seal(kid));
+    let sig = sign_doc(key, index)?;
+    index.seal.as_mut().expect("seal just set").sig = sig;
+    Ok(())
+}
+
+/// Offline check: signed by an index key, not expired at `now_unix`, not older than `min_version`.
+pub fn verify_index(index:&RegistryIndex, policy:&TrustPolicy, now_unix:u64, min_version:u64) -> Result<()> {
+    if index.kind != "registry.index.v1" { bail!("not a registry.index.v1 document"); }
+    let (seal, vk) = check_seal(index.seal.as_ref(), policy).context("index")?;
+    if !policy.index_keys.contains(&seal.kid) { bail!("key {} may not sign the index", seal.kid); }
+    let mut unsigned = index.clone();
+    unsigned.seal = Some(unsigned_seal(&seal.kid));
+    verify_doc(&vk, &unsigned, &seal.sig).context("index")?;
+    if index.expires_at <= now_unix { bail!("index v{} expired at {}", index.version, index.expires_at); }
+    if index.version < min_version { bail!("index v{} is older than v{min_version} (rollback)", index.version); }
+    Ok(())
+}
+
+/// Snapshot, sign and write `{dir}/index.json`, bumping the previous version.
+pub fn publish_index(reg:&FileRegistry, key:&SigningKey, kid:&str, ttl_secs:u64) -> Result<RegistryIndex> {
+    let prev = read_index(reg)?.map(|i| i.version).unwrap_or(0);
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+    let mut index = snapshot(reg, prev + 1, now + ttl_secs)?;
+    sign_index(key, kid, &mut index)?;
+    std::fs::create_dir_all(&reg.dir)?;
+    let tmp = reg.dir.join("index.json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(&index)?)?;
+    std::fs::rename(tmp, reg.dir.join("index.json"))?;
+    Ok(index)
+}
+
+pub fn read_index(reg:&FileRegistry) -> Result<Option<RegistryIndex>> {
+    let p = reg.dir.join("index.json");
+    if !p.exists() { return Ok(None); }
+    Ok(Some(serde_json::from_slice(&std::fs::read(p)?)?))
+}
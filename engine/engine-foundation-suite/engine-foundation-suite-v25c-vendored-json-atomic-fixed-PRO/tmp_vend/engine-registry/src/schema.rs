@@ -9,4 +9,7 @@ pub struct EngineRegistryEntry {
     pub version: String,
     pub cid: String,              // content address for the artifact
     pub meta: serde_json::Value,  // free-form metadata
+    /// Publisher signature over the canonical entry (ADR-0001, same scheme as grant seals).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seal: Option<engine_auth::grant::GrantSeal>,
 }
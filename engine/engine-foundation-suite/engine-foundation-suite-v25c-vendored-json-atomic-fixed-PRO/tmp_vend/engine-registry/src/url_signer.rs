@@ -0,0 +1,116 @@
+//! Presigned HTTP URLs for backends that have none of their own (the fs registry).
+//!
+//! `{base}/{bucket}/{key}?verb=GET&exp=<unix secs>[&max=<bytes>][&grant=<id>]&sig=<hex>`, where `sig` is
+//! HMAC-SHA256 over `fs.presign.v2\n{verb}\n{bucket}\n{key}\n{exp}\n{max}\n{grant}` (empty when absent).
+//! Whoever serves `base` checks a request with [`UrlSigner::check`]: same model as an S3 presigned URL,
+//! the key being shared between the issuer and the server only.
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+const DOMAIN: &str = "fs.presign.v2";
+
+/// What a URL grants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UrlClaims {
+    pub verb: String,
+    pub bucket: String,
+    pub key: String,
+    /// Unix seconds.
+    pub exp: u64,
+    /// Bytes a PUT may upload or a GET may return.
+    pub max: Option<u64>,
+    /// Grant issued with the URL; the server refuses the URL once it is revoked.
+    pub grant: Option<String>,
+}
+
+/// Query string of a presigned URL.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UrlQuery {
+    pub verb: String,
+    pub exp: u64,
+    pub max: Option<u64>,
+    pub grant: Option<String>,
+    pub sig: String,
+}
+
+/// Refusals a server maps to distinct responses (403 vs 405).
+#[derive(Debug, PartialEq, Eq)]
+pub enum UrlError {
+    BadSignature,
+    Expired,
+    /// The URL was issued for another verb.
+    WrongVerb { granted: String },
+}
+impl std::fmt::Display for UrlError {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadSignature => f.write_str("presigned URL signature does not match"),
+            Self::Expired => f.write_str("presigned URL expired"),
+            Self::WrongVerb{ granted } => write!(f, "presigned URL is for {granted}"),
+        }
+    }
+}
+impl std::error::Error for UrlError {}
+
+/// Percent-encodes all but unreserved characters and `/`.
+fn encode_path(s:&str) -> String {
+    s.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => (b as char).to_string(),
+        _ => format!("%{b:02X}"),
+    }).collect()
+}
+
+fn hex(bytes:&[u8]) -> String { bytes.iter().map(|b| format!("{b:02x}")).collect() }
+
+fn unhex(s:&str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) { return None; }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[derive(Clone)]
+pub struct UrlSigner { key: Vec<u8>, base: String }
+
+impl UrlSigner {
+    /// `base`: where objects are served, e.g. `http://127.0.0.1:8080/fs`.
+    pub fn new(key:impl Into<Vec<u8>>, base:impl Into<String>) -> Self {
+        Self{ key: key.into(), base: base.into().trim_end_matches('/').to_string() }
+    }
+
+    /// Signer for one scope (tenant): key = HMAC(key, `scope:<scope>`), base = `{base}/{scope}`.
+    pub fn scoped(&self, scope:&str) -> Self {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC takes any key length");
+        mac.update(format!("scope:{scope}").as_bytes());
+        Self{ key: mac.finalize().into_bytes().to_vec(), base: format!("{}/{}", self.base, encode_path(scope)) }
+    }
+
+    fn mac(&self, c:&UrlClaims) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC takes any key length");
+        let max = c.max.map(|m| m.to_string()).unwrap_or_default();
+        let grant = c.grant.as_deref().unwrap_or_default();
+        mac.update(format!("{DOMAIN}\n{}\n{}\n{}\n{}\n{max}\n{grant}", c.verb, c.bucket, c.key, c.exp).as_bytes());
+        mac
+    }
+
+    pub fn sign(&self, c:&UrlClaims) -> String {
+        let sig = hex(&self.mac(c).finalize().into_bytes());
+        let max = c.max.map(|m| format!("&max={m}")).unwrap_or_default();
+        let grant = c.grant.as_deref().map(|g| format!("&grant={}", encode_path(g))).unwrap_or_default();
+        format!("{}/{}/{}?verb={}&exp={}{max}{grant}&sig={sig}", self.base, encode_path(&c.bucket), encode_path(&c.key), c.verb, c.exp)
+    }
+
+    /// A URL for `verb` valid `ttl_secs` from `now` (unix seconds), bound to no grant.
+    pub fn presign(&self, verb:&str, bucket:&str, key:&str, now:u64, ttl_secs:u64, max:Option<u64>) -> String {
+        self.sign(&UrlClaims{ verb: verb.into(), bucket: bucket.into(), key: key.into(), exp: now.saturating_add(ttl_secs), max, grant: None })
+    }
+
+    /// Checks a request for `bucket`/`key` (path-decoded) made with `verb`; returns what the URL grants.
+    pub fn check(&self, verb:&str, bucket:&str, key:&str, q:&UrlQuery, now:u64) -> Result<UrlClaims, UrlError> {
+        let c = UrlClaims{ verb: q.verb.clone(), bucket: bucket.into(), key: key.into(), exp: q.exp, max: q.max, grant: q.grant.clone() };
+        let sig = unhex(&q.sig).ok_or(UrlError::BadSignature)?;
+        self.mac(&c).verify_slice(&sig).map_err(|_| UrlError::BadSignature)?;
+        if now > c.exp { return Err(UrlError::Expired); }
+        if !c.verb.eq_ignore_ascii_case(verb) { return Err(UrlError::WrongVerb{ granted: c.verb }); }
+        Ok(c)
+    }
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "fs")]
+use engine_registry::cas::{cid_of, Cas, IntegrityError};
+use engine_registry::file_registry::FileRegistry;
+use engine_registry::fs_registry::FsRegistry;
+use engine_registry::schema::EngineRegistryEntry;
+
+#[tokio::test]
+async fn keys_alias_one_blob_and_reads_are_verified() {
+    let dir = tempfile::tempdir().unwrap();
+    let cas = Cas::new(FsRegistry::new(dir.path()), "cas");
+    let a = cas.put_keyed("units", "a.wasm", b"same").await.unwrap();
+    let b = cas.put_keyed("units", "b.wasm", b"same").await.unwrap();
+    assert_eq!(a.cid_b3, b.cid_b3);
+    assert_eq!(cas.head_keyed("units", "b.wasm").await.unwrap().cid_b3, Some(cid_of(b"same")));
+
+    let cid = a.cid_b3.unwrap();
+    let href = format!("tdln://objects/cid:{cid}");
+    assert_eq!(cas.resolve_href(&href).await.unwrap(), b"same");
+
+    // Tamper with the blob on disk: reads must fail instead of returning the bytes.
+    let hex = &cid[3..];
+    std::fs::write(dir.path().join("cas/objects/b3").join(&hex[..2]).join(&hex[2..4]).join(hex), b"evil").unwrap();
+    assert!(cas.get_keyed("units", "a.wasm").await.unwrap_err().to_string().contains("integrity"));
+    assert!(cas.find(&cid).await.unwrap_err().downcast_ref::<IntegrityError>().is_some());
+    assert_eq!(cas.find(&cid_of(b"never stored")).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn gc_keeps_aliased_and_pinned_blobs() {
+    let dir = tempfile::tempdir().unwrap();
+    let cas = Cas::new(FsRegistry::new(dir.path()), "cas");
+    cas.put_keyed("units", "keep", b"kept").await.unwrap();
+    let pinned = cas.put(b"pinned").await.unwrap().cid_b3.unwrap();
+    let orphan = cas.put(b"orphan").await.unwrap().cid_b3.unwrap();
+    cas.put_keyed("units", "gone", b"dropped").await.unwrap();
+    cas.remove_key("units", "gone").await.unwrap();
+
+    let report = cas.gc(std::slice::from_ref(&pinned)).await.unwrap();
+    assert_eq!(report.kept, 2);
+    let mut removed = report.removed.clone(); removed.sort();
+    let mut want = vec![orphan.clone(), cid_of(b"dropped")]; want.sort();
+    assert_eq!(removed, want);
+    assert!(cas.get(&orphan).await.is_err());
+    assert_eq!(cas.get(&pinned).await.unwrap(), b"pinned");
+}
+
+#[tokio::test]
+async fn a_spec_store_inside_the_registry_is_not_a_package() {
+    let dir = tempfile::tempdir().unwrap();
+    let cid = Cas::new(FsRegistry::new(dir.path()), ".cas").put(br#"{"id":"quota"}"#).await.unwrap().cid_b3.unwrap();
+    let reg = FileRegistry::new(dir.path());
+    reg.put(&EngineRegistryEntry{ kind: "engine.registry.entry.v1".into(), id: "quota-1.0.0".into(), name: "quota".into(), version: "1.0.0".into(), cid, meta: serde_json::json!({}), seal: None }).unwrap();
+
+    let page = reg.list(None, None, 10).unwrap();
+    assert_eq!(page.items.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), ["quota"]);
+    let key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+    let index = engine_registry::provenance::publish_index(&reg, &key, "k", 60).unwrap();
+    assert_eq!(index.targets.keys().collect::<Vec<_>>(), ["quota@1.0.0"]);
+}
@@ -0,0 +1,108 @@
+use engine_registry::file_registry::{FileRegistry, RegistryError, UnitRef};
+use engine_registry::schema::EngineRegistryEntry;
+
+fn entry(name: &str, version: &str, cid: &str) -> EngineRegistryEntry {
+    EngineRegistryEntry{ kind: "engine.registry.entry.v1".into(), id: format!("{name}-{version}"), name: name.into(), version: version.into(), cid: cid.into(), meta: serde_json::json!({}), seal: None }
+}
+
+fn registry() -> (tempfile::TempDir, FileRegistry) {
+    let d = tempfile::tempdir().unwrap();
+    let reg = FileRegistry::new(d.path());
+    (d, reg)
+}
+
+#[test]
+fn versions_are_immutable() {
+    let (_dir, reg) = registry();
+    reg.put(&entry("quota", "1.0.0", "b3:aa")).unwrap();
+    reg.put(&entry("quota", "1.0.0", "b3:aa")).unwrap();
+    let err = reg.put(&entry("quota", "1.0.0", "b3:bb")).unwrap_err();
+    assert!(matches!(err.downcast_ref::<RegistryError>(), Some(RegistryError::VersionExists{ .. })));
+    assert!(reg.put(&entry("quota", "one", "b3:cc")).is_err());
+}
+
+#[test]
+fn ranges_channels_and_yanks() {
+    let (_dir, reg) = registry();
+    for (v, c) in [("1.2.0", "b3:120"), ("1.4.1", "b3:141"), ("1.10.0", "b3:1100"), ("2.0.0-rc.1", "b3:200rc"), ("2.0.0", "b3:200")] {
+        reg.put(&entry("quota", v, c)).unwrap();
+    }
+    assert_eq!(reg.resolve("quota", "^1.2").unwrap().version, "1.10.0");
+    assert_eq!(reg.resolve("quota", "~1.4").unwrap().version, "1.4.1");
+    assert_eq!(reg.resolve("quota", "latest").unwrap().version, "2.0.0");
+
+    reg.yank("quota", "1.10.0", "bad quota math").unwrap();
+    assert_eq!(reg.resolve("quota", "^1.2").unwrap().version, "1.4.1");
+    assert_eq!(reg.get("quota", "1.10.0").unwrap().unwrap().cid, "b3:1100");
+
+    reg.set_channel("quota", "stable", "1.4.1").unwrap();
+    reg.set_channel("quota", "canary", "2.0.0-rc.1").unwrap();
+    assert_eq!(reg.resolve("quota", "canary").unwrap().cid, "b3:200rc");
+    assert!(reg.set_channel("quota", "stable", "1.10.0").is_err());
+    reg.yank("quota", "1.4.1", "superseded").unwrap();
+    assert!(matches!(reg.resolve("quota", "stable").unwrap_err().downcast_ref::<RegistryError>(), Some(RegistryError::ChannelYanked{ .. })));
+}
+
+#[test]
+fn concurrent_state_updates_all_land() {
+    let (_dir, reg) = registry();
+    let versions: Vec<String> = (0..16).map(|i| format!("1.{i}.0")).collect();
+    for v in &versions { reg.put(&entry("quota", v, "b3:aa")).unwrap(); }
+    std::thread::scope(|s| {
+        for v in &versions { let reg = &reg; s.spawn(move || reg.yank("quota", v, "bulk").unwrap()); }
+        s.spawn(|| reg.set_channel("quota", "stable", "1.0.0").unwrap_or(()));
+    });
+    let st = reg.state("quota").unwrap();
+    assert_eq!(st.yanked.len(), 16);
+    assert!(!reg.dir.join("quota/.state.lock").exists());
+}
+
+#[test]
+fn list_paginates_across_packages() {
+    let (_dir, reg) = registry();
+    for v in ["1.0.0", "1.1.0", "1.2.0"] { reg.put(&entry("alpha", v, "b3:a")).unwrap(); }
+    for v in ["0.1.0", "0.2.0"] { reg.put(&entry("beta", v, "b3:b")).unwrap(); }
+    reg.set_channel("alpha", "stable", "1.1.0").unwrap();
+    reg.put_attachment("alpha", "1.0.0", "lint", &serde_json::json!({"ok":true})).unwrap();
+
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = reg.list(None, cursor.as_deref(), 2).unwrap();
+        seen.extend(page.items.iter().map(|i| format!("{}@{}", i.name, i.version)));
+        match page.next { Some(c) => cursor = Some(c), None => break }
+    }
+    assert_eq!(seen, ["alpha@1.0.0", "alpha@1.1.0", "alpha@1.2.0", "beta@0.1.0", "beta@0.2.0"]);
+}
+
+#[test]
+fn unit_refs() {
+    assert_eq!(UnitRef::parse("b3:abc"), UnitRef::Cid("b3:abc".into()));
+    assert_eq!(UnitRef::parse("quota@^1.2"), UnitRef::Named{ name: "quota".into(), req: "^1.2".into() });
+    assert_eq!(UnitRef::parse("allow_admin_quota"), UnitRef::Id("allow_admin_quota".into()));
+}
+
+#[test]
+fn names_and_versions_stay_inside_the_registry() {
+    let (_dir, reg) = registry();
+    reg.put(&entry("quota", "1.0.0", "b3:aa")).unwrap();
+    let invalid = |e:anyhow::Error| matches!(e.downcast_ref::<RegistryError>(), Some(RegistryError::InvalidName(_) | RegistryError::InvalidVersion(_)));
+    for name in ["../quota", "..", "a/b", "", ".hidden"] {
+        assert!(invalid(reg.put(&entry(name, "1.0.0", "b3:aa")).unwrap_err()), "{name}");
+        assert!(invalid(reg.get(name, "1.0.0").unwrap_err()), "{name}");
+        assert!(invalid(reg.resolve(name, "latest").unwrap_err()), "{name}");
+        assert!(invalid(reg.state(name).unwrap_err()), "{name}");
+        assert!(invalid(reg.yank(name, "1.0.0", "x").unwrap_err()), "{name}");
+        assert!(invalid(reg.unyank(name, "1.0.0").unwrap_err()), "{name}");
+        assert!(invalid(reg.set_channel(name, "stable", "1.0.0").unwrap_err()), "{name}");
+        assert!(invalid(reg.list(Some(name), None, 10).unwrap_err()), "{name}");
+    }
+    assert!(invalid(reg.get("quota", "../../x").unwrap_err()));
+    assert!(invalid(reg.put_attachment("quota", "1.0.0", "../lint", &serde_json::json!({})).unwrap_err()));
+    assert!(invalid(reg.get_attachment("quota", "../1.0.0", "lint").unwrap_err()));
+
+    // Unyanking something never published is a 404, and leaves no state behind.
+    assert!(matches!(reg.unyank("ghost", "1.0.0").unwrap_err().downcast_ref::<RegistryError>(), Some(RegistryError::NotFound(_))));
+    assert!(!reg.dir.join("ghost").exists());
+    assert_eq!(reg.versions("quota").unwrap().len(), 1);
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "fs")]
+use engine_registry::RegistryProvider;
+use engine_registry::fs_registry::{check_object, FsRegistry, InvalidObject};
+
+#[tokio::test]
+async fn objects_stay_inside_their_bucket() {
+    let dir = tempfile::tempdir().unwrap();
+    let reg = FsRegistry::new(dir.path()).scoped("alice").unwrap();
+    reg.put_bytes("b", "dir/a.txt", b"hi").await.unwrap();
+    assert_eq!(reg.get_bytes("b", "dir/a.txt").await.unwrap(), b"hi");
+    assert!(dir.path().join("alice/b/dir/a.txt").is_file());
+
+    for (bucket, key) in [("b", "/etc/passwd"), ("b", "../a.txt"), ("b", "dir/../../x"), ("b", "dir//a.txt"), ("b", ""), ("..", "a.txt"), ("b/c", "a.txt")] {
+        assert!(check_object(bucket, key).unwrap_err().is::<InvalidObject>(), "{bucket}/{key}");
+        assert!(reg.put_bytes(bucket, key, b"x").await.unwrap_err().is::<InvalidObject>(), "{bucket}/{key}");
+        assert!(reg.get_bytes(bucket, key).await.unwrap_err().is::<InvalidObject>(), "{bucket}/{key}");
+    }
+    assert!(FsRegistry::new(dir.path()).scoped("..").is_err());
+
+    // A symlink out of the bucket is neither read nor written through.
+    let outside = dir.path().join("outside.txt");
+    std::fs::write(&outside, b"secret").unwrap();
+    #[cfg(unix)] {
+        std::os::unix::fs::symlink(&outside, dir.path().join("alice/b/link")).unwrap();
+        assert!(reg.get_bytes("b", "link").await.unwrap_err().is::<InvalidObject>());
+        assert!(reg.put_bytes("b", "link", b"x").await.unwrap_err().is::<InvalidObject>());
+        assert_eq!(std::fs::read(&outside).unwrap(), b"secret");
+    }
+}
@@ -0,0 +1,75 @@
+use base64::Engine;
+use ed25519_dalek::SigningKey;
+use engine_auth::signing::key_id;
+use engine_registry::file_registry::FileRegistry;
+use engine_registry::provenance::*;
+use engine_registry::schema::EngineRegistryEntry;
+use rand_core::OsRng;
+
+fn entry(name: &str, version: &str) -> EngineRegistryEntry {
+    EngineRegistryEntry{ kind: "engine.registry.entry.v1".into(), id: format!("{name}-{version}"), name: name.into(), version: version.into(), cid: format!("b3:{name}{version}"), meta: serde_json::json!({}), seal: None }
+}
+
+fn policy(publisher: &SigningKey, indexer: &SigningKey) -> TrustPolicy {
+    let b64 = |k: &SigningKey| base64::engine::general_purpose::STANDARD.encode(k.verifying_key().as_bytes());
+    let (p, i) = (key_id(&publisher.verifying_key()), key_id(&indexer.verifying_key()));
+    TrustPolicy{
+        kind: "registry.trust.v1".into(),
+        keys: [(p.clone(), b64(publisher)), (i.clone(), b64(indexer))].into(),
+        publishers: vec![PublishRule{ names: vec!["acme-*".into()], kids: vec![p] }],
+        index_keys: vec![i],
+    }
+}
+
+#[test]
+fn entries_need_an_authorized_publisher() {
+    let (publisher, indexer) = (SigningKey::generate(&mut OsRng), SigningKey::generate(&mut OsRng));
+    let policy = policy(&publisher, &indexer);
+    let kid = key_id(&publisher.verifying_key());
+
+    let mut e = entry("acme-quota", "1.0.0");
+    assert!(verify_entry(&e, &policy).is_err());
+    sign_entry(&publisher, &kid, &mut e).unwrap();
+    assert_eq!(verify_entry(&e, &policy).unwrap(), kid);
+
+    let mut tampered = e.clone();
+    tampered.cid = "b3:evil".into();
+    assert!(verify_entry(&tampered, &policy).is_err());
+
+    let mut other = entry("globex-quota", "1.0.0");
+    sign_entry(&publisher, &kid, &mut other).unwrap();
+    assert!(verify_entry(&other, &policy).unwrap_err().to_string().contains("may not publish"));
+}
+
+#[test]
+fn index_snapshot_verifies_offline() {
+    let (publisher, indexer) = (SigningKey::generate(&mut OsRng), SigningKey::generate(&mut OsRng));
+    let policy = policy(&publisher, &indexer);
+    let dir = tempfile::tempdir().unwrap();
+    let reg = FileRegistry::new(dir.path());
+    let mut e = entry("acme-quota", "1.0.0");
+    sign_entry(&publisher, &key_id(&publisher.verifying_key()), &mut e).unwrap();
+    reg.put(&e).unwrap();
+    reg.set_channel("acme-quota", "stable", "1.0.0").unwrap();
+
+    let ikid = key_id(&indexer.verifying_key());
+    let v1 = publish_index(&reg, &indexer, &ikid, 3600).unwrap();
+    let v2 = publish_index(&reg, &indexer, &ikid, 3600).unwrap();
+    assert_eq!((v1.version, v2.version), (1, 2));
+    assert_eq!(v2.channels["acme-quota"]["stable"], "1.0.0");
+
+    let now = v2.expires_at - 10;
+    verify_index(&v2, &policy, now, 2).unwrap();
+    assert!(verify_index(&v1, &policy, now, 2).unwrap_err().to_string().contains("rollback"));
+    assert!(verify_index(&v2, &policy, v2.expires_at, 0).unwrap_err().to_string().contains("expired"));
+    v2.check_entry(&e).unwrap();
+
+    let mut forged = e.clone();
+    forged.meta = serde_json::json!({"note":"swapped"});
+    assert!(v2.check_entry(&forged).is_err());
+
+    // The publisher key signs entries, not the index.
+    let mut rogue = v2.clone();
+    sign_index(&publisher, &key_id(&publisher.verifying_key()), &mut rogue).unwrap();
+    assert!(verify_index(&rogue, &policy, now, 0).is_err());
+}
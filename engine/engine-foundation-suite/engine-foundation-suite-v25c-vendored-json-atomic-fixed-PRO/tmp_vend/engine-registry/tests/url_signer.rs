@@ -0,0 +1,39 @@
+use engine_registry::url_signer::{UrlClaims, UrlError, UrlQuery, UrlSigner};
+
+/// Splits a presigned URL into its (still encoded) path after `base` and its query.
+fn split(url:&str, base:&str) -> (String, UrlQuery) {
+    let (path, query) = url.strip_prefix(base).unwrap().split_once('?').unwrap();
+    let get = |k:&str| query.split('&').find_map(|kv| kv.strip_prefix(&format!("{k}="))).map(str::to_string);
+    (path.to_string(), UrlQuery{ verb: get("verb").unwrap(), exp: get("exp").unwrap().parse().unwrap(), max: get("max").map(|m| m.parse().unwrap()), grant: get("grant"), sig: get("sig").unwrap() })
+}
+
+#[test]
+fn urls_bind_verb_object_expiry_and_size() {
+    let base = "http://127.0.0.1:8080/fs";
+    let s = UrlSigner::new(b"k1".to_vec(), format!("{base}/"));
+    let url = s.presign("PUT", "b", "dir/a b.txt", 1_000, 60, Some(10));
+    let (path, q) = split(&url, base);
+    assert_eq!(path, "/b/dir/a%20b.txt");
+    assert_eq!((q.exp, q.max), (1_060, Some(10)));
+
+    let c = s.check("PUT", "b", "dir/a b.txt", &q, 1_060).unwrap();
+    assert_eq!((c.verb.as_str(), c.max), ("PUT", Some(10)));
+    assert_eq!(s.check("PUT", "b", "dir/a b.txt", &q, 1_061), Err(UrlError::Expired));
+    assert_eq!(s.check("GET", "b", "dir/a b.txt", &q, 1_000), Err(UrlError::WrongVerb{ granted: "PUT".into() }));
+    assert_eq!(s.check("PUT", "b", "dir/other.txt", &q, 1_000), Err(UrlError::BadSignature));
+    assert_eq!(s.check("PUT", "b", "dir/a b.txt", &UrlQuery{ max: Some(1 << 30), ..q.clone() }, 1_000), Err(UrlError::BadSignature));
+    assert_eq!(s.check("PUT", "b", "dir/a b.txt", &UrlQuery{ exp: 9_999, ..q.clone() }, 1_000), Err(UrlError::BadSignature));
+    assert_eq!(s.check("PUT", "b", "dir/a b.txt", &UrlQuery{ grant: Some("g".into()), ..q.clone() }, 1_000), Err(UrlError::BadSignature));
+    assert_eq!(UrlSigner::new(b"k2".to_vec(), base).check("PUT", "b", "dir/a b.txt", &q, 1_000), Err(UrlError::BadSignature));
+}
+
+#[test]
+fn the_grant_is_part_of_the_signature() {
+    let base = "http://127.0.0.1:8080/fs";
+    let s = UrlSigner::new(b"k1".to_vec(), base);
+    let url = s.sign(&UrlClaims{ verb: "GET".into(), bucket: "b".into(), key: "o".into(), exp: 1_060, max: None, grant: Some("01GRANT".into()) });
+    let (_, q) = split(&url, base);
+    assert_eq!(s.check("GET", "b", "o", &q, 1_000).unwrap().grant.as_deref(), Some("01GRANT"));
+    assert_eq!(s.check("GET", "b", "o", &UrlQuery{ grant: Some("01OTHER".into()), ..q.clone() }, 1_000), Err(UrlError::BadSignature));
+    assert_eq!(s.check("GET", "b", "o", &UrlQuery{ grant: None, ..q }, 1_000), Err(UrlError::BadSignature));
+}
@@ -1,47 +1,58 @@
+use anyhow::{Context, Result};
+use base64::Engine as _;
+use ed25519_dalek::pkcs8::DecodePublicKey;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use engine_core::model::ExecutionReceipt;
+use engine_core::verify::{replay, Code, Verdict};
+use serde_json::{json, Value as Json};
+use std::path::Path;
 
-use anyhow::{Result, anyhow};
-use serde_json::Value as Json;
-use std::fs;
-use ed25519_dalek::{Verifier, PublicKey};
+const USAGE: &str = "usage: receipt-verify <receipt.json> --unit <unit.json|yaml> [--pubkey pub.pem]";
 
-fn main()->Result<()> {
-    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
-    if args.is_empty() { eprintln!("usage: receipt-verify <receipt_card.json> [--pubkey pub.pem]"); std::process::exit(2); }
-    let card_path = args.remove(0);
-    let pubkey_pem = match args.as_slice() {
-        ["--pubkey", p] => Some(p.to_string()),
-        _ => None
+/// Replays the receipt against the unit and, with `--pubkey`, checks `proof.signature` over the replayed payload.
+fn verify(receipt:&Path, unit:&Path, pubkey:Option<&Path>) -> Verdict {
+    let loaded = (|| -> Result<_> {
+        let r: ExecutionReceipt = serde_json::from_slice(&std::fs::read(receipt).context("receipt")?).context("receipt")?;
+        let chip = engine_loader::unit_from_spec(&engine_loader::read_spec(unit).context("unit")?)?;
+        let pk = pubkey.map(|p| -> Result<_> { VerifyingKey::from_public_key_pem(&std::fs::read_to_string(p).context("pubkey")?).context("pubkey") }).transpose()?;
+        Ok((r, chip, pk))
+    })();
+    let (r, chip, pk) = match loaded {
+        Ok(l) => l,
+        Err(e) => {
+            let mut v = Verdict{ kind: "receipt.verdict.v1".into(), ok: false, chip_id: String::new(), input_cid: String::new(), output_cid: String::new(), signed_payload_cid: None, divergence: None, unsigned: false, payload: vec![] };
+            v.fail(Code::Malformed, "", Json::Null, json!(format!("{e:#}")));
+            return v;
+        }
     };
-    let s = fs::read_to_string(&card_path)?;
-    let card: Json = serde_json::from_str(&s)?;
-
-    // Basic structure checks
-    let proof = card.get("proof").ok_or_else(|| anyhow!("missing proof"))?;
-    let chain = proof.get("hash_chain").ok_or_else(|| anyhow!("missing hash_chain"))?.as_array().ok_or_else(|| anyhow!("hash_chain not array"))?;
-
-    if chain.len() < 2 {
-        return Err(anyhow!("hash_chain too short"));
-    }
-
-    // Optional signature verification
-    if let Some(sig_b64) = proof.get("signature").and_then(|v| v.as_str()) {
-        if let Some(pem_path) = pubkey_pem {
-            let pem = std::fs::read_to_string(pem_path)?;
-            let pk = PublicKey::from_public_key_pem(&pem)?;
-            let msg = serde_json::to_string(&chain)?;
-            let sig = base64::decode(sig_b64)?;
-            pk.verify(msg.as_bytes(), &ed25519_dalek::Signature::from_bytes(&sig)?)?;
-            println!("🔏 signature: OK");
-        } else {
-            println!("ℹ️ signature present but no --pubkey provided; skipped");
+    let mut v = replay(&chip, &r);
+    if let (true, false, Some(pk)) = (v.ok, v.unsigned, pk) {
+        match &r.proof.signature {
+            None => v.fail(Code::SignatureMissing, "/proof/signature", json!("ed25519 signature"), Json::Null),
+            Some(b64) => {
+                let sig = base64::prelude::BASE64_STANDARD.decode(b64).ok().and_then(|b| Signature::from_slice(&b).ok());
+                let valid = sig.is_some_and(|s| pk.verify(&v.payload, &s).is_ok());
+                if !valid {
+                    v.fail(Code::SignatureInvalid, "/proof/signature", json!(v.signed_payload_cid), json!(b64));
+                }
+            }
         }
-    } else {
-        println!("ℹ️ no signature");
     }
+    v
+}
 
-    // Surface CIDs
-    let input_cid = card.pointer("/input/cid").and_then(|v| v.as_str()).unwrap_or("");
-    let output_cid = card.pointer("/output/cid").and_then(|v| v.as_str()).unwrap_or("");
-    println!("✅ receipt OK  | input CID: {input_cid} | output CID: {output_cid} | chain_len: {}", chain.len());
+fn main()->Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (receipt, unit, pubkey) = match args.as_slice() {
+        [r, u, p, ..] if u == "--unit" => match args.get(3..) {
+            Some([]) => (r, p, None),
+            Some([k, pk]) if k == "--pubkey" => (r, p, Some(pk)),
+            _ => { eprintln!("{USAGE}"); std::process::exit(2) }
+        },
+        _ => { eprintln!("{USAGE}"); std::process::exit(2) }
+    };
+    let v = verify(Path::new(receipt), Path::new(unit), pubkey.map(Path::new));
+    println!("{}", serde_json::to_string_pretty(&v)?);
+    if !v.ok { std::process::exit(1); }
     Ok(())
 }
@@ -0,0 +1,341 @@
+//! SIRP: signed capsules and receipts that carry a run from the party asking for it to the one executing it.
+//!
+//! The issuer seals an INTENT [`Capsule`] naming the run; the executor acknowledges it with a
+//! [`Delivery`] receipt, records the work in an [`Execution`] receipt and answers with a RESULT
+//! capsule. Every document is sealed as in ADR-0001 by the DID it speaks for (`from`,
+//! `receiver_did`, `executor_did`): ed25519 over blake3 of its JSON✯Atomic bytes with the
+//! `signature` field left out. Documents point to each other by the CID of their sealed bytes, and
+//! [`Chain::verify`] checks the four links intent → delivery → execution → result.
+//!
+//! Between engines, the issuer queues the sealed intent in its [`outbox::Outbox`] and posts it to the
+//! peer's inbox (the endpoint [`DidKeys`] lists for the peer's DID). The peer [`Chain::admit`]s it,
+//! runs it and answers with the rest of the chain, or refuses it with a sealed [`Chain::reject`].
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine as _;
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use engine_auth::grant::GrantSeal;
+use engine_auth::signing::{key_id, sign_doc, verify_doc, verifying_key_b64, SEAL_ALG};
+use engine_core::json_atomic::compute_cid;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub mod outbox;
+pub mod store;
+
+pub const CAPSULE: &str = "sirp.capsule.v1";
+pub const DELIVERY: &str = "sirp.receipt.delivery.v1";
+pub const EXECUTION: &str = "sirp.receipt.execution.v1";
+/// `aad` key of an INTENT an engine sealed itself, for a caller that holds no key: the DID it ran for.
+pub const ATTESTED_FOR: &str = "attested_for";
+/// Ref kinds capsules use for what they carry.
+pub const RUN_MANIFEST: &str = "run.manifest";
+pub const ENGINE_RECEIPT: &str = "engine.receipt";
+
+fn now() -> String { chrono::Utc::now().to_rfc3339() }
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CapsuleType { Intent, Result }
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Ref { pub kind: String, pub cid: String }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Capsule {
+    pub kind: String,                      // "sirp.capsule.v1"
+    #[serde(rename = "type")]
+    pub capsule_type: CapsuleType,
+    pub from: String,
+    pub to: String,
+    pub ts: String,
+    pub refs: Vec<Ref>,
+    #[serde(default)]
+    pub aad: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<GrantSeal>,
+}
+
+impl Capsule {
+    fn new(capsule_type:CapsuleType, from:&str, to:&str, refs:Vec<Ref>) -> Self {
+        Self{ kind: CAPSULE.into(), capsule_type, from: from.into(), to: to.into(), ts: now(), refs, aad: json!({}), signature: None }
+    }
+    /// `from` asks `to` to execute the run `run_cid`.
+    pub fn intent(from:&str, to:&str, run_cid:&str) -> Self {
+        Self::new(CapsuleType::Intent, from, to, vec![Ref{ kind: RUN_MANIFEST.into(), cid: run_cid.into() }])
+    }
+    /// Answer to `intent`: the CID of the engine receipt the run produced.
+    pub fn result(intent:&Capsule, receipt_cid:&str) -> Result<Self> {
+        Ok(Self::new(CapsuleType::Result, &intent.to, &intent.from, vec![
+            Ref{ kind: CAPSULE.into(), cid: intent.cid()? },
+            Ref{ kind: ENGINE_RECEIPT.into(), cid: receipt_cid.into() },
+        ]))
+    }
+    /// CID of the first ref of `kind`.
+    pub fn find(&self, kind:&str) -> Option<&str> { self.refs.iter().find(|r| r.kind == kind).map(|r| r.cid.as_str()) }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Outcome { Delivered, Rejected }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    pub kind: String,                      // "sirp.receipt.delivery.v1"
+    pub capsule_cid: String,
+    pub sender_did: String,
+    pub receiver_did: String,
+    pub ts_received: String,
+    pub outcome: Outcome,
+    /// Why a capsule was rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<GrantSeal>,
+}
+
+impl Delivery {
+    /// Receipt for the sealed `capsule`, to be sealed by its recipient.
+    pub fn new(capsule:&Capsule, outcome:Outcome, reason:Option<String>) -> Result<Self> {
+        Ok(Self{
+            kind: DELIVERY.into(), capsule_cid: capsule.cid()?, sender_did: capsule.from.clone(), receiver_did: capsule.to.clone(),
+            ts_received: now(), outcome, reason, signature: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Execution {
+    pub kind: String,                      // "sirp.receipt.execution.v1"
+    pub capsule_cid: String,
+    pub executor_did: String,
+    pub ts_done: String,
+    /// CID of the engine receipt.
+    pub result_cid: String,
+    pub runtime_used: bool,
+    /// Certified-runtime execution record, when `runtime_used`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eer_cid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<GrantSeal>,
+}
+
+impl Execution {
+    pub fn new(intent:&Capsule, result_cid:&str, runtime_used:bool, eer_cid:Option<String>) -> Result<Self> {
+        Ok(Self{
+            kind: EXECUTION.into(), capsule_cid: intent.cid()?, executor_did: intent.to.clone(), ts_done: now(),
+            result_cid: result_cid.into(), runtime_used, eer_cid, signature: None,
+        })
+    }
+}
+
+/// Public keys by DID.
+pub trait DidResolver {
+    fn key(&self, did:&str) -> Option<VerifyingKey>;
+}
+impl<F: Fn(&str) -> Option<VerifyingKey>> DidResolver for F {
+    fn key(&self, did:&str) -> Option<VerifyingKey> { self(did) }
+}
+
+/// `sirp.dids.v1`: DID → base64 ed25519 public key, for the parties an engine exchanges capsules with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DidKeys {
+    pub kind: String,                      // "sirp.dids.v1"
+    pub keys: BTreeMap<String, String>,
+    /// DID → inbox URL of peer engines capsules can be sent to (`.../sirp/inbox`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub endpoints: BTreeMap<String, String>,
+}
+
+impl Default for DidKeys {
+    fn default() -> Self { Self{ kind: "sirp.dids.v1".into(), keys: BTreeMap::new(), endpoints: BTreeMap::new() } }
+}
+
+impl DidKeys {
+    pub fn load(path:impl AsRef<Path>) -> Result<Self> {
+        let p = path.as_ref();
+        let d: Self = serde_json::from_slice(&std::fs::read(p).with_context(|| format!("read {}", p.display()))?)?;
+        if d.kind != "sirp.dids.v1" { bail!("{} is not a sirp.dids.v1 document", p.display()); }
+        Ok(d)
+    }
+    pub fn with(mut self, did:&str, vk:&VerifyingKey) -> Self {
+        self.keys.insert(did.into(), B64.encode(vk.as_bytes()));
+        self
+    }
+    pub fn at(mut self, did:&str, inbox:&str) -> Self {
+        self.endpoints.insert(did.into(), inbox.into());
+        self
+    }
+    pub fn endpoint(&self, did:&str) -> Option<&str> { self.endpoints.get(did).map(String::as_str) }
+}
+
+impl DidResolver for DidKeys {
+    fn key(&self, did:&str) -> Option<VerifyingKey> { self.keys.get(did).and_then(|k| verifying_key_b64(k).ok()) }
+}
+
+#[derive(Debug)]
+pub enum SirpError {
+    /// No key for the DID a document speaks for.
+    UnknownDid(String),
+    /// Missing, malformed or not matching the signer's key.
+    BadSignature(String),
+    /// Documents that do not point to each other as the protocol requires.
+    Broken(String),
+}
+impl std::fmt::Display for SirpError {
+    fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownDid(did) => write!(f, "no key for {did}"),
+            Self::BadSignature(why) | Self::Broken(why) => f.write_str(why),
+        }
+    }
+}
+impl std::error::Error for SirpError {}
+
+/// A sealed SIRP document. The seal covers the document without its `signature` field.
+pub trait Signed: Serialize + Clone {
+    /// DID whose key seals the document.
+    fn signer(&self) -> &str;
+    fn signature(&self) -> &Option<GrantSeal>;
+    fn signature_mut(&mut self) -> &mut Option<GrantSeal>;
+
+    fn sign(&mut self, key:&SigningKey) -> Result<()> {
+        *self.signature_mut() = None;
+        let sig = sign_doc(key, self)?;
+        *self.signature_mut() = Some(GrantSeal{ alg: SEAL_ALG.into(), kid: key_id(&key.verifying_key()), sig });
+        Ok(())
+    }
+    /// Checks the seal against the key `dids` holds for [`Signed::signer`]; errors are [`SirpError`].
+    fn verify(&self, dids:&dyn DidResolver) -> Result<()> {
+        let seal = self.signature().as_ref().ok_or_else(|| SirpError::BadSignature(format!("unsigned document from {}", self.signer())))?;
+        if seal.alg != SEAL_ALG { return Err(SirpError::BadSignature(format!("unsupported seal alg {}", seal.alg)).into()); }
+        let vk = dids.key(self.signer()).ok_or_else(|| SirpError::UnknownDid(self.signer().into()))?;
+        let mut unsigned = self.clone();
+        *unsigned.signature_mut() = None;
+        verify_doc(&vk, &unsigned, &seal.sig).map_err(|e| SirpError::BadSignature(format!("{}: {e:#}", self.signer())))?;
+        Ok(())
+    }
+    /// CID of the sealed document; what other documents point to.
+    fn cid(&self) -> Result<String> { compute_cid(self) }
+}
+
+impl Signed for Capsule {
+    fn signer(&self) -> &str { &self.from }
+    fn signature(&self) -> &Option<GrantSeal> { &self.signature }
+    fn signature_mut(&mut self) -> &mut Option<GrantSeal> { &mut self.signature }
+}
+impl Signed for Delivery {
+    fn signer(&self) -> &str { &self.receiver_did }
+    fn signature(&self) -> &Option<GrantSeal> { &self.signature }
+    fn signature_mut(&mut self) -> &mut Option<GrantSeal> { &mut self.signature }
+}
+impl Signed for Execution {
+    fn signer(&self) -> &str { &self.executor_did }
+    fn signature(&self) -> &Option<GrantSeal> { &self.signature }
+    fn signature_mut(&mut self) -> &mut Option<GrantSeal> { &mut self.signature }
+}
+
+/// A DID and the key it signs with.
+#[derive(Clone, Copy)]
+pub struct Party<'a> { pub did: &'a str, pub key: &'a SigningKey }
+
+/// One run's four documents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chain {
+    pub intent: Capsule,
+    pub delivery: Delivery,
+    pub execution: Execution,
+    pub result: Capsule,
+}
+
+impl Chain {
+    /// Issuer and executor both at hand (one engine running for a caller): seal all four documents.
+    pub fn local(issuer:Party, executor:Party, run_cid:&str, receipt_cid:&str) -> Result<Self> {
+        let mut intent = Capsule::intent(issuer.did, executor.did, run_cid);
+        intent.sign(issuer.key)?;
+        Self::answer(intent, executor.key, receipt_cid)
+    }
+
+    /// Engine alone (a caller that authenticates with a token, not a key): the engine seals the INTENT as
+    /// itself, naming `on_behalf_of` under `aad.attested_for`. It vouches for the request it received; nothing
+    /// here is signed by the caller.
+    pub fn attested(engine:Party, on_behalf_of:&str, run_cid:&str, receipt_cid:&str) -> Result<Self> {
+        let mut intent = Capsule::intent(engine.did, engine.did, run_cid);
+        intent.aad = json!({ ATTESTED_FOR: on_behalf_of });
+        intent.sign(engine.key)?;
+        Self::answer(intent, engine.key, receipt_cid)
+    }
+
+    /// Who sealed the INTENT on the caller's behalf, when the engine did.
+    pub fn attested_for(&self) -> Option<&str> {
+        (self.intent.from == self.intent.to).then(|| self.intent.aad.get(ATTESTED_FOR)?.as_str()).flatten()
+    }
+
+    /// Executor side: deliver the sealed `intent` and answer it with the receipt `receipt_cid`.
+    pub fn answer(intent:Capsule, key:&SigningKey, receipt_cid:&str) -> Result<Self> {
+        let mut delivery = Delivery::new(&intent, Outcome::Delivered, None)?;
+        delivery.sign(key)?;
+        let mut execution = Execution::new(&intent, receipt_cid, false, None)?;
+        execution.sign(key)?;
+        let mut result = Capsule::result(&intent, receipt_cid)?;
+        result.sign(key)?;
+        Ok(Self{ intent, delivery, execution, result })
+    }
+
+    /// Executor side: whether the engine `me` takes `intent` at all — an INTENT addressed to it,
+    /// sealed by a DID `dids` knows. Errors are [`SirpError`].
+    pub fn admit(intent:&Capsule, me:&str, dids:&dyn DidResolver) -> Result<()> {
+        if intent.kind != CAPSULE || intent.capsule_type != CapsuleType::Intent { return Err(SirpError::Broken("expected an INTENT capsule".into()).into()); }
+        if intent.to != me { return Err(SirpError::Broken(format!("intent is for {}, not {me}", intent.to)).into()); }
+        intent.verify(dids)
+    }
+
+    /// Executor side: sealed refusal of an admitted `intent` that will not run (bad request, failed run).
+    pub fn reject(intent:&Capsule, key:&SigningKey, reason:&str) -> Result<Delivery> {
+        let mut d = Delivery::new(intent, Outcome::Rejected, Some(reason.into()))?;
+        d.sign(key)?;
+        Ok(d)
+    }
+
+    /// Every seal, and every link between the documents; errors are [`SirpError`].
+    pub fn verify(&self, dids:&dyn DidResolver) -> Result<()> {
+        let (i, d, e, r) = (&self.intent, &self.delivery, &self.execution, &self.result);
+        i.verify(dids)?;
+        d.verify(dids)?;
+        e.verify(dids)?;
+        r.verify(dids)?;
+        let intent_cid = i.cid()?;
+        let broken = |why:String| -> Result<()> { Err(SirpError::Broken(why).into()) };
+        if i.capsule_type != CapsuleType::Intent || r.capsule_type != CapsuleType::Result { return broken("expected an INTENT and a RESULT capsule".into()); }
+        if d.capsule_cid != intent_cid { return broken(format!("delivery is for {}, not the intent {intent_cid}", d.capsule_cid)); }
+        if (d.sender_did.as_str(), d.receiver_did.as_str()) != (i.from.as_str(), i.to.as_str()) { return broken("delivery parties differ from the intent's".into()); }
+        if d.outcome != Outcome::Delivered { return broken(format!("intent was not delivered ({:?})", d.outcome)); }
+        if e.capsule_cid != intent_cid || e.executor_did != i.to { return broken("execution does not answer the intent".into()); }
+        if (r.from.as_str(), r.to.as_str()) != (i.to.as_str(), i.from.as_str()) { return broken("result parties are not the intent's reversed".into()); }
+        if r.find(CAPSULE) != Some(intent_cid.as_str()) { return broken("result does not point to the intent".into()); }
+        if r.find(ENGINE_RECEIPT) != Some(e.result_cid.as_str()) { return broken("result and execution name different receipts".into()); }
+        Ok(())
+    }
+
+    /// `{kind, cid}` of each document, in protocol order; what a card lists under `refs`.
+    pub fn refs(&self) -> Result<Vec<Ref>> {
+        Ok(vec![
+            Ref{ kind: CAPSULE.into(), cid: self.intent.cid()? },
+            Ref{ kind: DELIVERY.into(), cid: self.delivery.cid()? },
+            Ref{ kind: EXECUTION.into(), cid: self.execution.cid()? },
+            Ref{ kind: CAPSULE.into(), cid: self.result.cid()? },
+        ])
+    }
+
+    /// The documents by role (`intent`, `delivery`, `execution`, `result`).
+    pub fn docs(&self) -> Result<Vec<(&'static str, Value)>> {
+        Ok(vec![
+            ("intent", serde_json::to_value(&self.intent)?),
+            ("delivery", serde_json::to_value(&self.delivery)?),
+            ("execution", serde_json::to_value(&self.execution)?),
+            ("result", serde_json::to_value(&self.result)?),
+        ])
+    }
+}
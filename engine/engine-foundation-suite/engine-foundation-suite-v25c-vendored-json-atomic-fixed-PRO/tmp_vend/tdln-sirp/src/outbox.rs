@@ -0,0 +1,178 @@
+//! Capsules on their way to a peer engine, kept on disk so delivery survives restarts.
+//!
+//! One file per capsule, `{dir}/{pending,delivered,dead}/<hex>.json` by the capsule's CID. A failed
+//! attempt leaves the entry pending until `next_at`, each retry waiting twice as long as the last
+//! ([`Backoff`]); after `max_attempts`, or once the peer refuses the capsule with a sealed
+//! [`Delivery`], the entry moves to `dead/`. Answers are only recorded once they verify. Senders
+//! [`Outbox::claim`] an entry before posting it, so an entry is in flight at most once.
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::store::file_name;
+use crate::{Capsule, Chain, Delivery, DidResolver, Outcome, Signed, SirpError};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status { Pending, Delivered, Dead }
+impl Status {
+    fn dir(self) -> &'static str {
+        match self { Self::Pending => "pending", Self::Delivered => "delivered", Self::Dead => "dead" }
+    }
+}
+
+/// Retry `n` (1-based) waits `base_ms * 2^(n-1)`, at most `max_ms`; attempt `max_attempts` is the last.
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff { pub base_ms: u64, pub max_ms: u64, pub max_attempts: u32 }
+impl Default for Backoff {
+    fn default() -> Self { Self{ base_ms: 1_000, max_ms: 300_000, max_attempts: 8 } }
+}
+impl Backoff {
+    pub fn delay_ms(&self, attempts:u32) -> u64 {
+        self.base_ms.saturating_mul(1u64 << attempts.saturating_sub(1).min(32)).min(self.max_ms)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub kind: String,                      // "sirp.outbox.entry.v1"
+    pub capsule_cid: String,
+    /// Peer inbox the capsule is posted to.
+    pub endpoint: String,
+    pub capsule: Capsule,
+    pub status: Status,
+    pub attempts: u32,
+    /// Not retried before this time (RFC 3339).
+    pub next_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// The peer's answer, once delivered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain: Option<Chain>,
+    /// The peer's sealed refusal, when that is why the entry is dead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejection: Option<Delivery>,
+}
+
+pub struct Outbox { dir: PathBuf, backoff: Backoff, lock: Mutex<()> }
+
+impl Outbox {
+    pub fn new(dir:impl Into<PathBuf>, backoff:Backoff) -> Self { Self{ dir: dir.into(), backoff, lock: Mutex::new(()) } }
+
+    fn path(&self, status:Status, cid:&str) -> Result<PathBuf> { Ok(self.dir.join(status.dir()).join(file_name(cid)?)) }
+
+    fn read(&self, status:Status, cid:&str) -> Result<Option<Entry>> {
+        match std::fs::read(self.path(status, cid)?) {
+            Ok(b) => Ok(Some(serde_json::from_slice(&b)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes `e` under its status, then drops the copy it had under `from`.
+    fn write(&self, e:&Entry, from:Status) -> Result<()> {
+        let path = self.path(e.status, &e.capsule_cid)?;
+        std::fs::create_dir_all(path.parent().unwrap_or(self.dir.as_path()))?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(e)?)?;
+        std::fs::rename(tmp, &path)?;
+        if from != e.status { let _ = std::fs::remove_file(self.path(from, &e.capsule_cid)?); }
+        Ok(())
+    }
+
+    /// Queues a sealed capsule for `endpoint`, due now. Queuing it again returns the entry it already has.
+    pub fn enqueue(&self, capsule:Capsule, endpoint:&str) -> Result<Entry> {
+        if capsule.signature.is_none() { bail!("capsule to {} is not sealed", capsule.to); }
+        let cid = capsule.cid()?;
+        let _g = self.lock.lock().unwrap();
+        if let Some(e) = self.get(&cid)? { return Ok(e); }
+        let e = Entry{
+            kind: "sirp.outbox.entry.v1".into(), capsule_cid: cid, endpoint: endpoint.into(), capsule,
+            status: Status::Pending, attempts: 0, next_at: Utc::now().to_rfc3339(), last_error: None, chain: None, rejection: None,
+        };
+        self.write(&e, Status::Pending)?;
+        Ok(e)
+    }
+
+    pub fn get(&self, cid:&str) -> Result<Option<Entry>> {
+        for s in [Status::Pending, Status::Delivered, Status::Dead] {
+            if let Some(e) = self.read(s, cid)? { return Ok(Some(e)); }
+        }
+        Ok(None)
+    }
+
+    /// Entries with `status`, oldest capsule first.
+    pub fn list(&self, status:Status) -> Result<Vec<Entry>> {
+        let rd = match std::fs::read_dir(self.dir.join(status.dir())) {
+            Ok(rd) => rd,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        let mut out = vec![];
+        for f in rd {
+            let p = f?.path();
+            if p.extension().and_then(|x| x.to_str()) != Some("json") { continue; }
+            out.push(serde_json::from_slice::<Entry>(&std::fs::read(&p)?)?);
+        }
+        out.sort_by(|a, b| (&a.capsule.ts, &a.capsule_cid).cmp(&(&b.capsule.ts, &b.capsule_cid)));
+        Ok(out)
+    }
+
+    /// Pending entries whose `next_at` has passed.
+    pub fn due(&self, now:DateTime<Utc>) -> Result<Vec<Entry>> {
+        Ok(self.list(Status::Pending)?.into_iter()
+            .filter(|e| DateTime::parse_from_rfc3339(&e.next_at).map_or(true, |t| t <= now))
+            .collect())
+    }
+
+    /// Takes the pending entry `cid` for one attempt if it is due: its `next_at` moves `lease` ahead, so nobody
+    /// else claims it while the attempt runs (and it comes due again if the attempt never settles). `None` when
+    /// it is not pending, or not due — e.g. claimed by another attempt.
+    pub fn claim(&self, cid:&str, now:DateTime<Utc>, lease:chrono::Duration) -> Result<Option<Entry>> {
+        let _g = self.lock.lock().unwrap();
+        let Some(mut e) = self.read(Status::Pending, cid)? else { return Ok(None) };
+        if DateTime::parse_from_rfc3339(&e.next_at).is_ok_and(|t| t > now) { return Ok(None); }
+        e.next_at = (now + lease).to_rfc3339();
+        self.write(&e, Status::Pending)?;
+        Ok(Some(e))
+    }
+
+    /// The pending entry `cid` after `f`; `None` when it is no longer pending (settled by another attempt).
+    fn settle(&self, cid:&str, f:impl FnOnce(&mut Entry)) -> Result<Option<Entry>> {
+        let _g = self.lock.lock().unwrap();
+        let Some(mut e) = self.read(Status::Pending, cid)? else { return Ok(None) };
+        e.attempts += 1;
+        f(&mut e);
+        self.write(&e, Status::Pending)?;
+        Ok(Some(e))
+    }
+
+    /// Records the peer's answer. It must [`Chain::verify`] against `dids` and start from this entry's
+    /// capsule; otherwise nothing is recorded and the error (a [`SirpError`]) counts as a [`Outbox::failed`] attempt.
+    pub fn delivered(&self, cid:&str, chain:Chain, dids:&dyn DidResolver) -> Result<Option<Entry>> {
+        chain.verify(dids)?;
+        if chain.intent.cid()? != cid { return Err(SirpError::Broken(format!("answer is for {}, not {cid}", chain.intent.cid()?)).into()); }
+        self.settle(cid, |e| { e.status = Status::Delivered; e.last_error = None; e.chain = Some(chain); })
+    }
+
+    /// A transport error or unusable answer: retry after the backoff, or dead-letter after `max_attempts`.
+    pub fn failed(&self, cid:&str, error:&str, now:DateTime<Utc>) -> Result<Option<Entry>> {
+        let b = self.backoff;
+        self.settle(cid, |e| {
+            e.last_error = Some(error.into());
+            if e.attempts >= b.max_attempts { e.status = Status::Dead; }
+            else { e.next_at = (now + chrono::Duration::milliseconds(b.delay_ms(e.attempts) as i64)).to_rfc3339(); }
+        })
+    }
+
+    /// The peer refused the capsule: dead-lettered at once, with its sealed [`Delivery`] (checked against `dids`).
+    pub fn rejected(&self, cid:&str, delivery:Delivery, dids:&dyn DidResolver) -> Result<Option<Entry>> {
+        delivery.verify(dids)?;
+        if delivery.capsule_cid != cid || delivery.outcome != Outcome::Rejected {
+            return Err(SirpError::Broken(format!("not a rejection of {cid}")).into());
+        }
+        self.settle(cid, |e| { e.status = Status::Dead; e.last_error = delivery.reason.clone(); e.rejection = Some(delivery); })
+    }
+}
@@ -0,0 +1,60 @@
+//! SIRP documents on disk, addressed by CID.
+use anyhow::{anyhow, bail, Result};
+use engine_core::json_atomic::{compute_cid, to_json_atomic_bytes};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::{Chain, Ref};
+
+/// `{dir}/<hex>.json` per document, holding its JSON✯Atomic bytes (so the file hashes to its CID).
+/// Replies to capsules from peers are kept apart, under `{dir}/answers/` by the capsule's CID.
+pub struct SirpStore { dir: PathBuf }
+
+pub(crate) fn file_name(cid:&str) -> Result<String> {
+    let hex = cid.strip_prefix("b3:").ok_or_else(|| anyhow!("not a b3 cid: {cid}"))?;
+    if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) { bail!("not a b3 cid: {cid}"); }
+    Ok(format!("{}.json", hex.to_ascii_lowercase()))
+}
+
+impl SirpStore {
+    pub fn new(dir:impl Into<PathBuf>) -> Self { Self{ dir: dir.into() } }
+
+    pub fn put<T: Serialize>(&self, doc:&T) -> Result<String> {
+        let cid = compute_cid(doc)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(file_name(&cid)?);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, to_json_atomic_bytes(doc)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(cid)
+    }
+
+    pub fn get(&self, cid:&str) -> Result<Option<Value>> { read(self.dir.join(file_name(cid)?)) }
+
+    /// What this engine answered to the capsule `cid`, so a redelivered capsule gets the same reply.
+    pub fn answer(&self, cid:&str) -> Result<Option<Value>> { read(self.dir.join("answers").join(file_name(cid)?)) }
+
+    pub fn put_answer(&self, cid:&str, reply:&Value) -> Result<()> {
+        let path = self.dir.join("answers").join(file_name(cid)?);
+        std::fs::create_dir_all(self.dir.join("answers"))?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(reply)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// Stores all four documents; returns [`Chain::refs`].
+    pub fn put_chain(&self, chain:&Chain) -> Result<Vec<Ref>> {
+        for (_, doc) in chain.docs()? { self.put(&doc)?; }
+        chain.refs()
+    }
+}
+
+fn read(path:PathBuf) -> Result<Option<Value>> {
+    match std::fs::read(path) {
+        Ok(b) => Ok(Some(serde_json::from_slice(&b)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
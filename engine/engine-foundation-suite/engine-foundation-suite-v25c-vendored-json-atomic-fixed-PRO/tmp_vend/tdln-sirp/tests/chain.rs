@@ -0,0 +1,86 @@
+use ed25519_dalek::SigningKey;
+use rand_core::OsRng;
+use tdln_sirp::store::SirpStore;
+use tdln_sirp::*;
+
+const ISSUER: &str = "did:tdln:tenant:acme";
+const ENGINE: &str = "did:tdln:engine:b";
+
+fn setup() -> (SigningKey, SigningKey, DidKeys, Chain) {
+    let (issuer, engine) = (SigningKey::generate(&mut OsRng), SigningKey::generate(&mut OsRng));
+    let dids = DidKeys::default().with(ISSUER, &issuer.verifying_key()).with(ENGINE, &engine.verifying_key());
+    let chain = Chain::local(Party{ did: ISSUER, key: &issuer }, Party{ did: ENGINE, key: &engine }, "b3:run", "b3:receipt").unwrap();
+    (issuer, engine, dids, chain)
+}
+
+fn err(r:anyhow::Result<()>) -> String {
+    let e = r.unwrap_err();
+    match e.downcast_ref::<SirpError>() {
+        Some(SirpError::UnknownDid(_)) => "unknown_did",
+        Some(SirpError::BadSignature(_)) => "bad_signature",
+        Some(SirpError::Broken(_)) => "broken",
+        None => panic!("{e}"),
+    }.into()
+}
+
+#[test]
+fn local_chains_verify_and_link_by_cid() {
+    let (_, _, dids, chain) = setup();
+    chain.verify(&dids).unwrap();
+    assert_eq!(chain.delivery.capsule_cid, chain.intent.cid().unwrap());
+    assert_eq!(chain.result.find(ENGINE_RECEIPT), Some("b3:receipt"));
+    assert_eq!((chain.result.from.as_str(), chain.result.to.as_str()), (ENGINE, ISSUER));
+    let kinds: Vec<_> = chain.refs().unwrap().into_iter().map(|r| r.kind).collect();
+    assert_eq!(kinds, [CAPSULE, DELIVERY, EXECUTION, CAPSULE]);
+    // The seal is not part of what it signs.
+    let v = serde_json::to_value(&chain.intent).unwrap();
+    assert_eq!(v["signature"]["alg"], "ed25519-blake3");
+    assert_eq!(v["type"], "INTENT");
+}
+
+#[test]
+fn engine_attested_intents_say_so() {
+    let (_, engine, dids, chain) = setup();
+    assert_eq!(chain.attested_for(), None);
+    let attested = Chain::attested(Party{ did: ENGINE, key: &engine }, ISSUER, "b3:run", "b3:receipt").unwrap();
+    attested.verify(&DidKeys::default().with(ENGINE, &engine.verifying_key())).unwrap();
+    assert_eq!((attested.intent.from.as_str(), attested.attested_for()), (ENGINE, Some(ISSUER)));
+
+    // The label is sealed with the intent.
+    let mut c = attested.clone();
+    c.intent.aad["attested_for"] = "did:tdln:tenant:globex".into();
+    assert_eq!(err(c.verify(&dids)), "bad_signature");
+}
+
+#[test]
+fn tampering_forgery_and_unknown_dids_are_rejected() {
+    let (issuer, engine, dids, chain) = setup();
+    let mut c = chain.clone();
+    c.delivery.outcome = Outcome::Rejected;
+    assert_eq!(err(c.verify(&dids)), "bad_signature");
+
+    // Re-sealed by the right key, but for another capsule.
+    let mut c = chain.clone();
+    c.delivery.capsule_cid = "b3:other".into();
+    c.delivery.sign(&engine).unwrap();
+    assert_eq!(err(c.verify(&dids)), "broken");
+
+    // The issuer cannot speak for the engine.
+    let mut c = chain.clone();
+    c.execution.sign(&issuer).unwrap();
+    assert_eq!(err(c.verify(&dids)), "bad_signature");
+
+    let only_issuer = DidKeys::default().with(ISSUER, &issuer.verifying_key());
+    assert_eq!(err(chain.verify(&only_issuer)), "unknown_did");
+}
+
+#[test]
+fn store_keeps_canonical_bytes_by_cid() {
+    let (_, _, _, chain) = setup();
+    let dir = tempfile::tempdir().unwrap();
+    let store = SirpStore::new(dir.path());
+    let refs = store.put_chain(&chain).unwrap();
+    let intent: Capsule = serde_json::from_value(store.get(&refs[0].cid).unwrap().unwrap()).unwrap();
+    assert_eq!(intent.cid().unwrap(), refs[0].cid);
+    assert!(store.get(&format!("b3:{}", "0".repeat(64))).unwrap().is_none());
+}
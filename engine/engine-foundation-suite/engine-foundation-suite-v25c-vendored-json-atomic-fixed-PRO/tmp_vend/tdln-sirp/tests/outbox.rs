@@ -0,0 +1,95 @@
+use chrono::{Duration, Utc};
+use ed25519_dalek::SigningKey;
+use rand_core::OsRng;
+use tdln_sirp::outbox::{Backoff, Outbox, Status};
+use tdln_sirp::*;
+
+const A: &str = "did:tdln:engine:a";
+const B: &str = "did:tdln:engine:b";
+
+fn outbox(max_attempts:u32) -> (tempfile::TempDir, Outbox) {
+    let dir = tempfile::tempdir().unwrap();
+    let out = Outbox::new(dir.path(), Backoff{ base_ms: 1_000, max_ms: 4_000, max_attempts });
+    (dir, out)
+}
+
+fn intent(a:&SigningKey) -> Capsule {
+    let mut c = Capsule::intent(A, B, "b3:run");
+    c.sign(a).unwrap();
+    c
+}
+
+#[test]
+fn peers_admit_answer_and_the_outbox_keeps_the_verified_chain() {
+    let (a, b) = (SigningKey::generate(&mut OsRng), SigningKey::generate(&mut OsRng));
+    let dids = DidKeys::default().with(A, &a.verifying_key()).with(B, &b.verifying_key()).at(B, "http://127.0.0.1:8081/sirp/inbox");
+    let (_dir, out) = outbox(3);
+    let e = out.enqueue(intent(&a), dids.endpoint(B).unwrap()).unwrap();
+    assert_eq!(out.enqueue(e.capsule.clone(), "elsewhere").unwrap().endpoint, e.endpoint);
+    assert_eq!(out.due(Utc::now()).unwrap().len(), 1);
+
+    // B's side.
+    Chain::admit(&e.capsule, B, &dids).unwrap();
+    assert!(Chain::admit(&e.capsule, A, &dids).is_err());
+    let chain = Chain::answer(e.capsule.clone(), &b, "b3:receipt").unwrap();
+
+    // An answer to some other capsule is not taken.
+    let other = Chain::answer({ let mut c = Capsule::intent(A, B, "b3:other"); c.sign(&a).unwrap(); c }, &b, "b3:r").unwrap();
+    assert!(out.delivered(&e.capsule_cid, other, &dids).is_err());
+    let done = out.delivered(&e.capsule_cid, chain, &dids).unwrap().unwrap();
+    assert_eq!((done.status, done.attempts), (Status::Delivered, 1));
+    assert_eq!(done.chain.unwrap().result.find(ENGINE_RECEIPT), Some("b3:receipt"));
+    assert!(out.due(Utc::now()).unwrap().is_empty());
+    // Settled entries are not settled again.
+    assert!(out.failed(&e.capsule_cid, "late", Utc::now()).unwrap().is_none());
+}
+
+#[test]
+fn failures_back_off_then_dead_letter() {
+    let (a, b) = (SigningKey::generate(&mut OsRng), SigningKey::generate(&mut OsRng));
+    let dids = DidKeys::default().with(A, &a.verifying_key()).with(B, &b.verifying_key());
+    let (_dir, out) = outbox(3);
+    let cid = out.enqueue(intent(&a), "http://127.0.0.1:1/sirp/inbox").unwrap().capsule_cid;
+    let now = Utc::now();
+    let e = out.failed(&cid, "connection refused", now).unwrap().unwrap();
+    assert_eq!((e.status, e.attempts), (Status::Pending, 1));
+    assert!(out.due(now).unwrap().is_empty());
+    assert_eq!(out.due(now + Duration::seconds(1)).unwrap().len(), 1);
+    let e = out.failed(&cid, "connection refused", now).unwrap().unwrap();
+    assert!(out.due(now + Duration::seconds(1)).unwrap().is_empty(), "second retry waits 2s");
+    assert_eq!(e.attempts, 2);
+    let e = out.failed(&cid, "connection refused", now).unwrap().unwrap();
+    assert_eq!(e.status, Status::Dead);
+    assert_eq!(out.list(Status::Dead).unwrap().len(), 1);
+    assert!(out.list(Status::Pending).unwrap().is_empty());
+
+    // A sealed refusal dead-letters at once; an unsealed one does not count.
+    let cid = out.enqueue({ let mut c = Capsule::intent(A, B, "b3:run2"); c.sign(&a).unwrap(); c }, "x").unwrap().capsule_cid;
+    let capsule = out.get(&cid).unwrap().unwrap().capsule;
+    let mut forged = Chain::reject(&capsule, &b, "unknown unit").unwrap();
+    forged.sign(&a).unwrap();
+    assert!(out.rejected(&cid, forged, &dids).is_err());
+    let e = out.rejected(&cid, Chain::reject(&capsule, &b, "unknown unit").unwrap(), &dids).unwrap().unwrap();
+    assert_eq!((e.status, e.last_error.as_deref()), (Status::Dead, Some("unknown unit")));
+    assert_eq!(Backoff::default().delay_ms(20), 300_000);
+}
+
+#[test]
+fn an_entry_is_claimed_once_until_it_settles_or_the_lease_runs_out() {
+    let a = SigningKey::generate(&mut OsRng);
+    let (_dir, out) = outbox(3);
+    let cid = out.enqueue(intent(&a), "x").unwrap().capsule_cid;
+    let (now, lease) = (Utc::now(), Duration::seconds(60));
+    assert!(out.claim(&cid, now, lease).unwrap().is_some());
+    assert!(out.claim(&cid, now, lease).unwrap().is_none());
+    assert!(out.due(now).unwrap().is_empty());
+    // An attempt that never settles comes due again after its lease.
+    assert_eq!(out.due(now + lease).unwrap().len(), 1);
+    assert!(out.claim(&cid, now + lease, lease).unwrap().is_some());
+    // A failed attempt backs off as usual; a settled entry is never claimed again.
+    out.failed(&cid, "timeout", now).unwrap().unwrap();
+    assert!(out.claim(&cid, now + Duration::seconds(1), lease).unwrap().is_some());
+    out.failed(&cid, "timeout", now).unwrap();
+    out.failed(&cid, "timeout", now).unwrap();
+    assert!(out.claim(&cid, now + Duration::days(1), lease).unwrap().is_none());
+}
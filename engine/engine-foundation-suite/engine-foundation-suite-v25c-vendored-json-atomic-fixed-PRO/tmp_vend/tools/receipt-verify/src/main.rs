@@ -1,9 +1,13 @@
 
-use std::{fs::File, io::Read, path::PathBuf};
+use std::{collections::BTreeMap, fs::File, io::Read, path::PathBuf};
 use clap::Parser;
 use anyhow::{Result, anyhow};
+use base64::Engine as _;
+use ed25519_dalek::{Signer as _, Verifier as _};
 use zip::ZipArchive;
 use serde::Deserialize;
+use serde_json::Value;
+use engine_core::verify::Verdict;
 
 #[derive(Parser, Debug)]
 #[command(name="receipt-verify", about="Offline verifier for LogLine bundle.zip")]
@@ -11,10 +15,10 @@ struct Args {
     /// Path to bundle.zip
     #[arg(value_name="BUNDLE")]
     bundle: PathBuf,
-    /// Path to ed25519 public key (base64)
+    /// Path to ed25519 public key (base64); pins the issuer instead of trusting `signer.pub`
     #[arg(long)]
     pubkey_b64: Option<PathBuf>,
-    /// Strict mode: verify bundle_hash == local recompute; emit JSON report
+    /// Strict mode: emit a JSON report instead of one line per check
     #[arg(long)]
     strict: bool,
     /// Output JSON report path (when --strict)
@@ -28,134 +32,193 @@ struct Args {
     signing_key_b64: Option<PathBuf>,
 }
 
+/// `manifest.json` (`bundle.manifest.v1`), as written by engine-bundle.
 #[derive(Deserialize)]
-struct Receipt {
+struct Manifest {
     kind: String,
-    signatures: Option<serde_json::Value>,
-    certification: Option<serde_json::Value>,
+    run_cid: String,
+    unit: UnitInfo,
+    input: String,
+    cids: BTreeMap<String, String>,
+    files: BTreeMap<String, String>,
+    signer: SignerInfo,
 }
+#[derive(Deserialize)]
+struct UnitInfo { hash: String, wasm: Option<String> }
+#[derive(Deserialize)]
+struct SignerInfo { kid: String, public_key_b64: String }
 
-fn read_zip_file(mut za: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>> {
-    let mut f = za.by_name(name)?;
-    let mut buf = Vec::new();
-    std::io::copy(&mut f, &mut buf)?;
-    Ok(buf)
-}
-
-fn main() -> Result<()> {
-    let args = Args::parse();
-    let f = File::open(&args.bundle)?;
-    let mut za = ZipArchive::new(f)?;
-
-    // Required artifacts inside bundle.zip
-    let policy = read_zip_file(&mut za, "policy.canonical.json")?;
-    let wasm   = read_zip_file(&mut za, "policy.wasm").ok();
-    let cert   = read_zip_file(&mut za, "certification.json")?;
+fn b3(bytes: &[u8]) -> String { format!("b3:{}", blake3::hash(bytes).to_hex()) }
 
-    // Compute digests
-    let policy_digest = blake3::hash(&policy);
-    let wasm_digest   = wasm.as_ref().map(|b| blake3::hash(b));
+/// JSON✯Atomic bytes: serde_json keeps object keys sorted (no `preserve_order`) and writes no whitespace.
+fn canon(v: &Value) -> Vec<u8> { serde_json::to_vec(v).expect("serialize json") }
 
-    // Parse receipt
-    let receipt: Receipt = serde_json::from_slice(&cert)?;
-    if receipt.kind != "verification.bundle.v1" && receipt.kind != "certification" {
-        eprintln!("WARN: unexpected receipt kind: {}", receipt.kind);
+fn read_all(za: &mut ZipArchive<File>) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut out = BTreeMap::new();
+    for i in 0..za.len() {
+        let mut f = za.by_index(i)?;
+        let mut buf = Vec::new();
+        f.read_to_end(&mut buf)?;
+        out.insert(f.name().to_string(), buf);
     }
+    Ok(out)
+}
 
-    // Recompute demo bundle hash
-    let mut demo_hash = blake3::Hasher::new();
-    demo_hash.update(&policy);
-    if let Some(w) = wasm.as_ref() { demo_hash.update(w); }
-    let demo_b3 = format!("b3:{}", demo_hash.finalize().to_hex());
-
-    // Optional: verify issuer signature if pubkey provided
-    if let Some(pk_path) = args.pubkey_b64 {
-        let pk_b64 = std::fs::read_to_string(pk_path)?;
-        // Expect signatures.bundle_hash + issuer_signature (ed25519-blake3) in certification.json
-        let v = serde_json::from_slice::<serde_json::Value>(&cert)?;
-        let sig = v.pointer("/signatures/issuer_signature")
-            .and_then(|x| x.as_str()).ok_or_else(|| anyhow!("issuer_signature missing"))?;
-        let bundle_hash = v.pointer("/signatures/bundle_hash")
-            .and_then(|x| x.as_str()).ok_or_else(|| anyhow!("bundle_hash missing"))?;
-        // For demo we rehash policy+wasm to compare (not strict)
-        if demo_b3 != bundle_hash {
-            eprintln!("WARN: local bundle hash {} != receipt {}", demo, bundle_hash);
-        }
-        // Verify signature
-        let pk = base64::prelude::BASE64_STANDARD.decode(pk_b64.trim()).map_err(|_| anyhow!("invalid pubkey"))?;
-        let sigb = base64::prelude::BASE64_STANDARD.decode(sig).map_err(|_| anyhow!("invalid signature b64"))?;
-        let vk = ed25519_dalek::VerifyingKey::from_bytes(&pk.try_into().map_err(|_| anyhow!("pubkey size"))?)?;
-        let digest = blake3::hash(&cert);
-        let sig = ed25519_dalek::Signature::from_bytes(&sigb.try_into().map_err(|_| anyhow!("sig size"))?);
-        vk.verify_strict(digest.as_bytes(), &sig).map_err(|e| anyhow!(e))?;
-        println!("✔ issuer signature: OK");
+/// One named check; `ok == false` carries what was expected and what was found.
+struct Checks(Vec<(String, bool, String)>);
+impl Checks {
+    fn eq(&mut self, name: &str, expected: &str, got: &str) {
+        self.0.push((name.into(), expected == got, if expected == got { got.into() } else { format!("expected {expected}, got {got}") }));
     }
+    fn ok(&mut self, name: &str, r: Result<String>) {
+        match r { Ok(v) => self.0.push((name.into(), true, v)), Err(e) => self.0.push((name.into(), false, format!("{e:#}"))) }
+    }
+    fn passed(&self) -> bool { self.0.iter().all(|(_, ok, _)| *ok) }
+}
 
-    println!("✔ policy.canonical.json b3:{}", policy_digest.to_hex());
-    if let Some(w) = wasm_digest { println!("✔ policy.wasm b3:{}", w.to_hex()); }
-
-
-if args.strict {
-    // Expect fields in certification.json
-    let v = serde_json::from_slice::<serde_json::Value>(&cert)?;
-    let bundle_hash = v.pointer("/signatures/bundle_hash").and_then(|x| x.as_str()).unwrap_or("");
-    let issuer = v.pointer("/certification/issuer/name").and_then(|x| x.as_str()).unwrap_or("unknown");
-    let did = v.pointer("/certification/did").and_then(|x| x.as_str()).unwrap_or("");
+fn verify(files: &BTreeMap<String, Vec<u8>>, pinned: Option<String>) -> Result<(Checks, Value, Option<Verdict>)> {
+    let file = |n: &str| files.get(n).ok_or_else(|| anyhow!("{n} missing from bundle"));
+    let json = |n: &str| -> Result<Value> { Ok(serde_json::from_slice(file(n)?)?) };
+    let manifest_bytes = file("manifest.json")?;
+    let m: Manifest = serde_json::from_slice(manifest_bytes)?;
+    if m.kind != "bundle.manifest.v1" { return Err(anyhow!("unexpected manifest kind {}", m.kind)); }
+    let cert = json("certification.json")?;
+    let cid = |k: &str| m.cids.get(k).cloned().unwrap_or_default();
+    let mut c = Checks(vec![]);
+
+    // Every file is listed and hashes to its manifest entry; nothing unlisted rides along.
+    for (name, cid) in &m.files {
+        match files.get(name) { Some(b) => c.eq(&format!("file {name}"), cid, &b3(b)), None => c.eq(&format!("file {name}"), cid, "missing") }
+    }
+    for name in files.keys().filter(|n| !m.files.contains_key(*n) && *n != "manifest.json" && *n != "certification.json") {
+        c.eq(&format!("file {name}"), "unlisted", "present");
+    }
 
-    let mut ok = true;
-    let mut issues: Vec<String> = Vec::new();
-    if demo_b3 != bundle_hash {
-        ok = false;
-        issues.push(format!("bundle_hash mismatch: local {} vs receipt {}", demo_b3, bundle_hash));
+    // Issuer signature over blake3(manifest bytes).
+    c.eq("manifest_b3", cert.get("manifest_b3").and_then(Value::as_str).unwrap_or(""), &b3(manifest_bytes));
+    // Unpinned, the key below comes from the bundle itself: the signature then only proves self-consistency.
+    let pk_b64 = match &pinned { Some(p) => p.clone(), None => String::from_utf8(file("signer.pub")?.clone())? };
+    if pinned.is_some() { c.eq("signer", &m.signer.public_key_b64, pk_b64.trim()); }
+    c.ok("issuer_signature", (|| {
+        let b64 = base64::prelude::BASE64_STANDARD;
+        let pk: [u8; 32] = b64.decode(pk_b64.trim())?.try_into().map_err(|_| anyhow!("pubkey size"))?;
+        let sig = cert.pointer("/signatures/issuer_signature").and_then(Value::as_str).ok_or_else(|| anyhow!("issuer_signature missing"))?;
+        let sig: [u8; 64] = b64.decode(sig)?.try_into().map_err(|_| anyhow!("sig size"))?;
+        ed25519_dalek::VerifyingKey::from_bytes(&pk)?
+            .verify(blake3::hash(manifest_bytes).as_bytes(), &ed25519_dalek::Signature::from_bytes(&sig))?;
+        Ok(m.signer.kid.clone())
+    })());
+
+    // Unit: policy bytes are the canonical spec, so their CID is the receipt's chip_hash.
+    let policy = file("policy.canonical.json")?;
+    let wasm = files.get("policy.wasm");
+    let receipt = json("receipt.json")?;
+    let rs = |p: &str| receipt.pointer(p).and_then(Value::as_str).unwrap_or("").to_string();
+    c.eq("unit.hash", &m.unit.hash, &b3(policy));
+    c.eq("receipt.chip_hash", &m.unit.hash, &rs("/chip_hash"));
+    c.eq("unit.wasm", m.unit.wasm.as_deref().unwrap_or("none"), &wasm.map(|w| b3(w)).unwrap_or("none".into()));
+    let mut h = blake3::Hasher::new();
+    h.update(policy);
+    if let Some(w) = wasm { h.update(w); }
+    c.eq("bundle_hash", cert.pointer("/signatures/bundle_hash").and_then(Value::as_str).unwrap_or(""), &format!("b3:{}", h.finalize().to_hex()));
+
+    // Receipt and card are stored canonical: their CIDs are the hash of the file bytes, and re-canonicalizing changes nothing.
+    c.eq("receipt", &cid("receipt"), &b3(&canon(&receipt)));
+    c.eq("card", &cid("card"), &b3(&canon(&json("card.json")?)));
+    let card = json("card.json")?;
+    if let Some(run) = card.get("run_cid").and_then(Value::as_str) { c.eq("card.run_cid", &m.run_cid, run); }
+
+    // Input → input CID → head of the hash chain; output → output CID → tail.
+    let input_cid = rs("/input/cid");
+    c.eq("input.cid", &cid("input"), &input_cid);
+    match m.input.as_str() {
+        "full" => c.eq("input.json", &input_cid, &b3(&canon(&json("input.json")?))),
+        _ => c.eq("input.commitments", &input_cid, json("input.commitments.json")?.get("cid").and_then(Value::as_str).unwrap_or("")),
     }
+    let output_cid = rs("/output/cid");
+    c.eq("output.cid", &cid("output"), &output_cid);
+    c.eq("output.raw", &output_cid, &b3(&canon(receipt.pointer("/output/raw").unwrap_or(&Value::Null))));
+    let chain: Vec<&str> = receipt.pointer("/proof/hash_chain").and_then(Value::as_array).map(|a| a.iter().filter_map(Value::as_str).collect()).unwrap_or_default();
+    c.eq("hash_chain.head", &input_cid, chain.first().copied().unwrap_or(""));
+    c.eq("hash_chain.tail", &output_cid, chain.last().copied().unwrap_or(""));
+
+    // With the full input, replay the run: every policy, the aggregation and the signed payload.
+    let mut verdict = None;
+    if m.input == "full" {
+        c.ok("replay", (|| {
+            let unit = engine_loader::unit_from_spec(&serde_json::from_slice(policy)?)?;
+            let v = engine_core::verify::replay(&unit, &serde_json::from_value(receipt.clone())?);
+            let r = match &v.divergence {
+                None => Ok(v.signed_payload_cid.clone().unwrap_or_default()),
+                Some(d) => Err(anyhow!("{} at {}: expected {}, got {}", serde_json::json!(d.code).as_str().unwrap_or(""), d.at, d.expected, d.got)),
+            };
+            verdict = Some(v);
+            r
+        })());
+    }
+    Ok((c, cert, verdict))
+}
 
-    let report = serde_json::json!({
-        "ok": ok,
-        "issuer": issuer,
-        "did": did,
-        "policy_b3": format!("b3:{}", blake3::hash(&policy).to_hex()),
-        "wasm_b3": wasm.as_ref().map(|w| format!("b3:{}", blake3::hash(w).to_hex())),
-        "bundle_hash": bundle_hash,
-        "local_bundle_hash": demo_b3,
-        "issues": issues
-    });
-
-    if let Some(out) = args.out_report {
-        std::fs::write(out, serde_json::to_vec_pretty(&report)?)?;
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let f = File::open(&args.bundle)?;
+    let files = read_all(&mut ZipArchive::new(f)?)?;
+    let pinned = args.pubkey_b64.as_ref().map(std::fs::read_to_string).transpose()?;
+    let issuer_pinned = pinned.is_some();
+    let (mut checks, cert, verdict) = verify(&files, pinned)?;
+    if !issuer_pinned {
+        let detail = "unpinned: no --pubkey-b64, the issuer key comes from the bundle".to_string();
+        if args.strict { checks.0.push(("issuer_pinned".into(), false, detail)); } else { eprintln!("⚠ issuer {detail}"); }
+    }
+    let ok = checks.passed();
+
+    if args.strict {
+        let report = serde_json::json!({
+            "ok": ok,
+            "run_cid": cert.get("run_cid"),
+            "issuer": cert.pointer("/certification/issuer/kid"),
+            "issuer_pinned": issuer_pinned,
+            "checks": checks.0.iter().map(|(name, ok, detail)| serde_json::json!({"check": name, "ok": ok, "detail": detail})).collect::<Vec<_>>(),
+            "replay": verdict,
+            "issues": checks.0.iter().filter(|(_, ok, _)| !ok).map(|(name, _, detail)| format!("{name}: {detail}")).collect::<Vec<_>>(),
+        });
+        if let Some(out) = &args.out_report {
+            std::fs::write(out, serde_json::to_vec_pretty(&report)?)?;
+        } else {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
     } else {
-        println!("{}", serde_json::to_string_pretty(&report)?);
+        for (name, ok, detail) in &checks.0 { println!("{} {name}: {detail}", if *ok { "✔" } else { "✘" }); }
     }
-
     if !ok { std::process::exit(2); }
-}
-    println!("✔ bundle parsed successfully");
-
-if args.prove {
-    let sk_path = args.signing_key_b64.as_ref().ok_or_else(|| anyhow!("--signing_key_b64 required with --prove"))?;
-    let sk_b64 = std::fs::read_to_string(sk_path)?;
-    let sk_bytes = base64::prelude::BASE64_STANDARD.decode(sk_b64.trim()).map_err(|_| anyhow!("invalid signing key b64"))?;
-    let sk = ed25519_dalek::SigningKey::from_bytes(&sk_bytes.try_into().map_err(|_| anyhow!("signing key size"))?);
-    let pk = sk.verifying_key();
-    let digest = blake3::hash(&cert);
-    let sig = sk.sign(digest.as_bytes());
-    let proof = serde_json::json!({
-        "kind":"verify.proof.v1",
-        "ts": format!("{}", time::OffsetDateTime::now_utc()),
-        "bundle": args.bundle.file_name().unwrap().to_string_lossy(),
-        "cert_b3": format!("b3:{}", blake3::hash(&cert).to_hex() ),
-        "policy_b3": format!("b3:{}", blake3::hash(&policy).to_hex() ),
-        "wasm_b3": wasm.as_ref().map(|w| format!("b3:{}", blake3::hash(w).to_hex())),
-        "signature": base64::prelude::BASE64_STANDARD.encode(sig.to_bytes()),
-        "public_key_b64": base64::prelude::BASE64_STANDARD.encode(pk.to_bytes())
-    });
-    if let Some(out) = args.out_report.as_ref() {
-        let p = out.with_extension("proof.json");
-        std::fs::write(&p, serde_json::to_vec_pretty(&proof)?)?;
-        println!("✔ proof saved: {}", p.to_string_lossy());
-    } else {
-        println!("{}", serde_json::to_string_pretty(&proof)?);
+    if !args.strict { println!("✔ bundle verified offline"); }
+
+    if args.prove {
+        let cert_bytes = &files["certification.json"];
+        let sk_path = args.signing_key_b64.as_ref().ok_or_else(|| anyhow!("--signing_key_b64 required with --prove"))?;
+        let sk_b64 = std::fs::read_to_string(sk_path)?;
+        let sk_bytes = base64::prelude::BASE64_STANDARD.decode(sk_b64.trim()).map_err(|_| anyhow!("invalid signing key b64"))?;
+        let sk = ed25519_dalek::SigningKey::from_bytes(&sk_bytes.try_into().map_err(|_| anyhow!("signing key size"))?);
+        let pk = sk.verifying_key();
+        let sig = sk.sign(blake3::hash(cert_bytes).as_bytes());
+        let proof = serde_json::json!({
+            "kind":"verify.proof.v1",
+            "ts": format!("{}", time::OffsetDateTime::now_utc()),
+            "bundle": args.bundle.file_name().unwrap().to_string_lossy(),
+            "cert_b3": b3(cert_bytes),
+            "policy_b3": b3(&files["policy.canonical.json"]),
+            "wasm_b3": files.get("policy.wasm").map(|w| b3(w)),
+            "signature": base64::prelude::BASE64_STANDARD.encode(sig.to_bytes()),
+            "public_key_b64": base64::prelude::BASE64_STANDARD.encode(pk.to_bytes())
+        });
+        if let Some(out) = args.out_report.as_ref() {
+            let p = out.with_extension("proof.json");
+            std::fs::write(&p, serde_json::to_vec_pretty(&proof)?)?;
+            println!("✔ proof saved: {}", p.to_string_lossy());
+        } else {
+            println!("{}", serde_json::to_string_pretty(&proof)?);
+        }
     }
-}
 
     Ok(())
 }
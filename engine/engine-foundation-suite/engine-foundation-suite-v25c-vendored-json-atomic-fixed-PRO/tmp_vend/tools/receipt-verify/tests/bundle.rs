@@ -0,0 +1,102 @@
+use base64::Engine as _;
+use ed25519_dalek::SigningKey;
+use engine_bundle::Bundle;
+use engine_core::json_atomic::compute_cid;
+use engine_core::model::*;
+use engine_core::runtime::Engine;
+use rand_core::OsRng;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::path::Path;
+
+fn seal(key:&SigningKey, card:Value) -> Vec<u8> {
+    let spec = json!({"id": "gate", "policies": [], "wiring": {"type": "all", "policies": []}});
+    let mut chip = SemanticChip::builder("gate").wiring(Wiring::All{ policies: vec![] }).build();
+    chip.hash = Some(compute_cid(&spec).unwrap());
+    let receipt = Engine::default().build().execute_chip(&chip, json!({"user": {"id": "u-1"}, "amount": 10}), None).unwrap();
+    Bundle::new("b3:run", &receipt, &card).unit(spec).seal(key).unwrap()
+}
+
+/// Same archive with `name` replaced by `bytes`.
+fn rewrite(zip:&[u8], name:&str, bytes:&[u8]) -> Vec<u8> {
+    let mut za = zip::ZipArchive::new(std::io::Cursor::new(zip)).unwrap();
+    let mut out = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for i in 0..za.len() {
+        let mut f = za.by_index(i).unwrap();
+        let mut buf = vec![];
+        f.read_to_end(&mut buf).unwrap();
+        out.start_file(f.name(), zip::write::FileOptions::default()).unwrap();
+        out.write_all(if f.name() == name { bytes } else { &buf }).unwrap();
+    }
+    out.finish().unwrap().into_inner()
+}
+
+/// Runs the tool with `--strict`; `(exit 0, report)`.
+fn verify(dir:&Path, zip:&[u8], pin:Option<&SigningKey>) -> (bool, Value) {
+    let bundle = dir.join("bundle.zip");
+    std::fs::write(&bundle, zip).unwrap();
+    let mut cmd = std::process::Command::new(env!("CARGO_BIN_EXE_receipt-verify"));
+    cmd.arg(&bundle).arg("--strict");
+    if let Some(k) = pin {
+        let p = dir.join("issuer.pub");
+        std::fs::write(&p, base64::prelude::BASE64_STANDARD.encode(k.verifying_key().as_bytes())).unwrap();
+        cmd.arg("--pubkey-b64").arg(p);
+    }
+    let out = cmd.output().unwrap();
+    (out.status.success(), serde_json::from_slice(&out.stdout).unwrap())
+}
+
+fn failed(report:&Value) -> Vec<String> {
+    report["checks"].as_array().unwrap().iter().filter(|c| c["ok"] == false).map(|c| c["check"].as_str().unwrap().to_string()).collect()
+}
+
+#[test]
+fn pinned_bundle_verifies_and_unpinned_fails_strict() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = SigningKey::generate(&mut OsRng);
+    let zip = seal(&key, json!({"kind": "receipt.card.v1", "run_cid": "b3:run"}));
+    let (ok, report) = verify(dir.path(), &zip, Some(&key));
+    assert!(ok, "{report:#}");
+    assert_eq!(report["issuer_pinned"], true);
+
+    let (ok, report) = verify(dir.path(), &zip, None);
+    assert!(!ok);
+    assert_eq!(report["issuer_pinned"], false);
+    assert_eq!(failed(&report), ["issuer_pinned"]);
+}
+
+#[test]
+fn tampered_file_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = SigningKey::generate(&mut OsRng);
+    let zip = seal(&key, json!({"kind": "receipt.card.v1", "run_cid": "b3:run"}));
+    let zip = rewrite(&zip, "card.json", br#"{"kind":"receipt.card.v1","run_cid":"b3:run","decision":"ACK"}"#);
+    let (ok, report) = verify(dir.path(), &zip, Some(&key));
+    assert!(!ok);
+    assert!(failed(&report).contains(&"file card.json".to_string()), "{report:#}");
+}
+
+#[test]
+fn re_signed_forgery_needs_a_pinned_issuer() {
+    let dir = tempfile::tempdir().unwrap();
+    let (issuer, forger) = (SigningKey::generate(&mut OsRng), SigningKey::generate(&mut OsRng));
+    // Consistent on its own: every hash matches and signer.pub is the forger's key.
+    let forged = seal(&forger, json!({"kind": "receipt.card.v1", "run_cid": "b3:run", "forged": true}));
+    let (ok, report) = verify(dir.path(), &forged, None);
+    assert!(!ok);
+    assert_eq!(failed(&report), ["issuer_pinned"]);
+
+    let (ok, report) = verify(dir.path(), &forged, Some(&issuer));
+    assert!(!ok);
+    assert_eq!(failed(&report), ["signer", "issuer_signature"]);
+}
+
+#[test]
+fn wrong_pinned_key_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = SigningKey::generate(&mut OsRng);
+    let zip = seal(&key, json!({"kind": "receipt.card.v1", "run_cid": "b3:run"}));
+    let (ok, report) = verify(dir.path(), &zip, Some(&SigningKey::generate(&mut OsRng)));
+    assert!(!ok);
+    assert_eq!(failed(&report), ["signer", "issuer_signature"]);
+}
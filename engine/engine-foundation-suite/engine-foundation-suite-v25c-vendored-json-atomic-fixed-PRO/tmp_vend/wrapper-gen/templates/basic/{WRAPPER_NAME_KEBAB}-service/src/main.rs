@@ -12,7 +12,8 @@ use engine_http::presign::StubPresigner;
 #[tokio::main]
 async fn main() {
     let cfg = config::AppConfig::from_env();
-    let api = build_router_with_flavors::<StubPresigner>("./out", "./registry", 2, StubPresigner).await;
+    let api = build_router_with_flavors::<StubPresigner>("./out", "./registry", 2, StubPresigner).await
+        .unwrap_or_else(|e| { eprintln!("engine router: {e:#}"); std::process::exit(1) });
     let static_dir = ServeDir::new("static");
     let mut cors = CorsLayer::permissive();
     if cfg.cors_origins.len() == 1 && cfg.cors_origins[0] == "*" { cors = CorsLayer::permissive(); }